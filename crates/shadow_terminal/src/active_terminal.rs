@@ -11,7 +11,7 @@
 #[non_exhaustive]
 pub struct ActiveTerminal {
     /// The task handle to the actively running [`crate::shadow_tty::ShadowTerminal`]
-    pub task_handle: tokio::task::JoinHandle<()>,
+    pub task_handle: tokio::task::JoinHandle<Result<(), crate::errors::PTYError>>,
     /// A Tokio channel that receives [`termwiz::surface::Surface`] updates of the underlying
     /// terminal.
     pub surface_output_rx: tokio::sync::mpsc::Receiver<crate::output::Output>,
@@ -20,6 +20,8 @@ pub struct ActiveTerminal {
     /// A Tokio broadcast sender to send protocol messages that control the shadow terminal and
     /// PTY. For example; resizing and shutting down.
     pub control_tx: tokio::sync::broadcast::Sender<crate::Protocol>,
+    /// Shared flags to check whether the shadow terminal and its PTY are still running.
+    pub liveness: std::sync::Arc<crate::shadow_terminal::Liveness>,
 }
 
 impl ActiveTerminal {
@@ -34,6 +36,15 @@ impl ActiveTerminal {
         let mut shadow_terminal =
             crate::shadow_terminal::ShadowTerminal::new(config, surface_output_tx);
         let control_tx = shadow_terminal.channels.control_tx.clone();
+        let liveness = std::sync::Arc::clone(&shadow_terminal.liveness);
+        // The task may not have started yet, but the terminal isn't dead. So it's reported as
+        // alive until the task itself says otherwise.
+        liveness
+            .pty
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        liveness
+            .main_loop
+            .store(true, std::sync::atomic::Ordering::Relaxed);
 
         let task_handle = tokio::spawn(async move { shadow_terminal.run(pty_input_rx).await });
         tracing::debug!("Shadow terminal started.");
@@ -43,9 +54,23 @@ impl ActiveTerminal {
             surface_output_rx,
             pty_input_tx,
             control_tx,
+            liveness,
         }
     }
 
+    /// Like `start()`, but first checks that the PTY's command can be run. So errors like a command
+    /// not being found are reported immediately, rather than the terminal silently never starting.
+    ///
+    /// # Errors
+    /// If the PTY's command can't be run.
+    #[inline]
+    pub fn try_start(
+        config: crate::shadow_terminal::Config,
+    ) -> Result<Self, crate::errors::PTYError> {
+        crate::pty::PTY::check_command(&config.command)?;
+        Ok(Self::start(config))
+    }
+
     /// Send input directly into the underlying PTY process. This doesn't go through the shadow
     /// terminal's "frontend".
     ///
@@ -59,6 +84,14 @@ impl ActiveTerminal {
         self.pty_input_tx.send(bytes).await
     }
 
+    /// Are the shadow terminal task and its PTY still running? Useful for supervisors that want
+    /// to restart dead terminals.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        !self.task_handle.is_finished() && self.liveness.is_alive()
+    }
+
     /// End all loops and send OS kill signals to the underlying PTY.
     ///
     /// # Errors
@@ -83,6 +116,18 @@ impl ActiveTerminal {
             .send(crate::Protocol::Resize { width, height })
     }
 
+    /// Send an OS signal to the PTY's foreground process group.
+    ///
+    /// # Errors
+    /// If sending message over channel fails.
+    #[inline]
+    pub fn send_signal(
+        &self,
+        signal: crate::Signal,
+    ) -> Result<usize, tokio::sync::broadcast::error::SendError<crate::Protocol>> {
+        self.control_tx.send(crate::Protocol::Signal(signal))
+    }
+
     /// Scroll the shadow Wezterm terminal up.
     ///
     /// # Errors
@@ -118,6 +163,18 @@ impl ActiveTerminal {
         self.control_tx
             .send(crate::Protocol::Scroll(crate::Scroll::Cancel))
     }
+
+    /// Ask for complete surfaces of the screen and scrollback, for when the receiver of the
+    /// output has lost sync.
+    ///
+    /// # Errors
+    /// If sending message over channel fails.
+    #[inline]
+    pub fn request_full_redraw(
+        &self,
+    ) -> Result<usize, tokio::sync::broadcast::error::SendError<crate::Protocol>> {
+        self.control_tx.send(crate::Protocol::RequestFullRedraw)
+    }
 }
 
 impl Drop for ActiveTerminal {
@@ -129,3 +186,28 @@ impl Drop for ActiveTerminal {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::ActiveTerminal;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn terminals_are_alive_as_soon_as_theyre_started() {
+        let config = crate::shadow_terminal::Config {
+            command: crate::steppable_terminal::get_canonical_shell(),
+            ..crate::shadow_terminal::Config::default()
+        };
+        let active_terminal = ActiveTerminal::start(config);
+        assert!(active_terminal.is_alive());
+
+        active_terminal.kill().unwrap();
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(5);
+        while active_terminal.is_alive() {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "Terminal never died"
+            );
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+    }
+}
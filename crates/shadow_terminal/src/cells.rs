@@ -0,0 +1,267 @@
+//! A flat grid of cells, with all their colours resolved to RGBA.
+//!
+//! This is for consumers, like bespoke GPU renderers, that don't want to depend on Termwiz's
+//! `Surface` API. The `Surface` based output is still the main way of getting the terminal's
+//! contents.
+
+use snafu::ResultExt as _;
+
+/// An RGBA colour, each channel is between 0.0 and 1.0.
+pub type Rgba = (f32, f32, f32, f32);
+
+/// The text attributes of a cell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CellFlags(pub u8);
+
+impl CellFlags {
+    /// Bold, or "increased intensity", text.
+    pub const BOLD: u8 = 1;
+    /// Faint, or "decreased intensity", text.
+    pub const FAINT: u8 = 1 << 1;
+    /// Italic text.
+    pub const ITALIC: u8 = 1 << 2;
+    /// Underlined text, of any style.
+    pub const UNDERLINE: u8 = 1 << 3;
+    /// Struck through text.
+    pub const STRIKETHROUGH: u8 = 1 << 4;
+    /// Blinking text, of any speed.
+    pub const BLINK: u8 = 1 << 5;
+    /// Invisible text.
+    pub const INVISIBLE: u8 = 1 << 6;
+
+    /// Whether all the given flags are set.
+    #[inline]
+    #[must_use]
+    pub const fn contains(self, flags: u8) -> bool {
+        self.0 & flags == flags
+    }
+
+    /// Get the flags from Termwiz's cell attributes.
+    fn from_attributes(attributes: &termwiz::cell::CellAttributes) -> Self {
+        let flags = [
+            (
+                attributes.intensity() == termwiz::cell::Intensity::Bold,
+                Self::BOLD,
+            ),
+            (
+                attributes.intensity() == termwiz::cell::Intensity::Half,
+                Self::FAINT,
+            ),
+            (attributes.italic(), Self::ITALIC),
+            (
+                attributes.underline() != termwiz::cell::Underline::None,
+                Self::UNDERLINE,
+            ),
+            (attributes.strikethrough(), Self::STRIKETHROUGH),
+            (
+                attributes.blink() != termwiz::cell::Blink::None,
+                Self::BLINK,
+            ),
+            (attributes.invisible(), Self::INVISIBLE),
+        ];
+
+        Self(
+            flags
+                .iter()
+                .filter(|(is_set, _)| *is_set)
+                .fold(0, |all, (_, flag)| all | flag),
+        )
+    }
+}
+
+/// A single cell of the terminal.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Cell {
+    /// The grapheme in the cell. Empty cells contain a single space.
+    pub text: String,
+    /// The number of columns that the grapheme takes up.
+    pub width: usize,
+    /// The resolved foreground colour. Reverse video has already been applied.
+    pub foreground: Rgba,
+    /// The resolved background colour. Reverse video has already been applied.
+    pub background: Rgba,
+    /// Text attributes, like bold and italic.
+    pub flags: CellFlags,
+}
+
+/// A grid of cells, row by row.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct CellGrid {
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+    /// All the cells, row by row. There are always `width * height` of them.
+    pub cells: Vec<Cell>,
+    /// The position of the cursor, relative to the grid, if it's inside the grid.
+    pub cursor: Option<(usize, usize)>,
+}
+
+impl CellGrid {
+    /// Get the cell at the given column and row.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x >= self.width {
+            return None;
+        }
+        self.cells.get(y * self.width + x)
+    }
+}
+
+/// Convert Wezterm's colour type.
+const fn rgba_from_srgba(colour: wezterm_term::color::SrgbaTuple) -> Rgba {
+    (colour.0, colour.1, colour.2, colour.3)
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Render the terminal to a simple grid of cells, with all their colours resolved using the
+    /// terminal's current palette.
+    ///
+    /// # Errors
+    /// If the requested part of the terminal can't be found.
+    #[inline]
+    pub fn render_cells(
+        &mut self,
+        requested_kind: &crate::output::SurfaceKind,
+    ) -> Result<CellGrid, crate::errors::ShadowTerminalError> {
+        let tty_size = self.terminal.get_size();
+        let kind = requested_kind.clamp_to(&tty_size);
+        let (line_ids, output_start) = self.calculate_line_ids(&kind, None)?;
+        let (first_column, width) = match kind {
+            crate::output::SurfaceKind::Viewport { x, width, .. } => (x, width),
+            crate::output::SurfaceKind::Scrollback | crate::output::SurfaceKind::Screen => {
+                (0, tty_size.cols)
+            }
+        };
+        let height = line_ids.len();
+
+        let cursor = self.terminal.cursor_pos();
+        let cursor_y: usize = cursor
+            .y
+            .try_into()
+            .with_whatever_context(|err| format!("Couldn't convert cursor row: {err:?}"))?;
+        let cursor_line_id = self.terminal.screen().scrollback_rows() - tty_size.rows + cursor_y;
+        let grid_cursor = cursor
+            .x
+            .checked_sub(first_column)
+            .zip(cursor_line_id.checked_sub(output_start))
+            .filter(|(x, y)| *x < width && *y < height);
+
+        let palette = self.terminal.palette();
+        let blank = Cell {
+            text: " ".to_owned(),
+            width: 1,
+            foreground: rgba_from_srgba(
+                palette.resolve_fg(termwiz::color::ColorAttribute::Default),
+            ),
+            background: rgba_from_srgba(
+                palette.resolve_bg(termwiz::color::ColorAttribute::Default),
+            ),
+            flags: CellFlags::default(),
+        };
+
+        let mut cells = Vec::with_capacity(width * height);
+        let screen = self.terminal.screen_mut();
+        for line_id in line_ids {
+            let line = screen.line_mut(line_id);
+            let row_start = cells.len();
+            // `visible_cells()` reads compressed scrollback lines without decompressing them. The
+            // columns covered by wide graphemes are left blank.
+            for cell in line.visible_cells() {
+                let Some(x) = cell.cell_index().checked_sub(first_column) else {
+                    continue;
+                };
+                if x >= width {
+                    break;
+                }
+                cells.resize(row_start + x, blank.clone());
+
+                let attributes = cell.attrs();
+                let mut foreground = palette.resolve_fg(attributes.foreground());
+                let mut background = palette.resolve_bg(attributes.background());
+                if attributes.reverse() {
+                    std::mem::swap(&mut foreground, &mut background);
+                }
+                cells.push(Cell {
+                    text: cell.str().to_owned(),
+                    width: cell.width(),
+                    foreground: rgba_from_srgba(foreground),
+                    background: rgba_from_srgba(background),
+                    flags: CellFlags::from_attributes(attributes),
+                });
+            }
+            cells.resize(row_start + width, blank.clone());
+        }
+
+        Ok(CellGrid {
+            width,
+            height,
+            cells,
+            cursor: grid_cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::SurfaceKind;
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    use super::CellFlags;
+
+    #[test]
+    fn cells_have_resolved_colours() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 4,
+            height: 2,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        shadow_terminal
+            .terminal
+            .advance_bytes("\x1b[1;38;2;255;0;0mab\x1b[0m\x1b[7mc\x1b[0m");
+
+        let grid = shadow_terminal.render_cells(&SurfaceKind::Screen).unwrap();
+        assert_eq!((grid.width, grid.height, grid.cells.len()), (4, 2, 8));
+        assert_eq!(grid.cursor, Some((3, 0)));
+
+        let bold = grid.get(0, 0).unwrap();
+        assert_eq!(bold.text, "a");
+        assert_eq!(bold.foreground, (1.0, 0.0, 0.0, 1.0));
+        assert!(bold.flags.contains(CellFlags::BOLD));
+
+        let plain = grid.get(3, 1).unwrap();
+        let reversed = grid.get(2, 0).unwrap();
+        assert_eq!(reversed.foreground, plain.background);
+        assert_eq!(reversed.background, plain.foreground);
+        assert!(grid.get(4, 0).is_none());
+    }
+
+    #[test]
+    fn viewport_cells() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 4,
+            height: 2,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        shadow_terminal.terminal.advance_bytes("abcd\r\nefgh");
+
+        let grid = shadow_terminal
+            .render_cells(&SurfaceKind::Viewport {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 1,
+            })
+            .unwrap();
+        let text: Vec<_> = grid.cells.iter().map(|cell| cell.text.as_str()).collect();
+        assert_eq!(text, vec!["f", "g"]);
+        assert_eq!(grid.cursor, None);
+    }
+}
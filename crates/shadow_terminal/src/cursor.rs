@@ -0,0 +1,141 @@
+//! Cursor blinking.
+//!
+//! The shadow terminal isn't rendered, so it can't actually blink a cursor. But anything that
+//! draws its own cursor, or effects that want to be in sync with the cursor, need to know when the
+//! cursor would be visible. So the shadow terminal keeps track of the blink phase, and broadcasts
+//! `Protocol::CursorBlink` whenever it changes.
+//!
+//! Only the blinking cursor shapes that applications set with DECSCUSR (`CSI Ps SP q`) blink. The
+//! default cursor shape is steady, just like Wezterm's. Like most terminals, the blink restarts,
+//! with the cursor visible, whenever there's new output.
+
+/// The default time, in milliseconds, between each change of the cursor's blink phase.
+pub(crate) const DEFAULT_CURSOR_BLINK_INTERVAL: u64 = 500;
+
+/// The state of the cursor's blink.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct CursorBlink {
+    /// When the current blink cycle started, the cursor is always visible at the start.
+    pub since: tokio::time::Instant,
+    /// The last blink phase that was broadcast.
+    pub is_visible: bool,
+}
+
+impl Default for CursorBlink {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            since: tokio::time::Instant::now(),
+            is_visible: true,
+        }
+    }
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Whether the cursor is in the visible phase of its blink. It's always `true` when the
+    /// cursor doesn't blink. Note that this says nothing about whether the application has hidden
+    /// the cursor.
+    #[inline]
+    #[must_use]
+    pub fn cursor_blink_phase(&self) -> bool {
+        let Some(interval) = self.cursor_blink_interval() else {
+            return true;
+        };
+
+        let elapsed = self.cursor_blink.since.elapsed().as_millis();
+        elapsed
+            .div_euclid(interval.as_millis().max(1))
+            .rem_euclid(2)
+            == 0
+    }
+
+    /// The blink interval, but only if the cursor is currently blinking.
+    fn cursor_blink_interval(&self) -> Option<tokio::time::Duration> {
+        let interval = self.config.cursor_blink_interval?;
+        let cursor = self.terminal.cursor_pos();
+        let is_blinking = cursor.shape.is_blinking()
+            && cursor.visibility == termwiz::surface::CursorVisibility::Visible;
+
+        is_blinking.then_some(interval)
+    }
+
+    /// When the blink phase next changes, if the cursor is blinking.
+    pub(crate) fn next_cursor_blink(&self) -> Option<tokio::time::Instant> {
+        let interval = self.cursor_blink_interval()?;
+        let elapsed = self.cursor_blink.since.elapsed().as_millis();
+        let interval_millis = interval.as_millis().max(1);
+        let next_change = (elapsed.div_euclid(interval_millis) + 1) * interval_millis;
+
+        Some(
+            self.cursor_blink.since
+                + tokio::time::Duration::from_millis(
+                    u64::try_from(next_change).unwrap_or(u64::MAX),
+                ),
+        )
+    }
+
+    /// Restart the blink cycle, so that the cursor is immediately visible. This happens whenever
+    /// there's new output.
+    pub(crate) fn restart_cursor_blink(&mut self) {
+        self.cursor_blink.since = tokio::time::Instant::now();
+        self.broadcast_cursor_blink();
+    }
+
+    /// Broadcast the current blink phase, if it's changed.
+    pub(crate) fn broadcast_cursor_blink(&mut self) {
+        let is_visible = self.cursor_blink_phase();
+        if is_visible == self.cursor_blink.is_visible {
+            return;
+        }
+
+        self.cursor_blink.is_visible = is_visible;
+        let result = self
+            .channels
+            .control_tx
+            .send(crate::Protocol::CursorBlink(is_visible));
+        if let Err(error) = result {
+            tracing::error!("Couldn't broadcast cursor blink: {error:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    #[test]
+    fn only_blinking_cursor_shapes_blink() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let mut shadow_terminal = ShadowTerminal::new(Config::default(), shadow_output_tx);
+        assert!(shadow_terminal.next_cursor_blink().is_none());
+
+        shadow_terminal.terminal.advance_bytes("\x1b[1 q");
+        assert!(shadow_terminal.cursor_blink_phase());
+        let next_blink = shadow_terminal.next_cursor_blink().unwrap();
+        assert_eq!(
+            next_blink - shadow_terminal.cursor_blink.since,
+            tokio::time::Duration::from_millis(super::DEFAULT_CURSOR_BLINK_INTERVAL)
+        );
+
+        shadow_terminal.terminal.advance_bytes("\x1b[?25l");
+        assert!(shadow_terminal.next_cursor_blink().is_none());
+
+        shadow_terminal.terminal.advance_bytes("\x1b[?25h\x1b[2 q");
+        assert!(shadow_terminal.next_cursor_blink().is_none());
+        assert!(shadow_terminal.cursor_blink_phase());
+    }
+
+    #[test]
+    fn blinking_can_be_disabled() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            cursor_blink_interval: None,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        shadow_terminal.terminal.advance_bytes("\x1b[5 q");
+        assert!(shadow_terminal.next_cursor_blink().is_none());
+        assert!(shadow_terminal.cursor_blink_phase());
+    }
+}
@@ -31,7 +31,29 @@ pub enum ShadowTerminalError {
 }
 
 #[derive(Debug, snafu::Snafu)]
+#[snafu(visibility(pub(crate)))]
 pub enum PTYError {
+    /// The PTY's command couldn't be found, either at its path or anywhere on `$PATH`.
+    #[snafu(display("PTY command not found: {command}"))]
+    CommandNotFound {
+        /// The command that was looked for
+        command: String,
+    },
+
+    /// The PTY's command was found, but isn't executable.
+    #[snafu(display("Permission denied running PTY command: {command}"))]
+    PermissionDenied {
+        /// The command that couldn't be run
+        command: String,
+    },
+
+    /// The OS couldn't allocate a new PTY.
+    #[snafu(display("Couldn't allocate a PTY: {message}"))]
+    PTYAllocation {
+        /// The reason the allocation failed
+        message: String,
+    },
+
     /// General errors that don't need to be matched on
     #[snafu(whatever, display("{message}"))]
     Whatever {
@@ -0,0 +1,46 @@
+//! Structured key input.
+//!
+//! Raw STDIN bytes can't always describe a key press precisely, for example `Ctrl+I` and `Tab`
+//! are both sent as `\t` in the legacy encoding. Modern TUIs can request an enhanced keyboard
+//! protocol, like Kitty's or CSI-u, which can tell the difference. So key events are sent to the
+//! Wezterm terminal, which knows which keyboard modes the running application has enabled, and
+//! it encodes them accordingly. When no enhanced mode is enabled the legacy encoding is used.
+
+use snafu::ResultExt as _;
+
+/// Whether a key was pressed or released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyEventKind {
+    /// The key was pressed. This also covers auto-repeat.
+    Press,
+    /// The key was released. This only produces bytes when the application has asked for
+    /// release events with the Kitty keyboard protocol.
+    Release,
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Encode a key event according to the keyboard modes currently enabled in the shadow
+    /// terminal, then send the resulting bytes to the PTY.
+    ///
+    /// # Errors
+    /// If the key can't be encoded or the bytes can't be sent to the PTY.
+    #[inline]
+    pub async fn send_key(
+        &mut self,
+        code: wezterm_term::KeyCode,
+        mods: wezterm_term::KeyModifiers,
+        event_kind: KeyEventKind,
+    ) -> Result<(), crate::errors::ShadowTerminalError> {
+        // Any responses to queries that haven't been forwarded yet are forwarded along with the
+        // key, in the order that they were written, rather than being lost.
+        match event_kind {
+            KeyEventKind::Press => self.terminal.key_down(code, mods),
+            KeyEventKind::Release => self.terminal.key_up(code, mods),
+        }
+        .with_whatever_context(|error| format!("Couldn't encode key {code:?}: {error:?}"))?;
+
+        tracing::trace!("Sending encoded key {code:?} ({mods:?}) to PTY");
+        self.forward_terminal_writer_output().await
+    }
+}
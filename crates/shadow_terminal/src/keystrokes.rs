@@ -0,0 +1,181 @@
+//! Keystroke events, for typewriter sounds and other effects that react to characters appearing.
+//!
+//! The printable characters in the PTY's output are counted as the bytes are advanced into the
+//! terminal, then broadcast as `Protocol::KeyOutput`. Escape sequences and control characters
+//! aren't counted. The sequences are recognised by the same parser that `sanitise.rs` uses. It
+//! doesn't matter how the output was split into payloads, because only the parser's state is
+//! kept between them.
+//!
+//! The broadcasts are debounced, so that a big burst of output, like `cat`ing a file, is a single
+//! event with a big count, rather than thousands of events.
+
+/// The shortest time between each `Protocol::KeyOutput` broadcast.
+pub(crate) const KEY_OUTPUT_DEBOUNCE: tokio::time::Duration =
+    tokio::time::Duration::from_millis(50);
+
+/// Counts the printable characters in a stream of PTY output.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct PrintableCounter {
+    /// The parser carried over from the previous payload.
+    parser: crate::sanitise::Parser,
+}
+
+impl PrintableCounter {
+    /// Count the printable characters in a payload of PTY output. A character that's made of
+    /// many UTF-8 bytes is only counted once.
+    #[inline]
+    pub fn count(&mut self, bytes: &[u8]) -> usize {
+        let mut count: usize = 0;
+        for byte in bytes {
+            let token = self.parser.advance(*byte);
+            if token == crate::sanitise::Token::Ground && is_printable(*byte) {
+                count = count.saturating_add(1);
+            }
+        }
+        count
+    }
+}
+
+/// Whether a byte of normal output starts a printable character.
+const fn is_printable(byte: u8) -> bool {
+    let is_control = byte < 0x20 || byte == 0x7f;
+    let is_utf8_continuation = matches!(byte, 0x80..=0xbf);
+    !is_control && !is_utf8_continuation
+}
+
+/// The keystrokes that are waiting to be broadcast.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct KeyOutput {
+    /// Counts the printable characters in the PTY's output.
+    pub counter: PrintableCounter,
+    /// The number of printable characters since the last broadcast.
+    pub pending: usize,
+    /// When the last broadcast was sent.
+    pub last_sent: Option<tokio::time::Instant>,
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Count the printable characters in PTY output that's being advanced into the terminal, and
+    /// broadcast them straight away if a broadcast is due.
+    pub(crate) fn count_key_output(&mut self, bytes: &[u8]) {
+        if !self.config.emit_key_output {
+            return;
+        }
+
+        let count = self.key_output.counter.count(bytes);
+        self.key_output.pending = self.key_output.pending.saturating_add(count);
+        self.broadcast_key_output();
+    }
+
+    /// When the pending keystrokes can next be broadcast, if there are any.
+    pub(crate) fn next_key_output(&self) -> Option<tokio::time::Instant> {
+        if self.key_output.pending == 0 {
+            return None;
+        }
+
+        Some(
+            self.key_output
+                .last_sent
+                .map_or_else(tokio::time::Instant::now, |last_sent| {
+                    last_sent + KEY_OUTPUT_DEBOUNCE
+                }),
+        )
+    }
+
+    /// Broadcast the pending keystrokes, unless the previous broadcast was too recent.
+    pub(crate) fn broadcast_key_output(&mut self) {
+        let Some(due) = self.next_key_output() else {
+            return;
+        };
+        let now = tokio::time::Instant::now();
+        if due > now {
+            return;
+        }
+
+        let count = std::mem::take(&mut self.key_output.pending);
+        self.key_output.last_sent = Some(now);
+        let result = self
+            .channels
+            .control_tx
+            .send(crate::Protocol::KeyOutput(count));
+        if let Err(error) = result {
+            tracing::error!("Couldn't broadcast key output: {error:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    #[test]
+    fn only_printable_characters_are_counted() {
+        let mut counter = PrintableCounter::default();
+        assert_eq!(counter.count(b"abc\r\n"), 3);
+        assert_eq!(counter.count("héllo 🦀".as_bytes()), 7);
+        assert_eq!(counter.count(b"\x1b[1;31mred\x1b[0m"), 3);
+        assert_eq!(counter.count(b"\x1b]0;title\x07\x1b]0;title\x1b\\ok"), 2);
+        assert_eq!(counter.count(b"\x1b(Bx\x1b7y"), 2);
+    }
+
+    #[test]
+    fn sequences_can_be_split_between_payloads() {
+        let mut counter = PrintableCounter::default();
+        assert_eq!(counter.count(b"a\x1b"), 1);
+        assert_eq!(counter.count(b"[38;5;"), 0);
+        assert_eq!(counter.count(b"196mb\x1b]2;ti"), 1);
+        assert_eq!(counter.count(b"tle\x1b"), 0);
+        assert_eq!(counter.count(b"\\c"), 1);
+    }
+
+    #[test]
+    fn key_output_is_debounced() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            emit_key_output: true,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        let mut control_rx = shadow_terminal.channels.control_tx.subscribe();
+
+        shadow_terminal.count_key_output(b"a");
+        assert!(matches!(
+            control_rx.try_recv(),
+            Ok(crate::Protocol::KeyOutput(1))
+        ));
+
+        shadow_terminal.count_key_output(b"bc");
+        shadow_terminal.count_key_output(b"d");
+        assert!(control_rx.try_recv().is_err());
+        assert_eq!(
+            shadow_terminal.next_key_output(),
+            shadow_terminal
+                .key_output
+                .last_sent
+                .map(|last_sent| last_sent + KEY_OUTPUT_DEBOUNCE)
+        );
+
+        // Pretend that the previous broadcast was a whole debounce ago.
+        shadow_terminal.key_output.last_sent = shadow_terminal
+            .key_output
+            .last_sent
+            .and_then(|last_sent| last_sent.checked_sub(KEY_OUTPUT_DEBOUNCE));
+        shadow_terminal.broadcast_key_output();
+        assert!(matches!(
+            control_rx.try_recv(),
+            Ok(crate::Protocol::KeyOutput(3))
+        ));
+        assert!(shadow_terminal.next_key_output().is_none());
+    }
+
+    #[test]
+    fn key_output_is_off_by_default() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let mut shadow_terminal = ShadowTerminal::new(Config::default(), shadow_output_tx);
+        shadow_terminal.count_key_output(b"abc");
+        assert!(shadow_terminal.next_key_output().is_none());
+    }
+}
@@ -21,11 +21,23 @@
 pub use wezterm_term;
 
 pub mod active_terminal;
+pub mod cells;
+pub mod cursor;
 mod errors;
+pub mod keys;
+pub mod keystrokes;
+pub mod lines;
 pub mod output;
 mod pty;
+pub mod sanitise;
+mod scrollback;
+pub mod sgr;
 pub mod shadow_terminal;
+pub mod state;
 pub mod steppable_terminal;
+pub mod stream;
+pub mod surface_diff;
+pub mod working_directory;
 
 /// asdasdad
 pub mod tests {
@@ -38,6 +50,9 @@ pub mod tests {
 pub enum Protocol {
     /// End all loops to allow graceful shutdown
     End,
+    /// The PTY has no more output, normally because its child process exited. `End` is always
+    /// sent soon afterwards.
+    Exited,
     /// Resize the PTY and shadow terminal
     Resize {
         /// Width of the shadow terminal
@@ -47,6 +62,77 @@ pub enum Protocol {
     },
     /// Scrolling of the terminal scrollback
     Scroll(Scroll),
+    /// A complete logical line of output that can no longer be changed by the PTY. Soft-wrapped
+    /// lines are joined. Only sent when enabled in the config.
+    OutputLine {
+        /// The plain text of the line, without any trailing whitespace.
+        text: String,
+        /// The stable row index of the start of the line.
+        row: wezterm_term::StableRowIndex,
+    },
+    /// A structured key event. It's encoded according to whichever keyboard protocol the running
+    /// application has enabled, falling back to the legacy encoding.
+    Key {
+        /// The key
+        code: wezterm_term::KeyCode,
+        /// Any modifiers held down, like `Ctrl` or `Alt`.
+        mods: wezterm_term::KeyModifiers,
+        /// Whether the key was pressed or released.
+        event_kind: keys::KeyEventKind,
+    },
+    /// The terminal gained (`true`) or lost (`false`) focus. Only forwarded to the PTY when the
+    /// running application has enabled focus reporting.
+    Focus(bool),
+    /// Send an OS signal to the PTY's foreground process group. Only supported on Unix.
+    Signal(Signal),
+    /// The blinking cursor became visible (`true`) or invisible (`false`). Only sent when the
+    /// running application has set a blinking cursor shape.
+    CursorBlink(bool),
+    /// Printable characters were output by the PTY, with how many there were since the last
+    /// `KeyOutput`. Useful for keystroke sounds and effects. It's debounced, and only sent when
+    /// enabled in the config.
+    KeyOutput(usize),
+    /// Whether new output keeps the view pinned to the bottom of the scrollback (`true`), or
+    /// leaves the view where it is (`false`). Enabling it also ends any scrolling.
+    FollowTail(bool),
+    /// The running application rang the terminal's bell, normally by printing `BEL`.
+    Bell,
+    /// The terminal's title changed, either because the running application set it with an OSC
+    /// sequence, or because it was set with [`crate::shadow_terminal::ShadowTerminal::set_title`].
+    Title(String),
+    /// The running application reported its working directory, with OSC 7. Only sent when it
+    /// changes, and when enabled in the config.
+    WorkingDirectory(working_directory::WorkingDirectory),
+    /// The PTY's application crashed, and is about to be restarted, because there's a restart
+    /// policy in the config. It's a good time to let the user know that it's reconnecting. The
+    /// scrollback is kept, so the restarted application's output just carries on after it.
+    Restarting {
+        /// The number of restarts so far, including this one.
+        attempt: u32,
+        /// How long until the application is restarted.
+        delay: tokio::time::Duration,
+    },
+    /// Send a complete screen and scrollback straight away, rather than diffs. Consumers that
+    /// build up their own copy of the terminal from diffs should request this whenever their copy
+    /// might be out of sync. For example, after reconnecting, after missing messages because
+    /// their channel lagged, or after resizing their copy without the shadow terminal knowing.
+    RequestFullRedraw,
+}
+
+/// The OS signals that can be sent to the PTY.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Signal {
+    /// `SIGINT`, like `Ctrl+C`.
+    Interrupt,
+    /// `SIGTERM`, for a clean shutdown.
+    Terminate,
+    /// `SIGTSTP`, like `Ctrl+Z`.
+    Suspend,
+    /// `SIGHUP`, as if the terminal was closed.
+    Hangup,
+    /// `SIGQUIT`, like `Ctrl+\`.
+    Quit,
 }
 
 /// The various states of scrolling
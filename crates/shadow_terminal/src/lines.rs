@@ -0,0 +1,178 @@
+//! Complete logical lines of terminal output.
+//!
+//! A line is considered "finalised" once it has scrolled out of the active region of the screen,
+//! because then it can no longer be changed by the PTY. Soft-wrapped lines are joined back together
+//! so that consumers see the same line that the application originally printed.
+
+/// Keeps track of which lines have already been emitted.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct FinalisedLines {
+    /// The first stable row that hasn't been checked for finalisation yet.
+    pub next_row: wezterm_term::StableRowIndex,
+    /// The text of a soft-wrapped logical line that has only been partially finalised.
+    pub pending_text: String,
+    /// The stable row at which the pending logical line started.
+    pub pending_row: Option<wezterm_term::StableRowIndex>,
+}
+
+/// The text of a single physical line of the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LineText {
+    /// The plain text of the line, including any trailing whitespace.
+    pub text: String,
+    /// Whether the line is soft-wrapped onto the next line.
+    pub is_wrapped: bool,
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Lazily iterate over the text of every physical line in the scrollback, oldest first. This
+    /// includes the lines of the screen. It's a cheap alternative to building a whole surface
+    /// when only the text is needed.
+    #[inline]
+    pub fn scrollback_lines(&mut self) -> impl Iterator<Item = LineText> + '_ {
+        let cols = self.terminal.get_size().cols;
+        let screen = self.terminal.screen_mut();
+        (0..screen.scrollback_rows()).map(move |physical_row| {
+            let line = screen.line_mut(physical_row);
+            LineText {
+                text: visible_text(line, cols).into_owned(),
+                is_wrapped: line.last_cell_was_wrapped(),
+            }
+        })
+    }
+
+    /// Export the visible screen as plain text, one logical line per line of text.
+    ///
+    /// Tabs aren't stored in the terminal, they just move the cursor to the next tab stop. So
+    /// the cells that a tab skipped over are exported as spaces, which keeps the text aligned to
+    /// the same tab stops that the application used, whether they're the defaults or custom ones.
+    ///
+    /// Only the visible half of double width lines (DECDWL/DECDHL) is exported.
+    #[inline]
+    pub fn to_text(&mut self) -> String {
+        let rows = self.terminal.get_size().rows;
+        let cols = self.terminal.get_size().cols;
+        let screen = self.terminal.screen_mut();
+        let first_row = screen.visible_row_to_stable_row(0);
+
+        let mut lines = Vec::new();
+        let mut logical_line = String::new();
+        let last_row = first_row + wezterm_term::StableRowIndex::try_from(rows).unwrap_or(0);
+        for row in first_row..last_row {
+            let Some(physical_row) = screen.stable_row_to_phys(row) else {
+                continue;
+            };
+            let line = screen.line_mut(physical_row);
+            let text = visible_text(line, cols);
+            if line.last_cell_was_wrapped() {
+                logical_line.push_str(&text);
+                continue;
+            }
+            logical_line.push_str(text.trim_end());
+            lines.push(std::mem::take(&mut logical_line));
+        }
+        if !logical_line.is_empty() {
+            lines.push(logical_line);
+        }
+
+        lines.join("\n")
+    }
+
+    /// Broadcast any lines that have been finalised since we last checked.
+    pub(crate) fn emit_finalised_lines(&mut self) {
+        if !self.config.emit_output_lines || self.terminal.is_alt_screen_active() {
+            return;
+        }
+
+        let cols = self.terminal.get_size().cols;
+        let screen = self.terminal.screen_mut();
+        let first_active_row = screen.visible_row_to_stable_row(0);
+
+        for row in self.finalised_lines.next_row..first_active_row {
+            // The row may have already been trimmed from the scrollback.
+            let Some(physical_row) = screen.stable_row_to_phys(row) else {
+                continue;
+            };
+            let line = screen.line_mut(physical_row);
+            let is_wrapped = line.last_cell_was_wrapped();
+            let text = visible_text(line, cols).into_owned();
+
+            let pending = &mut self.finalised_lines;
+            if pending.pending_row.is_none() {
+                pending.pending_row = Some(row);
+            }
+
+            if is_wrapped {
+                pending.pending_text.push_str(&text);
+                continue;
+            }
+
+            pending.pending_text.push_str(text.trim_end());
+            let line_text = std::mem::take(&mut pending.pending_text);
+            let line_row = pending.pending_row.take().unwrap_or(row);
+
+            tracing::trace!("Emitting finalised output line {line_row}: {line_text:.100}");
+            let result = self.channels.control_tx.send(crate::Protocol::OutputLine {
+                text: line_text,
+                row: line_row,
+            });
+            if let Err(error) = result {
+                tracing::error!("Couldn't broadcast finalised output line: {error:?}");
+            }
+        }
+
+        self.finalised_lines.next_row = self.finalised_lines.next_row.max(first_active_row);
+    }
+}
+
+/// The text of a line that can actually be seen, taking double width lines into account.
+fn visible_text(line: &wezterm_term::Line, width: usize) -> std::borrow::Cow<'_, str> {
+    let attribute = crate::output::LineAttribute::from_line(line);
+    if !attribute.is_double_width() {
+        return line.as_str();
+    }
+
+    let visible_columns = attribute.visible_columns(width);
+    line.visible_cells()
+        .take_while(|cell| cell.cell_index() < visible_columns)
+        .map(|cell| cell.str())
+        .collect::<String>()
+        .into()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    #[test]
+    fn scrollback_lines_with_wrap_flags() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 5,
+            height: 3,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        shadow_terminal
+            .terminal
+            .advance_bytes("one\r\ntwo\r\nwrapped\r\nfour\r\nfive");
+
+        let lines: Vec<(String, bool)> = shadow_terminal
+            .scrollback_lines()
+            .map(|line| (line.text.trim_end().to_owned(), line.is_wrapped))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("one".to_owned(), false),
+                ("two".to_owned(), false),
+                ("wrapp".to_owned(), true),
+                ("ed".to_owned(), false),
+                ("four".to_owned(), false),
+                ("five".to_owned(), false),
+            ]
+        );
+    }
+}
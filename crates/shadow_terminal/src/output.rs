@@ -20,6 +20,58 @@ pub enum ScreenMode {
     Alternate,
 }
 
+/// The DEC line attributes, that make every cell in a line render at double width, and optionally
+/// double height. They're rare, but are used by retro apps and by banner tools like `toilet`.
+///
+/// Only the first half of the columns of a double width line are visible. For double height lines
+/// the same text is normally sent twice, once on a top half line and once on a bottom half line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LineAttribute {
+    /// A normal line.
+    #[default]
+    SingleWidth,
+    /// Double width (DECDWL).
+    DoubleWidth,
+    /// The top half of a double height, and double width, line (DECDHL).
+    DoubleHeightTop,
+    /// The bottom half of a double height, and double width, line (DECDHL).
+    DoubleHeightBottom,
+}
+
+impl LineAttribute {
+    /// Get the attribute of a Wezterm line.
+    pub(crate) fn from_line(line: &wezterm_term::Line) -> Self {
+        if line.is_double_height_top() {
+            Self::DoubleHeightTop
+        } else if line.is_double_height_bottom() {
+            Self::DoubleHeightBottom
+        } else if line.is_double_width() {
+            Self::DoubleWidth
+        } else {
+            Self::SingleWidth
+        }
+    }
+
+    /// Is every cell in the line rendered twice as wide as normal?
+    #[inline]
+    #[must_use]
+    pub const fn is_double_width(self) -> bool {
+        !matches!(self, Self::SingleWidth)
+    }
+
+    /// The number of columns that can actually be seen in a line of the given terminal width.
+    #[inline]
+    #[must_use]
+    pub const fn visible_columns(self, width: usize) -> usize {
+        if self.is_double_width() {
+            width.div_euclid(2)
+        } else {
+            width
+        }
+    }
+}
+
 /// Hopefully the most common form of output, therefore a small diff of changes.
 #[derive(Clone)]
 #[non_exhaustive]
@@ -48,6 +100,8 @@ pub struct ScrollbackDiff {
     pub position: usize,
     /// The size of the current scrollback. Can increase up to the configured maximum.
     pub height: usize,
+    /// The line attributes of every line in this diff, by row.
+    pub line_attributes: Vec<(usize, LineAttribute)>,
 }
 
 /// The constant view into the terminal, regardless of whether it's in primary or alternate screen.
@@ -68,6 +122,8 @@ pub struct ScreenDiff {
     pub size: (usize, usize),
     /// All the details about the user's cursor.
     pub cursor: wezterm_term::CursorPosition,
+    /// The line attributes of every line in this diff, by row.
+    pub line_attributes: Vec<(usize, LineAttribute)>,
 }
 
 impl std::fmt::Debug for SurfaceDiff {
@@ -100,6 +156,8 @@ pub enum CompleteSurface {
     /// The current view of the terminal, regardless of whether it's the primary or alternate
     /// screen.
     Screen(CompleteScreen),
+    /// Just a rectangle of the current view of the terminal.
+    Viewport(CompleteViewport),
 }
 
 impl std::fmt::Debug for CompleteSurface {
@@ -109,6 +167,7 @@ impl std::fmt::Debug for CompleteSurface {
         let info = match self {
             Self::Scrollback(scrollback) => ("scrollback", &scrollback.surface),
             Self::Screen(screen) => ("screen", &screen.surface),
+            Self::Viewport(viewport) => ("viewport", &viewport.surface),
         };
         write!(
             f,
@@ -129,6 +188,8 @@ pub struct CompleteScrollback {
     pub surface: termwiz::surface::Surface,
     /// The position of the current scroll, so it would be 0, if the user is not scrolling.
     pub position: usize,
+    /// The rows of any lines that aren't single width. All other lines are single width.
+    pub line_attributes: Vec<(usize, LineAttribute)>,
 }
 
 /// Every cell in the current sreen, and the screen's mode.
@@ -139,6 +200,28 @@ pub struct CompleteScreen {
     pub surface: termwiz::surface::Surface,
     /// Whether the terminal is in primary or alternate mode.
     pub mode: ScreenMode,
+    /// The rows of any lines that aren't single width. All other lines are single width.
+    pub line_attributes: Vec<(usize, LineAttribute)>,
+    /// The rows that have changed since the previous output was sent. Every row has changed when
+    /// the screen has been resized, scrolled or fully redrawn.
+    pub changed_rows: Vec<usize>,
+}
+
+/// Every cell in a rectangle of the current screen. Useful when the shadow terminal is only partly
+/// visible, like in an embedded pane, so that off-screen cells don't need to be built and sent.
+#[derive(Default, Clone)]
+#[non_exhaustive]
+pub struct CompleteViewport {
+    /// The `termwiz` surface data. Its size is the requested size, clamped to the screen.
+    pub surface: termwiz::surface::Surface,
+    /// Whether the terminal is in primary or alternate mode.
+    pub mode: ScreenMode,
+    /// The column and row of the screen at which the viewport starts.
+    pub origin: (usize, usize),
+    /// The position of the cursor relative to the viewport, or `None` if it's outside of it.
+    pub cursor: Option<(usize, usize)>,
+    /// The rows of any lines that aren't single width. All other lines are single width.
+    pub line_attributes: Vec<(usize, LineAttribute)>,
 }
 
 #[derive(Clone, Debug)]
@@ -150,6 +233,21 @@ pub enum Output {
     /// In certain cases, it's likely more efficient to just send all the cell data for the
     /// terminal. Or perhaps it's useful in moments of recovery or reset.
     Complete(CompleteSurface),
+    /// The screen and its matching scrollback, together. Only sent, instead of sending them
+    /// separately, when [`crate::shadow_terminal::Config::emit_frames`] is enabled.
+    Frame(Frame),
+}
+
+/// The screen and the scrollback from the same update of the shadow terminal. Receiving one means
+/// that the update is complete, so both can be applied at once, avoiding the flicker of rendering
+/// a screen before its matching scrollback has arrived.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Frame {
+    /// The screen's output.
+    pub screen: Box<Output>,
+    /// The scrollback's output. There isn't one whilst the alternate screen is active.
+    pub scrollback: Option<Box<Output>>,
 }
 
 /// The kinds of surfaces that can be output.
@@ -161,6 +259,43 @@ pub enum SurfaceKind {
     /// The current view of the terminal, regardless of whether it's the primary or alternate
     /// screen.
     Screen,
+    /// A rectangle of the current view of the terminal. It's clamped to the screen. Viewports are
+    /// always built as complete surfaces.
+    Viewport {
+        /// The first column of the viewport.
+        x: usize,
+        /// The first row of the viewport.
+        y: usize,
+        /// The number of columns in the viewport.
+        width: usize,
+        /// The number of rows in the viewport.
+        height: usize,
+    },
+}
+
+impl SurfaceKind {
+    /// Make sure that a viewport fits inside a screen of the given size.
+    pub(crate) fn clamp_to(&self, tty_size: &wezterm_term::TerminalSize) -> Self {
+        match *self {
+            Self::Scrollback => Self::Scrollback,
+            Self::Screen => Self::Screen,
+            Self::Viewport {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let clamped_x = x.min(tty_size.cols);
+                let clamped_y = y.min(tty_size.rows);
+                Self::Viewport {
+                    x: clamped_x,
+                    y: clamped_y,
+                    width: width.min(tty_size.cols - clamped_x),
+                    height: height.min(tty_size.rows - clamped_y),
+                }
+            }
+        }
+    }
 }
 
 impl Default for SurfaceDiff {
@@ -171,14 +306,21 @@ impl Default for SurfaceDiff {
 }
 
 impl crate::shadow_terminal::ShadowTerminal {
-    /// Build output for broadcasting to end users.
-    pub(crate) fn build_current_output(
+    /// Build output for broadcasting to end users. It can also be called directly, for example to
+    /// build a viewport of just the part of the screen that's visible.
+    ///
+    /// # Errors
+    /// If the Wezterm terminal's rows can't be converted into output.
+    #[inline]
+    pub fn build_current_output(
         &mut self,
-        kind: &SurfaceKind,
+        requested_kind: &SurfaceKind,
     ) -> Result<Output, crate::errors::ShadowTerminalError> {
         tracing::trace!("Converting Wezterm terminal state to a `termwiz::surface::Surface`");
 
         let tty_size = self.terminal.get_size();
+        let clamped_kind = requested_kind.clamp_to(&tty_size);
+        let kind = &clamped_kind;
         let total_lines = self.terminal.screen().scrollback_rows();
         let changed_line_ids = self.terminal.screen().get_changed_stable_rows(
             0..total_lines.try_into().with_whatever_context(|err| {
@@ -191,16 +333,19 @@ impl crate::shadow_terminal::ShadowTerminal {
         let is_diff_efficient = match kind {
             SurfaceKind::Scrollback => changed_line_ids.len() < total_lines.div_euclid(2),
             SurfaceKind::Screen => changed_line_ids.len() < tty_size.rows,
+            SurfaceKind::Viewport { .. } => false,
         };
 
-        let is_building_screen = matches!(kind, SurfaceKind::Screen);
+        let is_building_screen = matches!(kind, SurfaceKind::Screen | SurfaceKind::Viewport { .. });
         let is_resized = self.last_sent.pty_size != (tty_size.cols, tty_size.rows);
-        let is_diff_possible = !is_resized && !is_building_screen;
+        let is_diff_possible = !is_resized && !is_building_screen && !self.is_full_redraw_requested;
 
         let output = if is_diff_efficient && is_diff_possible {
             self.build_diff(kind, changed_line_ids, tty_size, total_lines)?
         } else {
-            self.build_complete_surface(kind, tty_size, total_lines)?
+            let is_everything_changed = is_resized || self.is_full_redraw_requested;
+            let changed_rows = self.changed_screen_rows(&changed_line_ids, is_everything_changed);
+            self.build_complete_surface(kind, tty_size, total_lines, changed_rows)?
         };
 
         Ok(output)
@@ -215,6 +360,36 @@ impl crate::shadow_terminal::ShadowTerminal {
         }
     }
 
+    /// The stable row at the top of the screen, as it's currently scrolled.
+    pub(crate) fn screen_top(&self) -> wezterm_term::StableRowIndex {
+        let screen = self.terminal.screen();
+        let bottom = screen
+            .scrollback_rows()
+            .saturating_sub(self.scroll_position);
+        let top = bottom.saturating_sub(self.terminal.get_size().rows);
+        screen.phys_to_stable_row_index(top)
+    }
+
+    /// Convert the changed stable rows into rows of the screen. Rows that aren't on the screen are
+    /// ignored. If the top of the screen has moved then every row of the screen has changed.
+    fn changed_screen_rows(
+        &self,
+        changed_line_ids: &[wezterm_term::StableRowIndex],
+        is_everything_changed: bool,
+    ) -> Vec<usize> {
+        let rows = self.terminal.get_size().rows;
+        let top = self.screen_top();
+        if is_everything_changed || top != self.last_sent.screen_top {
+            return (0..rows).collect();
+        }
+
+        changed_line_ids
+            .iter()
+            .filter_map(|line_id| usize::try_from(line_id - top).ok())
+            .filter(|row| *row < rows)
+            .collect()
+    }
+
     /// Build a diff of the changes from the PTY
     fn build_diff(
         &mut self,
@@ -225,20 +400,25 @@ impl crate::shadow_terminal::ShadowTerminal {
     ) -> Result<Output, crate::errors::ShadowTerminalError> {
         tracing::trace!("Building diff from Wezterm for {kind:?} from lines: {changed_line_ids:?}");
 
-        let changes = self.generate_changes(kind, Some(changed_line_ids))?;
+        let (changes, line_attributes) = self.generate_changes(kind, Some(changed_line_ids))?;
         let diff = match kind {
             SurfaceKind::Scrollback => SurfaceDiff::Scrollback(ScrollbackDiff {
                 changes,
                 size: (tty_size.cols, tty_size.rows),
                 position: self.scroll_position,
                 height: total_lines,
+                line_attributes,
             }),
             SurfaceKind::Screen => SurfaceDiff::Screen(ScreenDiff {
                 mode: self.get_screen_mode(),
                 changes,
                 size: (tty_size.cols, tty_size.rows),
                 cursor: self.terminal.cursor_pos(),
+                line_attributes,
             }),
+            SurfaceKind::Viewport { .. } => {
+                snafu::whatever!("Viewports are always built as complete surfaces")
+            }
         };
         Ok(Output::Diff(diff))
     }
@@ -249,12 +429,14 @@ impl crate::shadow_terminal::ShadowTerminal {
         kind: &SurfaceKind,
         tty_size: wezterm_term::TerminalSize,
         total_lines: usize,
+        changed_rows: Vec<usize>,
     ) -> Result<Output, crate::errors::ShadowTerminalError> {
         tracing::trace!(
             "Building surface or diff from Wezterm for {kind:?} from lines: 0 to {total_lines:?}"
         );
 
-        let changes = self.generate_changes(kind, None)?;
+        let (changes, mut line_attributes) = self.generate_changes(kind, None)?;
+        line_attributes.retain(|(_, attribute)| attribute.is_double_width());
         let complete_surface = match kind {
             SurfaceKind::Scrollback => {
                 let changes_count = changes.len();
@@ -268,6 +450,7 @@ impl crate::shadow_terminal::ShadowTerminal {
                 CompleteSurface::Scrollback(CompleteScrollback {
                     surface,
                     position: self.scroll_position,
+                    line_attributes,
                 })
             }
             SurfaceKind::Screen => {
@@ -282,6 +465,33 @@ impl crate::shadow_terminal::ShadowTerminal {
                 CompleteSurface::Screen(CompleteScreen {
                     surface,
                     mode: self.get_screen_mode(),
+                    line_attributes,
+                    changed_rows,
+                })
+            }
+            SurfaceKind::Viewport {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let mut surface = termwiz::surface::Surface::new(*width, *height);
+                surface.add_changes(changes);
+                let cursor = self.terminal.cursor_pos();
+                let cursor_y = usize::try_from(cursor.y).unwrap_or(0);
+                let is_cursor_inside =
+                    (*x..x + width).contains(&cursor.x) && (*y..y + height).contains(&cursor_y);
+                let relative_cursor = if is_cursor_inside {
+                    Some((cursor.x - x, cursor_y - y))
+                } else {
+                    None
+                };
+                CompleteSurface::Viewport(CompleteViewport {
+                    surface,
+                    mode: self.get_screen_mode(),
+                    origin: (*x, *y),
+                    cursor: relative_cursor,
+                    line_attributes,
                 })
             }
         };
@@ -291,24 +501,51 @@ impl crate::shadow_terminal::ShadowTerminal {
 
     /// Generate a change set. It is used both for generating diffs and it is, perhaps
     /// surprisingly, the method required to construct an entire surface from scratch.
+    ///
+    /// Termwiz surfaces don't have line attributes, so they're returned separately, by row.
     fn generate_changes(
         &mut self,
         kind: &SurfaceKind,
         maybe_dirty_lines: Option<Vec<isize>>,
-    ) -> Result<Vec<TermwizChange>, crate::errors::ShadowTerminalError> {
+    ) -> Result<(Vec<TermwizChange>, Vec<(usize, LineAttribute)>), crate::errors::ShadowTerminalError>
+    {
         let mut changes = Vec::new();
+        let mut line_attributes = Vec::new();
         let (line_ids, output_start) = self.calculate_line_ids(kind, maybe_dirty_lines)?;
+        let (first_column, columns) = match kind {
+            SurfaceKind::Viewport { x, width, .. } => (*x, *width),
+            SurfaceKind::Scrollback | SurfaceKind::Screen => (0, usize::MAX),
+        };
         let screen = self.terminal.screen_mut();
 
         for line_id in line_ids {
             let line = screen.line_mut(line_id);
             let y = line_id - output_start;
+            line_attributes.push((y, LineAttribute::from_line(line)));
             changes.push(TermwizChange::CursorPosition {
                 x: TermwizPosition::Absolute(0),
                 y: TermwizPosition::Absolute(y),
             });
 
-            for cell in line.cells_mut() {
+            // `visible_cells()` reads compressed scrollback lines without decompressing them. It
+            // skips the columns covered by wide graphemes, so the cursor is moved whenever a cell
+            // isn't where the previous one ended.
+            let mut next_x = 0;
+            for cell in line.visible_cells() {
+                let Some(x) = cell.cell_index().checked_sub(first_column) else {
+                    continue;
+                };
+                if x >= columns {
+                    break;
+                }
+                if x != next_x {
+                    changes.push(TermwizChange::CursorPosition {
+                        x: TermwizPosition::Absolute(x),
+                        y: TermwizPosition::Absolute(y),
+                    });
+                }
+                next_x = x + cell.width();
+
                 let mut attributes = vec![
                     TermwizChange::AllAttributes(cell.attrs().clone()),
                     cell.str().into(),
@@ -318,21 +555,34 @@ impl crate::shadow_terminal::ShadowTerminal {
             }
         }
 
-        changes.push(self.original_cursor_position()?);
+        changes.push(self.original_cursor_position(kind)?);
 
-        Ok(changes)
+        Ok((changes, line_attributes))
     }
 
     /// Get the original position of the cursor, because we have to move the cursor around in order
     /// to generate the diffs/surfaces. We want to always make sure the cursor is reset.
+    ///
+    /// For viewports the position is relative to the viewport.
     fn original_cursor_position(
         &self,
+        kind: &SurfaceKind,
     ) -> Result<TermwizChange, crate::errors::ShadowTerminalError> {
         let position = self.terminal.cursor_pos();
-        let x = position.x;
-        let y = position.y.try_into().with_whatever_context(|err| {
+        let absolute_y: usize = position.y.try_into().with_whatever_context(|err| {
             format!("Couldn't convert cursor position to usize: {err:?}")
         })?;
+        let (x, y) = match kind {
+            SurfaceKind::Viewport {
+                x: origin_x,
+                y: origin_y,
+                ..
+            } => (
+                position.x.saturating_sub(*origin_x),
+                absolute_y.saturating_sub(*origin_y),
+            ),
+            SurfaceKind::Scrollback | SurfaceKind::Screen => (position.x, absolute_y),
+        };
 
         Ok(TermwizChange::CursorPosition {
             x: TermwizPosition::Absolute(x),
@@ -342,7 +592,7 @@ impl crate::shadow_terminal::ShadowTerminal {
 
     /// Calculate the IDs of the lines that need to be output. Could just be the changed lines, or
     /// all the lines of the screen/scrollback.
-    fn calculate_line_ids(
+    pub(crate) fn calculate_line_ids(
         &mut self,
         kind: &SurfaceKind,
         maybe_dirty_lines: Option<Vec<isize>>,
@@ -357,6 +607,11 @@ impl crate::shadow_terminal::ShadowTerminal {
                 let start = end - tty_size.rows;
                 (start, end)
             }
+            SurfaceKind::Viewport { y, height, .. } => {
+                let screen_end = screen.scrollback_rows() - self.scroll_position;
+                let start = screen_end - tty_size.rows + y;
+                (start, start + height)
+            }
         };
 
         match maybe_dirty_lines {
@@ -380,3 +635,147 @@ impl crate::shadow_terminal::ShadowTerminal {
         Ok((line_ids, output_start))
     }
 }
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// The stable rows that have changed since the last call to
+    /// [`Self::reset_change_tracking`], or since the shadow terminal was created. Useful for
+    /// cheaply learning what needs redrawing, instead of rebuilding entire surfaces.
+    ///
+    /// The change set only ever resets when [`Self::reset_change_tracking`] is called. It's
+    /// independent of the shadow terminal's own output, so broadcasting diffs doesn't reset it.
+    /// Rows that have since been trimmed from the scrollback aren't included.
+    #[inline]
+    #[must_use]
+    pub fn changed_rows(&self) -> Vec<wezterm_term::StableRowIndex> {
+        let screen = self.terminal.screen();
+        let first_row = screen.phys_to_stable_row_index(0);
+        let total_rows =
+            wezterm_term::StableRowIndex::try_from(screen.scrollback_rows()).unwrap_or(0);
+        screen.get_changed_stable_rows(
+            first_row..first_row + total_rows,
+            self.change_tracking_sequence,
+        )
+    }
+
+    /// Start tracking changes from now. Subsequent calls to [`Self::changed_rows`] only return
+    /// rows that change after this call.
+    #[inline]
+    pub fn reset_change_tracking(&mut self) {
+        self.change_tracking_sequence = self.terminal.current_seqno();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CompleteSurface, LineAttribute, Output, SurfaceKind};
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    #[test]
+    fn double_width_lines() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 20,
+            height: 5,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        shadow_terminal
+            .terminal
+            .advance_bytes("normal\r\n\x1b#6double wide text\r\n\x1b#3tall\r\n\x1b#4tall");
+
+        let output = shadow_terminal
+            .build_current_output(&SurfaceKind::Screen)
+            .unwrap();
+        let Output::Complete(CompleteSurface::Screen(screen)) = output else {
+            panic!("Expected a complete screen");
+        };
+        assert_eq!(
+            screen.line_attributes,
+            vec![
+                (1, LineAttribute::DoubleWidth),
+                (2, LineAttribute::DoubleHeightTop),
+                (3, LineAttribute::DoubleHeightBottom),
+            ]
+        );
+
+        assert_eq!(
+            shadow_terminal.to_text(),
+            "normal\ndouble wid\ntall\ntall\n"
+        );
+    }
+
+    #[test]
+    fn viewport_of_screen() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 10,
+            height: 4,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        shadow_terminal
+            .terminal
+            .advance_bytes("0123456789\r\nabcdefghij\r\nABCDEFGHIJ\r\nklm");
+
+        let output = shadow_terminal
+            .build_current_output(&SurfaceKind::Viewport {
+                x: 2,
+                y: 1,
+                width: 3,
+                height: 100,
+            })
+            .unwrap();
+        let Output::Complete(CompleteSurface::Viewport(viewport)) = output else {
+            panic!("Expected a complete viewport");
+        };
+        assert_eq!(viewport.surface.dimensions(), (3, 3));
+        assert_eq!(viewport.origin, (2, 1));
+        assert_eq!(viewport.surface.screen_chars_to_string(), "cde\nCDE\nm  \n");
+        assert_eq!(viewport.cursor, Some((1, 2)));
+    }
+
+    #[test]
+    fn changed_rows_since_reset() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let mut shadow_terminal = ShadowTerminal::new(Config::default(), shadow_output_tx);
+        shadow_terminal
+            .terminal
+            .advance_bytes("one\r\ntwo\r\nthree");
+        assert!(shadow_terminal.changed_rows().contains(&0));
+
+        shadow_terminal.reset_change_tracking();
+        assert!(shadow_terminal.changed_rows().is_empty());
+
+        shadow_terminal.terminal.advance_bytes("\x1b[2;1Hchanged");
+        assert_eq!(shadow_terminal.changed_rows(), vec![1]);
+
+        // Only an explicit reset clears the change set.
+        assert_eq!(shadow_terminal.changed_rows(), vec![1]);
+    }
+
+    #[test]
+    fn complete_screens_have_the_rows_that_changed() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let mut shadow_terminal = ShadowTerminal::new(Config::default(), shadow_output_tx);
+        let changed_rows = |shadow_terminal: &mut ShadowTerminal| {
+            let output = shadow_terminal
+                .build_current_output(&SurfaceKind::Screen)
+                .unwrap();
+            let Output::Complete(CompleteSurface::Screen(screen)) = output else {
+                panic!("Expected a complete screen");
+            };
+            screen.changed_rows
+        };
+
+        shadow_terminal.terminal.advance_bytes("one\r\ntwo");
+        shadow_terminal.last_sent.pty_sequence = shadow_terminal.terminal.current_seqno();
+        shadow_terminal.terminal.advance_bytes("\x1b[2;1Hchanged");
+        assert_eq!(changed_rows(&mut shadow_terminal), vec![1]);
+
+        shadow_terminal.is_full_redraw_requested = true;
+        assert_eq!(
+            changed_rows(&mut shadow_terminal).len(),
+            shadow_terminal.terminal.get_size().rows
+        );
+    }
+}
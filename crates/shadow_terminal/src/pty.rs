@@ -1,6 +1,11 @@
 //! Creates a PTY in an OS subprocess and sends and recieves bytes to/from it over channels.
 //! It doesn't actually maintain a visual representation, that requires the [`Wezterm`] terminal
 //! to parse the PTY's output, see: [`ShadowTerminal`].
+//!
+//! The platform's native PTY is used: a Unix pseudoterminal, or ConPTY on Windows. They both have
+//! the same interface, so the output, input and resize handling here is the same on all platforms.
+//! ConPTY is resized with `ResizePseudoConsole`, which `portable_pty` calls for us, so there's no
+//! `SIGWINCH` on Windows.
 
 use std::{ffi::OsString, io::Read as _};
 
@@ -8,12 +13,61 @@ use snafu::{OptionExt as _, ResultExt as _};
 use tokio::sync::mpsc;
 use tracing::Instrument as _;
 
-/// A single payload from the PTY output stream. This value comes from the `portable_pty` crate and
-/// doesn't seem adjustable.
-pub type BytesFromPTY = [u8; 4096];
+/// A single payload from the PTY output stream. It's only as long as the bytes that were actually
+/// read, which is at most the configured read buffer size.
+pub type BytesFromPTY = Vec<u8>;
+
+/// The default size of the buffer used to read PTY output.
+///
+/// Note that on Linux a single read often still returns at most 4095 bytes, whatever the size of
+/// the buffer, because that's the size of the kernel's TTY buffer. That's why the shadow terminal
+/// also waits a short time to coalesce consecutive payloads before rendering them.
+pub const DEFAULT_READ_BUFFER_SIZE: usize = 65536;
+/// How long to keep reading the PTY's output after its child process has exited. Normally the
+/// output ends straight away, but processes that the child left running in the background can
+/// keep the PTY open.
+const OUTPUT_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
 /// A single payload from the user's input stream (or sometimes internal input).
 pub type BytesFromSTDIN = [u8; 128];
 
+/// Whether there's still output to read from the PTY.
+enum StreamState {
+    /// The PTY may still send more output.
+    Open,
+    /// The PTY has reached EOF, normally because its child process exited.
+    Ended,
+}
+
+/// How a single run of the PTY's child process ended.
+enum SessionEnd {
+    /// The child process exited normally. Whether the PTY had reached the end of its output is
+    /// also recorded.
+    Finished {
+        /// Whether all of the child process's output was read.
+        is_output_ended: bool,
+    },
+    /// The child process was killed by a signal, or exited with one of the restart policy's crash
+    /// exit codes, and so could be restarted.
+    Crashed {
+        /// Whether all of the child process's output was read.
+        is_output_ended: bool,
+    },
+    /// `Protocol::End` was received, so everything is already shutting down.
+    Ended,
+}
+
+impl SessionEnd {
+    /// How the session ended, given how its child process exited.
+    const fn from_exit(is_crash: bool, is_output_ended: bool) -> Self {
+        if is_crash {
+            Self::Crashed { is_output_ended }
+        } else {
+            Self::Finished { is_output_ended }
+        }
+    }
+}
+
 /// This is the PTY process that replaces the user's current TTY
 #[non_exhaustive]
 pub struct PTY {
@@ -27,16 +81,30 @@ pub struct PTY {
     pub control_tx: tokio::sync::broadcast::Sender<crate::Protocol>,
     /// Send side of channel sending updates from the PTY process
     pub output_tx: tokio::sync::mpsc::Sender<crate::pty::BytesFromPTY>,
+    /// Whether the PTY's child process is still running.
+    pub is_alive: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// The size of the buffer used to read PTY output.
+    pub read_buffer_size: usize,
+    /// Whether, and how, to restart the child process when it crashes.
+    pub restart_policy: Option<crate::shadow_terminal::RestartPolicy>,
 }
 
 impl PTY {
-    /// Function just to isolate the PTY setup
-    fn setup_pty(&self) -> Result<portable_pty::PtyPair, crate::errors::PTYError> {
+    /// Function just to isolate the PTY setup. The exit status of the child process is sent on the
+    /// channel, it's up to the caller to decide what to do when the child exits.
+    fn setup_pty(
+        &self,
+        exit_tx: tokio::sync::oneshot::Sender<Option<portable_pty::ExitStatus>>,
+    ) -> Result<portable_pty::PtyPair, crate::errors::PTYError> {
         tracing::debug!("Setting up PTY");
         let pty_system = portable_pty::native_pty_system();
         let pair = pty_system
             .openpty(Self::pty_size(self.width, self.height))
-            .with_whatever_context(|_| "Error opening PTY")?;
+            .map_err(|error| crate::errors::PTYError::PTYAllocation {
+                message: error.to_string(),
+            })?;
+
+        Self::check_command(&self.command)?;
 
         tracing::debug!("Launching `{:?}` on PTY", self.command);
         let mut cmd = portable_pty::CommandBuilder::from_argv(self.command.clone());
@@ -49,23 +117,81 @@ impl PTY {
             .spawn_command(cmd)
             .with_whatever_context(|_| "Error spawning PTY command")?;
         let killer = spawn.clone_killer();
-        Self::wait_for_pty_end(self.control_tx.clone(), spawn);
+        self.is_alive
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        Self::wait_for_pty_end(spawn, std::sync::Arc::clone(&self.is_alive), exit_tx);
         Self::kill_on_protocol_end(self.control_tx.subscribe(), killer);
 
         tracing::trace!("Returning PTY pair");
         Ok(pair)
     }
 
+    /// Check that the command can actually be run. Spawning errors from the PTY crate aren't very
+    /// specific, and they can only be discovered well after the PTY has started. So this allows us
+    /// to give clearer errors immediately.
+    ///
+    /// On Windows the command is always assumed to be valid, because resolving executables there
+    /// involves a lot more than just checking `$PATH`.
+    ///
+    /// # Errors
+    /// If the command can't be found, or it's found but isn't executable.
+    #[inline]
+    pub fn check_command(command: &[OsString]) -> Result<(), crate::errors::PTYError> {
+        let Some(program) = command.first() else {
+            snafu::whatever!("No command given for the PTY");
+        };
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+
+            let program_path = std::path::Path::new(program);
+            let candidates: Vec<std::path::PathBuf> = if program_path.components().count() > 1 {
+                vec![program_path.to_path_buf()]
+            } else {
+                std::env::var_os("PATH")
+                    .map(|paths| {
+                        std::env::split_paths(&paths)
+                            .map(|directory| directory.join(program))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+
+            let command_name = program.to_string_lossy().into_owned();
+            let Some(found) = candidates.iter().find(|candidate| candidate.is_file()) else {
+                return crate::errors::CommandNotFoundSnafu {
+                    command: command_name,
+                }
+                .fail();
+            };
+
+            let is_executable = found
+                .metadata()
+                .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0);
+            if !is_executable {
+                return crate::errors::PermissionDeniedSnafu {
+                    command: command_name,
+                }
+                .fail();
+            }
+        }
+
+        tracing::trace!("PTY command {program:?} looks runnable");
+        Ok(())
+    }
+
     /// The PTY crate is not async, so here we're basically just listening to the PTY to be able to
     /// broadcast its output on an async channel.
     fn pty_reader_loop(
         pty_reader: std::boxed::Box<dyn std::io::Read + std::marker::Send>,
         pty_reader_tx: mpsc::Sender<BytesFromPTY>,
+        read_buffer_size: usize,
     ) -> tokio::task::JoinHandle<()> {
         tokio::task::spawn_blocking(move || {
             let mut reader = std::io::BufReader::new(pty_reader);
             loop {
-                let mut buffer: BytesFromPTY = [0; 4096];
+                let mut buffer: BytesFromPTY = vec![0; read_buffer_size];
 
                 let now = std::time::Instant::now();
                 let read_result = reader.read(&mut buffer);
@@ -76,41 +202,58 @@ impl PTY {
                         tracing::debug!("PTY reader loop received 0 bytes, exiting...");
                         break;
                     }
+                    Err(error) if Self::is_transient_read_error(&error) => {
+                        tracing::trace!("Retrying transient PTY read error: {error:?}");
+                    }
+                    Err(error) if Self::is_end_of_output(&error) => {
+                        tracing::debug!("PTY reader loop reached end of output, exiting...");
+                        break;
+                    }
                     Ok(n) => {
                         tracing::trace!(
                             "Read {} PTY bytes. Time since last output {:?}",
                             n,
                             elapsed
                         );
+                        buffer.truncate(n);
                         let send_result = pty_reader_tx.blocking_send(buffer);
                         if let Err(error) = send_result {
                             tracing::error!("Broadcasting PTY output: {error:?}");
                             break;
                         }
                     }
-                    Err(error) => tracing::error!("PTY reader: {error:?}"),
+                    Err(error) => {
+                        tracing::error!("PTY reader: {error:?}");
+                        break;
+                    }
                 }
             }
             tracing::trace!("Leaving PTY reader loop");
         })
     }
 
-    /// A dedicated loop to listen for the official PTY end event.
+    /// A dedicated loop to listen for the official PTY end event. The child's exit status is sent
+    /// on the channel, or `None` if it couldn't be found.
     fn wait_for_pty_end(
-        protocol_out: tokio::sync::broadcast::Sender<crate::Protocol>,
         mut spawn: Box<dyn portable_pty::Child + Send + Sync>,
+        is_alive: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        exit_tx: tokio::sync::oneshot::Sender<Option<portable_pty::ExitStatus>>,
     ) {
         tokio::task::spawn_blocking(move || {
             tracing::debug!("Starting to wait for PTY end");
-            let waiter_result = spawn.wait();
-            if let Err(error) = waiter_result {
-                tracing::error!("Waiting for PTY: {error:?}");
-            }
-            let sender_result = protocol_out.send(crate::Protocol::End);
-            if let Err(error) = sender_result {
-                tracing::error!("Sending `Protocol::End` after: {error:?} ");
+            let exit_status = match spawn.wait() {
+                Ok(status) => Some(status),
+                Err(error) => {
+                    tracing::error!("Waiting for PTY: {error:?}");
+                    None
+                }
+            };
+            is_alive.store(false, std::sync::atomic::Ordering::Relaxed);
+
+            tracing::info!("PTY child exited with: {exit_status:?}");
+            if exit_tx.send(exit_status).is_err() {
+                tracing::debug!("Nobody is waiting for the PTY child's exit status");
             }
-            tracing::info!("PTY ended by its own accord");
         });
     }
 
@@ -159,20 +302,102 @@ impl PTY {
         );
     }
 
-    /// Start the PTY
+    /// Start the PTY. If there's a restart policy, then the child process is restarted whenever
+    /// it crashes, until it's been restarted too many times.
     pub async fn run(
-        self,
-        user_input_rx: mpsc::Receiver<BytesFromSTDIN>,
-        internal_input_rx: mpsc::Receiver<BytesFromSTDIN>,
+        mut self,
+        mut user_input_rx: mpsc::Receiver<BytesFromSTDIN>,
+        mut internal_input_rx: mpsc::Receiver<BytesFromSTDIN>,
     ) -> Result<(), crate::errors::PTYError> {
-        let (pty_reader_tx, mut pty_reader_rx) = tokio::sync::mpsc::channel(1);
-
         // It's important that we subscribe now, as that is what starts the backlog of protocol
         // messages. It's possible that messages are sent during PTY startup and we don't want to
         // miss any of those messages later when we finally start the listening loop.
-        let mut protocol_for_main_loop = self.control_tx.subscribe();
+        let mut protocol = self.control_tx.subscribe();
+        let mut restarts = 0;
+
+        loop {
+            let (session_end, user_input, internal_input) = self
+                .run_session(user_input_rx, internal_input_rx, &mut protocol)
+                .await?;
+            user_input_rx = user_input;
+            internal_input_rx = internal_input;
+
+            let is_output_ended = match session_end {
+                SessionEnd::Ended => break,
+                SessionEnd::Finished { is_output_ended } => is_output_ended,
+                SessionEnd::Crashed { is_output_ended } => {
+                    let Some(delay) = self
+                        .restart_policy
+                        .as_ref()
+                        .and_then(|policy| policy.delay(restarts))
+                    else {
+                        tracing::warn!("PTY crashed, not restarting after {restarts} restarts");
+                        self.broadcast_exit(is_output_ended);
+                        break;
+                    };
+                    restarts += 1;
+
+                    tracing::info!("PTY crashed, restart {restarts} in {delay:?}");
+                    let send_result = self.control_tx.send(crate::Protocol::Restarting {
+                        attempt: restarts,
+                        delay,
+                    });
+                    if let Err(error) = send_result {
+                        tracing::error!("Couldn't broadcast `Protocol::Restarting`: {error:?}");
+                    }
+                    if !self.wait_to_restart(delay, &mut protocol).await {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            self.broadcast_exit(is_output_ended);
+            break;
+        }
 
-        let pty_pair = self.setup_pty()?;
+        tracing::debug!("PTY reader loop finished");
+        Ok(())
+    }
+
+    /// Tell everything that the child process has gone for good, by broadcasting `Protocol::End`.
+    /// `Protocol::Exited` is also sent first, but only when all of its output was read.
+    fn broadcast_exit(&self, is_output_ended: bool) {
+        if is_output_ended {
+            tracing::debug!("PTY output ended, broadcasting `Protocol::Exited`");
+            let send_result = self.control_tx.send(crate::Protocol::Exited);
+            if let Err(error) = send_result {
+                tracing::error!("Couldn't broadcast `Protocol::Exited`: {error:?}");
+            }
+        }
+
+        tracing::debug!("PTY child won't be restarted, broadcasting `Protocol::End`");
+        let send_result = self.control_tx.send(crate::Protocol::End);
+        if let Err(error) = send_result {
+            tracing::error!("Couldn't broadcast `Protocol::End`: {error:?}");
+        }
+    }
+
+    /// Run a single instance of the child process, until it exits. The input channels are handed
+    /// back, ready for a restart.
+    async fn run_session(
+        &mut self,
+        user_input_rx: mpsc::Receiver<BytesFromSTDIN>,
+        internal_input_rx: mpsc::Receiver<BytesFromSTDIN>,
+        protocol: &mut tokio::sync::broadcast::Receiver<crate::Protocol>,
+    ) -> Result<
+        (
+            SessionEnd,
+            mpsc::Receiver<BytesFromSTDIN>,
+            mpsc::Receiver<BytesFromSTDIN>,
+        ),
+        crate::errors::PTYError,
+    > {
+        let (pty_reader_tx, mut pty_reader_rx) = tokio::sync::mpsc::channel(1);
+        let is_restartable = self.restart_policy.is_some();
+        let (exit_tx, mut exit_rx) = tokio::sync::oneshot::channel();
+
+        let pty_pair = self.setup_pty(exit_tx)?;
         let pty_writer = pty_pair
             .master
             .take_writer()
@@ -182,96 +407,201 @@ impl PTY {
             .try_clone_reader()
             .with_whatever_context(|err| format!("Getting PTY reader: {err:?}"))?;
 
-        Self::pty_reader_loop(pty_reader, pty_reader_tx);
+        Self::pty_reader_loop(pty_reader, pty_reader_tx, self.read_buffer_size.max(1));
 
         // We have to drop the slave so that we don't hang on it when we exit.
         drop(pty_pair.slave);
 
-        // TODO: should we be handling any errors in here?
         let protocol_for_input_loop = self.control_tx.subscribe();
+        let (session_over_tx, session_over_rx) = tokio::sync::oneshot::channel();
         let current_span = tracing::Span::current();
-        tokio::spawn(async move {
-            let result = Self::forward_input(
+        let input_task = tokio::spawn(
+            Self::forward_input(
                 user_input_rx,
                 internal_input_rx,
                 pty_writer,
                 pty_pair.master,
                 protocol_for_input_loop,
+                session_over_rx,
             )
-            .instrument(current_span)
-            .await;
-            if let Err(err) = result {
-                tracing::error!("Writing to PTY stream: {err}");
-            }
-        });
+            .instrument(current_span),
+        );
 
         tracing::debug!("Starting PTY reader loop");
+        let mut is_output_ended = false;
+        let mut is_waiting_for_exit = true;
+        // Once the child has exited, whether it crashed, and when to stop waiting for the rest of
+        // its output.
+        let mut exit: Option<(bool, tokio::time::Instant)> = None;
         #[expect(
             clippy::integer_division_remainder_used,
             reason = "`tokio::select!` generates this."
         )]
-        loop {
+        let session_end = loop {
+            let drain_deadline =
+                exit.map_or_else(tokio::time::Instant::now, |(_, deadline)| deadline);
             tokio::select! {
-                result = self.read_stream(&mut pty_reader_rx) => {
-                    if let Err(error) = result {
-                        // TODO: The error should be bubbled, and logged centrally
-                        tracing::error!("{error:?}");
-                        snafu::whatever!("{error:?}");
+                result = self.read_stream(&mut pty_reader_rx), if !is_output_ended => {
+                    match result {
+                        Ok(StreamState::Open) => (),
+                        Ok(StreamState::Ended) => {
+                            is_output_ended = true;
+                            if let Some((is_crash, _)) = exit {
+                                break SessionEnd::from_exit(is_crash, is_output_ended);
+                            }
+                            // Without a restart policy there's no need to wait for the child's
+                            // exit status.
+                            if !is_restartable {
+                                break SessionEnd::Finished { is_output_ended };
+                            }
+                        }
+                        Err(error) => {
+                            // TODO: The error should be bubbled, and logged centrally
+                            tracing::error!("{error:?}");
+                            snafu::whatever!("{error:?}");
+                        }
+                    }
+                }
+                result = &mut exit_rx, if is_waiting_for_exit => {
+                    is_waiting_for_exit = false;
+                    let is_crash = |status: &portable_pty::ExitStatus| {
+                        self.restart_policy
+                            .as_ref()
+                            .is_some_and(|policy| policy.is_crash(status))
+                    };
+                    let is_crashed = match result {
+                        Ok(Some(status)) => is_crash(&status),
+                        // Not knowing how the child exited is treated as a crash.
+                        Ok(None) => is_restartable,
+                        Err(error) => {
+                            tracing::error!("Waiting for PTY child to exit: {error:?}");
+                            false
+                        }
+                    };
+                    if is_output_ended {
+                        break SessionEnd::from_exit(is_crashed, is_output_ended);
                     }
+                    exit = Some((is_crashed, tokio::time::Instant::now() + OUTPUT_DRAIN_TIMEOUT));
+                }
+                () = tokio::time::sleep_until(drain_deadline), if exit.is_some() => {
+                    tracing::debug!("PTY child exited, but its output didn't end in time");
+                    let is_crashed = exit.is_some_and(|(is_crashed, _)| is_crashed);
+                    break SessionEnd::from_exit(is_crashed, is_output_ended);
                 }
-                result = protocol_for_main_loop.recv() => {
+                result = protocol.recv() => {
                     match result {
                         Ok(message) => {
                             if matches!(message, crate::Protocol::End) {
-                                break;
+                                break SessionEnd::Ended;
                             }
+                            self.track_size(&message);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::warn!("PTY main loop lagged, missed {missed} protocol messages");
                         }
                         Err(err) => {
                             // TODO: The error should be bubbled, and logged centrally
                             tracing::error!("{err:?}");
                             snafu::whatever!("{err:?}");
                         },
-
                     }
                 }
-
             }
+        };
+
+        // The input loop may have already finished, because of `Protocol::End`.
+        if session_over_tx.send(()).is_err() {
+            tracing::trace!("PTY input loop already finished");
         }
+        let (user_input, internal_input) = input_task
+            .await
+            .with_whatever_context(|err| format!("Joining PTY input loop: {err:?}"))??;
 
-        tracing::debug!("PTY reader loop finished");
-        Ok(())
+        Ok((session_end, user_input, internal_input))
+    }
+
+    /// Keep track of the size of the terminal, so that restarted child processes start at the
+    /// right size.
+    const fn track_size(&mut self, message: &crate::Protocol) {
+        if let crate::Protocol::Resize { width, height } = message {
+            self.width = *width;
+            self.height = *height;
+        }
+    }
+
+    /// Wait before restarting a crashed child process. Returns `false` if Tattoy ended whilst
+    /// waiting, and so the child shouldn't be restarted.
+    async fn wait_to_restart(
+        &mut self,
+        delay: tokio::time::Duration,
+        protocol: &mut tokio::sync::broadcast::Receiver<crate::Protocol>,
+    ) -> bool {
+        let restart_at = tokio::time::Instant::now() + delay;
+
+        #[expect(
+            clippy::integer_division_remainder_used,
+            reason = "`tokio::select!` generates this."
+        )]
+        loop {
+            tokio::select! {
+                () = tokio::time::sleep_until(restart_at) => return true,
+                result = protocol.recv() => {
+                    match result {
+                        Ok(crate::Protocol::End) => return false,
+                        Ok(message) => self.track_size(&message),
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::warn!("PTY restart lagged, missed {missed} protocol messages");
+                        }
+                        Err(error) => {
+                            tracing::error!("Waiting to restart PTY: {error:?}");
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
     }
 
     /// Read bytes from the underlying PTY sub process and forward them to the Shadow Terminal.
     async fn read_stream(
         &self,
         pty_reader_rx: &mut mpsc::Receiver<BytesFromPTY>,
-    ) -> Result<(), crate::errors::PTYError> {
+    ) -> Result<StreamState, crate::errors::PTYError> {
         let Some(bytes) = pty_reader_rx.recv().await else {
-            return Ok(());
+            // The reader loop only closes its channel when the PTY has no more output.
+            return Ok(StreamState::Ended);
         };
 
+        let output = String::from_utf8_lossy(&bytes)
+            .to_string()
+            .replace('\x1b', "^");
+
         let result = self.output_tx.send(bytes).await;
         if let Err(err) = result {
             tracing::error!("Sending bytes on PTY output channel: {err}");
         }
 
-        let output = String::from_utf8_lossy(&bytes)
-            .to_string()
-            .replace('\x1b', "^");
         tracing::trace!("Sent PTY output, sample:\n{:.500}...", output);
 
-        Ok(())
+        Ok(StreamState::Open)
     }
 
-    /// Forward channel bytes from the user's input to the virtual PTY
+    /// Forward channel bytes from the user's input to the virtual PTY, until either Tattoy ends
+    /// or the child process's session is over. The input channels are then handed back.
     async fn forward_input(
         mut user_input: mpsc::Receiver<BytesFromSTDIN>,
         mut internal_input: mpsc::Receiver<BytesFromSTDIN>,
         mut pty_writer: std::boxed::Box<dyn std::io::Write + std::marker::Send>,
         pty_master: std::boxed::Box<(dyn portable_pty::MasterPty + std::marker::Send + 'static)>,
         mut protocol: tokio::sync::broadcast::Receiver<crate::Protocol>,
-    ) -> Result<(), crate::errors::PTYError> {
+        mut session_over: tokio::sync::oneshot::Receiver<()>,
+    ) -> Result<
+        (
+            mpsc::Receiver<BytesFromSTDIN>,
+            mpsc::Receiver<BytesFromSTDIN>,
+        ),
+        crate::errors::PTYError,
+    > {
         tracing::debug!("Starting `forward_input` loop");
 
         #[expect(
@@ -292,11 +622,12 @@ impl PTY {
                 Some(some_bytes) = internal_input.recv() => {
                     Self::handle_input_bytes(some_bytes, &mut pty_writer)?;
                 }
+                _ = &mut session_over => break,
             }
         }
 
         tracing::debug!("`forward_input` loop finished");
-        Ok(())
+        Ok((user_input, internal_input))
     }
 
     /// Handle a message from the Tattoy protocol broadcast channel.
@@ -317,13 +648,81 @@ impl PTY {
                     tracing::error!("Couldn't resize underlying PTY subprocesss: {result:?}");
                 }
             }
+            Ok(crate::Protocol::Signal(signal)) => {
+                tracing::debug!("Signal received on PTY input loop {message:?}");
+                Self::send_signal(signal, pty_master);
+            }
             Ok(_) => (),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!("PTY input loop lagged, missed {missed} protocol messages");
+            }
             Err(err) => snafu::whatever!("{err:?}"),
         }
 
         Ok(())
     }
 
+    /// Send an OS signal to the PTY's foreground process group. That's the same group that the
+    /// terminal's line discipline would signal for `Ctrl+C`, etc, so it's normally whichever
+    /// application is currently running in the shell.
+    #[cfg(not(target_os = "windows"))]
+    fn send_signal(
+        signal: &crate::Signal,
+        pty_master: &std::boxed::Box<(dyn portable_pty::MasterPty + std::marker::Send + 'static)>,
+    ) {
+        let Some(process_group) = pty_master.process_group_leader() else {
+            tracing::warn!("Couldn't find PTY's foreground process group to send {signal:?}");
+            return;
+        };
+
+        let signal_number = match signal {
+            crate::Signal::Interrupt => libc::SIGINT,
+            crate::Signal::Terminate => libc::SIGTERM,
+            crate::Signal::Suspend => libc::SIGTSTP,
+            crate::Signal::Hangup => libc::SIGHUP,
+            crate::Signal::Quit => libc::SIGQUIT,
+        };
+
+        // SAFETY: `kill()` has no memory safety requirements, it just takes 2 integers. A negative
+        // PID means that the signal is sent to the entire process group.
+        let result = unsafe { libc::kill(-process_group, signal_number) };
+        if result != 0 {
+            tracing::error!(
+                "Couldn't send {signal:?} to PTY process group {process_group}: {:?}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    /// Signals aren't supported on Windows.
+    #[cfg(target_os = "windows")]
+    fn send_signal(
+        signal: &crate::Signal,
+        _pty_master: &std::boxed::Box<(dyn portable_pty::MasterPty + std::marker::Send + 'static)>,
+    ) {
+        tracing::warn!("Sending signals to the PTY isn't supported on Windows: {signal:?}");
+    }
+
+    /// Read errors that just mean that we should try reading again.
+    fn is_transient_read_error(error: &std::io::Error) -> bool {
+        matches!(
+            error.kind(),
+            std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock
+        )
+    }
+
+    /// On Linux, once the child process has exited, reading from the PTY gives an `EIO` error
+    /// rather than 0 bytes. So it's actually EOF.
+    fn is_end_of_output(error: &std::io::Error) -> bool {
+        #[cfg(not(target_os = "windows"))]
+        let is_eio = error.raw_os_error() == Some(libc::EIO);
+
+        #[cfg(target_os = "windows")]
+        let is_eio = false;
+
+        is_eio || error.kind() == std::io::ErrorKind::BrokenPipe
+    }
+
     /// Handle input from end user.
     fn handle_input_bytes(
         bytes: BytesFromSTDIN,
@@ -447,6 +846,9 @@ mod test {
                 height: 10,
                 output_tx: pty_output_tx,
                 control_tx: protocol_tx.clone(),
+                is_alive: std::sync::Arc::default(),
+                read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+                restart_policy: None,
             };
             let result = pty.run(pty_input_rx, internal_input_rx).await;
             if let Err(err) = result {
@@ -469,12 +871,7 @@ mod test {
             .join("tests")
             .join("cat_me.txt");
 
-        #[cfg(not(target_os = "windows"))]
-        let sleep = "&& sleep 0.5";
-        #[cfg(target_os = "windows")]
-        let sleep = "; Start-Sleep -Milliseconds 5";
-
-        format!("{cat_command} {} {sleep}", path.display())
+        format!("{cat_command} {}", path.display())
     }
 
     fn stdin_bytes(input: &str) -> BytesFromSTDIN {
@@ -487,6 +884,180 @@ mod test {
         buffer
     }
 
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn missing_command() {
+        let result = PTY::check_command(&["definitely-not-a-real-command".into()]);
+        assert!(matches!(
+            result,
+            Err(crate::errors::PTYError::CommandNotFound { .. })
+        ));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn non_executable_command() {
+        let path = std::env::temp_dir().join("shadow-terminal-not-executable");
+        std::fs::write(&path, "").unwrap();
+        let result = PTY::check_command(&[path.clone().into()]);
+        std::fs::remove_file(path).unwrap();
+        assert!(matches!(
+            result,
+            Err(crate::errors::PTYError::PermissionDenied { .. })
+        ));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn clean_exit_when_child_exits_immediately() {
+        let (pty_output_tx, mut pty_output_rx) = mpsc::channel::<BytesFromPTY>(8);
+        let (_pty_input_tx, pty_input_rx) = mpsc::channel::<BytesFromSTDIN>(1);
+        let (_internal_input_tx, internal_input_rx) = mpsc::channel::<BytesFromSTDIN>(8);
+        let (protocol_tx, mut protocol_rx) = tokio::sync::broadcast::channel(16);
+        tokio::spawn(async move { while pty_output_rx.recv().await.is_some() {} });
+
+        let pty = PTY {
+            command: vec!["true".into()],
+            width: 10,
+            height: 10,
+            output_tx: pty_output_tx,
+            control_tx: protocol_tx.clone(),
+            is_alive: std::sync::Arc::default(),
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+            restart_policy: None,
+        };
+        let result = tokio::time::timeout(
+            tokio::time::Duration::from_secs(5),
+            pty.run(pty_input_rx, internal_input_rx),
+        )
+        .await
+        .unwrap();
+        assert!(result.is_ok());
+
+        let mut is_exited = false;
+        while let Ok(message) = protocol_rx.try_recv() {
+            if matches!(message, crate::Protocol::Exited) {
+                is_exited = true;
+            }
+        }
+        assert!(is_exited);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn crashed_children_are_restarted() {
+        let (pty_output_tx, mut pty_output_rx) = mpsc::channel::<BytesFromPTY>(8);
+        let (_pty_input_tx, pty_input_rx) = mpsc::channel::<BytesFromSTDIN>(1);
+        let (_internal_input_tx, internal_input_rx) = mpsc::channel::<BytesFromSTDIN>(8);
+        let (protocol_tx, mut protocol_rx) = tokio::sync::broadcast::channel(16);
+        let output_task = tokio::spawn(async move {
+            let mut result: Vec<u8> = vec![];
+            while let Some(bytes) = pty_output_rx.recv().await {
+                result.extend(bytes.iter().copied());
+            }
+            String::from_utf8_lossy(&result).into_owned()
+        });
+
+        let pty = PTY {
+            command: vec![
+                "sh".into(),
+                "-c".into(),
+                "echo started; sleep 0.1; kill -KILL $$".into(),
+            ],
+            width: 10,
+            height: 10,
+            output_tx: pty_output_tx,
+            control_tx: protocol_tx.clone(),
+            is_alive: std::sync::Arc::default(),
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+            restart_policy: Some(crate::shadow_terminal::RestartPolicy {
+                max_restarts: 2,
+                backoff: tokio::time::Duration::from_millis(10),
+                crash_exit_codes: Vec::new(),
+            }),
+        };
+        let result = tokio::time::timeout(
+            tokio::time::Duration::from_secs(5),
+            pty.run(pty_input_rx, internal_input_rx),
+        )
+        .await
+        .unwrap();
+        assert!(result.is_ok());
+
+        let mut restarts = Vec::new();
+        while let Ok(message) = protocol_rx.try_recv() {
+            if let crate::Protocol::Restarting { attempt, delay } = message {
+                restarts.push((attempt, delay.as_millis()));
+            }
+        }
+        assert_eq!(restarts, vec![(1, 10), (2, 20)]);
+
+        let output = output_task.await.unwrap();
+        assert_eq!(output.matches("started").count(), 3, "{output:?}");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn the_terminal_shuts_down_once_the_restarts_run_out() {
+        let config = crate::shadow_terminal::Config {
+            command: crate::tests::helpers::shell_command("echo crashing; sleep 0.1; exit 1"),
+            restart_policy: Some(crate::shadow_terminal::RestartPolicy {
+                max_restarts: 1,
+                backoff: tokio::time::Duration::from_millis(10),
+                crash_exit_codes: vec![1],
+            }),
+            ..crate::shadow_terminal::Config::default()
+        };
+        let Some(run) = crate::tests::helpers::run_config_in_pty(config)
+            .await
+            .unwrap()
+        else {
+            return;
+        };
+
+        let restarts = run
+            .protocol
+            .iter()
+            .filter(|message| matches!(message, crate::Protocol::Restarting { .. }))
+            .count();
+        assert_eq!(restarts, 1);
+        assert!(run
+            .protocol
+            .iter()
+            .any(|message| matches!(message, crate::Protocol::End)));
+        assert!(crate::tests::helpers::dump_surface(&run.screen).contains("crashing"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unsuccessful_exits_are_not_crashes() {
+        let config = crate::shadow_terminal::Config {
+            command: crate::tests::helpers::shell_command("echo exiting; exit 1"),
+            restart_policy: Some(crate::shadow_terminal::RestartPolicy {
+                max_restarts: 1,
+                backoff: tokio::time::Duration::from_millis(10),
+                crash_exit_codes: Vec::new(),
+            }),
+            ..crate::shadow_terminal::Config::default()
+        };
+        let Some(run) = crate::tests::helpers::run_config_in_pty(config)
+            .await
+            .unwrap()
+        else {
+            return;
+        };
+
+        assert!(!run
+            .protocol
+            .iter()
+            .any(|message| matches!(message, crate::Protocol::Restarting { .. })));
+        assert!(run
+            .protocol
+            .iter()
+            .any(|message| matches!(message, crate::Protocol::End)));
+        assert!(crate::tests::helpers::dump_surface(&run.screen).contains("exiting"));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn basic_output() {
         let mut command = crate::steppable_terminal::get_canonical_shell();
@@ -0,0 +1,346 @@
+//! An optional filter that strips certain escape sequences from the PTY output before it's parsed
+//! by the Wezterm terminal.
+//!
+//! Some exotic sequences can confuse Wezterm, or leak into whatever is rendering the shadow
+//! terminal. So when hosting untrusted or buggy programs it can be useful to just drop them. It's
+//! off by default, for full fidelity.
+//!
+//! The filter works on a stream, so a sequence can be split over any number of PTY payloads. Only
+//! the parser's state is kept between payloads, never the contents of a sequence, so it doesn't
+//! matter how long a sequence is. At most a single trailing `ESC` byte is held back until the next
+//! payload shows what it introduces.
+//!
+//! Only the 7-bit introducers (`ESC ]` etc) are recognised. The 8-bit C1 equivalents are also
+//! valid UTF-8 continuation bytes, so filtering them would corrupt ordinary text.
+//!
+//! The filter's `Parser` is also used to count the printable characters in the output, see
+//! `keystrokes.rs`.
+
+/// The escape byte that starts all the sequences.
+const ESC: u8 = 0x1b;
+
+/// The bell byte, which can also end an OSC sequence.
+const BEL: u8 = 0x07;
+
+/// The cancel byte, which aborts any sequence.
+const CAN: u8 = 0x18;
+
+/// The substitute byte, which also aborts any sequence.
+const SUB: u8 = 0x1a;
+
+/// The final byte of the String Terminator (`ESC \`).
+const STRING_TERMINATOR: u8 = b'\\';
+
+/// The kinds of escape sequence that can be stripped. They're all "string" sequences: an arbitrary
+/// payload that's ended by the String Terminator (`ESC \`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SequenceKind {
+    /// Operating System Command, `ESC ]`. Used for things like window titles, hyperlinks and the
+    /// clipboard. It can also be ended by `BEL`.
+    OSC,
+    /// Device Control String, `ESC P`. Used for things like Sixel graphics and terminfo queries.
+    DCS,
+    /// Application Program Command, `ESC _`. Used for things like the Kitty graphics protocol.
+    APC,
+    /// Privacy Message, `ESC ^`.
+    PM,
+    /// Start Of String, `ESC X`.
+    SOS,
+}
+
+impl SequenceKind {
+    /// The kind of sequence introduced by the byte after an `ESC`, if any.
+    const fn from_introducer(byte: u8) -> Option<Self> {
+        match byte {
+            b']' => Some(Self::OSC),
+            b'P' => Some(Self::DCS),
+            b'_' => Some(Self::APC),
+            b'^' => Some(Self::PM),
+            b'X' => Some(Self::SOS),
+            _ => None,
+        }
+    }
+}
+
+/// Where the parser is in the stream of bytes.
+#[derive(Debug, Clone, Copy, Default)]
+enum State {
+    /// Normal output.
+    #[default]
+    Ground,
+    /// An `ESC` has been seen, but not what it introduces.
+    Escape,
+    /// After the intermediate bytes of an escape sequence, like the `(` in `ESC ( B`.
+    EscapeIntermediate,
+    /// Inside a Control Sequence, `ESC [`, which ends with a final byte.
+    ControlSequence,
+    /// Inside a string sequence.
+    String {
+        /// The kind of sequence.
+        kind: SequenceKind,
+        /// Whether the last byte was an `ESC`, which might be the start of the String Terminator.
+        is_escaped: bool,
+    },
+}
+
+/// What a single byte of output is part of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// Normal output, either a printable character or a control character.
+    Ground,
+    /// An `ESC`. What it's part of is only known from the next byte.
+    Escape,
+    /// The byte after an `ESC` that introduces a string sequence.
+    StringIntroducer(SequenceKind),
+    /// The byte after an `ESC` that ends a string sequence, making the String Terminator.
+    StringTerminator(SequenceKind),
+    /// A byte of a string sequence's payload, or the `BEL`, `CAN` or `SUB` that ends it.
+    String(SequenceKind),
+    /// Any other byte of an escape sequence or a Control Sequence.
+    Sequence,
+}
+
+/// A streaming parser that works out what each byte of output is part of. Only its state is kept
+/// between bytes, so sequences can be split over any number of payloads.
+#[derive(Debug, Default)]
+pub(crate) struct Parser {
+    /// The state carried over from the previous byte.
+    state: State,
+}
+
+impl Parser {
+    /// Advance the parser by a single byte.
+    pub(crate) fn advance(&mut self, byte: u8) -> Token {
+        match self.state {
+            State::Ground => {
+                if byte == ESC {
+                    self.state = State::Escape;
+                    return Token::Escape;
+                }
+                Token::Ground
+            }
+            State::Escape => self.advance_escape(byte),
+            State::EscapeIntermediate => match byte {
+                ESC => {
+                    self.state = State::Escape;
+                    Token::Escape
+                }
+                CAN | SUB => {
+                    self.state = State::Ground;
+                    Token::Ground
+                }
+                0x20..=0x2f => Token::Sequence,
+                _ => {
+                    self.state = State::Ground;
+                    Token::Sequence
+                }
+            },
+            State::ControlSequence => match byte {
+                ESC => {
+                    self.state = State::Escape;
+                    Token::Escape
+                }
+                CAN | SUB => {
+                    self.state = State::Ground;
+                    Token::Ground
+                }
+                0x40..=0x7e => {
+                    self.state = State::Ground;
+                    Token::Sequence
+                }
+                _ => Token::Sequence,
+            },
+            State::String { kind, is_escaped } => {
+                if is_escaped {
+                    if byte == STRING_TERMINATOR {
+                        self.state = State::Ground;
+                        return Token::StringTerminator(kind);
+                    }
+
+                    // Any other escape aborts the string and starts a new sequence.
+                    return self.advance_escape(byte);
+                }
+
+                if byte == ESC {
+                    self.state = State::String {
+                        kind,
+                        is_escaped: true,
+                    };
+                    return Token::Escape;
+                }
+                let is_end =
+                    (byte == BEL && kind == SequenceKind::OSC) || [CAN, SUB].contains(&byte);
+                if is_end {
+                    self.state = State::Ground;
+                }
+                Token::String(kind)
+            }
+        }
+    }
+
+    /// Advance the parser by the byte after an `ESC`.
+    fn advance_escape(&mut self, byte: u8) -> Token {
+        if let Some(kind) = SequenceKind::from_introducer(byte) {
+            self.state = State::String {
+                kind,
+                is_escaped: false,
+            };
+            return Token::StringIntroducer(kind);
+        }
+
+        match byte {
+            ESC => {
+                self.state = State::Escape;
+                Token::Escape
+            }
+            CAN | SUB => {
+                self.state = State::Ground;
+                Token::Ground
+            }
+            b'[' => {
+                self.state = State::ControlSequence;
+                Token::Sequence
+            }
+            0x20..=0x2f => {
+                self.state = State::EscapeIntermediate;
+                Token::Sequence
+            }
+            _ => {
+                self.state = State::Ground;
+                Token::Sequence
+            }
+        }
+    }
+}
+
+/// A streaming filter for stripping escape sequences.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Sanitiser {
+    /// The kinds of sequence to strip.
+    kinds: Vec<SequenceKind>,
+    /// The parser carried over from the previous payload.
+    parser: Parser,
+    /// Whether an `ESC` is being held back until the next byte shows whether it's stripped.
+    is_escape_pending: bool,
+}
+
+impl Sanitiser {
+    /// Create a new filter that strips the given kinds of sequence.
+    #[inline]
+    #[must_use]
+    pub const fn new(kinds: Vec<SequenceKind>) -> Self {
+        Self {
+            kinds,
+            parser: Parser {
+                state: State::Ground,
+            },
+            is_escape_pending: false,
+        }
+    }
+
+    /// Filter a payload of PTY output. When there's nothing to strip the bytes are returned
+    /// untouched, without copying.
+    #[inline]
+    pub fn filter<'bytes>(&mut self, bytes: &'bytes [u8]) -> std::borrow::Cow<'bytes, [u8]> {
+        if self.kinds.is_empty() {
+            return std::borrow::Cow::Borrowed(bytes);
+        }
+
+        let mut filtered = Vec::with_capacity(bytes.len());
+        for byte in bytes {
+            self.filter_byte(*byte, &mut filtered);
+        }
+
+        std::borrow::Cow::Owned(filtered)
+    }
+
+    /// Advance the filter by a single byte, keeping it if it isn't part of a stripped sequence.
+    fn filter_byte(&mut self, byte: u8, filtered: &mut Vec<u8>) {
+        let token = self.parser.advance(byte);
+
+        if self.is_escape_pending {
+            self.is_escape_pending = false;
+            if let Token::StringIntroducer(kind) | Token::StringTerminator(kind) = token {
+                if !self.kinds.contains(&kind) {
+                    filtered.extend_from_slice(&[ESC, byte]);
+                }
+                return;
+            }
+            filtered.push(ESC);
+        }
+
+        match token {
+            Token::Escape => self.is_escape_pending = true,
+            Token::String(kind) => {
+                if !self.kinds.contains(&kind) {
+                    filtered.push(byte);
+                }
+            }
+            Token::Ground
+            | Token::StringIntroducer(_)
+            | Token::StringTerminator(_)
+            | Token::Sequence => filtered.push(byte),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Sanitiser, SequenceKind};
+
+    fn filter_all(sanitiser: &mut Sanitiser, payloads: &[&str]) -> String {
+        let filtered: Vec<u8> = payloads
+            .iter()
+            .flat_map(|payload| sanitiser.filter(payload.as_bytes()).into_owned())
+            .collect();
+        String::from_utf8(filtered).unwrap()
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut sanitiser = Sanitiser::new(Vec::new());
+        let output = "\x1b]0;title\x07hi\x1bPq#0\x1b\\";
+        assert!(matches!(
+            sanitiser.filter(output.as_bytes()),
+            std::borrow::Cow::Borrowed(_)
+        ));
+        assert_eq!(filter_all(&mut sanitiser, &[output]), output);
+    }
+
+    #[test]
+    fn strips_only_configured_sequences() {
+        let mut sanitiser = Sanitiser::new(vec![SequenceKind::OSC]);
+        let output = filter_all(
+            &mut sanitiser,
+            &["a\x1b]0;title\x07b\x1b]8;;url\x1b\\c\x1bPq#0\x1b\\\x1b[1md"],
+        );
+        assert_eq!(output, "abc\x1bPq#0\x1b\\\x1b[1md");
+    }
+
+    #[test]
+    fn strips_sequences_split_across_payloads() {
+        let mut sanitiser = Sanitiser::new(vec![SequenceKind::DCS, SequenceKind::APC]);
+        let output = filter_all(
+            &mut sanitiser,
+            &[
+                "one\x1b",
+                "Pq#0;2;0",
+                ";0;0\x1b",
+                "\\two\x1b_G",
+                "a=T\x1b",
+                "\\three\x1b",
+                "[0m",
+            ],
+        );
+        assert_eq!(output, "onetwothree\x1b[0m");
+    }
+
+    #[test]
+    fn new_escape_aborts_stripped_sequence() {
+        let mut sanitiser = Sanitiser::new(vec![SequenceKind::OSC]);
+        let output = filter_all(&mut sanitiser, &["\x1b]0;unterminated\x1b[2Jafter"]);
+        assert_eq!(output, "\x1b[2Jafter");
+    }
+}
@@ -0,0 +1,283 @@
+//! Keeping the scrollback within a memory budget.
+//!
+//! Wezterm only knows how to limit the scrollback by its number of lines. So instead we estimate
+//! the memory used by each line from its number of cells, and lower Wezterm's line limit until
+//! the lines fit within the budget. Wezterm then trims the oldest lines as new ones arrive.
+//!
+//! The lines above the screen rarely change, so their estimates are kept as a running total. Only
+//! the lines that have scrolled off the screen since the last batch of output are measured, along
+//! with the screen itself.
+//!
+//! Old lines can also be compressed. Wezterm stores compressed lines as runs of cells that share
+//! the same attributes, and transparently decompresses them whenever they're changed.
+
+/// A running total of the estimated memory used by the lines above the screen, so that the
+/// whole scrollback doesn't need to be measured for every batch of output.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ScrollbackMemory {
+    /// The stable row index of the oldest measured line.
+    first_row: wezterm_term::StableRowIndex,
+    /// The estimated memory of each measured line, oldest first.
+    lines: std::collections::VecDeque<usize>,
+    /// The total of all the measured lines.
+    total: usize,
+    /// The number of the oldest measured lines that don't fit within the budget.
+    excess_lines: usize,
+    /// The total memory of the lines that don't fit within the budget.
+    excess: usize,
+    /// Whether the lines were measured on the alternate screen, which has its own scrollback.
+    is_alt_screen: bool,
+}
+
+impl ScrollbackMemory {
+    /// Forget the lines that Wezterm has trimmed, and measure the lines that have scrolled off
+    /// the screen since the last measurement.
+    fn measure(&mut self, screen: &mut wezterm_term::Screen, rows: usize, is_alt_screen: bool) {
+        let oldest = screen.phys_to_stable_row_index(0);
+        let screen_top =
+            screen.phys_to_stable_row_index(screen.scrollback_rows().saturating_sub(rows));
+        let measured_until = self.measured_until();
+        if is_alt_screen != self.is_alt_screen || measured_until > screen_top {
+            *self = Self {
+                first_row: oldest,
+                is_alt_screen,
+                ..Self::default()
+            };
+        }
+
+        while self.first_row < oldest {
+            let Some(memory) = self.lines.pop_front() else {
+                self.first_row = oldest;
+                break;
+            };
+            self.first_row += 1;
+            self.total -= memory;
+            if self.excess_lines > 0 {
+                self.excess_lines -= 1;
+                self.excess -= memory;
+            }
+        }
+
+        for row in self.measured_until()..screen_top {
+            let Some(line_id) = screen.stable_row_to_phys(row) else {
+                continue;
+            };
+            let memory = estimate_line_memory(screen.line_mut(line_id).len());
+            self.lines.push_back(memory);
+            self.total += memory;
+        }
+    }
+
+    /// The stable row index just after the newest measured line.
+    fn measured_until(&self) -> wezterm_term::StableRowIndex {
+        self.first_row + isize::try_from(self.lines.len()).unwrap_or(isize::MAX)
+    }
+
+    /// The number of the newest measured lines that fit within the budget. The excess oldest
+    /// lines are remembered, so that only the changes since the last fit need to be checked.
+    fn fit(&mut self, budget: usize) -> usize {
+        while self.total - self.excess > budget {
+            let Some(memory) = self.lines.get(self.excess_lines) else {
+                break;
+            };
+            self.excess_lines += 1;
+            self.excess += memory;
+        }
+
+        while self.excess_lines > 0 {
+            let Some(memory) = self.lines.get(self.excess_lines - 1) else {
+                break;
+            };
+            if self.total - self.excess + memory > budget {
+                break;
+            }
+            self.excess_lines -= 1;
+            self.excess -= memory;
+        }
+
+        self.lines.len() - self.excess_lines
+    }
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Lower, or restore, the scrollback's line limit according to the memory budget.
+    pub(crate) fn apply_scrollback_memory_budget(&mut self) {
+        let Some(budget) = self.config.scrollback_memory_budget else {
+            return;
+        };
+
+        let rows = self.terminal.get_size().rows;
+        let is_alt_screen = self.terminal.is_alt_screen_active();
+        let screen = self.terminal.screen_mut();
+        self.scrollback_memory.measure(screen, rows, is_alt_screen);
+
+        let total_lines = screen.scrollback_rows();
+        let screen_memory: usize = (total_lines.saturating_sub(rows)..total_lines)
+            .map(|line_id| estimate_line_memory(screen.line_mut(line_id).len()))
+            .sum();
+        let fitting_lines = self
+            .scrollback_memory
+            .fit(budget.saturating_sub(screen_memory));
+
+        // When everything fits then there may be room for more lines than are currently stored.
+        let is_everything_fitting =
+            screen_memory <= budget && fitting_lines == self.scrollback_memory.lines.len();
+        let limit = if is_everything_fitting {
+            self.config.scrollback_size
+        } else {
+            fitting_lines.min(self.config.scrollback_size)
+        };
+
+        let previous = self
+            .scrollback_limit
+            .swap(limit, std::sync::atomic::Ordering::Relaxed);
+        if previous != limit {
+            tracing::debug!(
+                "Scrollback memory budget of {budget} bytes limits it to {limit} lines"
+            );
+        }
+    }
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Compress the lines that are further above the screen than the compression threshold.
+    pub(crate) fn compress_old_scrollback(&mut self) {
+        let Some(threshold) = self.config.scrollback_compression_threshold else {
+            return;
+        };
+
+        let rows = self.terminal.get_size().rows;
+        let screen = self.terminal.screen_mut();
+        let end = screen
+            .scrollback_rows()
+            .saturating_sub(rows)
+            .saturating_sub(threshold);
+        // Lines that have been trimmed from the scrollback no longer have a physical row.
+        let start = screen
+            .stable_row_to_phys(self.compressed_scrollback_until)
+            .unwrap_or(0);
+        if start >= end {
+            return;
+        }
+
+        for line_id in start..end {
+            screen.line_mut(line_id).compress_for_scrollback();
+        }
+        self.compressed_scrollback_until = screen.phys_to_stable_row_index(end);
+        tracing::trace!("Compressed scrollback lines {start} to {end}");
+    }
+}
+
+/// Estimate the memory used by a line, from its number of cells.
+const fn estimate_line_memory(cells: usize) -> usize {
+    std::mem::size_of::<wezterm_term::Line>() + cells * std::mem::size_of::<termwiz::cell::Cell>()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::SurfaceKind;
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    #[tokio::test]
+    async fn trims_scrollback_to_memory_budget() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let line_memory = super::estimate_line_memory(20);
+        let config = Config {
+            width: 20,
+            height: 5,
+            scrollback_memory_budget: Some(line_memory * 10),
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        for _ in 0..5 {
+            let lines: String = (0..10).map(|_| format!("{}\r\n", "x".repeat(20))).collect();
+            shadow_terminal.accumulated_pty_output = lines.into_bytes();
+            shadow_terminal.handle_pty_output().await.unwrap();
+        }
+
+        let total_lines = shadow_terminal.terminal.screen().scrollback_rows();
+        assert!(total_lines < 20, "Scrollback has {total_lines} lines");
+    }
+
+    #[tokio::test]
+    async fn the_running_total_matches_the_scrollback() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 20,
+            height: 5,
+            scrollback_memory_budget: Some(super::estimate_line_memory(20) * 10),
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        for batch in 0..5 {
+            let lines: String = (0..batch + 3).map(|line| format!("{line}\r\n")).collect();
+            shadow_terminal.accumulated_pty_output = lines.into_bytes();
+            shadow_terminal.handle_pty_output().await.unwrap();
+        }
+
+        let screen = shadow_terminal.terminal.screen_mut();
+        let above_screen = screen.scrollback_rows() - 5;
+        let total: usize = (0..above_screen)
+            .map(|line_id| super::estimate_line_memory(screen.line_mut(line_id).len()))
+            .sum();
+        assert_eq!(shadow_terminal.scrollback_memory.total, total);
+        assert_eq!(shadow_terminal.scrollback_memory.lines.len(), above_screen);
+    }
+
+    #[tokio::test]
+    async fn compressed_scrollback_keeps_its_text() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 20,
+            height: 5,
+            scrollback_compression_threshold: Some(2),
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        let lines: String = (0..20).map(|line| format!("line {line}\r\n")).collect();
+        shadow_terminal.accumulated_pty_output = lines.into_bytes();
+        shadow_terminal.handle_pty_output().await.unwrap();
+
+        // 21 lines, minus the 5 on the screen and the 2 nearest to it.
+        assert_eq!(shadow_terminal.compressed_scrollback_until, 14);
+        let screen = shadow_terminal.terminal.screen_mut();
+        assert_eq!(screen.line_mut(0).as_str().trim_end(), "line 0");
+        assert_eq!(screen.line_mut(13).as_str().trim_end(), "line 13");
+    }
+
+    #[tokio::test]
+    async fn rendering_the_scrollback_keeps_it_compressed() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 20,
+            height: 5,
+            scrollback_compression_threshold: Some(2),
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        let lines: String = (0..20).map(|line| format!("line {line}\r\n")).collect();
+        shadow_terminal.accumulated_pty_output = lines.into_bytes();
+        shadow_terminal.handle_pty_output().await.unwrap();
+
+        shadow_terminal
+            .build_current_output(&SurfaceKind::Scrollback)
+            .unwrap();
+        shadow_terminal
+            .render_cells(&SurfaceKind::Scrollback)
+            .unwrap();
+
+        let screen = shadow_terminal.terminal.screen_mut();
+        for line_id in 0..14 {
+            assert!(
+                is_compressed(screen.line_mut(line_id)),
+                "Line {line_id} was decompressed"
+            );
+        }
+    }
+
+    /// Wezterm doesn't expose how a line is stored, but its debug output names the storage.
+    fn is_compressed(line: &wezterm_term::Line) -> bool {
+        format!("{line:?}").contains("C(ClusteredLine")
+    }
+}
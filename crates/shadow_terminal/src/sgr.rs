@@ -0,0 +1,103 @@
+//! Encode a cell's attributes as an SGR ANSI code, so that cells can be written out as ANSI text
+//! and parsed back by a terminal. The codes themselves are generated by Termwiz.
+
+/// Build the SGR ANSI code for a cell's attributes. It starts with a reset, so it doesn't matter
+/// what the previous attributes were.
+#[inline]
+#[must_use]
+pub fn for_attributes(attributes: &termwiz::cell::CellAttributes) -> String {
+    use termwiz::escape::csi::Sgr;
+
+    let mut codes = vec![Sgr::Reset];
+    if attributes.intensity() != termwiz::cell::Intensity::Normal {
+        codes.push(Sgr::Intensity(attributes.intensity()));
+    }
+    if attributes.underline() != termwiz::cell::Underline::None {
+        codes.push(Sgr::Underline(attributes.underline()));
+    }
+    if attributes.underline_color() != termwiz::color::ColorAttribute::Default {
+        codes.push(Sgr::UnderlineColor(attributes.underline_color().into()));
+    }
+    if attributes.blink() != termwiz::cell::Blink::None {
+        codes.push(Sgr::Blink(attributes.blink()));
+    }
+    if attributes.italic() {
+        codes.push(Sgr::Italic(true));
+    }
+    if attributes.reverse() {
+        codes.push(Sgr::Inverse(true));
+    }
+    if attributes.invisible() {
+        codes.push(Sgr::Invisible(true));
+    }
+    if attributes.strikethrough() {
+        codes.push(Sgr::StrikeThrough(true));
+    }
+    if attributes.overline() {
+        codes.push(Sgr::Overline(true));
+    }
+    if attributes.foreground() != termwiz::color::ColorAttribute::Default {
+        codes.push(Sgr::Foreground(attributes.foreground().into()));
+    }
+    if attributes.background() != termwiz::color::ColorAttribute::Default {
+        codes.push(Sgr::Background(attributes.background().into()));
+    }
+
+    codes
+        .into_iter()
+        .map(|code| termwiz::escape::CSI::Sgr(code).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn attributes_survive_being_parsed_by_a_terminal() {
+        let mut attributes = termwiz::cell::CellAttributes::default();
+        attributes
+            .set_intensity(termwiz::cell::Intensity::Bold)
+            .set_underline(termwiz::cell::Underline::Double)
+            .set_blink(termwiz::cell::Blink::Slow)
+            .set_italic(true)
+            .set_reverse(true)
+            .set_strikethrough(true)
+            .set_overline(true)
+            .set_foreground(
+                termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(
+                    termwiz::color::SrgbaTuple(1.0, 0.0, 0.0, 1.0),
+                ),
+            )
+            .set_background(termwiz::color::ColorAttribute::PaletteIndex(4));
+
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let mut shadow_terminal = crate::shadow_terminal::ShadowTerminal::new(
+            crate::shadow_terminal::Config::default(),
+            shadow_output_tx,
+        );
+        shadow_terminal
+            .terminal
+            .advance_bytes(format!("{}x", for_attributes(&attributes)));
+
+        let lines = shadow_terminal.terminal.screen().lines_in_phys_range(0..1);
+        let cell = lines.first().unwrap().visible_cells().next().unwrap();
+        let parsed = cell.attrs();
+        assert_eq!(cell.str(), "x");
+        assert_eq!(parsed.intensity(), attributes.intensity());
+        assert_eq!(parsed.underline(), attributes.underline());
+        assert_eq!(parsed.blink(), attributes.blink());
+        assert!(parsed.italic());
+        assert!(parsed.reverse());
+        assert!(parsed.strikethrough());
+        assert!(parsed.overline());
+        assert_eq!(parsed.foreground(), attributes.foreground());
+        assert_eq!(parsed.background(), attributes.background());
+    }
+
+    #[test]
+    fn default_attributes_are_just_a_reset() {
+        let default = termwiz::cell::CellAttributes::default();
+        assert_eq!(for_attributes(&default), "\x1b[0m");
+    }
+}
@@ -7,13 +7,14 @@ use tracing::Instrument as _;
 /// Wezterm's internal configuration
 #[derive(Debug)]
 struct WeztermConfig {
-    /// The number of lines to store in the scrollback
-    scrollback: usize,
+    /// The number of lines to store in the scrollback. It's shared so that it can be lowered to
+    /// stay within the scrollback's memory budget.
+    scrollback: std::sync::Arc<std::sync::atomic::AtomicUsize>,
 }
 
 impl wezterm_term::TerminalConfiguration for WeztermConfig {
     fn scrollback_size(&self) -> usize {
-        self.scrollback
+        self.scrollback.load(std::sync::atomic::Ordering::Relaxed)
     }
 
     fn color_palette(&self) -> wezterm_term::color::ColorPalette {
@@ -21,6 +22,69 @@ impl wezterm_term::TerminalConfiguration for WeztermConfig {
     }
 }
 
+/// Wezterm writes any bytes that it generates, like encoded key presses and the responses to
+/// queries from applications, into this writer. We keep hold of the other end so that those bytes
+/// can be forwarded to the PTY.
+#[derive(Debug, Clone, Default)]
+struct TerminalWriter {
+    /// The bytes written by the Wezterm terminal that haven't been taken yet.
+    buffer: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+    /// Notified whenever new bytes are written. Wezterm may write from its own thread, so the main
+    /// loop listens for this rather than assuming that the bytes are there as soon as Wezterm has
+    /// parsed a query.
+    written: std::sync::Arc<tokio::sync::Notify>,
+}
+
+impl std::io::Write for TerminalWriter {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        match self.buffer.lock() {
+            Ok(mut buffer) => buffer.extend_from_slice(bytes),
+            Err(error) => tracing::error!("Couldn't lock terminal writer buffer: {error:?}"),
+        }
+        self.written.notify_one();
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Wezterm notifies this of alerts from the running application. Only the bell and title changes
+/// are broadcast, the other alerts are already part of the terminal's output.
+struct AlertBroadcaster {
+    /// The control channel that the alerts are broadcast on.
+    control_tx: tokio::sync::broadcast::Sender<crate::Protocol>,
+    /// The terminal's current title, kept up to date with the application's title changes.
+    title: std::sync::Arc<std::sync::Mutex<String>>,
+}
+
+impl wezterm_term::AlertHandler for AlertBroadcaster {
+    fn alert(&mut self, alert: wezterm_term::Alert) {
+        #[expect(
+            clippy::wildcard_enum_match_arm,
+            reason = "Only some alerts are broadcast"
+        )]
+        let message = match alert {
+            wezterm_term::Alert::Bell => crate::Protocol::Bell,
+            wezterm_term::Alert::WindowTitleChanged(title) => {
+                match self.title.lock() {
+                    Ok(mut current) => current.clone_from(&title),
+                    Err(error) => tracing::error!("Couldn't lock the terminal's title: {error:?}"),
+                }
+                crate::Protocol::Title(title)
+            }
+            _ => return,
+        };
+
+        tracing::trace!("Broadcasting terminal alert: {message:?}");
+        let result = self.control_tx.send(message);
+        if let Err(error) = result {
+            tracing::error!("Couldn't broadcast terminal alert: {error:?}");
+        }
+    }
+}
+
 /// Config for creating a shadow terminal.
 #[expect(
     clippy::exhaustive_structs,
@@ -40,8 +104,100 @@ pub struct Config {
     pub command: Vec<std::ffi::OsString>,
     /// The size of ther terminal's scrollback history.
     pub scrollback_size: usize,
+    /// An optional ceiling, in bytes, for the estimated memory used by the scrollback. Lines vary
+    /// a lot in width, so `scrollback_size` alone doesn't predict memory use. When the budget is
+    /// exceeded the oldest lines are trimmed, as new lines arrive.
+    pub scrollback_memory_budget: Option<usize>,
+    /// Compress the scrollback lines that are more than this many lines above the screen. They
+    /// use a lot less memory, at the cost of a little CPU to decompress them when they're
+    /// changed. The screen and the lines nearest to it are always kept uncompressed, for speed.
+    /// `None` disables compression.
+    pub scrollback_compression_threshold: Option<usize>,
     /// The number of lines that each scroll trigger moves.
     pub scrollback_step: usize,
+    /// An optional read-only "tee" of the raw PTY bytes, sent before they're parsed by the
+    /// Wezterm terminal. Useful for logging or for anything else that wants to observe the raw
+    /// output stream. It never blocks rendering: if the channel is full then the bytes are dropped.
+    pub tee_tx: Option<tokio::sync::mpsc::Sender<Vec<u8>>>,
+    /// Broadcast `Protocol::OutputLine` for every logical line as it's finalised.
+    pub emit_output_lines: bool,
+    /// Broadcast `Protocol::WorkingDirectory` whenever the running application reports a new
+    /// working directory.
+    pub emit_working_directory: bool,
+    /// The number of columns between the initial tab stops. Applications can still set their own
+    /// tab stops, which are honoured until the terminal is resized. Wezterm resets the tab stops
+    /// whenever it's resized, so then they're set back to every `tab_width` columns.
+    pub tab_width: usize,
+    /// Whether dropping the `ShadowTerminal` also shuts down the PTY. Disable this when the
+    /// terminal's lifetime is managed elsewhere, for example when ownership is handed to a
+    /// multiplexer. The PTY then keeps running until `Protocol::End` is sent.
+    pub kill_on_drop: bool,
+    /// Lines to preload into the scrollback history before the PTY starts, for example to restore
+    /// a previous session. They may contain ANSI codes, like colours.
+    pub initial_scrollback: Vec<String>,
+    /// The size, in bytes, of the buffer used to read output from the PTY. Bigger buffers mean
+    /// fewer, larger payloads for big repaints, although the OS may still cap the size of reads.
+    pub pty_read_buffer_size: usize,
+    /// Kinds of escape sequence to strip from the PTY output before it's parsed. This is a safety
+    /// valve for hosting untrusted or buggy programs. It's empty by default, for full fidelity.
+    /// Note that the tee still gets the raw, unfiltered bytes.
+    pub strip_sequences: Vec<crate::sanitise::SequenceKind>,
+    /// The time between each change of the cursor's blink phase, when the application has set a
+    /// blinking cursor shape. `None` disables blinking entirely.
+    pub cursor_blink_interval: Option<tokio::time::Duration>,
+    /// Broadcast `Protocol::KeyOutput` whenever the PTY outputs printable characters.
+    pub emit_key_output: bool,
+    /// Send the screen and scrollback together, as a single [`crate::output::Output::Frame`],
+    /// rather than as separate outputs.
+    pub emit_frames: bool,
+    /// Restart the PTY's application whenever it crashes, that is, whenever it's killed by a
+    /// signal or exits with one of the policy's crash exit codes. Useful for long-running
+    /// dashboards that should heal themselves. `None`
+    /// ends the shadow terminal when the application exits, whatever its exit status.
+    pub restart_policy: Option<RestartPolicy>,
+}
+
+/// How to restart the PTY's application when it crashes.
+#[expect(
+    clippy::exhaustive_structs,
+    reason = "It's config, just like `Config`, so it should be just as easy to specify"
+)]
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// The most times that the application is restarted. After that, the next crash ends the
+    /// shadow terminal as normal.
+    pub max_restarts: u32,
+    /// How long to wait before the first restart. The wait doubles with every restart after that.
+    pub backoff: tokio::time::Duration,
+    /// The exit codes that also count as crashes. Being killed by a signal always counts as a
+    /// crash, but an unsuccessful exit code often doesn't mean much. Shells, for example, exit
+    /// with the code of the last command that the user ran.
+    pub crash_exit_codes: Vec<u32>,
+}
+
+impl RestartPolicy {
+    /// How long to wait before restarting, given the number of restarts so far. `None` once there
+    /// have been too many restarts.
+    #[inline]
+    #[must_use]
+    pub fn delay(&self, restarts: u32) -> Option<tokio::time::Duration> {
+        if restarts >= self.max_restarts {
+            return None;
+        }
+
+        Some(self.backoff.saturating_mul(2u32.saturating_pow(restarts)))
+    }
+
+    /// Whether the application's exit counts as a crash, and so whether it should be restarted.
+    pub(crate) fn is_crash(&self, status: &portable_pty::ExitStatus) -> bool {
+        if status.success() {
+            return false;
+        }
+
+        // `portable_pty` doesn't expose the signal that killed the process, only its description.
+        let is_signalled = status.to_string().starts_with("Terminated by");
+        is_signalled || self.crash_exit_codes.contains(&status.exit_code())
+    }
 }
 
 impl Default for Config {
@@ -50,13 +206,63 @@ impl Default for Config {
         Self {
             width: 100,
             height: 30,
-            command: vec!["bash".into()],
+            command: default_command(),
             scrollback_size: 1000,
+            scrollback_memory_budget: None,
+            scrollback_compression_threshold: None,
             scrollback_step: 5,
+            tee_tx: None,
+            emit_output_lines: false,
+            emit_working_directory: true,
+            tab_width: DEFAULT_TAB_WIDTH,
+            kill_on_drop: true,
+            initial_scrollback: Vec::new(),
+            pty_read_buffer_size: crate::pty::DEFAULT_READ_BUFFER_SIZE,
+            strip_sequences: Vec::new(),
+            cursor_blink_interval: Some(tokio::time::Duration::from_millis(
+                crate::cursor::DEFAULT_CURSOR_BLINK_INTERVAL,
+            )),
+            emit_key_output: false,
+            emit_frames: false,
+            restart_policy: None,
         }
     }
 }
 
+/// Shells to try, in order, when `$SHELL` isn't set or doesn't exist.
+#[cfg(not(target_os = "windows"))]
+const FALLBACK_SHELLS: [&str; 3] = ["/bin/bash", "/bin/zsh", "/bin/sh"];
+
+/// The default command to run in the PTY.
+///
+/// On Unix it's resolved in this order, picking the first that exists:
+///   1. The user's `$SHELL`
+///   2. `/bin/bash`
+///   3. `/bin/zsh`
+///   4. `/bin/sh`
+///
+/// If none of them exist then it's just `bash`, and we let the PTY report the failure.
+///
+/// On Windows it's always `powershell.exe`.
+fn default_command() -> Vec<std::ffi::OsString> {
+    #[cfg(not(target_os = "windows"))]
+    let command = std::env::var_os("SHELL")
+        .filter(|shell| std::path::Path::new(shell).exists())
+        .or_else(|| {
+            FALLBACK_SHELLS
+                .iter()
+                .find(|shell| std::path::Path::new(shell).exists())
+                .map(std::convert::Into::into)
+        })
+        .unwrap_or_else(|| "bash".into());
+
+    #[cfg(target_os = "windows")]
+    let command: std::ffi::OsString = "powershell.exe".into();
+
+    tracing::trace!("Default shadow terminal command: {command:?}");
+    vec![command]
+}
+
 /// The various inter-task/thread channels needed to run the shadow terminal and the PTY
 /// simultaneously.
 #[non_exhaustive]
@@ -73,6 +279,97 @@ pub struct Channels {
     shadow_output: tokio::sync::mpsc::Sender<crate::output::Output>,
 }
 
+/// Shared flags for checking whether a shadow terminal is still running. They can be cloned and
+/// checked from outside the task that runs the shadow terminal.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Liveness {
+    /// Whether the PTY's child process is still running.
+    pub pty: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// Whether the shadow terminal's main loop is still running.
+    pub main_loop: std::sync::atomic::AtomicBool,
+}
+
+impl Liveness {
+    /// Are both the PTY and the shadow terminal's main loop still running?
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.pty.load(std::sync::atomic::Ordering::Relaxed)
+            && self.main_loop.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// A clonable handle for writing into the PTY, as if the text had been typed or pasted. It can be
+/// used from outside the task that runs the shadow terminal.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct InputSender {
+    /// The PTY's channel for internally generated input.
+    input_tx: tokio::sync::mpsc::Sender<crate::pty::BytesFromSTDIN>,
+    /// Whether the running application has enabled bracketed paste mode (DECSET 2004).
+    is_bracketed_paste: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl InputSender {
+    /// Write bytes directly into the PTY, as if they had been typed. Waits if the PTY's input
+    /// channel is full.
+    ///
+    /// # Errors
+    /// If the bytes can't be sent to the PTY.
+    #[inline]
+    pub async fn write_bytes(
+        &self,
+        bytes: &[u8],
+    ) -> Result<(), crate::errors::ShadowTerminalError> {
+        for chunk in bytes.chunks(128) {
+            let mut payload: crate::pty::BytesFromSTDIN = [0; 128];
+            crate::pty::PTY::add_bytes_to_buffer(&mut payload, chunk).with_whatever_context(
+                |error| format!("Couldn't add bytes to internal input buffer: {error:?}"),
+            )?;
+
+            let result = self.input_tx.send(payload).await;
+            if let Err(error) = result {
+                snafu::whatever!("Couldn't send internal input: {error:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write text directly into the PTY, as if it had been typed. For example, to run a command
+    /// end the text with a newline.
+    ///
+    /// # Errors
+    /// If the text can't be sent to the PTY.
+    #[inline]
+    pub async fn write_str(&self, text: &str) -> Result<(), crate::errors::ShadowTerminalError> {
+        self.write_bytes(text.as_bytes()).await
+    }
+
+    /// Paste text into the PTY. It's wrapped in bracketed paste codes if the running application
+    /// has enabled bracketed paste mode, so that the application knows it wasn't typed.
+    ///
+    /// # Errors
+    /// If the text can't be sent to the PTY.
+    #[inline]
+    pub async fn paste_str(&self, text: &str) -> Result<(), crate::errors::ShadowTerminalError> {
+        if !self
+            .is_bracketed_paste
+            .load(std::sync::atomic::Ordering::Relaxed)
+        {
+            return self.write_str(text).await;
+        }
+
+        // Pasted text mustn't be able to end the paste early.
+        let text = text.replace(BRACKETED_PASTE_END, "");
+        self.write_str(&format!(
+            "{BRACKETED_PASTE_START}{text}{BRACKETED_PASTE_END}"
+        ))
+        .await
+    }
+}
+
 /// Keep track of the metadata for the last sent output.
 #[non_exhaustive]
 pub struct LastSent {
@@ -80,10 +377,32 @@ pub struct LastSent {
     pub pty_sequence: usize,
     /// The size of the last sent terminal output.
     pub pty_size: (usize, usize),
+    /// The stable row at the top of the last sent screen.
+    pub screen_top: wezterm_term::StableRowIndex,
 }
 
-/// The special ANSI code that applications send to get a reply with the current cursor position.
-const CURSOR_POSITION_REQUEST: &str = "\x1b[6n";
+/// The conventional number of columns between tab stops, which is also Wezterm's default.
+const DEFAULT_TAB_WIDTH: usize = 8;
+
+/// The capacity of the control protocol's broadcast channel. It also carries output events, like
+/// finalised lines, which can arrive in bursts.
+const CONTROL_CHANNEL_CAPACITY: usize = 1024;
+
+/// The ANSI code (DECSET 2026) that applications send to start a synchronized update.
+const SYNCHRONIZED_OUTPUT_BEGIN: &str = "\x1b[?2026h";
+
+/// The ANSI code (DECRST 2026) that applications send to end a synchronized update.
+const SYNCHRONIZED_OUTPUT_END: &str = "\x1b[?2026l";
+
+/// The code that starts pasted text, when bracketed paste mode is enabled.
+const BRACKETED_PASTE_START: &str = "\x1b[200~";
+
+/// The code that ends pasted text, when bracketed paste mode is enabled.
+const BRACKETED_PASTE_END: &str = "\x1b[201~";
+
+/// The longest time, in milliseconds, to defer output for a synchronized update. This stops an
+/// application that never ends its synchronized update from stalling rendering forever.
+const SYNCHRONIZED_OUTPUT_TIMEOUT: u64 = 200;
 
 /// The time to wait for more output from the PTY. In microseconds (1000s of a millisecond).
 const TIME_TO_WAIT_FOR_MORE_PTY_OUTPUT: u64 = 1000;
@@ -110,8 +429,45 @@ pub struct ShadowTerminal {
     pub wait_for_output_until: Option<tokio::time::Instant>,
     /// The current position of the scollback buffer.
     pub scroll_position: usize,
+    /// Whether new output keeps the view pinned to the bottom of the scrollback. It's disabled
+    /// when the user scrolls up, so that new output doesn't move what they're reading.
+    pub follow_tail: bool,
     /// Metadata about the most recent sent output.
     pub last_sent: LastSent,
+    /// Whether the next output must be complete surfaces, because a consumer lost sync.
+    pub is_full_redraw_requested: bool,
+    /// Tracks which lines of output have already been broadcast as finalised.
+    pub finalised_lines: crate::lines::FinalisedLines,
+    /// The shared buffer that the Wezterm terminal writes its generated bytes into.
+    terminal_writer: TerminalWriter,
+    /// When the application started its current synchronized update, if it's in one.
+    pub synchronized_output_since: Option<tokio::time::Instant>,
+    /// Whether the PTY and the main loop are still running.
+    pub liveness: std::sync::Arc<Liveness>,
+    /// Strips any unwanted escape sequences from the PTY output.
+    pub sanitiser: crate::sanitise::Sanitiser,
+    /// The current phase of the cursor's blink.
+    pub cursor_blink: crate::cursor::CursorBlink,
+    /// The keystrokes that are waiting to be broadcast.
+    pub key_output: crate::keystrokes::KeyOutput,
+    /// The Wezterm sequence number from which [`Self::changed_rows`] reports changes.
+    pub change_tracking_sequence: usize,
+    /// The number of scrollback lines that Wezterm currently keeps. It's the configured
+    /// `scrollback_size`, unless it's been lowered to stay within the memory budget.
+    pub(crate) scrollback_limit: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    /// The stable row index of the oldest line that hasn't been compressed yet.
+    pub(crate) compressed_scrollback_until: wezterm_term::StableRowIndex,
+    /// The running total of the scrollback's estimated memory, for the memory budget.
+    pub(crate) scrollback_memory: crate::scrollback::ScrollbackMemory,
+    /// The URI of the last working directory that was broadcast.
+    pub(crate) working_directory_uri: Option<String>,
+    /// The terminal's current title, as set by the application or [`Self::set_title`]. It's kept
+    /// here, rather than in Wezterm, because Wezterm's title can only be set by parsing an OSC
+    /// sequence.
+    title: std::sync::Arc<std::sync::Mutex<String>>,
+    /// Whether the running application has enabled bracketed paste mode. It's shared with
+    /// [`InputSender`]s, so that they know whether to wrap pasted text.
+    is_bracketed_paste: std::sync::Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl ShadowTerminal {
@@ -121,22 +477,43 @@ impl ShadowTerminal {
         config: Config,
         shadow_output: tokio::sync::mpsc::Sender<crate::output::Output>,
     ) -> Self {
-        let (control_tx, _) = tokio::sync::broadcast::channel(64);
+        let (control_tx, _) = tokio::sync::broadcast::channel(CONTROL_CHANNEL_CAPACITY);
         let (output_tx, output_rx) = tokio::sync::mpsc::channel(1);
 
         tracing::debug!("Creating the in-memory Wezterm terminal");
-        let terminal = wezterm_term::Terminal::new(
+        let terminal_writer = TerminalWriter::default();
+        let scrollback_limit =
+            std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(config.scrollback_size));
+        let mut terminal = wezterm_term::Terminal::new(
             Self::wezterm_size(config.width.into(), config.height.into()),
             std::sync::Arc::new(WeztermConfig {
-                scrollback: config.scrollback_size,
+                scrollback: std::sync::Arc::clone(&scrollback_limit),
             }),
             "Tattoy",
             "O_o",
-            Box::<Vec<u8>>::default(),
+            Box::new(terminal_writer.clone()),
         );
+        Self::set_tab_stops(&mut terminal, config.tab_width, config.width.into());
+        Self::seed_scrollback(
+            &mut terminal,
+            &config.initial_scrollback,
+            config.height.into(),
+        );
+        // Only set after seeding, so that bells in the seeded scrollback aren't rung again.
+        let title = std::sync::Arc::new(std::sync::Mutex::new(terminal.get_title().to_owned()));
+        terminal.set_notification_handler(Box::new(AlertBroadcaster {
+            control_tx: control_tx.clone(),
+            title: std::sync::Arc::clone(&title),
+        }));
+        let finalised_lines = crate::lines::FinalisedLines {
+            // Seeded lines aren't new output.
+            next_row: terminal.screen().visible_row_to_stable_row(0),
+            ..Default::default()
+        };
 
         let pty_size = (config.width.into(), config.height.into());
-        Self {
+        let sanitiser = crate::sanitise::Sanitiser::new(config.strip_sequences.clone());
+        let shadow_terminal = Self {
             terminal,
             config,
             channels: Channels {
@@ -149,11 +526,33 @@ impl ShadowTerminal {
             accumulated_pty_output: Vec::new(),
             wait_for_output_until: None,
             scroll_position: 0,
+            follow_tail: true,
             last_sent: LastSent {
                 pty_sequence: 0,
                 pty_size,
+                screen_top: 0,
             },
-        }
+            is_full_redraw_requested: false,
+            finalised_lines,
+            terminal_writer,
+            synchronized_output_since: None,
+            liveness: std::sync::Arc::default(),
+            sanitiser,
+            cursor_blink: crate::cursor::CursorBlink::default(),
+            key_output: crate::keystrokes::KeyOutput::default(),
+            change_tracking_sequence: 0,
+            scrollback_limit,
+            compressed_scrollback_until: 0,
+            scrollback_memory: crate::scrollback::ScrollbackMemory::default(),
+            working_directory_uri: None,
+            title,
+            is_bracketed_paste: std::sync::Arc::default(),
+        };
+
+        // The seeded scrollback is a replay of old output, not a live application. So any queries
+        // in it mustn't be answered.
+        shadow_terminal.take_terminal_writer_output();
+        shadow_terminal
     }
 
     /// Start the background PTY process.
@@ -171,41 +570,76 @@ impl ShadowTerminal {
             height: self.config.height,
             control_tx: self.channels.control_tx.clone(),
             output_tx: self.channels.output_tx.clone(),
+            is_alive: std::sync::Arc::clone(&self.liveness.pty),
+            read_buffer_size: self.config.pty_read_buffer_size,
+            restart_policy: self.config.restart_policy.clone(),
         };
 
         // I don't think the PTY should be run in a standard thread, because it's not actually CPU
         // intensive in terms of the current thread. It runs in an OS sub process, so in theory
         // shouldn't conflict with Tokio's IO-focussed scheduler?
         let current_span = tracing::Span::current();
+        // The child hasn't been spawned yet, but it's reported as alive until it's known to have
+        // exited, or the PTY has failed to spawn it.
+        let is_alive = std::sync::Arc::clone(&self.liveness.pty);
+        is_alive.store(true, std::sync::atomic::Ordering::Relaxed);
         tokio::spawn(async move {
-            pty.run(user_input_rx, internal_input_rx)
+            let result = pty
+                .run(user_input_rx, internal_input_rx)
                 .instrument(current_span)
-                .await
+                .await;
+            is_alive.store(false, std::sync::atomic::Ordering::Relaxed);
+            result
         })
     }
 
     /// Start listening to a stream of PTY bytes and render them to a shadow Termwiz surface
+    ///
+    /// # Errors
+    /// If the PTY fails, for example because its command can't be run.
     #[inline]
     pub async fn run(
         &mut self,
         user_input_rx: tokio::sync::mpsc::Receiver<crate::pty::BytesFromSTDIN>,
-    ) {
+    ) -> Result<(), crate::errors::PTYError> {
         tracing::debug!("Starting Shadow Terminal loop...");
 
         let mut control_rx = self.channels.control_tx.subscribe();
-        self.start(user_input_rx);
+        let terminal_written = std::sync::Arc::clone(&self.terminal_writer.written);
+        let mut pty = self.start(user_input_rx);
 
         tracing::debug!("Starting Shadow Terminal main loop");
+        self.liveness
+            .main_loop
+            .store(true, std::sync::atomic::Ordering::Relaxed);
         #[expect(
             clippy::integer_division_remainder_used,
             reason = "`tokio::select!` generates this."
         )]
-        loop {
+        // Note that the branches don't use pattern matching, eg `Some(bytes) = ...`. Because when
+        // a pattern doesn't match, its branch is disabled, and if every branch is disabled then
+        // `tokio::select!` panics. Instead closed channels are handled explicitly, so we always
+        // know why the loop ended.
+        let mut is_output_open = true;
+        let mut is_pty_running = true;
+        let shutdown = loop {
             let is_wait = self.wait_for_output_until.is_some();
             let wait_until = self.wait_for_output_until;
+            let next_cursor_blink = self.next_cursor_blink();
+            let is_blinking = next_cursor_blink.is_some();
+            let blink_at = next_cursor_blink.unwrap_or_else(tokio::time::Instant::now);
+            let next_key_output = self.next_key_output();
+            let is_key_output_pending = next_key_output.is_some();
+            let key_output_at = next_key_output.unwrap_or_else(tokio::time::Instant::now);
             tokio::select! {
-                Some(bytes) = self.channels.output_rx.recv() => {
-                    self.accumulate_pty_output(&bytes);
+                maybe_bytes = self.channels.output_rx.recv(), if is_output_open => {
+                    match maybe_bytes {
+                        Some(bytes) => self.accumulate_pty_output(&bytes),
+                        None => {
+                            tracing::warn!("PTY output channel closed, no more output will be rendered");
+                            is_output_open = false;
+                        }
+                    }
                 },
                 () = Self::wait_for_more_pty_output(wait_until), if is_wait => {
                     let result = self.handle_pty_output().await;
@@ -213,16 +647,53 @@ impl ShadowTerminal {
                         tracing::error!("Handling PTY output: {error:?}");
                     }
                 }
-                Ok(message) = control_rx.recv() => {
-                    self.handle_protocol_message(&message).await;
-                    if matches!(message, crate::Protocol::End) {
-                        break;
+                () = tokio::time::sleep_until(blink_at), if is_blinking => {
+                    self.broadcast_cursor_blink();
+                }
+                () = tokio::time::sleep_until(key_output_at), if is_key_output_pending => {
+                    self.broadcast_key_output();
+                }
+                result = &mut pty, if is_pty_running => {
+                    is_pty_running = false;
+                    let result: Result<(), crate::errors::PTYError> = result
+                        .with_whatever_context(|error| format!("PTY task failed: {error:?}"))
+                        .and_then(std::convert::identity);
+                    // When the PTY finishes normally it broadcasts `Protocol::End`.
+                    if let Err(error) = result {
+                        break Err(error);
+                    }
+                }
+                () = terminal_written.notified() => {
+                    let result = self.forward_terminal_writer_output().await;
+                    if let Err(error) = result {
+                        tracing::error!("Forwarding Wezterm's responses to the PTY: {error:?}");
+                    }
+                }
+                result = control_rx.recv() => {
+                    match result {
+                        Ok(message) => {
+                            self.handle_protocol_message(&message).await;
+                            if matches!(message, crate::Protocol::End) {
+                                break Ok("received `Protocol::End`");
+                            }
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::warn!("Shadow Terminal lagged, missed {missed} protocol messages");
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                            break Ok("control channel closed");
+                        }
                     }
                 }
             }
-        }
+        };
+        self.liveness
+            .main_loop
+            .store(false, std::sync::atomic::Ordering::Relaxed);
 
-        tracing::debug!("Shadow Terminal loop finished");
+        let shutdown_reason = shutdown?;
+        tracing::debug!("Shadow Terminal loop finished: {shutdown_reason}");
+        Ok(())
     }
 
     /// The PTY crate that we use only sends output at 4kb a time. Often, on bigger terminals, a
@@ -235,19 +706,98 @@ impl ShadowTerminal {
         }
     }
 
+    /// Replace Wezterm's default tab stops with ones at every `tab_width` columns. We do this in
+    /// the same way that an application would: with the ANSI codes to clear all tab stops (TBC)
+    /// and then set new ones (HTS). The cursor is moved back to its column afterwards.
+    fn set_tab_stops(terminal: &mut wezterm_term::Terminal, tab_width: usize, width: usize) {
+        if tab_width == DEFAULT_TAB_WIDTH || tab_width == 0 {
+            return;
+        }
+
+        let column = terminal.cursor_pos().x;
+        let tab_stops: String = (tab_width..width)
+            .step_by(tab_width)
+            .map(|stop| format!("\x1b[{}G\x1bH", stop + 1))
+            .collect();
+        terminal.advance_bytes(format!("\x1b[3g{tab_stops}\x1b[{}G", column + 1));
+    }
+
+    /// Resize the Wezterm terminal. Wezterm resets the tab stops when it's resized, so the
+    /// configured ones are set again. Any that the application set itself are lost, just like
+    /// they are in Wezterm.
+    fn resize_terminal(&mut self, width: usize, height: usize) {
+        self.terminal.resize(Self::wezterm_size(width, height));
+        Self::set_tab_stops(&mut self.terminal, self.config.tab_width, width);
+    }
+
+    /// Preload lines into the scrollback. They're written just like normal output and then the
+    /// screen is scrolled until they're all off the screen. So they're real Wezterm lines, that
+    /// scroll and rewrap on resize like any others.
+    fn seed_scrollback(terminal: &mut wezterm_term::Terminal, lines: &[String], height: usize) {
+        if lines.is_empty() {
+            return;
+        }
+
+        let mut seed = lines.join("\r\n");
+        seed.push_str(&"\r\n".repeat(height));
+        seed.push_str("\x1b[H");
+        terminal.advance_bytes(seed);
+    }
+
     /// Accumulate PTY outputs.
     fn accumulate_pty_output(&mut self, bytes: &crate::pty::BytesFromPTY) {
-        self.accumulated_pty_output.append(&mut bytes.to_vec());
+        self.accumulated_pty_output.extend_from_slice(bytes);
         let next_output_broadcast = tokio::time::Instant::now()
             + tokio::time::Duration::from_micros(TIME_TO_WAIT_FOR_MORE_PTY_OUTPUT);
         self.wait_for_output_until = Some(next_output_broadcast);
     }
 
-    /// Find bytes in bytes.
-    fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    /// Find the last occurence of bytes in bytes.
+    fn rfind_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
         haystack
             .windows(needle.len())
-            .position(|window| window == needle)
+            .rposition(|window| window == needle)
+    }
+
+    /// Track whether the application is in the middle of a synchronized update (DECSET 2026).
+    /// Returns `true` if output should be deferred until the update has finished.
+    fn is_output_deferred(&mut self, bytes: &[u8]) -> bool {
+        let begin = Self::rfind_subsequence(bytes, SYNCHRONIZED_OUTPUT_BEGIN.as_bytes());
+        let end = Self::rfind_subsequence(bytes, SYNCHRONIZED_OUTPUT_END.as_bytes());
+        let is_synchronizing = match (begin, end) {
+            (Some(begin_at), Some(end_at)) => begin_at > end_at,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => self.synchronized_output_since.is_some(),
+        };
+
+        if !is_synchronizing {
+            self.synchronized_output_since = None;
+            return false;
+        }
+
+        let now = tokio::time::Instant::now();
+        let since = *self.synchronized_output_since.get_or_insert(now);
+        let deadline = since + tokio::time::Duration::from_millis(SYNCHRONIZED_OUTPUT_TIMEOUT);
+        if now >= deadline {
+            tracing::warn!("Synchronized update timed out, rendering anyway");
+            self.synchronized_output_since = None;
+            return false;
+        }
+
+        // Make sure that we wake up to render, even if the application never ends the update.
+        self.wait_for_output_until = Some(deadline);
+        true
+    }
+
+    /// Share whether the application has enabled bracketed paste mode (DECSET 2004), so that
+    /// [`InputSender::paste_str`] knows whether to wrap pasted text. Wezterm tracks the mode, so
+    /// it's read from Wezterm once the PTY's output has been parsed.
+    fn track_bracketed_paste(&self) {
+        self.is_bracketed_paste.store(
+            self.terminal.bracketed_paste_enabled(),
+            std::sync::atomic::Ordering::Relaxed,
+        );
     }
 
     /// Handle bytes from the PTY
@@ -257,94 +807,299 @@ impl ShadowTerminal {
         let bytes_copy = self.accumulated_pty_output.clone();
         let bytes = bytes_copy.as_slice();
 
-        self.handle_cursor_position_request(bytes).await?;
-        self.terminal.advance_bytes(bytes);
-        tracing::trace!("Wezterm shadow terminal advanced {} bytes", bytes.len());
+        self.tee_pty_output(bytes);
+        let first_row_before = self.terminal.screen().visible_row_to_stable_row(0);
+        let sanitised = self.sanitiser.filter(bytes);
+        tracing::trace!(
+            "Wezterm shadow terminal advancing {} bytes",
+            sanitised.len()
+        );
+        self.count_key_output(&sanitised);
+        self.terminal.advance_bytes(sanitised);
+        self.track_bracketed_paste();
+        // Applications query the terminal, for example for its device attributes (DA1/DA2) or the
+        // cursor's position (DSR). Wezterm answers them by writing to its writer, and the
+        // application is waiting for the answer on its STDIN.
+        self.forward_terminal_writer_output().await?;
+        self.emit_finalised_lines();
+        self.broadcast_working_directory();
+        self.keep_scroll_position(first_row_before);
+        self.apply_scrollback_memory_budget();
+        self.compress_old_scrollback();
+        self.restart_cursor_blink();
+        self.accumulated_pty_output = Vec::new();
+        self.wait_for_output_until = None;
+
+        if self.is_output_deferred(bytes) {
+            tracing::trace!("Deferring output until synchronized update has finished");
+            return Ok(());
+        }
+
         let result = self.send_outputs().await;
         if let Err(error) = result {
             tracing::error!("{error:?}");
         }
-        self.accumulated_pty_output = Vec::new();
-        self.wait_for_output_until = None;
         Ok(())
     }
 
-    /// Some CLI applications need to know where the current cursor is, so that they can decide how
-    /// to draw themselves. They request the cursor position from the host terminal emulator by
-    /// sending the special code: `^[6n`. It is the responsibility of the terminal emulator to
-    /// respond to this request with another ANSI code containing the coordinates of the cursor.
-    #[expect(
-        clippy::needless_pass_by_ref_mut,
-        reason = "
-            When I set this to `&self` then we get an actual compiler error that the `send()` method
-            on the channel is not safe because it's not `Send`. I don't understand this.
-        "
-    )]
-    async fn handle_cursor_position_request(
+    /// When not following the tail of the output, move the scroll position by the number of lines
+    /// of new output, so that the view stays on the same lines.
+    fn keep_scroll_position(&mut self, first_row_before: wezterm_term::StableRowIndex) {
+        if self.follow_tail {
+            self.scroll_position = 0;
+            return;
+        }
+
+        let screen = self.terminal.screen();
+        let first_row_after = screen.visible_row_to_stable_row(0);
+        let new_lines = usize::try_from(first_row_after - first_row_before).unwrap_or(0);
+        let max_position = screen.scrollback_rows() - self.terminal.get_size().rows;
+        self.scroll_position = (self.scroll_position + new_lines).min(max_position);
+    }
+
+    /// Forward a copy of the raw PTY bytes to the optional tee channel.
+    fn tee_pty_output(&self, bytes: &[u8]) {
+        let Some(tee_tx) = self.config.tee_tx.as_ref() else {
+            return;
+        };
+
+        if bytes.is_empty() {
+            return;
+        }
+
+        let result = tee_tx.try_send(bytes.to_vec());
+        if let Err(error) = result {
+            tracing::warn!("Dropping bytes for PTY tee channel: {error:?}");
+        }
+    }
+
+    /// A clonable handle for writing into the PTY from outside the shadow terminal's task. It's
+    /// only available once the PTY has been started.
+    #[inline]
+    #[must_use]
+    pub fn input_sender(&self) -> Option<InputSender> {
+        let input_tx = self.channels.internal_input_tx.clone()?;
+        Some(InputSender {
+            input_tx,
+            is_bracketed_paste: std::sync::Arc::clone(&self.is_bracketed_paste),
+        })
+    }
+
+    /// Send internally generated bytes, like responses to queries, into the PTY.
+    pub(crate) async fn send_internal_input(
+        &self,
+        bytes: &[u8],
+    ) -> Result<(), crate::errors::ShadowTerminalError> {
+        let Some(sender) = self.input_sender() else {
+            return Ok(());
+        };
+
+        sender.write_bytes(bytes).await
+    }
+
+    /// Send everything that the Wezterm terminal has written since last time to the PTY.
+    pub(crate) async fn forward_terminal_writer_output(
+        &mut self,
+    ) -> Result<(), crate::errors::ShadowTerminalError> {
+        let bytes = self.take_terminal_writer_output();
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        tracing::trace!(
+            "Forwarding bytes from Wezterm terminal to PTY: {}",
+            String::from_utf8_lossy(&bytes).replace('\x1b', "^")
+        );
+        self.send_internal_input(&bytes).await
+    }
+
+    /// Write bytes directly into the PTY, as if they had been typed. Waits if the PTY's input
+    /// channel is full.
+    ///
+    /// # Errors
+    /// If the bytes can't be sent to the PTY.
+    #[inline]
+    pub async fn write_bytes(
+        &self,
+        bytes: &[u8],
+    ) -> Result<(), crate::errors::ShadowTerminalError> {
+        self.send_internal_input(bytes).await
+    }
+
+    /// Process bytes as if they had been output by the PTY. The PTY itself doesn't need to be
+    /// running, so it's useful for replaying recordings of terminal sessions.
+    ///
+    /// # Errors
+    /// If the responses to any queries in the bytes can't be forwarded.
+    #[inline]
+    pub async fn replay_pty_output(
         &mut self,
         bytes: &[u8],
     ) -> Result<(), crate::errors::ShadowTerminalError> {
-        if Self::find_subsequence(bytes, CURSOR_POSITION_REQUEST.as_bytes()).is_none() {
+        self.accumulate_pty_output(&bytes.to_vec());
+        self.handle_pty_output().await
+    }
+
+    /// Write text directly into the PTY, as if it had been typed. For example, to run a command
+    /// end the text with a newline.
+    ///
+    /// # Errors
+    /// If the text can't be sent to the PTY.
+    #[inline]
+    pub async fn write_str(&self, text: &str) -> Result<(), crate::errors::ShadowTerminalError> {
+        self.write_bytes(text.as_bytes()).await
+    }
+
+    /// Paste text into the PTY. It's wrapped in bracketed paste codes if the running application
+    /// has enabled bracketed paste mode, so that the application knows it wasn't typed.
+    ///
+    /// # Errors
+    /// If the text can't be sent to the PTY.
+    #[inline]
+    pub async fn paste_str(&self, text: &str) -> Result<(), crate::errors::ShadowTerminalError> {
+        let Some(sender) = self.input_sender() else {
             return Ok(());
+        };
+
+        sender.paste_str(text).await
+    }
+
+    /// Tell the running application that the terminal gained or lost focus. Wezterm only sends
+    /// the `^[[I`/`^[[O` sequences if the application enabled focus reporting (DECSET 1004),
+    /// otherwise this does nothing.
+    ///
+    /// # Errors
+    /// If the focus sequence can't be sent to the PTY.
+    #[inline]
+    pub async fn send_focus(
+        &mut self,
+        is_focused: bool,
+    ) -> Result<(), crate::errors::ShadowTerminalError> {
+        self.terminal.focus_changed(is_focused);
+        self.forward_terminal_writer_output().await
+    }
+
+    /// The terminal's current title, as last set by the running application or by
+    /// [`Self::set_title`].
+    #[inline]
+    #[must_use]
+    pub fn title(&self) -> String {
+        match self.title.lock() {
+            Ok(title) => title.clone(),
+            Err(error) => {
+                tracing::error!("Couldn't lock the terminal's title: {error:?}");
+                self.terminal.get_title().to_owned()
+            }
         }
+    }
 
-        let mut payload: crate::pty::BytesFromSTDIN = [0; 128];
-        let cursor_position = self.terminal.cursor_pos();
-        let response_string = format!("\x1b[{};{}R", cursor_position.y, cursor_position.x);
-        let response_bytes = response_string.as_bytes();
+    /// The total number of rows of output, including both the scrollback history and the screen.
+    #[inline]
+    #[must_use]
+    pub fn scrollback_len(&self) -> usize {
+        self.terminal.screen().scrollback_rows()
+    }
 
-        for chunk in response_bytes.chunks(128) {
-            crate::pty::PTY::add_bytes_to_buffer(&mut payload, chunk).with_whatever_context(
-                |error| format!("Couldn't add response to payload buffer: {error:?}"),
-            )?;
+    /// The size of the visible part of the terminal, in columns and rows.
+    #[inline]
+    #[must_use]
+    pub fn viewport_size(&self) -> (u16, u16) {
+        let size = self.terminal.get_size();
+        (
+            u16::try_from(size.cols).unwrap_or(u16::MAX),
+            u16::try_from(size.rows).unwrap_or(u16::MAX),
+        )
+    }
 
-            if let Some(sender) = self.channels.internal_input_tx.as_ref() {
-                tracing::debug!(
-                    "Responding to cursor position request with: {}",
-                    response_string.replace('\x1b', "^")
-                );
-                let result = sender.send(payload).await;
-                if let Err(error) = result {
-                    snafu::whatever!("Couldn't send internal input: {error:?}");
-                }
+    /// How many rows the view is scrolled up from the bottom of the scrollback. 0 when it isn't
+    /// scrolled at all.
+    #[inline]
+    #[must_use]
+    pub const fn scroll_offset(&self) -> usize {
+        self.scroll_position
+    }
+
+    /// Set the terminal's title, as if the running application had set it, and broadcast
+    /// `Protocol::Title` just the same. It doesn't go through the terminal's parser, so it can't
+    /// interrupt a sequence that the application is in the middle of sending. Control characters
+    /// are removed, just as they would be from an application's title.
+    #[inline]
+    pub fn set_title(&self, title: &str) {
+        let safe_title: String = title
+            .chars()
+            .filter(|character| !character.is_control())
+            .collect();
+        match self.title.lock() {
+            Ok(mut current) => current.clone_from(&safe_title),
+            Err(error) => {
+                tracing::error!("Couldn't lock the terminal's title: {error:?}");
+                return;
             }
         }
 
-        Ok(())
+        let result = self
+            .channels
+            .control_tx
+            .send(crate::Protocol::Title(safe_title));
+        if let Err(error) = result {
+            tracing::error!("Couldn't broadcast the new title: {error:?}");
+        }
+    }
+
+    /// Take all the bytes that the Wezterm terminal has written since the last time they were
+    /// taken. Anything taken, but not forwarded to the PTY, is lost. Which is only ever what we
+    /// want for bytes that no application is waiting for.
+    pub(crate) fn take_terminal_writer_output(&self) -> Vec<u8> {
+        match self.terminal_writer.buffer.lock() {
+            Ok(mut buffer) => std::mem::take(&mut *buffer),
+            Err(error) => {
+                tracing::error!("Couldn't lock terminal writer buffer: {error:?}");
+                Vec::new()
+            }
+        }
     }
 
-    // The output of the PTY seems to be capped at 4095 bytes. Making the size of
-    // [`crate::pty::BytesFromPTY`] bigger than that doesn't seem to make a difference. This means
-    // that for large screen updates `self.build_current_surface()` can be called an unnecessary
-    // number of times.
+    // The output of the PTY is often capped at 4095 bytes per read, even when the read buffer
+    // (see `Config::pty_read_buffer_size`) is bigger. That's an OS limit: the size of the kernel's
+    // TTY buffer. This means that for large screen updates the output could be built an unnecessary
+    // number of times. So consecutive payloads are coalesced for
+    // `TIME_TO_WAIT_FOR_MORE_PTY_OUTPUT` before the output is built and sent.
     //
-    // Possible solutions:
-    //   * Ideally get the PTY to send bigger payloads.
-    //   * Only call `self.build_current_surface()` at a given frame rate, probably 60fps.
-    //     This could be augmented with a check for the size so the payloads smaller than
-    //     4095 get rendered immediately.
-    //   * When receiving a payload of exactly 4095 bytes, wait a fixed amount of time for
-    //     more payloads, because in most cases 4095 means that there wasn't enough room to
-    //     fit everything in a single payload.
-    //   * Make `self.build_current_surface()` able to detect new payloads as they happen
-    //     so it can cancel itself and immediately start working on the new one.
+    // Other possible improvements:
+    //   * Only build the output at a given frame rate, probably 60fps.
+    //   * Make output building able to detect new payloads as they happen so it can cancel
+    //     itself and immediately start working on the new one.
     //
     /// Send the current state of the shadow terminal as a Termwiz surface or changeset to whoever
     /// is externally listening.
     async fn send_outputs(&mut self) -> Result<(), crate::errors::ShadowTerminalError> {
         let screen_output = self.build_current_output(&crate::output::SurfaceKind::Screen)?;
-        self.send_output(screen_output).await?;
+        let scrollback_output = if self.terminal.is_alt_screen_active() {
+            None
+        } else {
+            Some(self.build_current_output(&crate::output::SurfaceKind::Scrollback)?)
+        };
 
-        if !self.terminal.is_alt_screen_active() {
-            let scrollback_output =
-                self.build_current_output(&crate::output::SurfaceKind::Scrollback)?;
-            self.send_output(scrollback_output).await?;
+        if self.config.emit_frames {
+            let frame = crate::output::Frame {
+                screen: Box::new(screen_output),
+                scrollback: scrollback_output.map(Box::new),
+            };
+            self.send_output(crate::output::Output::Frame(frame))
+                .await?;
+        } else {
+            self.send_output(screen_output).await?;
+            if let Some(output) = scrollback_output {
+                self.send_output(output).await?;
+            }
         }
 
         self.last_sent = LastSent {
             pty_sequence: self.terminal.current_seqno(),
             pty_size: (self.terminal.get_size().cols, self.terminal.get_size().rows),
+            screen_top: self.screen_top(),
         };
+        self.is_full_redraw_requested = false;
 
         Ok(())
     }
@@ -372,6 +1127,16 @@ impl ShadowTerminal {
         Ok(())
     }
 
+    /// Is the underlying PTY's child process still running? Note that the main loop can't be
+    /// running at the same time as this is called, because `.run()` borrows the shadow terminal
+    /// mutably. To check the main loop from elsewhere, clone [`Self::liveness`] before calling
+    /// `.run()`.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.liveness.pty.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
     /// Broadcast the shutdown signal. This should exit both the underlying PTY process and the
     /// main `ShadowTerminal` loop.
     ///
@@ -398,10 +1163,10 @@ impl ShadowTerminal {
         #[expect(clippy::wildcard_enum_match_arm, reason = "It's our internal protocol")]
         match message {
             crate::Protocol::Resize { width, height } => {
-                self.terminal.resize(Self::wezterm_size(
-                    usize::from(*width),
-                    usize::from(*height),
-                ));
+                self.resize_terminal(usize::from(*width), usize::from(*height));
+                // Resizing rewraps all the lines, so they all need to be checked again.
+                self.compressed_scrollback_until = 0;
+                self.scrollback_memory = crate::scrollback::ScrollbackMemory::default();
                 tracing::trace!("Wezterm terminal resized to: {width}x{height}");
             }
             crate::Protocol::Scroll(scroll) => {
@@ -412,6 +1177,7 @@ impl ShadowTerminal {
 
                         self.scroll_position += self.config.scrollback_step;
                         self.scroll_position = self.scroll_position.min(total_lines);
+                        self.follow_tail = self.scroll_position == 0;
                     }
                     crate::Scroll::Down => {
                         if self.scroll_position < self.config.scrollback_step {
@@ -419,9 +1185,11 @@ impl ShadowTerminal {
                         } else {
                             self.scroll_position -= self.config.scrollback_step;
                         }
+                        self.follow_tail = self.scroll_position == 0;
                     }
                     crate::Scroll::Cancel => {
                         self.scroll_position = 0;
+                        self.follow_tail = true;
                     }
                 }
 
@@ -430,6 +1198,39 @@ impl ShadowTerminal {
                     tracing::error!("Couldn't send PTY output from shadow terminal: {error:?}");
                 }
             }
+            crate::Protocol::Key {
+                code,
+                mods,
+                event_kind,
+            } => {
+                let result = self.send_key(*code, *mods, *event_kind).await;
+                if let Err(error) = result {
+                    tracing::error!("Couldn't send key event to PTY: {error:?}");
+                }
+            }
+            crate::Protocol::FollowTail(is_following) => {
+                self.follow_tail = *is_following;
+                if *is_following && self.scroll_position != 0 {
+                    self.scroll_position = 0;
+                    let result = self.send_outputs().await;
+                    if let Err(error) = result {
+                        tracing::error!("Couldn't send PTY output from shadow terminal: {error:?}");
+                    }
+                }
+            }
+            crate::Protocol::Focus(is_focused) => {
+                let result = self.send_focus(*is_focused).await;
+                if let Err(error) = result {
+                    tracing::error!("Couldn't send focus event to PTY: {error:?}");
+                }
+            }
+            crate::Protocol::RequestFullRedraw => {
+                self.is_full_redraw_requested = true;
+                let result = self.send_outputs().await;
+                if let Err(error) = result {
+                    tracing::error!("Couldn't send full redraw from shadow terminal: {error:?}");
+                }
+            }
 
             _ => (),
         }
@@ -459,8 +1260,7 @@ impl ShadowTerminal {
         self.channels
             .control_tx
             .send(crate::Protocol::Resize { width, height })?;
-        self.terminal
-            .resize(Self::wezterm_size(width.into(), height.into()));
+        self.resize_terminal(width.into(), height.into());
         Ok(())
     }
 }
@@ -469,9 +1269,394 @@ impl Drop for ShadowTerminal {
     #[inline]
     fn drop(&mut self) {
         tracing::trace!("Running ShadowTerminal.drop()");
+        if !self.config.kill_on_drop {
+            tracing::trace!("Not killing ShadowTerminal on drop because `kill_on_drop` is off");
+            return;
+        }
+
         let result = self.kill();
         if let Err(error) = result {
             tracing::error!("{error:?}");
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Config, ShadowTerminal};
+
+    fn terminal() -> ShadowTerminal {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 10,
+            height: 3,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        let lines: String = (0..10).map(|line| format!("{line}\r\n")).collect();
+        shadow_terminal.terminal.advance_bytes(lines);
+        shadow_terminal
+    }
+
+    async fn output(shadow_terminal: &mut ShadowTerminal, bytes: &str) {
+        shadow_terminal.accumulate_pty_output(&bytes.as_bytes().to_vec());
+        shadow_terminal.handle_pty_output().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn synchronized_output_is_deferred_until_the_update_ends() {
+        let (shadow_output_tx, mut shadow_output_rx) = tokio::sync::mpsc::channel(16);
+        let mut shadow_terminal = ShadowTerminal::new(Config::default(), shadow_output_tx);
+
+        output(&mut shadow_terminal, "\x1b[?2026hhidden").await;
+        output(&mut shadow_terminal, " still hidden").await;
+        assert!(shadow_output_rx.try_recv().is_err());
+
+        output(&mut shadow_terminal, "\x1b[?2026l").await;
+        assert!(shadow_output_rx.try_recv().is_ok());
+        assert!(shadow_terminal.synchronized_output_since.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn synchronized_output_is_rendered_when_the_update_times_out() {
+        let (shadow_output_tx, mut shadow_output_rx) = tokio::sync::mpsc::channel(16);
+        let mut shadow_terminal = ShadowTerminal::new(Config::default(), shadow_output_tx);
+        let timeout = tokio::time::Duration::from_millis(super::SYNCHRONIZED_OUTPUT_TIMEOUT);
+
+        output(&mut shadow_terminal, "\x1b[?2026hnever ended").await;
+        assert!(shadow_output_rx.try_recv().is_err());
+        assert_eq!(
+            shadow_terminal.wait_for_output_until,
+            Some(tokio::time::Instant::now() + timeout)
+        );
+
+        // The main loop wakes up to render at the deadline.
+        tokio::time::advance(timeout).await;
+        shadow_terminal.handle_pty_output().await.unwrap();
+        assert!(shadow_output_rx.try_recv().is_ok());
+        assert!(shadow_terminal.synchronized_output_since.is_none());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dropping_the_terminal_kills_its_child() {
+        let pid_file = std::env::temp_dir().join(format!(
+            "shadow-terminal-kill-on-drop-{}",
+            std::process::id()
+        ));
+        let script = format!("echo $$ > {}; exec sleep 30", pid_file.display());
+        let (shadow_output_tx, _shadow_output_rx) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            command: crate::tests::helpers::shell_command(&script),
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        let liveness = std::sync::Arc::clone(&shadow_terminal.liveness);
+        let (_user_input_tx, user_input_rx) = tokio::sync::mpsc::channel(1);
+        let _pty = shadow_terminal.start(user_input_rx);
+
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(5);
+        let pid = loop {
+            let contents = std::fs::read_to_string(&pid_file).unwrap_or_default();
+            if let Ok(pid) = contents.trim().parse::<libc::pid_t>() {
+                break pid;
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "Child never started"
+            );
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        };
+        std::fs::remove_file(&pid_file).unwrap();
+
+        drop(shadow_terminal);
+        while liveness.pty.load(std::sync::atomic::Ordering::Relaxed) {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "Child wasn't killed"
+            );
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+
+        // SAFETY: `kill()` has no memory safety requirements, it just takes 2 integers. Signal 0
+        // only checks whether the process exists.
+        let result = unsafe { libc::kill(pid, 0) };
+        assert_eq!(result, -1, "Child {pid} is still running");
+    }
+
+    #[tokio::test]
+    async fn the_configured_tab_stops_are_set_again_after_a_resize() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            width: 10,
+            height: 3,
+            tab_width: 4,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        // An application's own tab stop, at column 2.
+        shadow_terminal
+            .terminal
+            .advance_bytes("\x1b[3G\x1bH\x1b[1G");
+
+        shadow_terminal
+            .handle_protocol_message(&crate::Protocol::Resize {
+                width: 20,
+                height: 3,
+            })
+            .await;
+        shadow_terminal.terminal.advance_bytes("\t");
+        assert_eq!(shadow_terminal.terminal.cursor_pos().x, 4);
+        shadow_terminal.terminal.advance_bytes("\t\t");
+        assert_eq!(shadow_terminal.terminal.cursor_pos().x, 12);
+    }
+
+    #[tokio::test]
+    async fn running_a_missing_command_fails() {
+        let (shadow_output_tx, _shadow_output_rx) = tokio::sync::mpsc::channel(1);
+        let config = Config {
+            command: vec!["definitely-not-a-real-command".into()],
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        let (_user_input_tx, user_input_rx) = tokio::sync::mpsc::channel(1);
+
+        let result = tokio::time::timeout(
+            tokio::time::Duration::from_secs(5),
+            shadow_terminal.run(user_input_rx),
+        )
+        .await
+        .unwrap();
+        assert!(result.is_err());
+        assert!(!shadow_terminal.liveness.is_alive());
+    }
+
+    #[tokio::test]
+    async fn new_output_keeps_view_when_not_following_tail() {
+        let mut shadow_terminal = terminal();
+        shadow_terminal.scroll_position = 2;
+        shadow_terminal.follow_tail = false;
+
+        output(&mut shadow_terminal, "new\r\nlines\r\n").await;
+        assert_eq!(shadow_terminal.scroll_position, 4);
+    }
+
+    #[test]
+    fn scroll_geometry() {
+        let mut shadow_terminal = terminal();
+        assert_eq!(shadow_terminal.scrollback_len(), 11);
+        assert_eq!(shadow_terminal.viewport_size(), (10, 3));
+        assert_eq!(shadow_terminal.scroll_offset(), 0);
+
+        shadow_terminal.scroll_position = 2;
+        assert_eq!(shadow_terminal.scroll_offset(), 2);
+    }
+
+    #[tokio::test]
+    async fn new_output_pins_view_to_bottom_when_following_tail() {
+        let mut shadow_terminal = terminal();
+        shadow_terminal.scroll_position = 2;
+
+        output(&mut shadow_terminal, "new\r\nlines\r\n").await;
+        assert_eq!(shadow_terminal.scroll_position, 0);
+    }
+
+    /// Wait for the next input that the shadow terminal sends to the PTY.
+    async fn next_pty_input(
+        shadow_terminal: &mut ShadowTerminal,
+        input_rx: &mut tokio::sync::mpsc::Receiver<crate::pty::BytesFromSTDIN>,
+    ) -> String {
+        tokio::time::timeout(tokio::time::Duration::from_secs(1), async {
+            loop {
+                shadow_terminal
+                    .forward_terminal_writer_output()
+                    .await
+                    .unwrap();
+                if let Ok(payload) = input_rx.try_recv() {
+                    let bytes: Vec<u8> =
+                        payload.into_iter().take_while(|byte| *byte != 0).collect();
+                    return String::from_utf8(bytes).unwrap();
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn device_attributes_queries_are_answered() {
+        let mut shadow_terminal = terminal();
+        let (input_tx, mut input_rx) = tokio::sync::mpsc::channel(8);
+        shadow_terminal.channels.internal_input_tx = Some(input_tx);
+
+        output(&mut shadow_terminal, "\x1b[c").await;
+        let response = next_pty_input(&mut shadow_terminal, &mut input_rx).await;
+        assert!(response.starts_with("\x1b[?"), "{response:?}");
+        assert!(response.ends_with('c'), "{response:?}");
+    }
+
+    #[test]
+    fn restarts_back_off_until_there_have_been_too_many() {
+        let policy = super::RestartPolicy {
+            max_restarts: 3,
+            backoff: tokio::time::Duration::from_millis(100),
+            crash_exit_codes: Vec::new(),
+        };
+        let delays: Vec<_> = (0..4).map(|restarts| policy.delay(restarts)).collect();
+        assert_eq!(
+            delays,
+            vec![
+                Some(tokio::time::Duration::from_millis(100)),
+                Some(tokio::time::Duration::from_millis(200)),
+                Some(tokio::time::Duration::from_millis(400)),
+                None,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn full_redraws_send_complete_surfaces() {
+        let mut shadow_terminal = terminal();
+        shadow_terminal.last_sent.pty_sequence = shadow_terminal.terminal.current_seqno();
+        let output = shadow_terminal
+            .build_current_output(&crate::output::SurfaceKind::Scrollback)
+            .unwrap();
+        assert!(matches!(output, crate::output::Output::Diff(_)));
+
+        shadow_terminal.is_full_redraw_requested = true;
+        let output = shadow_terminal
+            .build_current_output(&crate::output::SurfaceKind::Scrollback)
+            .unwrap();
+        assert!(matches!(output, crate::output::Output::Complete(_)));
+    }
+
+    #[tokio::test]
+    async fn titles_are_broadcast() {
+        let mut shadow_terminal = terminal();
+        let mut control_rx = shadow_terminal.channels.control_tx.subscribe();
+        let titles = |control_rx: &mut tokio::sync::broadcast::Receiver<crate::Protocol>| {
+            std::iter::from_fn(|| control_rx.try_recv().ok())
+                .filter_map(|message| {
+                    if let crate::Protocol::Title(title) = message {
+                        Some(title)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        output(&mut shadow_terminal, "\x1b]2;from the app\x07").await;
+        assert_eq!(shadow_terminal.title(), "from the app");
+        assert_eq!(titles(&mut control_rx), vec!["from the app"]);
+
+        shadow_terminal.set_title("from\x07 the host");
+        assert_eq!(shadow_terminal.title(), "from the host");
+        assert_eq!(titles(&mut control_rx), vec!["from the host"]);
+
+        // Setting the title in the middle of the application's own sequence doesn't break it.
+        output(&mut shadow_terminal, "\x1b]2;half").await;
+        shadow_terminal.set_title("interruption");
+        output(&mut shadow_terminal, " and half\x07").await;
+        assert_eq!(shadow_terminal.title(), "half and half");
+        assert_eq!(
+            titles(&mut control_rx),
+            vec!["interruption", "half and half"]
+        );
+    }
+
+    #[tokio::test]
+    async fn bells_are_broadcast() {
+        let mut shadow_terminal = terminal();
+        let mut control_rx = shadow_terminal.channels.control_tx.subscribe();
+
+        output(&mut shadow_terminal, "ding\x07").await;
+        let is_rung = std::iter::from_fn(|| control_rx.try_recv().ok())
+            .any(|message| matches!(message, crate::Protocol::Bell));
+        assert!(is_rung);
+    }
+
+    #[tokio::test]
+    async fn cursor_position_reports_are_answered_exactly_once() {
+        let mut shadow_terminal = terminal();
+        let (input_tx, mut input_rx) = tokio::sync::mpsc::channel(8);
+        shadow_terminal.channels.internal_input_tx = Some(input_tx);
+
+        output(&mut shadow_terminal, "ab\x1b[6n").await;
+        let response = next_pty_input(&mut shadow_terminal, &mut input_rx).await;
+        // Reports are 1-based.
+        assert_eq!(response, "\x1b[1;3R");
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        shadow_terminal
+            .forward_terminal_writer_output()
+            .await
+            .unwrap();
+        assert!(input_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn pastes_are_only_bracketed_when_the_application_asks() {
+        let mut shadow_terminal = terminal();
+        let (input_tx, mut input_rx) = tokio::sync::mpsc::channel(8);
+        shadow_terminal.channels.internal_input_tx = Some(input_tx);
+        let sender = shadow_terminal.input_sender().unwrap();
+
+        sender.paste_str("plain").await.unwrap();
+        let pasted = next_pty_input(&mut shadow_terminal, &mut input_rx).await;
+        assert_eq!(pasted, "plain");
+
+        // The mode can be enabled by a sequence that's split between payloads.
+        output(&mut shadow_terminal, "\x1b[?20").await;
+        output(&mut shadow_terminal, "04h").await;
+        sender.paste_str("a\x1b[201~b").await.unwrap();
+        let pasted = next_pty_input(&mut shadow_terminal, &mut input_rx).await;
+        assert_eq!(pasted, "\x1b[200~ab\x1b[201~");
+
+        output(&mut shadow_terminal, "\x1b[?2004l").await;
+        shadow_terminal.write_str("typed").await.unwrap();
+        let typed = next_pty_input(&mut shadow_terminal, &mut input_rx).await;
+        assert_eq!(typed, "typed");
+    }
+
+    #[tokio::test]
+    async fn pending_responses_are_forwarded_along_with_key_presses() {
+        let mut shadow_terminal = terminal();
+        let (input_tx, mut input_rx) = tokio::sync::mpsc::channel(8);
+        shadow_terminal.channels.internal_input_tx = Some(input_tx);
+
+        shadow_terminal.terminal.advance_bytes("\x1b[c");
+        shadow_terminal
+            .send_key(
+                wezterm_term::KeyCode::Char('a'),
+                wezterm_term::KeyModifiers::NONE,
+                crate::keys::KeyEventKind::Press,
+            )
+            .await
+            .unwrap();
+
+        let mut input = String::new();
+        while !(input.contains("\x1b[?") && input.contains('a')) {
+            input.push_str(&next_pty_input(&mut shadow_terminal, &mut input_rx).await);
+        }
+    }
+
+    #[tokio::test]
+    async fn screen_and_scrollback_are_sent_together_as_frames() {
+        let (shadow_output_tx, mut shadow_output_rx) = tokio::sync::mpsc::channel(2);
+        let config = Config {
+            emit_frames: true,
+            ..Config::default()
+        };
+        let mut shadow_terminal = ShadowTerminal::new(config, shadow_output_tx);
+        shadow_terminal.terminal.advance_bytes("hello");
+        shadow_terminal.send_outputs().await.unwrap();
+
+        let Ok(crate::output::Output::Frame(frame)) = shadow_output_rx.try_recv() else {
+            panic!("Expected a frame");
+        };
+        assert!(frame.scrollback.is_some());
+        assert!(shadow_output_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Persist and restore the state of a shadow terminal, for crash recovery and session restore.
+//!
+//! The state is stored in a small versioned binary format. The contents of each line are encoded
+//! as plain text with ANSI codes for their attributes. Restoring is then just a matter of having
+//! the Wezterm terminal parse them, just like any other output. That way the restored terminal
+//! builds its surfaces in exactly the same way as the original.
+//!
+//! Note that Wezterm only exposes the currently active screen. So when the alternate screen is
+//! active, it's only the alternate screen that's saved. The primary screen's scrollback is lost.
+
+use snafu::{OptionExt as _, ResultExt as _};
+
+/// Identifies the bytes as a serialized shadow terminal.
+const MAGIC: &[u8; 4] = b"SHDW";
+
+/// The current version of the state format. Bump this whenever the format changes.
+const VERSION: u8 = 1;
+
+/// The ANSI codes for entering the alternate screen.
+const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+
+/// The ANSI code (DECDWL) for making the current line double width.
+const DOUBLE_WIDTH: &str = "\x1b#6";
+
+/// The ANSI code (DECDHL) for making the current line the top half of a double height line.
+const DOUBLE_HEIGHT_TOP: &str = "\x1b#3";
+
+/// The ANSI code (DECDHL) for making the current line the bottom half of a double height line.
+const DOUBLE_HEIGHT_BOTTOM: &str = "\x1b#4";
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Serialize the screen, scrollback, cursor position and alternate screen state.
+    #[inline]
+    #[must_use]
+    pub fn serialize_state(&self) -> Vec<u8> {
+        let size = self.terminal.get_size();
+        let cursor = self.terminal.cursor_pos();
+        let screen = self.terminal.screen();
+        let lines = screen.lines_in_phys_range(0..screen.scrollback_rows());
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        push_u32(&mut bytes, size.cols);
+        push_u32(&mut bytes, size.rows);
+        push_u32(&mut bytes, cursor.x);
+        push_u32(&mut bytes, usize::try_from(cursor.y).unwrap_or(0));
+        bytes.push(u8::from(self.terminal.is_alt_screen_active()));
+        push_u32(&mut bytes, lines.len());
+        for line in &lines {
+            let encoded = encode_line(line);
+            push_u32(&mut bytes, encoded.len());
+            bytes.extend_from_slice(encoded.as_bytes());
+            bytes.push(u8::from(line.last_cell_was_wrapped()));
+        }
+
+        bytes
+    }
+
+    /// Create a new shadow terminal from previously serialized state. The size of the terminal is
+    /// taken from the state, not the config.
+    ///
+    /// # Errors
+    /// If the state isn't valid.
+    #[inline]
+    pub fn restore(
+        state: &[u8],
+        mut config: crate::shadow_terminal::Config,
+        shadow_output: tokio::sync::mpsc::Sender<crate::output::Output>,
+    ) -> Result<Self, crate::errors::ShadowTerminalError> {
+        let mut reader = Reader { bytes: state };
+        if reader.take(MAGIC.len())? != MAGIC {
+            snafu::whatever!("Not a serialized shadow terminal");
+        }
+        let version = reader.take_u8()?;
+        if version != VERSION {
+            snafu::whatever!("Unsupported shadow terminal state version: {version}");
+        }
+
+        let width = reader.take_u32()?;
+        let height = reader.take_u32()?;
+        let cursor_x = reader.take_u32()?;
+        let cursor_y = reader.take_u32()?;
+        let is_alternate_screen = reader.take_u8()? == 1;
+
+        let mut contents = String::new();
+        if is_alternate_screen {
+            contents.push_str(ENTER_ALTERNATE_SCREEN);
+        }
+        let line_count = reader.take_u32()?;
+        for index in 0..line_count {
+            let length = reader.take_u32()?;
+            let line = std::str::from_utf8(reader.take(length)?)
+                .with_whatever_context(|error| format!("Line {index} isn't UTF8: {error:?}"))?;
+            contents.push_str(line);
+            let is_wrapped = reader.take_u8()? == 1;
+            if !is_wrapped && index + 1 < line_count {
+                contents.push_str("\r\n");
+            }
+        }
+
+        config.width = width
+            .try_into()
+            .with_whatever_context(|error| format!("Bad width: {error:?}"))?;
+        config.height = height
+            .try_into()
+            .with_whatever_context(|error| format!("Bad height: {error:?}"))?;
+        config.initial_scrollback = Vec::new();
+
+        let mut shadow_terminal = Self::new(config, shadow_output);
+        shadow_terminal.terminal.advance_bytes(contents);
+        shadow_terminal
+            .terminal
+            .advance_bytes(format!("\x1b[{};{}H", cursor_y + 1, cursor_x + 1));
+        // Like seeded scrollback, the restored contents are old output, so their queries mustn't
+        // be answered.
+        shadow_terminal.take_terminal_writer_output();
+        shadow_terminal.finalised_lines.next_row = shadow_terminal
+            .terminal
+            .screen()
+            .visible_row_to_stable_row(0);
+
+        Ok(shadow_terminal)
+    }
+}
+
+/// Append a number to the state bytes.
+fn push_u32(bytes: &mut Vec<u8>, number: usize) {
+    let number_u32 = u32::try_from(number).unwrap_or(u32::MAX);
+    bytes.extend_from_slice(&number_u32.to_le_bytes());
+}
+
+/// Encode a line as text with ANSI codes for the cell attributes.
+fn encode_line(line: &wezterm_term::Line) -> String {
+    let default_attributes = termwiz::cell::CellAttributes::default();
+    let mut cells: Vec<_> = line.visible_cells().collect();
+    if !line.last_cell_was_wrapped() {
+        while cells
+            .last()
+            .is_some_and(|cell| cell.str() == " " && cell.attrs() == &default_attributes)
+        {
+            cells.pop();
+        }
+    }
+
+    let mut encoded = match crate::output::LineAttribute::from_line(line) {
+        crate::output::LineAttribute::DoubleWidth => DOUBLE_WIDTH.to_owned(),
+        crate::output::LineAttribute::DoubleHeightTop => DOUBLE_HEIGHT_TOP.to_owned(),
+        crate::output::LineAttribute::DoubleHeightBottom => DOUBLE_HEIGHT_BOTTOM.to_owned(),
+        crate::output::LineAttribute::SingleWidth => String::new(),
+    };
+    let mut current_attributes = &default_attributes;
+    for cell in &cells {
+        if cell.attrs() != current_attributes {
+            current_attributes = cell.attrs();
+            encoded.push_str(&crate::sgr::for_attributes(current_attributes));
+        }
+        encoded.push_str(cell.str());
+    }
+    if current_attributes != &default_attributes {
+        encoded.push_str("\x1b[0m");
+    }
+
+    encoded
+}
+
+/// Reads the state bytes, with errors if there aren't enough bytes left.
+struct Reader<'bytes> {
+    /// The bytes that haven't been read yet.
+    bytes: &'bytes [u8],
+}
+
+impl<'bytes> Reader<'bytes> {
+    /// Read the given number of bytes.
+    fn take(&mut self, length: usize) -> Result<&'bytes [u8], crate::errors::ShadowTerminalError> {
+        let taken = self
+            .bytes
+            .get(..length)
+            .with_whatever_context(|| "Shadow terminal state is truncated")?;
+        self.bytes = self.bytes.get(length..).unwrap_or_default();
+        Ok(taken)
+    }
+
+    /// Read a single byte.
+    fn take_u8(&mut self) -> Result<u8, crate::errors::ShadowTerminalError> {
+        let byte = self
+            .take(1)?
+            .first()
+            .copied()
+            .with_whatever_context(|| "Shadow terminal state is truncated")?;
+        Ok(byte)
+    }
+
+    /// Read a number.
+    fn take_u32(&mut self) -> Result<usize, crate::errors::ShadowTerminalError> {
+        let bytes: [u8; 4] = self
+            .take(4)?
+            .try_into()
+            .with_whatever_context(|error| format!("Couldn't read number: {error:?}"))?;
+        usize::try_from(u32::from_le_bytes(bytes))
+            .with_whatever_context(|error| format!("Couldn't convert number: {error:?}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    fn terminal(config: Config) -> ShadowTerminal {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        ShadowTerminal::new(config, shadow_output_tx)
+    }
+
+    #[test]
+    fn round_trip_state() {
+        let config = Config {
+            width: 20,
+            height: 5,
+            ..Config::default()
+        };
+        let mut original = terminal(config);
+        original.terminal.advance_bytes(
+            "one\r\n\x1b[1;31mtwo\x1b[0m\r\nthree\r\nfour\r\nfive\r\nsix\r\n1234567890abcdefghijklmnop",
+        );
+
+        let state = original.serialize_state();
+        let mut restored = ShadowTerminal::restore(&state, Config::default(), {
+            let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+            shadow_output_tx
+        })
+        .unwrap();
+
+        assert_eq!(restored.terminal.get_size().cols, 20);
+        assert_eq!(restored.to_text(), original.to_text());
+        assert_eq!(
+            restored.terminal.cursor_pos().x,
+            original.terminal.cursor_pos().x
+        );
+        assert_eq!(
+            restored.terminal.cursor_pos().y,
+            original.terminal.cursor_pos().y
+        );
+        assert_eq!(
+            restored.terminal.screen().scrollback_rows(),
+            original.terminal.screen().scrollback_rows()
+        );
+
+        let second_line = restored.terminal.screen().lines_in_phys_range(1..2);
+        let red_colour = second_line
+            .first()
+            .unwrap()
+            .visible_cells()
+            .next()
+            .unwrap()
+            .attrs()
+            .foreground();
+        assert_eq!(red_colour, termwiz::color::ColorAttribute::PaletteIndex(1));
+    }
+
+    #[test]
+    fn reject_bad_state() {
+        let result = ShadowTerminal::restore(b"nope", Config::default(), {
+            let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+            shadow_output_tx
+        });
+        assert!(result.is_err());
+    }
+}
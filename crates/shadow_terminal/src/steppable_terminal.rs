@@ -9,6 +9,9 @@ use tracing::Instrument as _;
 /// The default time to wait looking for terminal screen content.
 const DEFAULT_TIMEOUT: u32 = 500;
 
+/// How long the terminal has to stay unchanged before its screen is considered settled.
+const SETTLE_TIME: tokio::time::Duration = tokio::time::Duration::from_millis(20);
+
 /// Handle various kinds of input.
 ///
 /// Simulating STDIN has actually been quite hard. For one, it seems like terminal input parsers
@@ -436,6 +439,71 @@ impl SteppableTerminal {
         Ok(())
     }
 
+    /// Feed raw bytes into the terminal, as if the PTY had output them, then wait until the screen
+    /// settles. The screen has settled once the terminal hasn't changed for `SETTLE_TIME`, or once
+    /// the timeout has passed, whichever is first. Any output from the PTY itself, like answers
+    /// to queries in the bytes, is also rendered whilst waiting.
+    ///
+    /// Useful for testing the visual effect of escape sequences without guessing how long to
+    /// sleep for.
+    ///
+    /// # Errors
+    /// * If the bytes or the PTY's output can't be handled.
+    /// * If the screen's surface can't be built.
+    #[inline]
+    pub async fn apply_and_settle(
+        &mut self,
+        bytes: &[u8],
+        timeout: tokio::time::Duration,
+    ) -> Result<termwiz::surface::Surface, crate::errors::SteppableTerminalError> {
+        Box::pin(self.shadow_terminal.replay_pty_output(bytes))
+            .await
+            .with_whatever_context(|err| format!("Couldn't apply bytes: {err:?}"))?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut last_change = tokio::time::Instant::now();
+        let mut sequence = self.shadow_terminal.terminal.current_seqno();
+        loop {
+            self.render_all_output()
+                .await
+                .with_whatever_context(|err| format!("Couldn't render output: {err:?}"))?;
+
+            let now = tokio::time::Instant::now();
+            let current_sequence = self.shadow_terminal.terminal.current_seqno();
+            if current_sequence != sequence {
+                sequence = current_sequence;
+                last_change = now;
+            }
+            if now.duration_since(last_change) >= SETTLE_TIME || now >= deadline {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
+        }
+
+        self.screen_surface()
+    }
+
+    /// The current screen as a `termwiz` surface.
+    ///
+    /// # Errors
+    /// If the surface can't be built.
+    #[inline]
+    pub fn screen_surface(
+        &mut self,
+    ) -> Result<termwiz::surface::Surface, crate::errors::SteppableTerminalError> {
+        let output = self
+            .shadow_terminal
+            .build_current_output(&crate::output::SurfaceKind::Screen)
+            .with_whatever_context(|err| format!("Couldn't build screen: {err:?}"))?;
+        let crate::output::Output::Complete(crate::output::CompleteSurface::Screen(screen)) =
+            output
+        else {
+            snafu::whatever!("The screen wasn't built as a complete surface");
+        };
+
+        Ok(screen.surface)
+    }
+
     /// Wait for the given string to appear anywhere in the screen.
     ///
     /// # Errors
@@ -661,6 +729,28 @@ mod test {
         assert_eq!(resized_menu_item_paste, "Paste");
     }
 
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn applying_escape_sequences() {
+        let mut stepper = Box::pin(run(None, None)).await;
+
+        let surface = stepper
+            .apply_and_settle(
+                b"\x1b[2J\x1b[H\x1b[31mred",
+                tokio::time::Duration::from_secs(1),
+            )
+            .await
+            .unwrap();
+
+        let cells = surface.screen_cells();
+        let line = cells.first().unwrap();
+        assert_eq!(line.first().unwrap().str(), "r");
+        assert_eq!(
+            line.first().unwrap().attrs().foreground(),
+            termwiz::color::ColorAttribute::PaletteIndex(1)
+        );
+    }
+
     #[cfg(not(target_os = "windows"))]
     #[tokio::test(flavor = "multi_thread")]
     async fn cursor_position_response() {
@@ -673,6 +763,209 @@ mod test {
 
         stepper.send_command(command).unwrap();
 
-        stepper.wait_for_string("1;0", None).await.unwrap();
+        // Reports are 1-based, so this is the start of the line below the command.
+        stepper.wait_for_string("2;1", None).await.unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tee_raw_pty_output() {
+        let (tee_tx, mut tee_rx) = tokio::sync::mpsc::channel(1024);
+        let config = Config {
+            width: 50,
+            height: 10,
+            command: get_canonical_shell(),
+            tee_tx: Some(tee_tx),
+            ..Config::default()
+        };
+        let mut stepper = Box::pin(SteppableTerminal::start(config)).await.unwrap();
+
+        stepper.send_command("echo tee-$((1+1))").unwrap();
+        stepper.wait_for_string("tee-2", None).await.unwrap();
+
+        let mut teed = Vec::new();
+        while let Ok(bytes) = tee_rx.try_recv() {
+            teed.extend(bytes);
+        }
+        assert!(!teed.contains(&0));
+        assert!(String::from_utf8_lossy(&teed).contains("tee-2"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn finalised_output_lines() {
+        let config = Config {
+            width: 50,
+            height: 10,
+            command: get_canonical_shell(),
+            emit_output_lines: true,
+            ..Config::default()
+        };
+        let mut stepper = Box::pin(SteppableTerminal::start(config)).await.unwrap();
+        let mut control_rx = stepper.shadow_terminal.channels.control_tx.subscribe();
+
+        stepper
+            .send_command("echo $(printf 'w%.0s' {1..70}) && seq 1 20")
+            .unwrap();
+        stepper.wait_for_string("20", None).await.unwrap();
+
+        let mut lines = Vec::new();
+        while let Ok(message) = control_rx.try_recv() {
+            if let crate::Protocol::OutputLine { text, .. } = message {
+                lines.push(text);
+            }
+        }
+        assert!(lines.contains(&"w".repeat(70)));
+        assert!(lines.contains(&"1".to_owned()));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn key_events_use_enhanced_keyboard_protocol() {
+        let mut stepper = Box::pin(run(None, None)).await;
+
+        // Enable the Kitty keyboard protocol's "disambiguate escape codes" flag.
+        stepper
+            .send_command("printf '\\e[>1ure%s\\n' ady && cat -v")
+            .unwrap();
+        stepper.wait_for_string("ready", None).await.unwrap();
+
+        stepper
+            .shadow_terminal
+            .send_key(
+                wezterm_term::KeyCode::Char('i'),
+                wezterm_term::KeyModifiers::CTRL,
+                crate::keys::KeyEventKind::Press,
+            )
+            .await
+            .unwrap();
+        stepper
+            .send_input(Input::Characters("\n".to_owned()))
+            .unwrap();
+
+        // In the legacy encoding `Ctrl+I` is indistinguishable from `Tab`.
+        stepper.wait_for_string("[105;5u", None).await.unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn focus_events_only_sent_when_enabled() {
+        let mut stepper = Box::pin(run(None, None)).await;
+
+        stepper.send_command("cat -v").unwrap();
+        stepper.shadow_terminal.send_focus(false).await.unwrap();
+        stepper
+            .send_input(Input::Characters("one\n".to_owned()))
+            .unwrap();
+        stepper.wait_for_string("one", None).await.unwrap();
+        assert!(!stepper.screen_as_string().unwrap().contains("[O"));
+        stepper.send_input(Input::Event("\x03".to_owned())).unwrap();
+
+        stepper
+            .send_command("printf '\\e[?1004hre%s\\n' ady && cat -v")
+            .unwrap();
+        stepper.wait_for_string("ready", None).await.unwrap();
+        stepper.shadow_terminal.send_focus(false).await.unwrap();
+        stepper
+            .send_input(Input::Characters("\n".to_owned()))
+            .unwrap();
+        stepper.wait_for_string("^[[O", None).await.unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn text_export_expands_tabs_to_tab_stops() {
+        let config = Config {
+            width: 50,
+            height: 10,
+            command: get_canonical_shell(),
+            tab_width: 4,
+            ..Config::default()
+        };
+        let mut stepper = Box::pin(SteppableTerminal::start(config)).await.unwrap();
+
+        stepper
+            .send_command("printf 'a\\tb  c\\td\\n%s%s\\n' do ne")
+            .unwrap();
+        stepper.wait_for_string("done", None).await.unwrap();
+
+        let text = stepper.shadow_terminal.to_text();
+        assert!(text.lines().any(|line| line == "a   b  c d"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn write_str_and_paste_str() {
+        let mut stepper = Box::pin(run(None, None)).await;
+
+        stepper
+            .shadow_terminal
+            .write_str("echo typed-$((2+2))\n")
+            .await
+            .unwrap();
+        stepper.wait_for_string("typed-4", None).await.unwrap();
+
+        stepper
+            .shadow_terminal
+            .paste_str("echo pasted-$((3+3))")
+            .await
+            .unwrap();
+        stepper.shadow_terminal.write_str("\n").await.unwrap();
+        stepper.wait_for_string("pasted-6", None).await.unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn initial_scrollback() {
+        let config = Config {
+            width: 50,
+            height: 10,
+            command: get_canonical_shell(),
+            initial_scrollback: vec!["restored-1".to_owned(), "restored-2".to_owned()],
+            ..Config::default()
+        };
+        let stepper = Box::pin(SteppableTerminal::start(config)).await.unwrap();
+
+        let screen = stepper.shadow_terminal.terminal.screen();
+        assert!(screen.scrollback_rows() >= 12);
+        let seeded: Vec<String> = screen
+            .lines_in_phys_range(0..2)
+            .iter()
+            .map(|line| line.as_str().trim_end().to_owned())
+            .collect();
+        assert_eq!(seeded, vec!["restored-1", "restored-2"]);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn signal_foreground_process() {
+        let mut stepper = Box::pin(run(None, None)).await;
+
+        stepper.send_command("sleep 5; echo status-$?").unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+        stepper
+            .shadow_terminal
+            .channels
+            .control_tx
+            .send(crate::Protocol::Signal(crate::Signal::Terminate))
+            .unwrap();
+
+        stepper.wait_for_string("status-143", None).await.unwrap();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn liveness_of_pty() {
+        let stepper = Box::pin(run(None, None)).await;
+        assert!(stepper.shadow_terminal.is_alive());
+
+        stepper.send_command("exit").unwrap();
+        for _ in 0..100u8 {
+            if !stepper.shadow_terminal.is_alive() {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+        assert!(!stepper.shadow_terminal.is_alive());
     }
 }
@@ -0,0 +1,190 @@
+//! Output from the shadow terminal as a [`Stream`], rather than a channel receiver, so that it
+//! composes with stream combinators, like those from `tokio_stream::StreamExt`.
+//!
+//! ```ignore
+//! use tokio_stream::StreamExt as _;
+//!
+//! let mut screens = shadow_terminal::stream::screen_stream(active_terminal.surface_output_rx);
+//! while let Some(output) = screens.next().await {
+//!     // ...
+//! }
+//! ```
+//!
+//! # Backpressure
+//! The streams are backed by the same bounded channel that the shadow terminal sends its output
+//! on. So when a stream isn't polled, the channel fills up and the shadow terminal waits before
+//! sending any more output, just as it would with the receiver. Filtered streams still receive
+//! every output, the ones they filter out are dropped. With [`split`] both streams share the one
+//! channel: when either stream isn't polled the other one also stops receiving output.
+//!
+//! # Frames
+//! A [`crate::output::Output::Frame`] holds both a screen and a scrollback. So the screen and the
+//! scrollback streams each unpack their own part from frames, and lose the guarantee that the two
+//! parts are applied together. Use [`output_stream`] to keep the frames whole.
+
+pub use tokio_stream::Stream;
+
+/// The shadow terminal's output channel as a stream.
+pub type OutputStream = tokio_stream::wrappers::ReceiverStream<crate::output::Output>;
+
+/// The number of outputs that each of the streams from [`split`] buffer.
+const SPLIT_BUFFER_SIZE: usize = 1;
+
+/// Turn the shadow terminal's output channel into a stream.
+#[inline]
+#[must_use]
+pub fn output_stream(receiver: tokio::sync::mpsc::Receiver<crate::output::Output>) -> OutputStream {
+    tokio_stream::wrappers::ReceiverStream::new(receiver)
+}
+
+/// Only the output of the screen, that's the current view of the terminal, from the shadow
+/// terminal's output channel.
+#[inline]
+pub fn screen_stream(
+    receiver: tokio::sync::mpsc::Receiver<crate::output::Output>,
+) -> impl Stream<Item = crate::output::Output> {
+    tokio_stream::StreamExt::filter_map(output_stream(receiver), |output| parts(output).0)
+}
+
+/// Only the output of the scrollback from the shadow terminal's output channel.
+#[inline]
+pub fn scrollback_stream(
+    receiver: tokio::sync::mpsc::Receiver<crate::output::Output>,
+) -> impl Stream<Item = crate::output::Output> {
+    tokio_stream::StreamExt::filter_map(output_stream(receiver), |output| parts(output).1)
+}
+
+/// Split the shadow terminal's output channel into a stream of the screen's output and a stream
+/// of the scrollback's output. A task routes the output to each stream, so it must be called from
+/// within a Tokio runtime.
+#[inline]
+#[must_use]
+pub fn split(
+    mut receiver: tokio::sync::mpsc::Receiver<crate::output::Output>,
+) -> (OutputStream, OutputStream) {
+    let (screen_tx, screen_rx) = tokio::sync::mpsc::channel(SPLIT_BUFFER_SIZE);
+    let (scrollback_tx, scrollback_rx) = tokio::sync::mpsc::channel(SPLIT_BUFFER_SIZE);
+
+    tokio::spawn(async move {
+        while let Some(output) = receiver.recv().await {
+            let (screen, scrollback) = parts(output);
+            for (sender, part) in [(&screen_tx, screen), (&scrollback_tx, scrollback)] {
+                let Some(part) = part else {
+                    continue;
+                };
+                // A dropped stream just doesn't get any more of its output.
+                if sender.send(part).await.is_err() {
+                    tracing::trace!("Output stream was dropped, discarding its output");
+                }
+            }
+
+            if screen_tx.is_closed() && scrollback_tx.is_closed() {
+                break;
+            }
+        }
+    });
+
+    (output_stream(screen_rx), output_stream(scrollback_rx))
+}
+
+/// Split an output into its screen part and its scrollback part. Only frames have both.
+fn parts(
+    output: crate::output::Output,
+) -> (Option<crate::output::Output>, Option<crate::output::Output>) {
+    match output {
+        crate::output::Output::Frame(frame) => (
+            Some(*frame.screen),
+            frame.scrollback.map(|scrollback| *scrollback),
+        ),
+        output if is_screen(&output) => (Some(output), None),
+        output => (None, Some(output)),
+    }
+}
+
+/// Is the output for the screen, rather than the scrollback? Viewports are part of the screen.
+const fn is_screen(output: &crate::output::Output) -> bool {
+    matches!(
+        output,
+        crate::output::Output::Diff(crate::output::SurfaceDiff::Screen(_))
+            | crate::output::Output::Complete(
+                crate::output::CompleteSurface::Screen(_)
+                    | crate::output::CompleteSurface::Viewport(_)
+            )
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use tokio_stream::StreamExt as _;
+
+    use super::*;
+
+    fn outputs() -> tokio::sync::mpsc::Receiver<crate::output::Output> {
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+        for output in [
+            crate::output::Output::Diff(crate::output::SurfaceDiff::Screen(
+                crate::output::ScreenDiff::default(),
+            )),
+            crate::output::Output::Diff(crate::output::SurfaceDiff::Scrollback(
+                crate::output::ScrollbackDiff::default(),
+            )),
+            crate::output::Output::Diff(crate::output::SurfaceDiff::Screen(
+                crate::output::ScreenDiff::default(),
+            )),
+        ] {
+            sender.try_send(output).unwrap();
+        }
+        receiver
+    }
+
+    #[tokio::test]
+    async fn streams_are_filtered_to_screen_or_scrollback() {
+        assert_eq!(screen_stream(outputs()).collect::<Vec<_>>().await.len(), 2);
+        assert_eq!(
+            scrollback_stream(outputs()).collect::<Vec<_>>().await.len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn split_streams() {
+        let (screen, scrollback) = split(outputs());
+        let (screen, scrollback) =
+            tokio::join!(screen.collect::<Vec<_>>(), scrollback.collect::<Vec<_>>());
+        assert_eq!(screen.len(), 2);
+        assert_eq!(scrollback.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn frames_are_split_into_their_screen_and_scrollback() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let frame = crate::output::Frame {
+            screen: Box::new(crate::output::Output::Diff(
+                crate::output::SurfaceDiff::Screen(crate::output::ScreenDiff::default()),
+            )),
+            scrollback: Some(Box::new(crate::output::Output::Diff(
+                crate::output::SurfaceDiff::Scrollback(crate::output::ScrollbackDiff::default()),
+            ))),
+        };
+        sender
+            .try_send(crate::output::Output::Frame(frame))
+            .unwrap();
+        drop(sender);
+
+        let (screen, scrollback) = split(receiver);
+        let (screen, scrollback) =
+            tokio::join!(screen.collect::<Vec<_>>(), scrollback.collect::<Vec<_>>());
+        assert!(matches!(
+            screen.as_slice(),
+            [crate::output::Output::Diff(
+                crate::output::SurfaceDiff::Screen(_)
+            )]
+        ));
+        assert!(matches!(
+            scrollback.as_slice(),
+            [crate::output::Output::Diff(
+                crate::output::SurfaceDiff::Scrollback(_)
+            )]
+        ));
+    }
+}
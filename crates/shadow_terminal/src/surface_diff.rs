@@ -0,0 +1,153 @@
+//! Finding the regions that differ between two surfaces.
+//!
+//! Useful for renderers that only want to redraw what's changed, and for tests that want to
+//! assert that only certain cells changed.
+
+/// A rectangle of cells.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Rect {
+    /// The first column.
+    pub x: usize,
+    /// The first row.
+    pub y: usize,
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+}
+
+impl Rect {
+    /// Create a new rectangle.
+    #[inline]
+    #[must_use]
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Get the rectangles of cells that differ between two surfaces, along with the new surface's
+/// cells for each rectangle, row by row.
+///
+/// Each row's changed cells are grouped into spans, and spans on consecutive rows that cover
+/// exactly the same columns are merged. When the surfaces are different sizes, the whole of the
+/// new surface is returned.
+#[inline]
+#[must_use]
+pub fn surface_diff(
+    old: &termwiz::surface::Surface,
+    new: &termwiz::surface::Surface,
+) -> Vec<(Rect, Vec<termwiz::cell::Cell>)> {
+    let (width, height) = new.dimensions();
+    let new_rows = surface_cells(new);
+    if old.dimensions() != new.dimensions() {
+        return vec![(Rect::new(0, 0, width, height), new_rows.concat())];
+    }
+
+    let old_rows = surface_cells(old);
+    let mut rects: Vec<Rect> = Vec::new();
+    for (y, (old_row, new_row)) in old_rows.iter().zip(&new_rows).enumerate() {
+        for (x, span_width) in changed_spans(old_row, new_row) {
+            let previous = rects
+                .iter_mut()
+                .rev()
+                .find(|rect| rect.x == x && rect.width == span_width && rect.y + rect.height == y);
+            match previous {
+                Some(rect) => rect.height += 1,
+                None => rects.push(Rect::new(x, y, span_width, 1)),
+            }
+        }
+    }
+
+    rects
+        .into_iter()
+        .map(|rect| {
+            let cells = new_rows
+                .iter()
+                .skip(rect.y)
+                .take(rect.height)
+                .flat_map(|row| row.iter().skip(rect.x).take(rect.width).cloned())
+                .collect();
+            (rect, cells)
+        })
+        .collect()
+}
+
+/// Every cell of a surface, row by row. The columns covered by wide graphemes are left blank.
+fn surface_cells(surface: &termwiz::surface::Surface) -> Vec<Vec<termwiz::cell::Cell>> {
+    let (width, _) = surface.dimensions();
+    surface
+        .screen_lines()
+        .iter()
+        .map(|line| {
+            let mut row = vec![termwiz::cell::Cell::blank(); width];
+            for cell in line.visible_cells() {
+                if let Some(slot) = row.get_mut(cell.cell_index()) {
+                    *slot = cell.as_cell();
+                }
+            }
+            row
+        })
+        .collect()
+}
+
+/// The start and width of every run of cells that differ between two rows.
+fn changed_spans(old: &[termwiz::cell::Cell], new: &[termwiz::cell::Cell]) -> Vec<(usize, usize)> {
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for (x, (old_cell, new_cell)) in old.iter().zip(new).enumerate() {
+        if old_cell == new_cell {
+            continue;
+        }
+        match spans.last_mut() {
+            Some((start, span_width)) if *start + *span_width == x => *span_width += 1,
+            _ => spans.push((x, 1)),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn surface(text: &str) -> termwiz::surface::Surface {
+        let mut surface = termwiz::surface::Surface::new(4, 3);
+        surface.add_change(text);
+        surface
+    }
+
+    #[test]
+    fn identical_surfaces() {
+        assert!(surface_diff(&surface("abcd"), &surface("abcd")).is_empty());
+    }
+
+    #[test]
+    fn changed_regions() {
+        let diffs = surface_diff(&surface("abcdefghijkl"), &surface("aXYdeXYhijkZ"));
+        let rects: Vec<_> = diffs.iter().map(|(rect, _)| *rect).collect();
+        assert_eq!(rects, vec![Rect::new(1, 0, 2, 2), Rect::new(3, 2, 1, 1)]);
+
+        let text: Vec<_> = diffs
+            .first()
+            .unwrap()
+            .1
+            .iter()
+            .map(|cell| cell.str().to_owned())
+            .collect();
+        assert_eq!(text, vec!["X", "Y", "X", "Y"]);
+    }
+
+    #[test]
+    fn resizing_is_a_full_redraw() {
+        let new = termwiz::surface::Surface::new(2, 2);
+        let diffs = surface_diff(&surface("abcd"), &new);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs.first().unwrap().0, Rect::new(0, 0, 2, 2));
+        assert_eq!(diffs.first().unwrap().1.len(), 4);
+    }
+}
@@ -1,5 +1,7 @@
 //! asasdasdasd
 
+use snafu::ResultExt as _;
+
 /// asddasdasd
 ///
 /// # Panics
@@ -19,3 +21,251 @@ pub fn workspace_dir() -> std::path::PathBuf {
     tracing::debug!("Using workspace directory: {workspace_dir:?}");
     workspace_dir
 }
+
+/// A readable dump of a surface's text, one line per row, along with its cursor position. Useful
+/// in the messages of failing assertions.
+#[inline]
+#[must_use]
+pub fn dump_surface(surface: &termwiz::surface::Surface) -> String {
+    let (width, height) = surface.dimensions();
+    let (cursor_x, cursor_y) = surface.cursor_position();
+    let rows: Vec<String> = surface
+        .screen_lines()
+        .iter()
+        .enumerate()
+        .map(|(y, line)| format!("{y:>3}|{}|", line.as_str()))
+        .collect();
+    format!(
+        "Surface {width}x{height}, cursor at ({cursor_x}, {cursor_y}):\n{}",
+        rows.join("\n")
+    )
+}
+
+/// Assert that the cell at the given coordinates has the given grapheme and colours.
+///
+/// # Panics
+/// When the cell doesn't exist, or doesn't match.
+#[expect(clippy::panic, reason = "It's for use in tests only")]
+#[inline]
+pub fn assert_cell(
+    surface: &mut termwiz::surface::Surface,
+    x: usize,
+    y: usize,
+    grapheme: &str,
+    foreground: termwiz::color::ColorAttribute,
+    background: termwiz::color::ColorAttribute,
+) {
+    let dump = dump_surface(surface);
+    let cells = surface.screen_cells();
+    let Some(cell) = cells.get(y).and_then(|line| line.get(x)) else {
+        panic!("No cell at ({x}, {y}) in:\n{dump}");
+    };
+    let actual = (
+        cell.str().to_owned(),
+        cell.attrs().foreground(),
+        cell.attrs().background(),
+    );
+    let expected = (grapheme.to_owned(), foreground, background);
+    assert!(
+        actual == expected,
+        "Cell at ({x}, {y}) is {actual:?}, expected {expected:?}, in:\n{dump}"
+    );
+}
+
+/// The longest time to wait for a program run by `run_in_pty()` to exit.
+const PTY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Run a program in a real PTY, wait for it to exit, and render everything that it output. It
+/// goes through the whole pipeline: spawning the program, reading its output, answering any of
+/// its queries, like for the cursor position, and handling its exit.
+///
+/// The PTY reads the rest of the program's output after it exits, so programs don't need to
+/// pause before exiting.
+///
+/// Returns `None` when the program isn't available on this platform, so that tests can be
+/// skipped rather than fail.
+///
+/// # Errors
+/// If the program doesn't exit in time, or its output can't be rendered.
+#[inline]
+pub async fn run_in_pty(
+    command: Vec<std::ffi::OsString>,
+    width: u16,
+    height: u16,
+) -> Result<Option<termwiz::surface::Surface>, crate::errors::ShadowTerminalError> {
+    let config = crate::shadow_terminal::Config {
+        width,
+        height,
+        command,
+        ..crate::shadow_terminal::Config::default()
+    };
+    Ok(run_config_in_pty(config).await?.map(|run| run.screen))
+}
+
+/// Everything from running a program with `run_config_in_pty()`.
+#[non_exhaustive]
+pub struct PtyRun {
+    /// Everything that the program output, rendered.
+    pub screen: termwiz::surface::Surface,
+    /// Every protocol message that was broadcast whilst the program ran, in order.
+    pub protocol: Vec<crate::Protocol>,
+}
+
+/// The same as `run_in_pty()`, but with a whole shadow terminal config, for testing things like
+/// restart policies. The protocol messages that were broadcast are also returned.
+///
+/// # Errors
+/// If the program doesn't exit in time, or its output can't be rendered.
+#[inline]
+pub async fn run_config_in_pty(
+    config: crate::shadow_terminal::Config,
+) -> Result<Option<PtyRun>, crate::errors::ShadowTerminalError> {
+    if let Err(error) = crate::pty::PTY::check_command(&config.command) {
+        tracing::warn!("Not running {:?} in a PTY: {error}", config.command);
+        return Ok(None);
+    }
+
+    let (shadow_output_tx, mut shadow_output_rx) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(async move { while shadow_output_rx.recv().await.is_some() {} });
+    let mut shadow_terminal = crate::shadow_terminal::ShadowTerminal::new(config, shadow_output_tx);
+    let mut protocol_rx = shadow_terminal.channels.control_tx.subscribe();
+    let (_user_input_tx, user_input_rx) = tokio::sync::mpsc::channel(1);
+    let mut pty = shadow_terminal.start(user_input_rx);
+
+    let deadline = tokio::time::Instant::now() + PTY_TIMEOUT;
+    #[expect(
+        clippy::integer_division_remainder_used,
+        reason = "`tokio::select!` generates this."
+    )]
+    let result = loop {
+        tokio::select! {
+            Some(bytes) = shadow_terminal.channels.output_rx.recv() => {
+                shadow_terminal.replay_pty_output(&bytes).await?;
+            }
+            result = &mut pty => break result,
+            () = tokio::time::sleep_until(deadline) => {
+                snafu::whatever!("PTY program didn't exit within {PTY_TIMEOUT:?}");
+            }
+        }
+    };
+    result
+        .with_whatever_context(|error| format!("PTY task failed: {error:?}"))?
+        .with_whatever_context(|error| format!("PTY failed: {error:?}"))?;
+
+    // The PTY has sent all of the program's output by the time it's finished, but some of it may
+    // not have been rendered yet.
+    while let Ok(bytes) = shadow_terminal.channels.output_rx.try_recv() {
+        shadow_terminal.replay_pty_output(&bytes).await?;
+    }
+
+    let output = shadow_terminal.build_current_output(&crate::output::SurfaceKind::Screen)?;
+    let crate::output::Output::Complete(crate::output::CompleteSurface::Screen(screen)) = output
+    else {
+        snafu::whatever!("The screen wasn't built as a complete surface");
+    };
+
+    let mut protocol = Vec::new();
+    while let Ok(message) = protocol_rx.try_recv() {
+        protocol.push(message);
+    }
+
+    Ok(Some(PtyRun {
+        screen: screen.surface,
+        protocol,
+    }))
+}
+
+/// A command that runs the script in the canonical shell, see `get_canonical_shell()`.
+#[inline]
+#[must_use]
+pub fn shell_command(script: &str) -> Vec<std::ffi::OsString> {
+    let mut command = crate::steppable_terminal::get_canonical_shell();
+
+    #[cfg(not(target_os = "windows"))]
+    command.push("-c".into());
+    #[cfg(target_os = "windows")]
+    command.push("-Command".into());
+
+    command.push(script.into());
+    command
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn asserting_cells() {
+        let mut surface = termwiz::surface::Surface::new(3, 2);
+        surface.add_changes(vec![
+            termwiz::surface::Change::CursorPosition {
+                x: termwiz::surface::Position::Absolute(1),
+                y: termwiz::surface::Position::Absolute(1),
+            },
+            termwiz::surface::Change::Attribute(termwiz::cell::AttributeChange::Foreground(
+                termwiz::color::AnsiColor::Red.into(),
+            )),
+            "x".into(),
+        ]);
+
+        assert_cell(
+            &mut surface,
+            1,
+            1,
+            "x",
+            termwiz::color::AnsiColor::Red.into(),
+            termwiz::color::ColorAttribute::Default,
+        );
+        assert_eq!(
+            dump_surface(&surface),
+            "Surface 3x2, cursor at (2, 1):\n  0|   |\n  1| x |"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell at (0, 0) is")]
+    fn mismatched_cells_show_the_surface() {
+        let mut surface = termwiz::surface::Surface::new(1, 1);
+        assert_cell(
+            &mut surface,
+            0,
+            0,
+            "x",
+            termwiz::color::ColorAttribute::Default,
+            termwiz::color::ColorAttribute::Default,
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn programs_in_a_pty_are_rendered() {
+        #[cfg(not(target_os = "windows"))]
+        let script = "printf 'hello\\nworld'";
+        #[cfg(target_os = "windows")]
+        let script = "Write-Host \"hello`nworld\"";
+
+        let Some(surface) = run_in_pty(shell_command(script), 20, 5).await.unwrap() else {
+            return;
+        };
+        let dump = dump_surface(&surface);
+        assert!(dump.contains("  0|hello "), "{dump}");
+        assert!(dump.contains("  1|world "), "{dump}");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn queries_from_programs_in_a_pty_are_answered() {
+        let script = "printf '\\033[6n'; read -rs -d R position; echo \"at ${position#*[}\"";
+
+        let Some(surface) = run_in_pty(shell_command(script), 20, 5).await.unwrap() else {
+            return;
+        };
+        let dump = dump_surface(&surface);
+        assert!(dump.contains("  0|at 1;1 "), "{dump}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn missing_programs_arent_run_in_a_pty() {
+        let command = vec!["definitely-not-a-real-command".into()];
+        assert!(run_in_pty(command, 20, 5).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,176 @@
+//! Broadcast the working directory that the running application reports.
+//!
+//! Shells report their current directory with OSC 7, as a `file://` URI, for example
+//! `^[]7;file://hostname/home/user^G`. The URI's host can be a different machine, like when the
+//! shell is running over SSH. In which case the path isn't a valid local path, so the raw URI is
+//! also given.
+
+/// A working directory reported by the running application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WorkingDirectory {
+    /// The raw URI, exactly as it was reported.
+    pub uri: String,
+    /// The host from the URI. It's empty when the URI didn't have one.
+    pub host: String,
+    /// The decoded path of the directory.
+    pub path: std::path::PathBuf,
+    /// Whether the directory is on a different machine. Its path then can't be used locally.
+    pub is_remote: bool,
+}
+
+impl WorkingDirectory {
+    /// Parse a `file://` URI. Returns `None` for other kinds of URI.
+    #[inline]
+    #[must_use]
+    pub fn parse(uri: &str) -> Option<Self> {
+        let url = url::Url::parse(uri).ok()?;
+        if url.scheme() != "file" {
+            return None;
+        }
+        let host = url.host_str().unwrap_or_default();
+
+        // `Url::to_file_path()` refuses paths on other hosts, but the path is still worth
+        // knowing, for example to show it.
+        let path = url.to_file_path().unwrap_or_else(|()| {
+            percent_encoding::percent_decode_str(url.path())
+                .decode_utf8_lossy()
+                .into_owned()
+                .into()
+        });
+
+        Some(Self {
+            uri: uri.to_owned(),
+            host: host.to_owned(),
+            path,
+            is_remote: !is_local_host(host),
+        })
+    }
+}
+
+impl crate::shadow_terminal::ShadowTerminal {
+    /// Broadcast `Protocol::WorkingDirectory` whenever the reported working directory changes.
+    pub(crate) fn broadcast_working_directory(&mut self) {
+        if !self.config.emit_working_directory {
+            return;
+        }
+
+        let Some(uri) = self
+            .terminal
+            .get_current_dir()
+            .map(|url| url.as_str().to_owned())
+        else {
+            return;
+        };
+        if self.working_directory_uri.as_ref() == Some(&uri) {
+            return;
+        }
+
+        let Some(working_directory) = WorkingDirectory::parse(&uri) else {
+            tracing::debug!("Ignoring working directory that isn't a file URI: {uri}");
+            return;
+        };
+        self.working_directory_uri = Some(uri);
+
+        tracing::trace!("Broadcasting working directory: {working_directory:?}");
+        let result = self
+            .channels
+            .control_tx
+            .send(crate::Protocol::WorkingDirectory(working_directory));
+        if let Err(error) = result {
+            tracing::error!("Couldn't broadcast working directory: {error:?}");
+        }
+    }
+}
+
+/// Is the host this machine? Hosts are compared without their domains, because shells don't
+/// agree about whether to include them.
+fn is_local_host(host: &str) -> bool {
+    let without_domain = |name: &str| name.split('.').next().unwrap_or_default().to_lowercase();
+    host.is_empty()
+        || host.eq_ignore_ascii_case("localhost")
+        || local_hostname().is_some_and(|local| without_domain(&local) == without_domain(host))
+}
+
+/// The name of this machine.
+#[cfg(unix)]
+fn local_hostname() -> Option<String> {
+    let mut buffer = [0_u8; 256];
+    // SAFETY: `gethostname()` writes no more than the given length into the buffer, which is
+    // the buffer's actual length.
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return None;
+    }
+
+    let length = buffer
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(buffer.len());
+    String::from_utf8(buffer.get(..length)?.to_vec()).ok()
+}
+
+/// The name of this machine.
+#[cfg(not(unix))]
+fn local_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shadow_terminal::{Config, ShadowTerminal};
+
+    #[test]
+    fn local_directories_are_decoded() {
+        let working_directory = WorkingDirectory::parse("file://localhost/tmp/a%20b").unwrap();
+        assert_eq!(working_directory.host, "localhost");
+        assert_eq!(working_directory.path, std::path::PathBuf::from("/tmp/a b"));
+        assert!(!working_directory.is_remote);
+
+        let without_host = WorkingDirectory::parse("file:///tmp/100%").unwrap();
+        assert_eq!(without_host.path, std::path::PathBuf::from("/tmp/100%"));
+        assert!(!without_host.is_remote);
+    }
+
+    #[test]
+    fn remote_directories_keep_their_uri() {
+        let uri = "file://some-other-machine.example/home/me%20too";
+        let working_directory = WorkingDirectory::parse(uri).unwrap();
+        assert!(working_directory.is_remote);
+        assert_eq!(working_directory.uri, uri);
+        assert_eq!(working_directory.host, "some-other-machine.example");
+        assert_eq!(
+            working_directory.path,
+            std::path::PathBuf::from("/home/me too")
+        );
+    }
+
+    #[test]
+    fn other_uris_are_ignored() {
+        assert!(WorkingDirectory::parse("https://example.com/").is_none());
+    }
+
+    #[tokio::test]
+    async fn working_directories_are_broadcast_when_they_change() {
+        let (shadow_output_tx, _) = tokio::sync::mpsc::channel(1);
+        let mut shadow_terminal = ShadowTerminal::new(Config::default(), shadow_output_tx);
+        let mut control_rx = shadow_terminal.channels.control_tx.subscribe();
+
+        for _ in 0..2 {
+            shadow_terminal.accumulated_pty_output = b"\x1b]7;file://localhost/tmp\x07".to_vec();
+            shadow_terminal.handle_pty_output().await.unwrap();
+        }
+
+        let paths: Vec<std::path::PathBuf> = std::iter::from_fn(|| control_rx.try_recv().ok())
+            .filter_map(|message| {
+                if let crate::Protocol::WorkingDirectory(working_directory) = message {
+                    Some(working_directory.path)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(paths, vec![std::path::PathBuf::from("/tmp")]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Easing curves and tweens, so that all the animated effects move in the same way, rather than
+//! each one hand-rolling its own interpolation.
+
+/// How an animation progresses over its duration.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Easing {
+    /// A constant rate.
+    #[default]
+    Linear,
+    /// Slowly at first and then quickly.
+    EaseIn,
+    /// Quickly at first and then slowly.
+    EaseOut,
+    /// Slowly at the start and the end, and quickly in the middle.
+    EaseInOut,
+    /// Like `EaseInOut`, but more pronounced.
+    Cubic,
+    /// Reaches the end early, then falls back and bounces up to it again a few times, like a
+    /// dropped ball. It never goes past the end.
+    Bounce,
+}
+
+impl Easing {
+    /// Ease the progress of an animation. Both the progress and the result go from 0.0 at the
+    /// start of the animation to 1.0 at the end.
+    pub fn ease(self, progress: f32) -> f32 {
+        let time = progress.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => time,
+            Self::EaseIn => time * time,
+            Self::EaseOut => 1.0 - (1.0 - time) * (1.0 - time),
+            Self::EaseInOut => {
+                if time < 0.5 {
+                    2.0 * time * time
+                } else {
+                    1.0 - (-2.0 * time + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::Cubic => {
+                if time < 0.5 {
+                    4.0 * time * time * time
+                } else {
+                    1.0 - (-2.0 * time + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::Bounce => bounce(time),
+        }
+    }
+}
+
+/// The classic "ease out bounce" curve.
+fn bounce(time: f32) -> f32 {
+    /// How sharply each bounce curves.
+    const STIFFNESS: f32 = 7.5625;
+    /// The time that the first bounce takes.
+    const FIRST_BOUNCE: f32 = 2.75;
+
+    if time < 1.0 / FIRST_BOUNCE {
+        STIFFNESS * time * time
+    } else if time < 2.0 / FIRST_BOUNCE {
+        let offset = time - 1.5 / FIRST_BOUNCE;
+        STIFFNESS * offset * offset + 0.75
+    } else if time < 2.5 / FIRST_BOUNCE {
+        let offset = time - 2.25 / FIRST_BOUNCE;
+        STIFFNESS * offset * offset + 0.9375
+    } else {
+        let offset = time - 2.625 / FIRST_BOUNCE;
+        STIFFNESS * offset * offset + 0.984_375
+    }
+}
+
+/// Values that can be smoothly interpolated between.
+pub(crate) trait Lerp: Copy {
+    /// The value that's the given amount of the way from this value to the other. An amount of
+    /// 0.0 is this value and 1.0 is the other value.
+    fn lerp(self, other: Self, amount: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, amount: f32) -> Self {
+        self + (other - self) * amount
+    }
+}
+
+impl Lerp for crate::surface::Colour {
+    fn lerp(self, other: Self, amount: f32) -> Self {
+        (
+            self.0.lerp(other.0, amount),
+            self.1.lerp(other.1, amount),
+            self.2.lerp(other.2, amount),
+            self.3.lerp(other.3, amount),
+        )
+    }
+}
+
+/// Animates a value from one value to another, over a duration. It's advanced by the time since
+/// the previous frame, so it doesn't depend on the frame rate.
+#[derive(Debug, Clone)]
+pub(crate) struct Tween<T: Lerp> {
+    /// The value at the start.
+    from: T,
+    /// The value at the end.
+    to: T,
+    /// How long the tween lasts.
+    duration: std::time::Duration,
+    /// How long the tween has been running.
+    elapsed: std::time::Duration,
+    /// How the value progresses.
+    easing: Easing,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Instantiate
+    pub const fn new(from: T, to: T, duration: std::time::Duration, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: std::time::Duration::ZERO,
+            easing,
+        }
+    }
+
+    /// Move the tween on by the time since the previous frame, and return the new value.
+    pub fn advance(&mut self, delta: std::time::Duration) -> T {
+        self.elapsed = self.elapsed.saturating_add(delta).min(self.duration);
+        self.value()
+    }
+
+    /// How far through the tween it is, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    /// The current value.
+    pub fn value(&self) -> T {
+        self.from.lerp(self.to, self.easing.ease(self.progress()))
+    }
+
+    /// Has the tween reached its end?
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    const EASINGS: [Easing; 6] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::Cubic,
+        Easing::Bounce,
+    ];
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        for easing in EASINGS {
+            assert_eq!(easing.ease(0.0), 0.0, "{easing:?}");
+            assert!((easing.ease(1.0) - 1.0).abs() < 0.0001, "{easing:?}");
+            assert_eq!(easing.ease(2.0), easing.ease(1.0), "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn easings_have_their_shapes() {
+        assert!(Easing::EaseIn.ease(0.5) < Easing::Linear.ease(0.5));
+        assert!(Easing::EaseOut.ease(0.5) > Easing::Linear.ease(0.5));
+        assert_eq!(Easing::EaseInOut.ease(0.5), 0.5);
+        assert!(Easing::Cubic.ease(0.25) < Easing::EaseInOut.ease(0.25));
+    }
+
+    #[test]
+    fn tweening() {
+        let mut tween = Tween::new(
+            10.0,
+            20.0,
+            std::time::Duration::from_secs(2),
+            Easing::Linear,
+        );
+        assert_eq!(tween.value(), 10.0);
+        assert_eq!(tween.advance(std::time::Duration::from_secs(1)), 15.0);
+        assert!(!tween.is_finished());
+        assert_eq!(tween.advance(std::time::Duration::from_secs(5)), 20.0);
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn tweening_colours() {
+        let mut tween = Tween::new(
+            crate::surface::BLACK,
+            crate::surface::WHITE,
+            std::time::Duration::from_secs(1),
+            Easing::Linear,
+        );
+        let colour = tween.advance(std::time::Duration::from_millis(500));
+        assert_eq!(colour, (0.5, 0.5, 0.5, 1.0));
+    }
+}
@@ -0,0 +1,156 @@
+//! Animate the blink attribute of the PTY's text. Slow (SGR 5) and rapid (SGR 6) blinking text
+//! each alternate between visible and hidden at their own intervals. Blinking can be disabled
+//! entirely for accessibility.
+
+use termwiz::cell::{Blink, Cell};
+
+/// The user's config for blinking text.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Animate blinking text. When disabled, blinking text is always shown.
+    pub enabled: bool,
+    /// How long, in seconds, slowly blinking text is shown, and then hidden, for.
+    pub slow_interval: f32,
+    /// How long, in seconds, rapidly blinking text is shown, and then hidden, for.
+    pub rapid_interval: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            slow_interval: 0.5,
+            rapid_interval: 0.2,
+        }
+    }
+}
+
+/// Whether each speed of blinking text is currently visible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Phase {
+    /// Is slowly blinking text visible?
+    pub is_slow_visible: bool,
+    /// Is rapidly blinking text visible?
+    pub is_rapid_visible: bool,
+}
+
+impl Default for Phase {
+    fn default() -> Self {
+        Self {
+            is_slow_visible: true,
+            is_rapid_visible: true,
+        }
+    }
+}
+
+impl Phase {
+    /// The phase of the blink, the given time after blinking started.
+    pub fn at(elapsed: std::time::Duration, config: &Config) -> Self {
+        Self {
+            is_slow_visible: is_visible(elapsed, config.slow_interval),
+            is_rapid_visible: is_visible(elapsed, config.rapid_interval),
+        }
+    }
+}
+
+/// Text is visible for the first interval, hidden for the next, and so on.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "The number of intervals is always positive and we only need to know if it's even"
+)]
+fn is_visible(elapsed: std::time::Duration, interval: f32) -> bool {
+    if interval <= 0.0 {
+        return true;
+    }
+    let intervals = (elapsed.as_secs_f32() / interval).floor() as u64;
+    intervals % 2 == 0
+}
+
+/// Whether any of the frame's cells are blinking.
+pub(crate) fn has_blinking_cells(frame: &[&mut [Cell]]) -> bool {
+    frame
+        .iter()
+        .flat_map(|line| line.iter())
+        .any(|cell| cell.attrs().blink() != Blink::None)
+}
+
+/// Hide the text of the blinking cells that are in the hidden part of their blink. Their
+/// background is still shown. Tattoy is animating the blink itself, so the blink attribute is
+/// cleared from every blinking cell, otherwise the user's terminal would blink them as well.
+pub(crate) fn hide_blinking_cells(frame: &mut [&mut [Cell]], phase: Phase) {
+    for cell in frame.iter_mut().flat_map(|line| line.iter_mut()) {
+        let is_visible = match cell.attrs().blink() {
+            Blink::None => continue,
+            Blink::Slow => phase.is_slow_visible,
+            Blink::Rapid => phase.is_rapid_visible,
+        };
+        cell.attrs_mut().set_blink(Blink::None);
+        if !is_visible {
+            *cell = Cell::new(' ', cell.attrs().clone());
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::indexing_slicing, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slow_and_rapid_blinks_have_their_own_intervals() {
+        let config = Config::default();
+        let at = |millis| Phase::at(std::time::Duration::from_millis(millis), &config);
+        assert_eq!(at(0), Phase::default());
+        assert!(at(300).is_slow_visible);
+        assert!(!at(300).is_rapid_visible);
+        assert!(!at(600).is_slow_visible);
+        assert!(at(1100).is_slow_visible);
+    }
+
+    #[test]
+    fn hidden_blinking_cells_keep_their_background() {
+        let mut surface = termwiz::surface::Surface::new(2, 1);
+        surface.add_change("a");
+        surface.add_change(termwiz::surface::Change::AllAttributes(
+            termwiz::cell::CellAttributes::default()
+                .set_blink(Blink::Rapid)
+                .set_background(termwiz::color::ColorAttribute::PaletteIndex(1))
+                .clone(),
+        ));
+        surface.add_change("b");
+        let mut frame = surface.screen_cells();
+        assert!(has_blinking_cells(&frame));
+
+        let phase = Phase {
+            is_slow_visible: true,
+            is_rapid_visible: false,
+        };
+        hide_blinking_cells(&mut frame, phase);
+        assert_eq!(frame[0][0].str(), "a");
+        assert_eq!(frame[0][1].str(), " ");
+        assert_eq!(
+            frame[0][1].attrs().background(),
+            termwiz::color::ColorAttribute::PaletteIndex(1)
+        );
+        assert_eq!(frame[0][1].attrs().blink(), Blink::None);
+    }
+
+    #[test]
+    fn visible_blinking_cells_are_not_blinked_by_the_terminal() {
+        let mut surface = termwiz::surface::Surface::new(1, 1);
+        surface.add_change(termwiz::surface::Change::AllAttributes(
+            termwiz::cell::CellAttributes::default()
+                .set_blink(Blink::Slow)
+                .clone(),
+        ));
+        surface.add_change("a");
+        let mut frame = surface.screen_cells();
+
+        hide_blinking_cells(&mut frame, Phase::default());
+        assert_eq!(frame[0][0].str(), "a");
+        assert_eq!(frame[0][0].attrs().blink(), Blink::None);
+    }
+}
@@ -27,6 +27,48 @@ pub(crate) struct CliArgs {
     #[arg(long, value_name = "Path to screenshot file")]
     pub parse_palette: Option<String>,
 
+    /// Check that the config, palette and enabled tattoys are all valid, then exit without
+    /// starting the terminal. Useful for CI and setup scripts.
+    #[arg(long)]
+    pub validate: bool,
+
+    /// List all the available tattoys, with their descriptions and default config, then exit.
+    #[arg(long)]
+    pub list_tattoys: bool,
+
+    /// Also write every rendered frame, as ANSI, to this file or named pipe. Use `-` for STDOUT.
+    #[arg(long, value_name = "Path to file, named pipe or -")]
+    pub frame_sink: Option<std::path::PathBuf>,
+
+    /// The format of the frames written to the frame sink.
+    #[arg(long, value_enum, default_value_t)]
+    pub frame_format: crate::frame_sink::FrameFormat,
+
+    /// Replay a recording of raw PTY output, composited with the enabled tattoys, and export it as
+    /// an animated GIF. The terminal isn't started.
+    #[arg(long, value_name = "Path to recording of PTY output")]
+    pub export: Option<std::path::PathBuf>,
+
+    /// Where to save the exported GIF.
+    #[arg(long, default_value = "tattoy.gif", value_name = "Path to GIF")]
+    pub export_to: std::path::PathBuf,
+
+    /// The number of frames per second in the exported GIF.
+    #[arg(long, default_value_t = 10)]
+    pub export_frame_rate: u32,
+
+    /// How long, in seconds, the exported GIF lasts. The recording is spread evenly over it.
+    #[arg(long, default_value_t = 5.0)]
+    pub export_duration: f32,
+
+    /// The width, in columns, of the exported terminal.
+    #[arg(long, default_value_t = 80)]
+    pub export_columns: u16,
+
+    /// The height, in rows, of the exported terminal.
+    #[arg(long, default_value_t = 24)]
+    pub export_rows: u16,
+
     /// Path to config file directory. A directory must be used because Tattoy has various config
     /// files.
     #[arg(long, value_name = "Path to config directory")]
@@ -41,6 +83,11 @@ pub(crate) struct CliArgs {
     )]
     pub main_config: std::path::PathBuf,
 
+    /// Start with a config profile laid over the main config. Profiles are kept in the config
+    /// directory's `profiles` directory, eg: `profiles/presenting.toml` is the "presenting" profile.
+    #[arg(long, value_name = "Name of config profile")]
+    pub profile: Option<String>,
+
     /// Path to the log file, overrides the setting in config.
     #[arg(long, value_name = "Path to log file")]
     pub log_path: Option<std::path::PathBuf>,
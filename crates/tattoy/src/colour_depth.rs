@@ -0,0 +1,290 @@
+//! Tattoy renders everything in true colour. But not every terminal supports true colour, so for
+//! those terminals the final frame is quantized to the nearest colours of the 256 or 16 colour
+//! palettes. The user's own parsed palette is used when there is one, otherwise the standard
+//! xterm palette is used.
+
+/// The most quantized colours that are cached. Animations can make a lot of different colours,
+/// so the cache is cleared when it gets this big, rather than growing forever.
+const MAX_CACHED_COLOURS: usize = 65536;
+
+/// The number of colours that the user's terminal can show.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ColourDepth {
+    /// Detect it from the user's terminal.
+    #[default]
+    Auto,
+    /// 24 bit colour.
+    TrueColour,
+    /// The 256 colours of the standard palette.
+    Ansi256,
+    /// The first 16 colours of the palette.
+    Ansi16,
+}
+
+impl ColourDepth {
+    /// Detect the colour depth of the user's terminal from `$COLORTERM`, terminfo and `$TERM`.
+    /// This must be called before Tattoy overrides `$COLORTERM` for the PTY.
+    pub fn detect() -> Self {
+        let capabilities = match termwiz::caps::Capabilities::new_from_env() {
+            Ok(capabilities) => capabilities,
+            Err(error) => {
+                tracing::warn!("Couldn't detect the terminal's colour depth: {error:?}");
+                return Self::TrueColour;
+            }
+        };
+
+        // `$COLORTERM` always wins. Without it, Termwiz only knows about some of the ways that
+        // terminals report direct colour.
+        if std::env::var_os("COLORTERM").is_none() {
+            let has_direct_colour_flag = capabilities.terminfo_db().is_some_and(|terminfo| {
+                terminfo.raw("RGB").is_some() || terminfo.raw("Tc").is_some()
+            });
+            let term = std::env::var("TERM").unwrap_or_default();
+            if has_direct_colour_flag || is_direct_colour_term(&term) {
+                return Self::TrueColour;
+            }
+        }
+
+        match capabilities.color_level() {
+            termwiz::caps::ColorLevel::TrueColor => Self::TrueColour,
+            termwiz::caps::ColorLevel::TwoFiftySix => Self::Ansi256,
+            termwiz::caps::ColorLevel::Sixteen | termwiz::caps::ColorLevel::MonoChrome => {
+                Self::Ansi16
+            }
+        }
+    }
+
+    /// The colour depth to render with. The user's config overrides the detected depth.
+    pub const fn resolve(configured: Self, detected: Self) -> Self {
+        match (configured, detected) {
+            (Self::Auto, Self::Auto) => Self::TrueColour,
+            (Self::Auto, depth) | (depth, _) => depth,
+        }
+    }
+
+    /// The number of palette colours that can be used, if the depth isn't true colour.
+    const fn palette_size(self) -> Option<u16> {
+        match self {
+            Self::Auto | Self::TrueColour => None,
+            Self::Ansi256 => Some(256),
+            Self::Ansi16 => Some(16),
+        }
+    }
+}
+
+/// Whether the `$TERM` is for a terminfo entry that's conventionally used for direct colour, like
+/// `xterm-direct`.
+fn is_direct_colour_term(term: &str) -> bool {
+    term.ends_with("-direct") || term.contains("truecolor") || term.contains("24bit")
+}
+
+/// The standard first 16 colours of the palette, as defined by xterm.
+const ANSI_16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The levels of each channel in the 6x6x6 colour cube of the 256 colour palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The true colour of an index in the standard 256 colour palette.
+#[expect(
+    clippy::integer_division,
+    clippy::integer_division_remainder_used,
+    reason = "The palette's colour cube is indexed in base 6"
+)]
+pub(crate) fn standard_colour(index: u8) -> (u8, u8, u8) {
+    let cube_level = |level: u8| CUBE_LEVELS.get(usize::from(level)).copied().unwrap_or(0);
+    match index {
+        0..=15 => ANSI_16
+            .get(usize::from(index))
+            .copied()
+            .unwrap_or((0, 0, 0)),
+        16..=231 => {
+            let cube = index - 16;
+            (
+                cube_level(cube / 36),
+                cube_level((cube % 36) / 6),
+                cube_level(cube % 6),
+            )
+        }
+        232..=255 => {
+            let grey = 8 + (index - 232) * 10;
+            (grey, grey, grey)
+        }
+    }
+}
+
+/// Quantizes colours to a colour depth. Finding the nearest palette colour is expensive, and it's
+/// done for every cell of every frame, so each colour's nearest index is cached.
+pub(crate) struct Quantizer {
+    /// The palette to use when the user hasn't parsed their own.
+    standard: crate::palette::converter::Palette,
+    /// The nearest palette index of each colour that's been quantized, along with the size of the
+    /// palette that it was quantized to.
+    cache: std::collections::HashMap<((u8, u8, u8), u16), u8>,
+}
+
+impl Default for Quantizer {
+    fn default() -> Self {
+        Self {
+            standard: crate::palette::converter::Palette::standard(),
+            cache: std::collections::HashMap::new(),
+        }
+    }
+}
+
+impl Quantizer {
+    /// Forget all the quantized colours. The cache must be cleared whenever the palette changes.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Quantize a colour to the colour depth, using the user's palette if there is one. Colours
+    /// that are already palette indexes within the depth, and default colours, aren't changed.
+    pub fn quantize(
+        &mut self,
+        colour: termwiz::color::ColorAttribute,
+        depth: ColourDepth,
+        palette: Option<&crate::palette::converter::Palette>,
+    ) -> termwiz::color::ColorAttribute {
+        let Some(palette_size) = depth.palette_size() else {
+            return colour;
+        };
+        let palette = palette.unwrap_or(&self.standard);
+
+        let true_colour = match colour {
+            termwiz::color::ColorAttribute::Default => return colour,
+            termwiz::color::ColorAttribute::PaletteIndex(index) => {
+                if u16::from(index) < palette_size {
+                    return colour;
+                }
+                palette.colour(index)
+            }
+            termwiz::color::ColorAttribute::TrueColorWithPaletteFallback(srgba, _)
+            | termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(srgba) => {
+                let (red, green, blue, _) = srgba.to_srgb_u8();
+                (red, green, blue)
+            }
+        };
+
+        if self.cache.len() >= MAX_CACHED_COLOURS {
+            self.cache.clear();
+        }
+        let index = *self
+            .cache
+            .entry((true_colour, palette_size))
+            .or_insert_with(|| palette.nearest_index(true_colour, palette_size));
+        termwiz::color::ColorAttribute::PaletteIndex(index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn the_standard_palette() {
+        assert_eq!(standard_colour(1), (205, 0, 0));
+        assert_eq!(standard_colour(16), (0, 0, 0));
+        assert_eq!(standard_colour(196), (255, 0, 0));
+        assert_eq!(standard_colour(231), (255, 255, 255));
+        assert_eq!(standard_colour(244), (128, 128, 128));
+    }
+
+    fn quantize(
+        colour: termwiz::color::ColorAttribute,
+        depth: ColourDepth,
+    ) -> termwiz::color::ColorAttribute {
+        Quantizer::default().quantize(colour, depth, None)
+    }
+
+    #[test]
+    fn true_colours_are_quantized_to_the_nearest_index() {
+        let orange = termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(
+            termwiz::color::SrgbaTuple(1.0, 0.53, 0.0, 1.0),
+        );
+        assert_eq!(
+            quantize(orange, ColourDepth::Ansi256),
+            termwiz::color::ColorAttribute::PaletteIndex(208)
+        );
+        assert_eq!(
+            quantize(orange, ColourDepth::Ansi16),
+            termwiz::color::ColorAttribute::PaletteIndex(1)
+        );
+        assert_eq!(quantize(orange, ColourDepth::TrueColour), orange);
+    }
+
+    #[test]
+    fn high_palette_indexes_are_quantized_to_16_colours() {
+        let red = termwiz::color::ColorAttribute::PaletteIndex(196);
+        assert_eq!(quantize(red, ColourDepth::Ansi256), red);
+        assert_eq!(
+            quantize(red, ColourDepth::Ansi16),
+            termwiz::color::ColorAttribute::PaletteIndex(9)
+        );
+    }
+
+    #[test]
+    fn colours_are_quantized_to_the_users_palette() {
+        // A palette where red is a dark red, and bright red is orange.
+        let mut palette = crate::palette::converter::Palette::standard();
+        palette.set_colour(1, (120, 0, 0));
+        palette.set_colour(9, (255, 135, 0));
+        let orange = termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(
+            termwiz::color::SrgbaTuple(1.0, 0.53, 0.0, 1.0),
+        );
+
+        let mut quantizer = Quantizer::default();
+        assert_eq!(
+            quantizer.quantize(orange, ColourDepth::Ansi16, Some(&palette)),
+            termwiz::color::ColorAttribute::PaletteIndex(9)
+        );
+
+        // Cached colours are forgotten when the palette changes.
+        quantizer.clear();
+        assert_eq!(
+            quantizer.quantize(orange, ColourDepth::Ansi16, None),
+            termwiz::color::ColorAttribute::PaletteIndex(1)
+        );
+    }
+
+    #[test]
+    fn direct_colour_terms_are_detected() {
+        assert!(is_direct_colour_term("xterm-direct"));
+        assert!(is_direct_colour_term("konsole-direct"));
+        assert!(!is_direct_colour_term("xterm-256color"));
+        assert!(!is_direct_colour_term(""));
+    }
+
+    #[test]
+    fn the_config_overrides_detection() {
+        assert_eq!(
+            ColourDepth::resolve(ColourDepth::Ansi16, ColourDepth::TrueColour),
+            ColourDepth::Ansi16
+        );
+        assert_eq!(
+            ColourDepth::resolve(ColourDepth::Auto, ColourDepth::Ansi256),
+            ColourDepth::Ansi256
+        );
+        assert_eq!(
+            ColourDepth::resolve(ColourDepth::Auto, ColourDepth::Auto),
+            ColourDepth::TrueColour
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Simulate, or correct for, colour vision deficiencies. Simulating them is a way of checking that
+//! a TUI is still readable for people with colour blindness. Correcting for them, also known as
+//! "daltonization", shifts the colours that can't be told apart into ones that can.
+//!
+//! The simulation matrices are from Machado, Oliveira and Fernandes (2009), at full severity.
+
+/// A 3x3 matrix for transforming linear RGB colours.
+type Matrix = [[f32; 3]; 3];
+
+/// Protanopia, the absence of red cones.
+const PROTANOPIA: Matrix = [
+    [0.152_286, 1.052_583, -0.204_868],
+    [0.114_503, 0.786_281, 0.099_216],
+    [-0.003_882, -0.048_116, 1.051_998],
+];
+
+/// Deuteranopia, the absence of green cones.
+const DEUTERANOPIA: Matrix = [
+    [0.367_322, 0.860_646, -0.227_968],
+    [0.280_085, 0.672_501, 0.047_413],
+    [-0.011_820, 0.042_940, 0.968_881],
+];
+
+/// Tritanopia, the absence of blue cones.
+const TRITANOPIA: Matrix = [
+    [1.255_528, -0.076_749, -0.178_779],
+    [-0.078_411, 0.930_809, 0.147_602],
+    [0.004_733, 0.691_367, 0.303_900],
+];
+
+/// Spreads the colour information that's lost from the red and green channels into the green and
+/// blue channels.
+const DALTONIZATION: Matrix = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+
+/// The colour vision deficiencies that can be simulated or corrected for.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Deficiency {
+    /// Normal colour vision, colours aren't changed.
+    #[default]
+    None,
+    /// Red blindness.
+    Protanopia,
+    /// Green blindness.
+    Deuteranopia,
+    /// Blue blindness.
+    Tritanopia,
+}
+
+/// What to do about the colour vision deficiency.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Mode {
+    /// Show colours as they would be seen with the deficiency.
+    #[default]
+    Simulate,
+    /// Adjust colours so that they're easier to tell apart with the deficiency.
+    Correct,
+}
+
+/// Apply the colour vision filter to a colour.
+pub(crate) fn filter(
+    colour: termwiz::color::SrgbaTuple,
+    deficiency: Deficiency,
+    mode: Mode,
+) -> termwiz::color::SrgbaTuple {
+    let matrix = match deficiency {
+        Deficiency::None => return colour,
+        Deficiency::Protanopia => PROTANOPIA,
+        Deficiency::Deuteranopia => DEUTERANOPIA,
+        Deficiency::Tritanopia => TRITANOPIA,
+    };
+
+    let linear = [
+        to_linear(colour.0),
+        to_linear(colour.1),
+        to_linear(colour.2),
+    ];
+    let simulated = transform(&matrix, linear);
+    let filtered = match mode {
+        Mode::Simulate => simulated,
+        Mode::Correct => {
+            let error = [
+                linear[0] - simulated[0],
+                linear[1] - simulated[1],
+                linear[2] - simulated[2],
+            ];
+            let correction = transform(&DALTONIZATION, error);
+            [
+                linear[0] + correction[0],
+                linear[1] + correction[1],
+                linear[2] + correction[2],
+            ]
+        }
+    };
+
+    termwiz::color::SrgbaTuple(
+        to_srgb(filtered[0]),
+        to_srgb(filtered[1]),
+        to_srgb(filtered[2]),
+        colour.3,
+    )
+}
+
+/// Multiply a colour by a matrix.
+fn transform(matrix: &Matrix, colour: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * colour[0] + row[1] * colour[1] + row[2] * colour[2])
+}
+
+/// Convert an sRGB channel to linear light.
+pub(crate) fn to_linear(channel: f32) -> f32 {
+    if channel <= 0.040_45 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear light channel back to sRGB.
+fn to_srgb(channel: f32) -> f32 {
+    let clamped = channel.clamp(0.0, 1.0);
+    if clamped <= 0.003_130_8 {
+        clamped * 12.92
+    } else {
+        1.055 * clamped.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED: termwiz::color::SrgbaTuple = termwiz::color::SrgbaTuple(1.0, 0.0, 0.0, 1.0);
+    const GREEN: termwiz::color::SrgbaTuple = termwiz::color::SrgbaTuple(0.0, 1.0, 0.0, 1.0);
+
+    fn distance(left: termwiz::color::SrgbaTuple, right: termwiz::color::SrgbaTuple) -> f32 {
+        (left.0 - right.0)
+            .hypot(left.1 - right.1)
+            .hypot(left.2 - right.2)
+    }
+
+    #[test]
+    fn greys_are_unchanged() {
+        let grey = termwiz::color::SrgbaTuple(0.5, 0.5, 0.5, 0.25);
+        for deficiency in [
+            Deficiency::Protanopia,
+            Deficiency::Deuteranopia,
+            Deficiency::Tritanopia,
+        ] {
+            let filtered = filter(grey, deficiency, Mode::Simulate);
+            assert!(distance(filtered, grey) < 0.01, "{deficiency:?}");
+            assert!((filtered.3 - grey.3).abs() < f32::EPSILON);
+        }
+    }
+
+    #[test]
+    fn red_and_green_are_harder_to_tell_apart_when_simulated() {
+        let simulate = |colour| filter(colour, Deficiency::Deuteranopia, Mode::Simulate);
+        assert!(distance(simulate(RED), simulate(GREEN)) < distance(RED, GREEN));
+    }
+
+    #[test]
+    fn correction_makes_red_and_green_easier_to_tell_apart() {
+        let simulate = |colour| filter(colour, Deficiency::Protanopia, Mode::Simulate);
+        let correct = |colour| filter(colour, Deficiency::Protanopia, Mode::Correct);
+        let corrected = distance(simulate(correct(RED)), simulate(correct(GREEN)));
+        assert!(corrected > distance(simulate(RED), simulate(GREEN)));
+    }
+}
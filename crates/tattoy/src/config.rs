@@ -11,11 +11,27 @@ static DEFAULT_CONFIG: &str = include_str!("../default_config.toml");
 /// Bundle an example shader with Tattoy.
 static EXAMPLE_SHADER: &str = include_str!("tattoys/shaders/point_lights.glsl");
 
+/// The name of the JSON Schema file for the main config, so that editors can autocomplete and
+/// validate it.
+const SCHEMA_FILE_NAME: &str = "tattoy.schema.json";
+
 /// The name of the directory where shader files are kept.
 const SHADER_DIRECTORY_NAME: &str = "shaders";
 
+/// The name of the directory where config profiles are kept.
+const PROFILES_DIRECTORY_NAME: &str = "profiles";
+
 /// The valid log levels. Based on our `tracing` crate.
-#[derive(serde::Serialize, serde::Deserialize, clap::ValueEnum, Debug, Clone, PartialEq, Eq)]
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    clap::ValueEnum,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum LogLevel {
     /// Error
@@ -37,9 +53,11 @@ pub(crate) enum LogLevel {
     clippy::unsafe_derive_deserialize,
     reason = "Are the unsafe methods on the `f32`s?"
 )]
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
 #[serde(default)]
 pub(crate) struct Config {
+    /// The version of the config file, so that older config files can be upgraded.
+    pub version: u32,
     /// The `TERM` value to send to the underlying PTY. This may not actually be needed, but
     /// currently "TERM=xterm-256color" is fixing some bugs for me.
     pub term: String,
@@ -50,16 +68,72 @@ pub(crate) struct Config {
     pub log_level: LogLevel,
     /// The location of the log file.
     pub log_path: std::path::PathBuf,
+    /// Mirroring the PTY's raw output to a log file.
+    pub pty_log: crate::pty_log::Config,
     /// Colour grading
     pub color: Color,
+    /// Show bold text, that uses one of the first 8 palette colours, with the bright version of
+    /// the colour, like xterm and VTE do.
+    pub bold_is_bright: bool,
+    /// The number of colours that the user's terminal can show. Detected by default.
+    pub color_depth: crate::colour_depth::ColourDepth,
+    /// Dimming the whole terminal whilst it's idle.
+    pub idle_dimming: IdleDimming,
+    /// Fading the tattoys out whilst the alternate screen is in use.
+    pub alternate_screen: AlternateScreen,
+    /// Shaking the whole terminal.
+    pub screen_shake: crate::shake::Config,
+    /// Blinking text.
+    pub blink: crate::blink::Config,
+    /// Predictively showing typed characters before the PTY echoes them.
+    pub local_echo: crate::local_echo::Config,
     /// Target frame rate
     pub frame_rate: u32,
+    /// The number of rows to reserve at the bottom of the terminal for tattoys, like a status
+    /// line. The PTY is shrunk by that many rows, so that its applications never draw over them.
+    /// The clock is drawn in them when it fits.
+    pub status_rows: u16,
+    /// Seed for all the random numbers that tattoys use. Setting it makes effects reproducible,
+    /// so that the same input always renders the same frames.
+    pub random_seed: Option<u64>,
     /// The smokey particles cursor
     pub smokey_cursor: crate::tattoys::smokey_cursor::config::Config,
     /// The minimap
     pub minimap: crate::tattoys::minimap::Config,
     /// The shaders
     pub shader: crate::tattoys::shaders::main::Config,
+    /// The WASM plugin
+    pub wasm: crate::tattoys::wasm::Config,
+    /// The Rhai script
+    pub script: crate::tattoys::script::Config,
+    /// The audio visualiser
+    pub audio: crate::tattoys::audio::Config,
+    /// The background image
+    pub background_image: crate::tattoys::background_image::Config,
+    /// The background gradient
+    pub gradient: crate::tattoys::gradient::Config,
+    /// The fading trail behind the cursor
+    pub cursor_trail: crate::tattoys::cursor_trail::Config,
+    /// Flashing the cursor when it jumps a long way
+    pub cursor_flash: crate::tattoys::cursor_flash::Config,
+    /// A custom, animated cursor
+    pub cursor: crate::tattoys::cursor::Config,
+    /// Ripples from typed characters
+    pub ripple: crate::tattoys::ripple::Config,
+    /// Fading in new text
+    pub reveal: crate::tattoys::reveal::Config,
+    /// The clock in a corner of the terminal
+    pub clock: crate::tattoys::clock::Config,
+    /// The screensaver that takes over the terminal whilst it's idle
+    pub screensaver: crate::tattoys::screensaver::Config,
+    /// Show a tiny indicator in the top right corner when the terminal is too small for any of
+    /// the tattoys.
+    pub show_too_small_indicator: bool,
+    /// Smooth the edges of the shapes that tattoys draw, like the scrollbar, with partial block
+    /// characters.
+    pub antialiasing: bool,
+    /// Rectangles, keyed by tattoy ID, that clip tattoys to part of the terminal.
+    pub masks: std::collections::HashMap<String, crate::mask::Rect>,
 }
 
 impl Default for Config {
@@ -82,21 +156,48 @@ impl Default for Config {
         let log_path = log_directory.join("tattoy").join("tattoy.log");
 
         Self {
+            version: crate::config_migrations::CURRENT_VERSION,
             term: "xterm-256color".to_owned(),
             command,
             log_level: LogLevel::Off,
             log_path,
+            pty_log: crate::pty_log::Config::default(),
             color: Color::default(),
+            bold_is_bright: true,
+            color_depth: crate::colour_depth::ColourDepth::default(),
+            idle_dimming: IdleDimming::default(),
+            alternate_screen: AlternateScreen::default(),
+            screen_shake: crate::shake::Config::default(),
+            blink: crate::blink::Config::default(),
+            local_echo: crate::local_echo::Config::default(),
             frame_rate: 30,
+            status_rows: 0,
+            random_seed: None,
             smokey_cursor: crate::tattoys::smokey_cursor::config::Config::default(),
             minimap: crate::tattoys::minimap::Config::default(),
             shader: crate::tattoys::shaders::main::Config::default(),
+            wasm: crate::tattoys::wasm::Config::default(),
+            script: crate::tattoys::script::Config::default(),
+            audio: crate::tattoys::audio::Config::default(),
+            background_image: crate::tattoys::background_image::Config::default(),
+            gradient: crate::tattoys::gradient::Config::default(),
+            cursor_trail: crate::tattoys::cursor_trail::Config::default(),
+            cursor_flash: crate::tattoys::cursor_flash::Config::default(),
+            cursor: crate::tattoys::cursor::Config::default(),
+            ripple: crate::tattoys::ripple::Config::default(),
+            reveal: crate::tattoys::reveal::Config::default(),
+            clock: crate::tattoys::clock::Config::default(),
+            screensaver: crate::tattoys::screensaver::Config::default(),
+            show_too_small_indicator: true,
+            antialiasing: true,
+            masks: std::collections::HashMap::new(),
         }
     }
 }
 
 /// Final colour grading for the whole terminal render.
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
 pub(crate) struct Color {
     /// Saturation
     pub saturation: f32,
@@ -104,6 +205,10 @@ pub(crate) struct Color {
     pub brightness: f32,
     /// Hue
     pub hue: f32,
+    /// A colour vision deficiency to simulate or correct for.
+    pub vision_deficiency: crate::colour_vision::Deficiency,
+    /// Whether to simulate or correct for the colour vision deficiency.
+    pub vision_mode: crate::colour_vision::Mode,
 }
 
 impl Default for Color {
@@ -112,11 +217,106 @@ impl Default for Color {
             saturation: 0.0,
             brightness: 0.0,
             hue: 0.0,
+            vision_deficiency: crate::colour_vision::Deficiency::default(),
+            vision_mode: crate::colour_vision::Mode::default(),
         }
     }
 }
 
+/// Gradually dimming the whole terminal once it's been idle for a while, to save OLED screens
+/// from burn-in. Any input or output restores the brightness straight away.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct IdleDimming {
+    /// The number of seconds without any input or output before dimming starts. 0 disables it.
+    pub timeout: u64,
+    /// How much to dim by, using the same scale as `color.brightness`.
+    pub brightness: f32,
+}
+
+impl Default for IdleDimming {
+    fn default() -> Self {
+        Self {
+            timeout: 0,
+            brightness: -0.3,
+        }
+    }
+}
+
+/// Fading the tattoys out whilst a full screen application, like Vim, is using the alternate
+/// screen. They fade back in once it exits.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct AlternateScreen {
+    /// Enable/disable hiding the tattoys on the alternate screen.
+    pub hide_tattoys: bool,
+    /// How long, in seconds, the tattoys take to fade out and back in.
+    pub fade_duration: f32,
+    /// How the tattoys fade.
+    pub easing: crate::animation::Easing,
+}
+
+impl Default for AlternateScreen {
+    fn default() -> Self {
+        Self {
+            hide_tattoys: false,
+            fade_duration: 0.3,
+            easing: crate::animation::Easing::EaseInOut,
+        }
+    }
+}
+
+/// The result of one of the checks made by `Config::validate_setup()`.
+#[derive(Debug)]
+pub(crate) struct SetupCheck {
+    /// What was checked.
+    pub name: String,
+    /// What went wrong, if the check failed.
+    pub problem: Option<String>,
+}
+
+/// The results of all the checks made by `Config::validate_setup()`.
+#[derive(Debug, Default)]
+pub(crate) struct SetupReport {
+    /// Every check, in the order that they were made.
+    pub checks: Vec<SetupCheck>,
+}
+
+impl SetupReport {
+    /// Record the result of a check.
+    fn add<T, E: std::fmt::Display>(&mut self, name: String, result: &std::result::Result<T, E>) {
+        self.checks.push(SetupCheck {
+            name,
+            problem: result.as_ref().err().map(ToString::to_string),
+        });
+    }
+
+    /// Did every check pass?
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|check| check.problem.is_none())
+    }
+}
+
+impl std::fmt::Display for SetupReport {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            match &check.problem {
+                None => writeln!(formatter, "✓ {}", check.name)?,
+                Some(problem) => writeln!(formatter, "✗ {}: {problem}", check.name)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Config {
+    /// The height of the PTY in a terminal of the given height, once the status rows have been
+    /// reserved from the bottom of it. The PTY always keeps at least one row.
+    pub fn pty_height(height: u16, status_rows: u16) -> u16 {
+        height.saturating_sub(status_rows).max(1)
+    }
+
     /// Canonical path to the config directory.
     pub async fn directory(
         state: &std::sync::Arc<crate::shared_state::SharedState>,
@@ -131,6 +331,15 @@ impl Config {
             .join("tattoy"))
     }
 
+    /// A section of the default config, like `[minimap]`, if there is one.
+    pub fn default_section(name: &str) -> Option<toml::Table> {
+        let mut config = toml::from_str::<toml::Table>(DEFAULT_CONFIG).ok()?;
+        let toml::Value::Table(section) = config.remove(name)? else {
+            return None;
+        };
+        Some(section)
+    }
+
     /// Figure out where our config is being stored, and create the directory if needed.
     pub async fn setup_directory(
         maybe_custom_path: Option<std::path::PathBuf>,
@@ -145,12 +354,28 @@ impl Config {
 
         let shaders_directory = path.join(SHADER_DIRECTORY_NAME);
         std::fs::create_dir_all(shaders_directory)?;
+        std::fs::create_dir_all(path.join(PROFILES_DIRECTORY_NAME))?;
+
+        // Always overwrite the schema, so that it matches this version of Tattoy. It's only
+        // for editors, so Tattoy still starts without it.
+        let schema_path = path.join(SCHEMA_FILE_NAME);
+        let result =
+            Self::json_schema().and_then(|schema| Ok(std::fs::write(&schema_path, schema)?));
+        if let Err(error) = result {
+            tracing::error!("Couldn't write the config's schema to {schema_path:?}: {error:?}");
+        }
 
         *state.config_path.write().await = path;
 
         Ok(())
     }
 
+    /// A JSON Schema for the main config file, including the config for every tattoy.
+    pub fn json_schema() -> Result<String> {
+        let schema = schemars::schema_for!(Self);
+        Ok(serde_json::to_string_pretty(&schema)?)
+    }
+
     /// Canonical path to the main config file.
     pub async fn main_config_path(
         state: &std::sync::Arc<crate::shared_state::SharedState>,
@@ -160,8 +385,58 @@ impl Config {
         directory.join(main_config_file)
     }
 
-    /// Load the main config
+    /// The path to a named profile. Profiles can only be in the profiles directory, so their
+    /// names can't be paths.
+    pub async fn profile_path(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        name: &str,
+    ) -> Result<std::path::PathBuf> {
+        if !is_valid_profile_name(name) {
+            color_eyre::eyre::bail!(
+                "Invalid profile name '{name}', only letters, numbers, '-' and '_' are allowed"
+            );
+        }
+
+        Ok(Self::directory(state)
+            .await
+            .join(PROFILES_DIRECTORY_NAME)
+            .join(format!("{name}.toml")))
+    }
+
+    /// The names of all the profiles in the profiles directory, in alphabetical order.
+    pub async fn profile_names(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<Vec<String>> {
+        let directory = Self::directory(state).await.join(PROFILES_DIRECTORY_NAME);
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            let is_toml = path
+                .extension()
+                .is_some_and(|extension| extension == "toml");
+            if !is_toml {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(name.to_owned());
+            }
+        }
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Load the main config, with the current profile, if there is one.
     pub async fn load(state: &std::sync::Arc<crate::shared_state::SharedState>) -> Result<Self> {
+        let profile = state.profile.read().await.clone();
+        Self::load_with_profile(state, profile.as_deref()).await
+    }
+
+    /// Load the main config, with the named profile laid over it.
+    pub async fn load_with_profile(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        profile: Option<&str>,
+    ) -> Result<Self> {
         let config_path = Self::main_config_path(state).await;
         let config_file_name = config_path
             .file_name()
@@ -177,12 +452,19 @@ impl Config {
             std::fs::write(shader_path, EXAMPLE_SHADER)?;
         }
 
+        if config_path.exists() {
+            crate::config_migrations::migrate_file(&config_path)?;
+        }
+
         tracing::info!("(Re)loading the main Tattoy config from: {config_path:?}");
         let result = std::fs::read_to_string(config_path.clone());
         match result {
             Ok(data) => {
-                let config = toml::from_str::<Self>(&data)?;
-                Ok(config)
+                let profile_data = match profile {
+                    Some(name) => Some(Self::read_profile(state, name).await?),
+                    None => None,
+                };
+                Self::parse(&data, profile_data.as_deref())
             }
             Err(err) => {
                 tracing::error!("Loading config: {err:?}");
@@ -194,6 +476,38 @@ impl Config {
         }
     }
 
+    /// Read a profile's config file. Profiles are upgraded from older versions of Tattoy just
+    /// like the main config.
+    async fn read_profile(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        name: &str,
+    ) -> Result<String> {
+        let path = Self::profile_path(state, name).await?;
+        if !path.exists() {
+            let names = Self::profile_names(state).await.unwrap_or_default();
+            color_eyre::eyre::bail!(
+                "There's no '{name}' profile, the profiles are: {}",
+                names.join(", ")
+            );
+        }
+        crate::config_migrations::migrate_file(&path)?;
+        tracing::info!("Loading the '{name}' config profile from: {path:?}");
+        std::fs::read_to_string(&path).map_err(|error| {
+            color_eyre::eyre::eyre!("Couldn't load the '{name}' profile at {path:?}: {error}")
+        })
+    }
+
+    /// Parse the main config, with a profile's config, if there is one, laid over it.
+    fn parse(main: &str, profile: Option<&str>) -> Result<Self> {
+        let Some(profile) = profile else {
+            return Ok(toml::from_str::<Self>(main)?);
+        };
+
+        let mut config = toml::from_str::<toml::Table>(main)?;
+        overlay_table(&mut config, toml::from_str::<toml::Table>(profile)?);
+        Ok(toml::Value::Table(config).try_into::<Self>()?)
+    }
+
     /// Load the main config
     pub async fn load_config_into_shared_state(
         state: &std::sync::Arc<crate::shared_state::SharedState>,
@@ -206,6 +520,40 @@ impl Config {
         Ok(new_config)
     }
 
+    /// Check that everything Tattoy needs to start is valid, without starting a PTY or rendering
+    /// anything: the config parses, the palette loads and all the enabled tattoys pass their own
+    /// setup checks, like loading their shader, image, plugin or script. Every check is made, so
+    /// that all the problems are reported at once.
+    pub async fn validate_setup(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        registry: &crate::loader::TattoyRegistry,
+        enabled_tattoys: &[String],
+    ) -> SetupReport {
+        let mut report = SetupReport::default();
+        report.add(
+            "config".to_owned(),
+            &Self::load_config_into_shared_state(state).await,
+        );
+        report.add("palette".to_owned(), &Self::load_palette(state).await);
+
+        let mut names = std::collections::BTreeSet::new();
+        for name in registry.names() {
+            if crate::loader::is_enabled(name, enabled_tattoys, state).await {
+                names.insert(name.to_owned());
+            }
+        }
+        names.extend(enabled_tattoys.iter().cloned());
+
+        for name in names {
+            let result = registry.check_setup(&name, state).await.unwrap_or_else(|| {
+                Err(color_eyre::eyre::eyre!("There's no tattoy with this name"))
+            });
+            report.add(format!("'{name}' tattoy"), &result);
+        }
+
+        report
+    }
+
     /// Watch the config file for any changes and then automatically update the shared state with
     /// the contents of the new config file.
     pub fn watch(
@@ -229,6 +577,11 @@ impl Config {
                 notify::Config::default(),
             )?;
             watcher.watch(&path, notify::RecursiveMode::NonRecursive)?;
+            let profiles_path = path.join(PROFILES_DIRECTORY_NAME);
+            if profiles_path.is_dir() {
+                tracing::debug!("Watching config profiles ({profiles_path:?}) for changes.");
+                watcher.watch(&profiles_path, notify::RecursiveMode::NonRecursive)?;
+            }
 
             #[expect(
                 clippy::integer_division_remainder_used,
@@ -238,8 +591,12 @@ impl Config {
                 tokio::select! {
                     Some(result) = config_file_change_rx.recv() => Self::handle_file_change_event(result, &state, &tattoy_protocol_tx).await,
                     Ok(message) = tattoy_protocol_rx.recv() => {
-                        if matches!(message, crate::run::Protocol::End) {
-                            break;
+                        #[expect(clippy::wildcard_enum_match_arm, reason = "It's our internal protocol")]
+                        match message {
+                            crate::run::Protocol::End => break,
+                            crate::run::Protocol::ReloadConfig => Self::reload(&state, &tattoy_protocol_tx).await,
+                            crate::run::Protocol::SwitchProfile(name) => Self::switch_profile(&state, &tattoy_protocol_tx, &name).await,
+                            _ => (),
                         }
                     }
                 }
@@ -271,6 +628,12 @@ impl Config {
         ) {
             return;
         }
+        if Self::is_inactive_profile_change(&event, state).await {
+            return;
+        }
+        if Self::is_palette_change(&event, state).await {
+            Self::broadcast_palette_change(tattoy_protocol_tx);
+        }
         tracing::debug!("Config file change detected, updating shared state.");
 
         let maybe_new_config = Self::load_config_into_shared_state(state).await;
@@ -285,10 +648,125 @@ impl Config {
             }
             Err(error) => {
                 tracing::error!("Updating shared state after config file change: {error:?}");
+                let protocol_send_result = tattoy_protocol_tx
+                    .send(crate::run::Protocol::ConfigReloaded(Err(error.to_string())));
+                if let Err(send_error) = protocol_send_result {
+                    tracing::error!(
+                        "Couldn't send config reload result on protocol channel: {send_error:?}"
+                    );
+                }
             }
         }
     }
 
+    /// Reload the config and palette on demand, then broadcast whether it worked.
+    async fn reload(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        tattoy_protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+    ) {
+        tracing::debug!("Reloading the config on demand.");
+        let result = Self::reload_config_and_palette(state, tattoy_protocol_tx)
+            .await
+            .map_err(|error| error.to_string());
+        if let Err(error) = &result {
+            tracing::error!("Reloading the config: {error}");
+        }
+
+        let protocol_send_result =
+            tattoy_protocol_tx.send(crate::run::Protocol::ConfigReloaded(result));
+        if let Err(error) = protocol_send_result {
+            tracing::error!("Couldn't send config reload result on protocol channel: {error:?}");
+        }
+    }
+
+    /// Load the config into shared state, and check that the palette still loads. The palette
+    /// itself is reloaded by whatever uses it, when it's told that the palette has changed.
+    async fn reload_config_and_palette(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        tattoy_protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+    ) -> Result<()> {
+        let config = Self::load_config_into_shared_state(state).await?;
+        tattoy_protocol_tx.send(crate::run::Protocol::Config(config))?;
+        Self::load_palette(state).await?;
+        Self::broadcast_palette_change(tattoy_protocol_tx);
+        Ok(())
+    }
+
+    /// Whether a file watcher event is for the palette file.
+    async fn is_palette_change(
+        event: &notify::Event,
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> bool {
+        let palette_path = crate::palette::parser::Parser::palette_config_path(state).await;
+        event.paths.iter().any(|path| *path == palette_path)
+    }
+
+    /// Whether a file watcher event is only for profiles that aren't the current profile. They
+    /// aren't part of the current config, so there's nothing to reload.
+    async fn is_inactive_profile_change(
+        event: &notify::Event,
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> bool {
+        let profiles_path = Self::directory(state).await.join(PROFILES_DIRECTORY_NAME);
+        let current = state.profile.read().await.clone();
+        let current_path = match current {
+            Some(name) => Self::profile_path(state, &name).await.ok(),
+            None => None,
+        };
+
+        !event.paths.is_empty()
+            && event.paths.iter().all(|path| {
+                path.parent() == Some(profiles_path.as_path())
+                    && Some(path) != current_path.as_ref()
+            })
+    }
+
+    /// Tell everything that uses the palette to reload it.
+    fn broadcast_palette_change(
+        tattoy_protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+    ) {
+        tracing::debug!("Palette changed, broadcasting to reload it.");
+        let result = tattoy_protocol_tx.send(crate::run::Protocol::PaletteChanged);
+        if let Err(error) = result {
+            tracing::error!("Couldn't send palette change on protocol channel: {error:?}");
+        }
+    }
+
+    /// Switch to the named profile, then broadcast whether it worked. The profile is loaded before
+    /// anything changes, so a profile that doesn't load leaves the current config as it is.
+    async fn switch_profile(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        tattoy_protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        name: &str,
+    ) {
+        tracing::debug!("Switching to the '{name}' config profile.");
+        let result = Self::load_profile_into_shared_state(state, tattoy_protocol_tx, name)
+            .await
+            .map_err(|error| error.to_string());
+        if let Err(error) = &result {
+            tracing::error!("Switching to the '{name}' config profile: {error}");
+        }
+
+        let protocol_send_result =
+            tattoy_protocol_tx.send(crate::run::Protocol::ConfigReloaded(result));
+        if let Err(error) = protocol_send_result {
+            tracing::error!("Couldn't send profile switch result on protocol channel: {error:?}");
+        }
+    }
+
+    /// Load the config with the named profile into shared state, and make it the current profile.
+    async fn load_profile_into_shared_state(
+        state: &std::sync::Arc<crate::shared_state::SharedState>,
+        tattoy_protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        name: &str,
+    ) -> Result<()> {
+        let config = Self::load_with_profile(state, Some(name)).await?;
+        *state.config.write().await = config.clone();
+        *state.profile.write().await = Some(name.to_owned());
+        tattoy_protocol_tx.send(crate::run::Protocol::Config(config))?;
+        Ok(())
+    }
+
     /// Get a temporary file handle.
     pub fn temporary_file(name: &str) -> Result<std::path::PathBuf> {
         let file = tempfile::Builder::new()
@@ -308,7 +786,8 @@ impl Config {
             tracing::info!("Loading the terminal palette's true colours from config");
             let data = std::fs::read_to_string(path)?;
             let map = toml::from_str::<crate::palette::converter::PaletteHashMap>(&data)?;
-            let palette = crate::palette::converter::Palette { map };
+            let bold_is_bright = state.config.read().await.bold_is_bright;
+            let palette = crate::palette::converter::Palette::new(map, bold_is_bright);
             Ok(Some(palette))
         } else {
             tracing::debug!("Terminal palette colours config file not found in config directory");
@@ -316,3 +795,147 @@ impl Config {
         }
     }
 }
+
+/// Whether a profile name is safe to use as a file name. Profiles are named by users, and over
+/// signals, so they mustn't be able to point outside the profiles directory.
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_'))
+}
+
+/// Lay one TOML table over another. Nested tables are merged, so that a profile only needs the
+/// settings that it changes. Any other value, including an array, replaces the original.
+fn overlay_table(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        if let toml::Value::Table(table) = value {
+            if let Some(toml::Value::Table(base_table)) = base.get_mut(&key) {
+                overlay_table(base_table, table);
+            } else {
+                base.insert(key, toml::Value::Table(table));
+            }
+        } else {
+            base.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn an_empty_config_is_all_defaults() {
+        let config = toml::from_str::<Config>("").unwrap();
+        let defaults = Config::default();
+        assert_eq!(config.term, defaults.term);
+        assert_eq!(config.frame_rate, defaults.frame_rate);
+        assert_eq!(config.color.saturation, defaults.color.saturation);
+        assert_eq!(config.minimap.enabled, defaults.minimap.enabled);
+        assert!(config.masks.is_empty());
+    }
+
+    #[test]
+    fn partial_sections_default_their_missing_fields() {
+        let config = toml::from_str::<Config>("[color]\nsaturation = 0.5\n").unwrap();
+        assert_eq!(config.color.saturation, 0.5);
+        assert_eq!(config.color.brightness, Color::default().brightness);
+        assert_eq!(config.idle_dimming.timeout, IdleDimming::default().timeout);
+    }
+
+    #[test]
+    fn status_rows_are_reserved_from_the_pty() {
+        assert_eq!(Config::pty_height(30, 0), 30);
+        assert_eq!(Config::pty_height(30, 2), 28);
+        assert_eq!(Config::pty_height(2, 5), 1);
+    }
+
+    #[test]
+    fn profiles_only_override_the_settings_they_change() {
+        let main = "frame_rate = 60\n[color]\nsaturation = 0.5\nbrightness = 0.8\n";
+        let profile = "[color]\nsaturation = 1.5\n[minimap]\nenabled = true\n";
+        let config = Config::parse(main, Some(profile)).unwrap();
+        assert_eq!(config.frame_rate, 60);
+        assert_eq!(config.color.saturation, 1.5);
+        assert_eq!(config.color.brightness, 0.8);
+        assert!(config.minimap.enabled);
+
+        assert!(Config::parse(main, Some("frame_rate = \"fast\"")).is_err());
+    }
+
+    #[test]
+    fn profile_names_cant_be_paths() {
+        assert!(is_valid_profile_name("presenting"));
+        assert!(is_valid_profile_name("low-power_2"));
+        assert!(!is_valid_profile_name(""));
+        assert!(!is_valid_profile_name("../tattoy"));
+        assert!(!is_valid_profile_name("nested/profile"));
+    }
+
+    #[tokio::test]
+    async fn palette_file_changes_are_detected() {
+        let state = std::sync::Arc::new(crate::shared_state::SharedState::default());
+        *state.config_path.write().await = "/config".into();
+        let event = |path: &str| {
+            notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+                .add_path(path.into())
+        };
+        assert!(Config::is_palette_change(&event("/config/palette.toml"), &state).await);
+        assert!(!Config::is_palette_change(&event("/config/tattoy.toml"), &state).await);
+        assert!(!Config::is_palette_change(&event("/elsewhere/palette.toml"), &state).await);
+    }
+
+    #[tokio::test]
+    async fn only_the_current_profile_is_watched() {
+        let state = std::sync::Arc::new(crate::shared_state::SharedState::default());
+        *state.config_path.write().await = "/config".into();
+        let event = |path: &str| {
+            notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+                .add_path(path.into())
+        };
+        let coding = event("/config/profiles/coding.toml");
+
+        assert!(Config::is_inactive_profile_change(&coding, &state).await);
+        *state.profile.write().await = Some("coding".to_owned());
+        assert!(!Config::is_inactive_profile_change(&coding, &state).await);
+        let main = event("/config/tattoy.toml");
+        assert!(!Config::is_inactive_profile_change(&main, &state).await);
+    }
+
+    #[tokio::test]
+    async fn profiles_are_migrated() {
+        let config_directory = tempfile::tempdir().unwrap();
+        let profiles = config_directory.path().join(PROFILES_DIRECTORY_NAME);
+        std::fs::create_dir(&profiles).unwrap();
+        std::fs::write(profiles.join("old.toml"), "frame_rate = 60\n").unwrap();
+        let state = std::sync::Arc::new(crate::shared_state::SharedState::default());
+        *state.config_path.write().await = config_directory.path().to_path_buf();
+
+        let profile = Config::read_profile(&state, "old").await.unwrap();
+        assert!(profile.contains(&format!(
+            "version = {}",
+            crate::config_migrations::CURRENT_VERSION
+        )));
+        assert!(profiles.join("old.toml.v0.backup").exists());
+        assert_eq!(
+            Config::profile_names(&state).await.unwrap(),
+            vec!["old".to_owned()]
+        );
+    }
+
+    #[test]
+    fn the_schema_is_valid_json() {
+        let schema: serde_json::Value =
+            serde_json::from_str(&Config::json_schema().unwrap()).unwrap();
+        let properties = schema.get("properties").unwrap();
+        assert!(properties.get("frame_rate").is_some());
+        assert!(properties.get("masks").is_some());
+    }
+
+    #[test]
+    fn the_default_config_file_is_valid() {
+        toml::from_str::<Config>(DEFAULT_CONFIG).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+//! Upgrade users' config files from older versions of Tattoy.
+//!
+//! Every config file has a `version`. Files from before versioning don't have one, so they're
+//! version 0. When a config field changes in a way that would break, or change the meaning of,
+//! existing config files, bump `CURRENT_VERSION` and add a migration that upgrades the previous
+//! version.
+//!
+//! Migrations edit the TOML document itself, rather than the deserialised config, so that the
+//! user's comments and formatting are kept.
+
+use color_eyre::eyre::{ContextCompat as _, Result};
+
+/// The version of the config that this version of Tattoy uses.
+pub(crate) const CURRENT_VERSION: u32 = 1;
+
+/// Upgrades a config document by one version.
+type Migration = fn(&mut toml_edit::DocumentMut);
+
+/// Every migration, in order. The migration at index N upgrades version N to version N + 1.
+const MIGRATIONS: [Migration; 1] = [from_unversioned];
+
+/// The first versioned config is the same as the unversioned config, it just has a `version`.
+const fn from_unversioned(_document: &mut toml_edit::DocumentMut) {}
+
+/// The version of a config document. Documents without a version are from before versioning.
+fn version(document: &toml_edit::DocumentMut) -> Result<u32> {
+    let Some(version) = document.get("version") else {
+        return Ok(0);
+    };
+    let number = version
+        .as_integer()
+        .context("The config's `version` isn't a number")?;
+    Ok(u32::try_from(number)?)
+}
+
+/// Upgrade a config document to the current version. Returns whether anything changed.
+fn migrate(document: &mut toml_edit::DocumentMut) -> Result<bool> {
+    let from = version(document)?;
+    if from > CURRENT_VERSION {
+        color_eyre::eyre::bail!(
+            "The config is version {from}, which is newer than this version of Tattoy supports \
+             ({CURRENT_VERSION})"
+        );
+    }
+    if from == CURRENT_VERSION {
+        return Ok(false);
+    }
+
+    for migration in MIGRATIONS.iter().skip(usize::try_from(from)?) {
+        migration(document);
+    }
+    document.insert("version", toml_edit::value(i64::from(CURRENT_VERSION)));
+    Ok(true)
+}
+
+/// Upgrade the config file at the path to the current version, if it's old. A copy of the old
+/// file is kept next to it, and the upgraded file is written atomically, so that a half-written
+/// config is never loaded.
+pub(crate) fn migrate_file(path: &std::path::Path) -> Result<()> {
+    let data = std::fs::read_to_string(path)?;
+    let mut document = data.parse::<toml_edit::DocumentMut>()?;
+    let from = version(&document)?;
+    if !migrate(&mut document)? {
+        return Ok(());
+    }
+
+    let file_name = path
+        .file_name()
+        .context("Couldn't get file name from config path")?
+        .to_string_lossy();
+    let backup = path.with_file_name(format!("{file_name}.v{from}.backup"));
+    std::fs::copy(path, &backup)?;
+
+    let directory = path
+        .parent()
+        .context("Couldn't get directory of config path")?;
+    let mut file = tempfile::NamedTempFile::new_in(directory)?;
+    std::io::Write::write_all(&mut file, document.to_string().as_bytes())?;
+    file.persist(path)?;
+
+    tracing::info!(
+        "Upgraded the config at {path:?} from version {from} to {CURRENT_VERSION}, the old config \
+         is at {backup:?}"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unversioned_configs_are_upgraded_with_their_comments() {
+        let mut document = "# My shell\ncommand = \"zsh\"\n"
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert!(migrate(&mut document).unwrap());
+        assert_eq!(version(&document).unwrap(), CURRENT_VERSION);
+        assert!(document
+            .to_string()
+            .starts_with("# My shell\ncommand = \"zsh\"\n"));
+    }
+
+    #[test]
+    fn current_configs_arent_changed() {
+        let mut document = format!("version = {CURRENT_VERSION}\n")
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert!(!migrate(&mut document).unwrap());
+    }
+
+    #[test]
+    fn newer_configs_are_an_error() {
+        let mut document = format!("version = {}\n", CURRENT_VERSION + 1)
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert!(migrate(&mut document).is_err());
+    }
+
+    #[test]
+    fn migrating_a_file_keeps_a_backup() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("tattoy.toml");
+        std::fs::write(&path, "frame_rate = 60\n").unwrap();
+
+        migrate_file(&path).unwrap();
+        let migrated = std::fs::read_to_string(&path).unwrap();
+        assert!(migrated.contains(&format!("version = {CURRENT_VERSION}")));
+        let backup =
+            std::fs::read_to_string(directory.path().join("tattoy.toml.v0.backup")).unwrap();
+        assert_eq!(backup, "frame_rate = 60\n");
+    }
+}
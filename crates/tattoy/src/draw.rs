@@ -0,0 +1,482 @@
+//! Draw lines and shapes onto a tattoy surface.
+//!
+//! Positions and sizes are in cells, with fractions of a cell allowed. Rows are about twice as
+//! tall as columns are wide, so sizes that aren't tied to an axis, like a line's thickness or a
+//! circle's radius, are measured in columns. A thickness of 2.0 is therefore 2 columns wide but
+//! only 1 row tall.
+//!
+//! Each cell is sampled on a grid to find out how much of it the shape covers. Without
+//! anti-aliasing a cell is either filled or left alone. With it, cells on the edges of a shape get
+//! whichever partial block glyph, like "▄" or "▎", best matches the part of the cell that the
+//! shape covers.
+
+use crate::surface::{Colour, Surface};
+
+/// How many samples to take along each side of a cell. Partial block glyphs come in eighths.
+const SAMPLES: u32 = 8;
+
+/// How many times taller a row is than a column is wide.
+const ASPECT: f32 = 2.0;
+
+/// Every sample of a cell that's covered, one bit per sample. Bit 0 is the top left sample and
+/// the bits go along each row of samples.
+type Coverage = u64;
+
+/// How to draw a shape.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Style {
+    /// The colour of the shape.
+    pub colour: Colour,
+    /// How thick lines and outlines are, in columns.
+    pub thickness: f32,
+    /// Whether to smooth the shape's edges with partial block glyphs.
+    pub is_antialiased: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            colour: crate::surface::WHITE,
+            thickness: 1.0,
+            is_antialiased: true,
+        }
+    }
+}
+
+/// A shape that can be filled.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Shape {
+    /// A rectangle, from its top left corner.
+    Rect {
+        /// The left edge.
+        x: f32,
+        /// The top edge.
+        y: f32,
+        /// The width in columns.
+        width: f32,
+        /// The height in rows.
+        height: f32,
+    },
+    /// A circle. It's round on the screen, so it spans more columns than rows.
+    Circle {
+        /// The centre, as column and row.
+        centre: (f32, f32),
+        /// The radius in columns.
+        radius: f32,
+    },
+}
+
+/// Draw a straight line, with rounded ends.
+#[cfg_attr(
+    not(test),
+    expect(dead_code, reason = "Not every shape is drawn by a tattoy yet")
+)]
+pub(crate) fn line(surface: &mut Surface, from: (f32, f32), to: (f32, f32), style: &Style) {
+    let reach = style.thickness / 2.0;
+    let bounds = (
+        from.0.min(to.0) - reach,
+        from.1.min(to.1) - reach / ASPECT,
+        from.0.max(to.0) + reach,
+        from.1.max(to.1) + reach / ASPECT,
+    );
+    rasterise(surface, bounds, style, |x, y| {
+        distance_to_segment((x, y), from, to) <= reach
+    });
+}
+
+/// Draw the outline of a rectangle. The outline is inside the rectangle's edges.
+#[cfg_attr(
+    not(test),
+    expect(dead_code, reason = "Not every shape is drawn by a tattoy yet")
+)]
+pub(crate) fn rect(
+    surface: &mut Surface,
+    (x, y): (f32, f32),
+    (width, height): (f32, f32),
+    style: &Style,
+) {
+    let inset_x = style.thickness;
+    let inset_y = style.thickness / ASPECT;
+    let bounds = (x, y, x + width, y + height);
+    rasterise(surface, bounds, style, |sample_x, sample_y| {
+        let is_inside = |left: f32, top: f32, right: f32, bottom: f32| {
+            sample_x >= left && sample_x < right && sample_y >= top && sample_y < bottom
+        };
+        is_inside(x, y, x + width, y + height)
+            && !is_inside(
+                x + inset_x,
+                y + inset_y,
+                x + width - inset_x,
+                y + height - inset_y,
+            )
+    });
+}
+
+/// Draw the outline of a circle. The outline is centred on the circle's radius.
+pub(crate) fn circle(surface: &mut Surface, centre: (f32, f32), radius: f32, style: &Style) {
+    let reach = radius + style.thickness / 2.0;
+    let bounds = (
+        centre.0 - reach,
+        centre.1 - reach / ASPECT,
+        centre.0 + reach,
+        centre.1 + reach / ASPECT,
+    );
+    rasterise(surface, bounds, style, |x, y| {
+        (distance((x, y), centre) - radius).abs() <= style.thickness / 2.0
+    });
+}
+
+/// Fill the whole of a shape. The style's thickness isn't used.
+pub(crate) fn fill(surface: &mut Surface, shape: &Shape, style: &Style) {
+    fill_all(surface, std::slice::from_ref(shape), style);
+}
+
+/// Fill several shapes as if they were one. Filling them one at a time would give the cells
+/// where they meet the glyph of only the last shape, rather than a glyph that covers them all.
+pub(crate) fn fill_all(surface: &mut Surface, shapes: &[Shape], style: &Style) {
+    let Some(bounds) = shapes.iter().map(Shape::bounds).reduce(|all, bounds| {
+        (
+            all.0.min(bounds.0),
+            all.1.min(bounds.1),
+            all.2.max(bounds.2),
+            all.3.max(bounds.3),
+        )
+    }) else {
+        return;
+    };
+    rasterise(surface, bounds, style, |x, y| {
+        shapes.iter().any(|shape| shape.contains(x, y))
+    });
+}
+
+impl Shape {
+    /// The smallest rectangle, as `(left, top, right, bottom)`, that contains the shape.
+    fn bounds(&self) -> (f32, f32, f32, f32) {
+        match *self {
+            Self::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => (x, y, x + width, y + height),
+            Self::Circle { centre, radius } => (
+                centre.0 - radius,
+                centre.1 - radius / ASPECT,
+                centre.0 + radius,
+                centre.1 + radius / ASPECT,
+            ),
+        }
+    }
+
+    /// Whether the point is inside the shape.
+    fn contains(&self, sample_x: f32, sample_y: f32) -> bool {
+        match *self {
+            Self::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => sample_x >= x && sample_x < x + width && sample_y >= y && sample_y < y + height,
+            Self::Circle { centre, radius } => distance((sample_x, sample_y), centre) <= radius,
+        }
+    }
+}
+
+/// The distance, in columns, between 2 points.
+fn distance(from: (f32, f32), to: (f32, f32)) -> f32 {
+    (to.0 - from.0).hypot((to.1 - from.1) * ASPECT)
+}
+
+/// The distance, in columns, from a point to the closest part of a line segment.
+fn distance_to_segment(point: (f32, f32), from: (f32, f32), to: (f32, f32)) -> f32 {
+    let segment = (to.0 - from.0, (to.1 - from.1) * ASPECT);
+    let offset = (point.0 - from.0, (point.1 - from.1) * ASPECT);
+    let length_squared = segment.0.mul_add(segment.0, segment.1 * segment.1);
+    let along = if length_squared > 0.0 {
+        (offset.0.mul_add(segment.0, offset.1 * segment.1) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    along
+        .mul_add(-segment.0, offset.0)
+        .hypot(along.mul_add(-segment.1, offset.1))
+}
+
+/// Draw every cell within the bounds, `(left, top, right, bottom)`, that the shape covers.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "Terminals are far too small to lose precision"
+)]
+fn rasterise(
+    surface: &mut Surface,
+    (left, top, right, bottom): (f32, f32, f32, f32),
+    style: &Style,
+    contains: impl Fn(f32, f32) -> bool,
+) {
+    let columns =
+        (left.floor().max(0.0) as usize)..(right.ceil().max(0.0) as usize).min(surface.width);
+    let rows =
+        (top.floor().max(0.0) as usize)..(bottom.ceil().max(0.0) as usize).min(surface.height);
+
+    let sample_size = 1.0 / SAMPLES as f32;
+    for row in rows {
+        for column in columns.clone() {
+            let mut coverage: Coverage = 0;
+            for sample_row in 0..SAMPLES {
+                for sample_column in 0..SAMPLES {
+                    let sample_x = (sample_column as f32 + 0.5).mul_add(sample_size, column as f32);
+                    let sample_y = (sample_row as f32 + 0.5).mul_add(sample_size, row as f32);
+                    if contains(sample_x, sample_y) {
+                        coverage |= 1 << (sample_row * SAMPLES + sample_column);
+                    }
+                }
+            }
+
+            match glyph(coverage, style.is_antialiased) {
+                None => (),
+                Some(FULL) => {
+                    surface.add_text(column, row, " ".into(), Some(style.colour), None);
+                }
+                Some(partial) => {
+                    surface.add_text(column, row, partial.into(), None, Some(style.colour));
+                }
+            }
+        }
+    }
+}
+
+/// The glyph for a completely covered cell. It's drawn as a coloured background, rather than as
+/// "█", so that it blends with the cells around it.
+const FULL: &str = "█";
+
+/// Samples covered by the rows of samples from `from` up to `to`.
+const fn sample_rows(from: u32, to: u32) -> Coverage {
+    let mut coverage = 0;
+    let mut row = from;
+    while row < to {
+        coverage |= 0xFF << (row * SAMPLES);
+        row += 1;
+    }
+    coverage
+}
+
+/// Samples covered by the columns of samples from `from` up to `to`.
+const fn sample_columns(from: u32, to: u32) -> Coverage {
+    let mut coverage = 0;
+    let mut row = 0;
+    while row < SAMPLES {
+        let mut column = from;
+        while column < to {
+            coverage |= 1 << (row * SAMPLES + column);
+            column += 1;
+        }
+        row += 1;
+    }
+    coverage
+}
+
+/// The partial block glyphs, and the samples that they cover.
+const PARTIAL_BLOCKS: [(&str, Coverage); 18] = [
+    ("▁", sample_rows(7, 8)),
+    ("▂", sample_rows(6, 8)),
+    ("▃", sample_rows(5, 8)),
+    ("▄", sample_rows(4, 8)),
+    ("▅", sample_rows(3, 8)),
+    ("▆", sample_rows(2, 8)),
+    ("▇", sample_rows(1, 8)),
+    ("▏", sample_columns(0, 1)),
+    ("▎", sample_columns(0, 2)),
+    ("▍", sample_columns(0, 3)),
+    ("▌", sample_columns(0, 4)),
+    ("▋", sample_columns(0, 5)),
+    ("▊", sample_columns(0, 6)),
+    ("▉", sample_columns(0, 7)),
+    ("▀", sample_rows(0, 4)),
+    ("▔", sample_rows(0, 1)),
+    ("▐", sample_columns(4, 8)),
+    ("▕", sample_columns(7, 8)),
+];
+
+/// The glyph that best matches the covered samples of a cell. `None` means that the cell is best
+/// left empty.
+fn glyph(coverage: Coverage, is_antialiased: bool) -> Option<&'static str> {
+    let mismatch = |glyph_coverage: Coverage| (coverage ^ glyph_coverage).count_ones();
+
+    let mut best = (None, mismatch(0));
+    let full = (Some(FULL), mismatch(Coverage::MAX));
+    if full.1 < best.1 {
+        best = full;
+    }
+    if is_antialiased {
+        for (partial, glyph_coverage) in PARTIAL_BLOCKS {
+            let candidate = (Some(partial), mismatch(glyph_coverage));
+            if candidate.1 < best.1 {
+                best = candidate;
+            }
+        }
+    }
+
+    best.0
+}
+
+#[cfg(test)]
+/// Render a surface as text, with filled cells shown as "█". It makes tests easy to read.
+pub(crate) fn ascii(surface: &Surface) -> Vec<String> {
+    let mut cells = surface.surface.clone();
+    cells
+        .screen_cells()
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|cell| {
+                    if cell.attrs().background() == termwiz::color::ColorAttribute::Default {
+                        cell.str().to_owned()
+                    } else {
+                        FULL.to_owned()
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[expect(clippy::shadow_unrelated, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    /// The default style, with or without anti-aliasing.
+    fn style(is_antialiased: bool) -> Style {
+        Style {
+            is_antialiased,
+            ..Style::default()
+        }
+    }
+
+    #[test]
+    fn rectangle_outlines() {
+        let mut surface = Surface::new("test".into(), 6, 3, -1);
+        let thick = Style {
+            thickness: 2.0,
+            ..style(false)
+        };
+        rect(&mut surface, (0.0, 0.0), (6.0, 3.0), &thick);
+
+        assert_eq!(ascii(&surface), ["██████", "██  ██", "██████"]);
+    }
+
+    #[test]
+    fn lines() {
+        let mut surface = Surface::new("test".into(), 5, 3, -1);
+        let thick = Style {
+            thickness: 2.0,
+            ..style(false)
+        };
+        line(&mut surface, (0.0, 1.5), (5.0, 1.5), &thick);
+        assert_eq!(ascii(&surface), ["     ", "█████", "     "]);
+
+        let mut surface = Surface::new("test".into(), 5, 3, -1);
+        line(&mut surface, (0.0, 1.75), (5.0, 1.75), &style(true));
+        assert_eq!(ascii(&surface), ["     ", "▄▄▄▄▄", "     "]);
+    }
+
+    #[test]
+    fn filled_rectangles_are_smoothed_at_their_ends() {
+        let shape = Shape::Rect {
+            x: 0.0,
+            y: 0.5,
+            width: 1.0,
+            height: 1.75,
+        };
+
+        let mut surface = Surface::new("test".into(), 1, 3, -1);
+        fill(&mut surface, &shape, &style(true));
+        assert_eq!(ascii(&surface), ["▄", "█", "▔"]);
+
+        let mut surface = Surface::new("test".into(), 1, 3, -1);
+        fill(&mut surface, &shape, &style(false));
+        assert_eq!(ascii(&surface), [" ", "█", " "]);
+    }
+
+    #[test]
+    fn shapes_that_meet_are_filled_together() {
+        let halves = [
+            Shape::Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 1.0,
+                height: 0.5,
+            },
+            Shape::Rect {
+                x: 0.0,
+                y: 0.5,
+                width: 1.0,
+                height: 0.5,
+            },
+        ];
+
+        let mut surface = Surface::new("test".into(), 1, 1, -1);
+        for half in &halves {
+            fill(&mut surface, half, &style(true));
+        }
+        assert_eq!(ascii(&surface), ["▄"]);
+
+        let mut surface = Surface::new("test".into(), 1, 1, -1);
+        fill_all(&mut surface, &halves, &style(true));
+        assert_eq!(ascii(&surface), ["█"]);
+    }
+
+    #[test]
+    fn circles() {
+        let shape = Shape::Circle {
+            centre: (4.5, 2.5),
+            radius: 4.0,
+        };
+
+        let mut surface = Surface::new("test".into(), 9, 5, -1);
+        fill(&mut surface, &shape, &style(false));
+        assert_eq!(
+            ascii(&surface),
+            [
+                "         ",
+                " ███████ ",
+                " ███████ ",
+                " ███████ ",
+                "         "
+            ]
+        );
+
+        let mut surface = Surface::new("test".into(), 9, 5, -1);
+        fill(&mut surface, &shape, &style(true));
+        assert_eq!(
+            ascii(&surface),
+            [
+                "  ▂▃▄▃▂  ",
+                " ▆█████▆ ",
+                "▐███████▌",
+                " ███████ ",
+                "  ▔▀▀▀▔  "
+            ]
+        );
+
+        let mut surface = Surface::new("test".into(), 11, 5, -1);
+        let thick = Style {
+            thickness: 2.0,
+            ..style(false)
+        };
+        circle(&mut surface, (5.5, 2.5), 4.5, &thick);
+        assert_eq!(
+            ascii(&surface),
+            [
+                "  ███████  ",
+                "███     ███",
+                "██       ██",
+                "███     ███",
+                "  ███████  "
+            ]
+        );
+    }
+}
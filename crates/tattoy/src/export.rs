@@ -0,0 +1,243 @@
+//! Export a recording of a terminal session, composited with the user's tattoys, as an animated
+//! GIF. Useful for making demos of Tattoy setups.
+//!
+//! The recording is just the raw bytes that were output by a PTY, for example from `script` or
+//! from the shadow terminal's tee. There isn't any timing information in raw bytes, so they're
+//! spread evenly over the duration of the export.
+//!
+//! The tattoys don't render in real time during an export. Each of the export's frames steps
+//! them with a frame clock, and waits for them to render, so slow tattoys are still exported at
+//! the full frame rate.
+//!
+//! There's no font rendering, so text is drawn as solid blocks of its colour. Half block pixels
+//! are drawn exactly.
+
+use std::sync::Arc;
+
+use color_eyre::eyre::Result;
+
+/// The width, in pixels, of each terminal cell in the exported image.
+const CELL_WIDTH: u32 = 6;
+
+/// The height, in pixels, of each terminal cell in the exported image.
+const CELL_HEIGHT: u32 = 12;
+
+/// Everything needed to make an export.
+pub(crate) struct Options {
+    /// The raw PTY output to replay.
+    pub recording: std::path::PathBuf,
+    /// Where to save the GIF.
+    pub output: std::path::PathBuf,
+    /// The number of frames per second.
+    pub frame_rate: u32,
+    /// How long, in seconds, the export lasts.
+    pub duration: f32,
+    /// The width of the terminal in columns.
+    pub columns: u16,
+    /// The height of the terminal in rows.
+    pub rows: u16,
+}
+
+/// Replay the recording through a shadow terminal and the enabled tattoys, compositing a frame
+/// at a time.
+pub(crate) async fn export(
+    state: &Arc<crate::shared_state::SharedState>,
+    mut registry: crate::loader::TattoyRegistry,
+    enabled_tattoys: Vec<String>,
+    options: &Options,
+) -> Result<()> {
+    let recording = std::fs::read(&options.recording)?;
+    let frame_rate = options.frame_rate.max(1);
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        reason = "Exports are far too short to lose precision"
+    )]
+    let frame_count = ((options.duration.max(0.0) * frame_rate as f32).ceil() as usize).max(1);
+
+    state.set_tty_size(options.columns, options.rows).await;
+    // Keep a receiver, so that sending doesn't fail before the tattoys have subscribed.
+    let (protocol_tx, _protocol_rx) = tokio::sync::broadcast::channel(1024);
+    let (surfaces_tx, mut surfaces_rx) = tokio::sync::mpsc::channel(1024);
+    let pixel_shader = registry.take_pixel_shader();
+    let mut frame_clock = crate::frame_clock::FrameClock::default();
+    let tattoys_handle = crate::loader::start_tattoys(
+        registry,
+        enabled_tattoys,
+        protocol_tx.clone(),
+        surfaces_tx,
+        Arc::clone(state),
+        Some(frame_clock.ticks()),
+    );
+    let result = protocol_tx.send(crate::run::Protocol::Resize {
+        width: options.columns,
+        height: options.rows,
+    });
+    if let Err(error) = result {
+        tracing::error!("Couldn't send the export's size to tattoys: {error:?}");
+    }
+
+    let (output_tx, mut output_rx) = tokio::sync::mpsc::channel(16);
+    let mut shadow_terminal = shadow_terminal::shadow_terminal::ShadowTerminal::new(
+        shadow_terminal::shadow_terminal::Config {
+            width: options.columns,
+            height: options.rows,
+            command: Vec::new(),
+            kill_on_drop: false,
+            ..Default::default()
+        },
+        output_tx,
+    );
+    let mut renderer = crate::renderer::Renderer {
+        state: Arc::clone(state),
+        width: options.columns,
+        height: options.rows,
+        pixel_shader,
+        palette: crate::config::Config::load_palette(state).await?,
+        ..crate::renderer::Renderer::default()
+    };
+
+    let chunk_size = recording.len().div_ceil(frame_count).max(1);
+    let mut chunks = recording.chunks(chunk_size);
+    let mut encoder = gif_encoder(&options.output)?;
+    let delay = image::Delay::from_numer_denom_ms(1000, frame_rate);
+    for _ in 0..frame_count {
+        let mut updates = Vec::new();
+        if let Some(chunk) = chunks.next() {
+            shadow_terminal.replay_pty_output(chunk).await?;
+            while let Ok(output) = output_rx.try_recv() {
+                if let shadow_terminal::output::Output::Complete(
+                    shadow_terminal::output::CompleteSurface::Screen(screen),
+                ) = &output
+                {
+                    *state.shadow_tty_screen.write().await = screen.surface.clone();
+                    updates.push(crate::run::FrameUpdate::PTYSurface);
+                }
+                let result = protocol_tx.send(crate::run::Protocol::Output(output));
+                if let Err(error) = result {
+                    tracing::error!("Couldn't send replayed output to tattoys: {error:?}");
+                }
+            }
+        }
+
+        frame_clock.tick().await;
+        while let Ok(update) = surfaces_rx.try_recv() {
+            updates.push(update);
+        }
+
+        let mut surface = renderer.render_frame(updates).await?;
+        let image = to_image(&mut surface, renderer.palette.as_ref())?;
+        encoder.encode_frame(image::Frame::from_parts(image, 0, 0, delay))?;
+    }
+
+    crate::run::broadcast_protocol_end(&protocol_tx);
+    tattoys_handle
+        .join()
+        .map_err(|err| color_eyre::eyre::eyre!("Tattoys handle: {err:?}"))??;
+
+    Ok(())
+}
+
+/// Draw a composited frame as an image.
+fn to_image(
+    surface: &mut termwiz::surface::Surface,
+    palette: Option<&crate::palette::converter::Palette>,
+) -> Result<image::RgbaImage> {
+    let (columns, rows) = surface.dimensions();
+    let mut image = image::RgbaImage::new(
+        u32::try_from(columns)? * CELL_WIDTH,
+        u32::try_from(rows)? * CELL_HEIGHT,
+    );
+
+    for (y, line) in surface.screen_cells().iter().enumerate() {
+        for (x, cell) in line.iter().enumerate() {
+            let mut attributes = cell.attrs().clone();
+            if let Some(palette) = palette {
+                palette.cell_attributes_to_true_colour(&mut attributes);
+            }
+            let foreground = to_pixel(attributes.foreground(), crate::surface::WHITE);
+            let background = to_pixel(attributes.background(), crate::surface::BLACK);
+
+            let (left, top) = (
+                u32::try_from(x)? * CELL_WIDTH,
+                u32::try_from(y)? * CELL_HEIGHT,
+            );
+            for pixel_y in 0..CELL_HEIGHT {
+                for pixel_x in 0..CELL_WIDTH {
+                    let colour = if is_ink(cell.str(), pixel_x, pixel_y) {
+                        foreground
+                    } else {
+                        background
+                    };
+                    image.put_pixel(left + pixel_x, top + pixel_y, colour);
+                }
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+/// Whether a pixel of a cell is drawn in the cell's foreground colour.
+fn is_ink(glyph: &str, x: u32, y: u32) -> bool {
+    let is_top = y < CELL_HEIGHT / 2;
+    match glyph {
+        "" | " " => false,
+        "▀" => is_top,
+        "▄" => !is_top,
+        "█" => true,
+        _ => x >= 1 && x < CELL_WIDTH - 1 && y >= 3 && y < CELL_HEIGHT - 2,
+    }
+}
+
+/// Convert a cell's colour to a pixel, using the fallback for colours that aren't true colour.
+fn to_pixel(
+    colour: termwiz::color::ColorAttribute,
+    fallback: crate::surface::Colour,
+) -> image::Rgba<u8> {
+    let srgba = crate::opaque_cell::OpaqueCell::extract_colour(colour).unwrap_or(
+        termwiz::color::SrgbaTuple(fallback.0, fallback.1, fallback.2, fallback.3),
+    );
+    let (red, green, blue, _) = srgba.to_srgb_u8();
+    image::Rgba([red, green, blue, 255])
+}
+
+/// An encoder for an animated GIF that loops forever. Frames are encoded as they're rendered,
+/// so long exports don't have to be held in memory.
+fn gif_encoder(
+    path: &std::path::Path,
+) -> Result<image::codecs::gif::GifEncoder<std::io::BufWriter<std::fs::File>>> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = image::codecs::gif::GifEncoder::new(file);
+    encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+    Ok(encoder)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn half_blocks_are_drawn_exactly() {
+        let mut surface = crate::surface::Surface::new("test".into(), 1, 1, 1);
+        surface.add_pixel(0, 0, crate::surface::RED).unwrap();
+        surface.add_pixel(0, 1, crate::surface::WHITE).unwrap();
+
+        let image = to_image(&mut surface.surface, None).unwrap();
+        assert_eq!(image.dimensions(), (CELL_WIDTH, CELL_HEIGHT));
+        assert_eq!(image.get_pixel(0, 0), &image::Rgba([255, 0, 0, 255]));
+        assert_eq!(
+            image.get_pixel(0, CELL_HEIGHT - 1),
+            &image::Rgba([255, 255, 255, 255])
+        );
+    }
+
+    #[test]
+    fn text_is_drawn_as_blocks() {
+        assert!(!is_ink(" ", 2, 6));
+        assert!(is_ink("a", 2, 6));
+        assert!(!is_ink("a", 0, 0));
+    }
+}
@@ -0,0 +1,186 @@
+//! An explicit clock for the tattoys' frames.
+//!
+//! Tattoys normally render in real time, at their own frame rates. But an export needs a fresh
+//! frame from every tattoy for each of its own frames, however long they take to render. So the
+//! export steps this clock one frame at a time, and waits for the tattoys that follow it to
+//! render each frame.
+//!
+//! The clock is handed to the tattoys through a task local that the loader sets, so tattoys
+//! don't need to know whether they're being exported. A tattoy only starts following the clock
+//! when it first waits for a frame tick, so tattoys that don't render on frame ticks never hold
+//! the clock up.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+tokio::task_local! {
+    /// The frame clock for the tattoys that are created in the current task.
+    static FRAME_TICKS: FrameTicks;
+}
+
+/// How long to wait for the tattoys to render a frame. It's only a safety net, in case a tattoy
+/// gets stuck.
+const FRAME_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Steps the frames of all the tattoys that follow it.
+pub(crate) struct FrameClock {
+    /// The number of the current frame.
+    frame: tokio::sync::watch::Sender<u64>,
+    /// Each follower's acknowledgement that it's rendered a frame, by the frame's number.
+    rendered: tokio::sync::mpsc::UnboundedReceiver<u64>,
+    /// The followers' side of the clock.
+    ticks: FrameTicks,
+}
+
+/// The tattoys' side of a frame clock.
+#[derive(Clone)]
+pub(crate) struct FrameTicks {
+    /// The number of the current frame.
+    frame: tokio::sync::watch::Receiver<u64>,
+    /// Acknowledges that a frame has been rendered.
+    rendered: tokio::sync::mpsc::UnboundedSender<u64>,
+    /// The number of tattoys that are following the clock.
+    followers: Arc<AtomicUsize>,
+}
+
+/// A tattoy's place on the frame clock.
+pub(crate) struct FrameFollower {
+    /// The clock being followed.
+    ticks: FrameTicks,
+    /// Whether the tattoy has started following the clock.
+    is_following: bool,
+    /// The frame that the tattoy is rendering, if it's rendering one.
+    rendering: Option<u64>,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        let (frame, frame_rx) = tokio::sync::watch::channel(0);
+        let (rendered_tx, rendered) = tokio::sync::mpsc::unbounded_channel();
+        Self {
+            frame,
+            rendered,
+            ticks: FrameTicks {
+                frame: frame_rx,
+                rendered: rendered_tx,
+                followers: Arc::default(),
+            },
+        }
+    }
+}
+
+impl FrameClock {
+    /// The followers' side of the clock, to give to the loader.
+    pub fn ticks(&self) -> FrameTicks {
+        self.ticks.clone()
+    }
+
+    /// Move on to the next frame, and wait until every follower has rendered it.
+    pub async fn tick(&mut self) {
+        let followers = self.ticks.followers.load(Ordering::Acquire);
+        let mut frame = 0;
+        self.frame.send_modify(|current| {
+            *current = current.wrapping_add(1);
+            frame = *current;
+        });
+
+        let deadline = tokio::time::Instant::now() + FRAME_TIMEOUT;
+        let mut rendered = 0;
+        while rendered < followers {
+            match tokio::time::timeout_at(deadline, self.rendered.recv()).await {
+                Ok(Some(rendered_frame)) if rendered_frame == frame => rendered += 1,
+                Ok(Some(_)) => (),
+                Ok(None) | Err(_) => {
+                    tracing::warn!(
+                        "Only {rendered} of {followers} tattoys rendered frame {frame} in time"
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl FrameTicks {
+    /// Run a future, so that every tattoy that's created in it follows this clock.
+    pub fn scope<F: std::future::Future>(
+        self,
+        future: F,
+    ) -> impl std::future::Future<Output = F::Output> {
+        FRAME_TICKS.scope(self, future)
+    }
+
+    /// A follower of the current task's frame clock, if the task has one.
+    pub fn follower() -> Option<FrameFollower> {
+        FRAME_TICKS
+            .try_with(|ticks| FrameFollower {
+                ticks: ticks.clone(),
+                is_following: false,
+                rendering: None,
+            })
+            .ok()
+    }
+}
+
+impl FrameFollower {
+    /// Wait for the next frame. Waiting also acknowledges that the previous frame has been
+    /// rendered. It's cancel safe, so it can be used in a `tokio::select!`.
+    pub async fn next_frame(&mut self) {
+        if !self.is_following {
+            self.is_following = true;
+            self.ticks.followers.fetch_add(1, Ordering::AcqRel);
+        }
+
+        if let Some(frame) = self.rendering.take() {
+            if self.ticks.rendered.send(frame).is_err() {
+                tracing::trace!("Frame clock stopped before frame {frame} was acknowledged");
+            }
+        }
+
+        if self.ticks.frame.changed().await.is_err() {
+            // There won't be any more frames.
+            std::future::pending::<()>().await;
+        }
+        self.rendering = Some(*self.ticks.frame.borrow_and_update());
+    }
+}
+
+impl Drop for FrameFollower {
+    fn drop(&mut self) {
+        if self.is_following {
+            self.ticks.followers.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn each_tick_waits_for_every_follower() {
+        let mut clock = FrameClock::default();
+        assert!(FrameTicks::follower().is_none());
+
+        let renders = Arc::new(AtomicUsize::new(0));
+        let follower_renders = Arc::clone(&renders);
+        let follower = tokio::spawn(clock.ticks().scope(async move {
+            let mut follower = FrameTicks::follower().unwrap();
+            loop {
+                follower.next_frame().await;
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                follower_renders.fetch_add(1, Ordering::AcqRel);
+            }
+        }));
+        while clock.ticks.followers.load(Ordering::Acquire) == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        for frame in 1..=3 {
+            clock.tick().await;
+            assert_eq!(renders.load(Ordering::Acquire), frame);
+        }
+
+        follower.abort();
+    }
+}
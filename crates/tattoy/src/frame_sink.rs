@@ -0,0 +1,290 @@
+//! Write every composited frame to a file, a named pipe or STDOUT, so that Tattoy's output can be
+//! piped into recording or streaming tools.
+//!
+//! Tattoy itself renders to the TTY, not STDOUT, so STDOUT is free to be used as a sink.
+
+use std::fmt::Write as _;
+
+use color_eyre::eyre::Result;
+
+/// The ANSI code to reset all the text attributes.
+const RESET_ATTRIBUTES: &str = "\x1b[0m";
+
+/// The number of encoded frames that can be waiting to be written. Frames are dropped when a slow
+/// reader lets it fill up, rather than holding up rendering.
+const FRAME_SINK_CAPACITY: usize = 8;
+
+/// How each frame is written to the sink.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum FrameFormat {
+    /// Just the frame as ANSI. It starts by moving the cursor to the top left, so writing the
+    /// frames to a terminal plays them.
+    #[default]
+    Raw,
+    /// The number of bytes in the ANSI frame, as a big-endian `u32`, followed by the frame. For
+    /// tools that need to know where each frame ends.
+    LengthPrefixed,
+}
+
+/// Writes frames at no more than the target frame rate. The writing itself is done in a blocking
+/// task, so that a slow pipe or file never holds up rendering.
+pub(crate) struct FrameSink {
+    /// Sends encoded frames to the task that writes them.
+    frames: tokio::sync::mpsc::Sender<Vec<u8>>,
+    /// How the frames are written.
+    format: FrameFormat,
+    /// The shortest time between frames.
+    minimum_interval: std::time::Duration,
+    /// When the previous frame was written.
+    last_write: Option<std::time::Instant>,
+}
+
+impl FrameSink {
+    /// Open a sink at the given path. `-` is STDOUT. Opening a named pipe blocks until something
+    /// starts reading from it, so the path is opened in the writing task, and frames are dropped
+    /// until it's open. If it can't be opened then the error is logged and the sink stops.
+    pub fn open(path: &std::path::Path, format: FrameFormat, frame_rate: u32) -> Self {
+        let path = path.to_owned();
+        Self::start(
+            move || {
+                let writer: Box<dyn std::io::Write + Send> = if path == std::path::Path::new("-") {
+                    Box::new(std::io::stdout())
+                } else {
+                    Box::new(
+                        std::fs::OpenOptions::new()
+                            .write(true)
+                            .create(true)
+                            .truncate(true)
+                            .open(&path)?,
+                    )
+                };
+                Ok(writer)
+            },
+            format,
+            frame_rate,
+        )
+    }
+
+    /// Instantiate, and start the task that writes the frames.
+    pub fn new(
+        writer: Box<dyn std::io::Write + Send>,
+        format: FrameFormat,
+        frame_rate: u32,
+    ) -> Self {
+        Self::start(move || Ok(writer), format, frame_rate)
+    }
+
+    /// Start the task that opens the writer and then writes the frames to it.
+    fn start(
+        open: impl FnOnce() -> std::io::Result<Box<dyn std::io::Write + Send>> + Send + 'static,
+        format: FrameFormat,
+        frame_rate: u32,
+    ) -> Self {
+        let (frames, frames_rx) = tokio::sync::mpsc::channel(FRAME_SINK_CAPACITY);
+        tokio::task::spawn_blocking(move || {
+            let result = open()
+                .map_err(color_eyre::eyre::Report::from)
+                .and_then(|writer| Self::write_frames(writer, frames_rx));
+            if let Err(error) = result {
+                tracing::error!("Writing to the frame sink: {error:?}");
+            }
+        });
+
+        let micros = crate::renderer::ONE_MICROSECOND.wrapping_div(frame_rate.max(1).into());
+        Self {
+            frames,
+            format,
+            minimum_interval: std::time::Duration::from_micros(micros),
+            last_write: None,
+        }
+    }
+
+    /// Write the frame, unless the previous frame was written too recently.
+    pub fn write_frame(&mut self, surface: &mut termwiz::surface::Surface) -> Result<()> {
+        let now = std::time::Instant::now();
+        let is_too_soon = self
+            .last_write
+            .is_some_and(|last_write| now.duration_since(last_write) < self.minimum_interval);
+        if is_too_soon {
+            return Ok(());
+        }
+        self.last_write = Some(now);
+
+        let result = self.frames.try_send(encode_frame(surface, self.format)?);
+        match result {
+            Ok(()) => Ok(()),
+            Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                tracing::debug!("The frame sink is behind, dropping a frame");
+                Ok(())
+            }
+            Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                color_eyre::eyre::bail!("The frame sink has stopped")
+            }
+        }
+    }
+
+    /// Write each frame as it arrives, until the sink is dropped.
+    fn write_frames(
+        mut writer: Box<dyn std::io::Write + Send>,
+        mut frames: tokio::sync::mpsc::Receiver<Vec<u8>>,
+    ) -> Result<()> {
+        while let Some(frame) = frames.blocking_recv() {
+            writer.write_all(&frame)?;
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Encode a frame in the given format.
+fn encode_frame(surface: &mut termwiz::surface::Surface, format: FrameFormat) -> Result<Vec<u8>> {
+    let ansi = to_ansi(surface)?.into_bytes();
+    match format {
+        FrameFormat::Raw => Ok(ansi),
+        FrameFormat::LengthPrefixed => {
+            let mut bytes = u32::try_from(ansi.len())?.to_be_bytes().to_vec();
+            bytes.extend(ansi);
+            Ok(bytes)
+        }
+    }
+}
+
+/// Serialise a whole frame as ANSI, with all of each cell's attributes.
+pub(crate) fn to_ansi(surface: &mut termwiz::surface::Surface) -> Result<String> {
+    let mut ansi = String::new();
+    for (row, line) in surface.screen_cells().iter().enumerate() {
+        write!(ansi, "\x1b[{};1H", row.saturating_add(1))?;
+        ansi.push_str(RESET_ATTRIBUTES);
+
+        let mut attributes = None;
+        let mut skip: usize = 0;
+        for cell in line.iter() {
+            // The cells covered by wide characters.
+            if skip > 0 {
+                skip = skip.saturating_sub(1);
+                continue;
+            }
+            skip = cell.width().saturating_sub(1);
+
+            if attributes != Some(cell.attrs()) {
+                ansi.push_str(&shadow_terminal::sgr::for_attributes(cell.attrs()));
+                attributes = Some(cell.attrs());
+            }
+            ansi.push_str(cell.str());
+        }
+        ansi.push_str(RESET_ATTRIBUTES);
+    }
+
+    Ok(ansi)
+}
+
+#[cfg(test)]
+#[expect(clippy::indexing_slicing, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    fn surface() -> termwiz::surface::Surface {
+        let mut surface = termwiz::surface::Surface::new(2, 1);
+        surface.add_change(termwiz::surface::Change::Attribute(
+            termwiz::cell::AttributeChange::Foreground(
+                termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(
+                    termwiz::color::SrgbaTuple(1.0, 0.0, 0.0, 1.0),
+                ),
+            ),
+        ));
+        surface.add_change("ab");
+        surface
+    }
+
+    #[test]
+    fn frames_are_serialised_as_ansi() {
+        let mut surface = surface();
+        let attributes = surface.screen_cells()[0][0].attrs().clone();
+        assert_eq!(
+            to_ansi(&mut surface).unwrap(),
+            format!(
+                "\x1b[1;1H\x1b[0m{}ab\x1b[0m",
+                shadow_terminal::sgr::for_attributes(&attributes)
+            )
+        );
+    }
+
+    #[test]
+    fn text_attributes_are_kept() {
+        let mut surface = surface();
+        surface.add_changes(vec![
+            termwiz::surface::Change::CursorPosition {
+                x: termwiz::surface::Position::Absolute(0),
+                y: termwiz::surface::Position::Absolute(0),
+            },
+            termwiz::surface::Change::Attribute(termwiz::cell::AttributeChange::Intensity(
+                termwiz::cell::Intensity::Bold,
+            )),
+            termwiz::surface::Change::Attribute(termwiz::cell::AttributeChange::Underline(
+                termwiz::cell::Underline::Single,
+            )),
+            termwiz::surface::Change::Attribute(termwiz::cell::AttributeChange::Reverse(true)),
+        ]);
+        surface.add_change("a");
+
+        let ansi = to_ansi(&mut surface).unwrap();
+        assert!(ansi.contains("\x1b[1m"));
+        assert!(ansi.contains("\x1b[4m"));
+        assert!(ansi.contains("\x1b[7m"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn frames_are_written_in_the_background() {
+        #[derive(Clone, Default)]
+        struct Shared(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Shared {
+            fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(bytes);
+                Ok(bytes.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let written = Shared::default();
+        let mut sink = FrameSink::new(Box::new(written.clone()), FrameFormat::Raw, 30);
+        sink.write_frame(&mut surface()).unwrap();
+
+        let expected = to_ansi(&mut surface()).unwrap().into_bytes();
+        tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            while *written.0.lock().unwrap() != expected {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_sink_that_cant_be_opened_stops() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("missing").join("frames");
+        let mut sink = FrameSink::open(&path, FrameFormat::Raw, 1000);
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            while sink.write_frame(&mut surface()).is_ok() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn length_prefixed_frames() {
+        let ansi = to_ansi(&mut surface()).unwrap();
+        let encoded = encode_frame(&mut surface(), FrameFormat::LengthPrefixed).unwrap();
+        let (length, frame) = encoded.split_at(4);
+        assert_eq!(length, u32::try_from(ansi.len()).unwrap().to_be_bytes());
+        assert_eq!(frame, ansi.as_bytes());
+    }
+}
@@ -0,0 +1,162 @@
+//! Eye-candy for your terminal.
+//!
+//! Tattoy is usually run as a binary, but it can also be embedded, so that other crates can add
+//! their own tattoys. Register them on a [`TattoyRegistry`], then pass it to [`run`]:
+//!
+//! ```no_run
+//! # async fn example() -> color_eyre::eyre::Result<()> {
+//! let mut registry = tattoy::TattoyRegistry::with_builtins();
+//! registry.register("mine", "My own tattoy.", |args| {
+//!     Box::pin(async move {
+//!         let mut protocol = args.protocol_tx.subscribe();
+//!         while let Ok(message) = protocol.recv().await {
+//!             if matches!(message, tattoy::Protocol::End) {
+//!                 break;
+//!             }
+//!         }
+//!         Ok::<(), color_eyre::eyre::Error>(())
+//!     })
+//! });
+//! tattoy::run(registry).await
+//! # }
+//! ```
+//!
+//! Registered tattoys are started with `--use`, just like the built-ins.
+
+#![expect(clippy::pub_use, reason = "How else are you supposed re-export??")]
+#![expect(
+    private_interfaces,
+    reason = "
+        Tattoy's internal types, like its config, are reachable through the protocol. Only the
+        re-exported types are meant to be used by embedders.
+    "
+)]
+
+// TODO: Consider using `mod.rs`. As pointed out by @Justus_Fluegel, the disadvantage of
+// this approach is that when moving files/modules, you _also_ have to move these module
+// definitions.
+
+mod animation;
+mod blink;
+mod cli_args;
+mod colour_depth;
+mod colour_vision;
+mod config;
+mod config_migrations;
+mod draw;
+mod export;
+mod frame_clock;
+mod frame_sink;
+mod input;
+mod loader;
+mod local_echo;
+mod mask;
+mod opaque_cell;
+/// The palette code is for helping convert a terminal's palette to true colour.
+mod palette {
+    pub mod converter;
+    pub mod parser;
+    pub mod state_machine;
+}
+mod pixel_shader;
+mod pty_log;
+mod renderer;
+mod run;
+mod shake;
+mod shared_state;
+#[cfg(unix)]
+mod signals;
+mod surface;
+mod terminal_proxy;
+mod utils;
+
+/// This is where all the various tattoys are kept
+mod tattoys {
+    pub mod audio;
+    pub mod background_image;
+    pub mod clock;
+    pub mod cursor;
+    pub mod cursor_flash;
+    pub mod cursor_trail;
+    pub mod gradient;
+    pub mod minimap;
+    pub mod notice;
+    pub mod random_walker;
+    pub mod reveal;
+    pub mod ripple;
+    pub mod screensaver;
+    pub mod script;
+    pub mod scrollbar;
+    pub mod tattoyer;
+    pub mod wasm;
+
+    /// Shadertoy-like shaders
+    pub mod shaders {
+        pub mod gpu;
+        pub mod main;
+    }
+
+    /// The smokey cursor Tattoy
+    pub mod smokey_cursor {
+        pub mod config;
+        pub mod glyphs;
+        pub mod main;
+        pub mod particle;
+        pub mod particles;
+        pub mod simulation;
+        pub mod turbulence;
+    }
+}
+
+pub use loader::{TattoyArgs, TattoyFuture, TattoyRegistry};
+pub use pixel_shader::{CellContext, CellOutput};
+pub use run::{FrameUpdate, Protocol};
+pub use shared_state::SharedState;
+pub use surface::Surface;
+
+use color_eyre::eyre::Result;
+
+/// Run Tattoy, with the tattoys in the registry, until the user exits. This is all that the
+/// `tattoy` binary does, with just the built-in tattoys.
+///
+/// # Errors
+/// When Tattoy's error reporting or shared state can't be setup, or when Tattoy itself fails, for
+/// example because `--validate` found a broken config. Errors are also logged.
+#[expect(clippy::non_ascii_literal, reason = "It's just for debugging")]
+#[expect(
+    clippy::print_stdout,
+    clippy::print_stderr,
+    reason = "It's our central place for communicating with the user on CLI"
+)]
+#[inline]
+pub async fn run(registry: TattoyRegistry) -> Result<()> {
+    color_eyre::install()?;
+    let state_arc = shared_state::SharedState::init().await?;
+    let result = run::run(&std::sync::Arc::clone(&state_arc), registry).await;
+    if state_arc
+        .is_tui_started
+        .load(std::sync::atomic::Ordering::Acquire)
+    {
+        println!("{}", utils::RESET_SCREEN);
+    }
+
+    let logpath = state_arc.config.read().await.log_path.clone();
+    let is_logging = *state_arc.is_logging.read().await;
+    tracing::debug!("Tattoy is exiting 🙇");
+
+    match result {
+        Ok(()) => {
+            if is_logging {
+                println!("Logs saved to {}", logpath.display());
+            }
+            Ok(())
+        }
+        Err(error) => {
+            tracing::error!("{error:?}");
+            if is_logging {
+                eprintln!("See {} for more details", logpath.display());
+            }
+            Err(error)
+        }
+    }
+}
@@ -2,6 +2,10 @@
 //!
 //! I want to base the plugin architecture on Nushell's, see:
 //! <https://www.nushell.sh/contributor-book/plugin_protocol_reference.html>
+//!
+//! Tattoys are created by name from the `TattoyRegistry`. The built-in tattoys are registered by
+//! default, but anything else can register its own tattoys, or replace the built-ins, before
+//! the tattoys are started.
 
 use std::sync::Arc;
 
@@ -9,70 +13,604 @@ use color_eyre::eyre::Result;
 
 use crate::run::{FrameUpdate, Protocol};
 
-/// Start the main loader thread
+/// The result of a tattoy's task, along with the tattoy's ID. The result is `None` when the
+/// tattoy was stopped.
+type TattoyResult = (String, Option<Result<()>>);
+
+/// The number of times that a tattoy can fail, and be restarted, before it's disabled.
+const MAX_TATTOY_FAILURES: u8 = 3;
+
+/// Spawn a tattoy in its own task, so that we can catch and identify it if it panics. The returned
+/// handle stops the tattoy.
+fn spawn_tattoy(
+    tattoys: &mut tokio::task::JoinSet<TattoyResult>,
+    id: &str,
+    tattoy: impl std::future::Future<Output = Result<()>> + Send + 'static,
+    frame_ticks: Option<&crate::frame_clock::FrameTicks>,
+) -> tokio::task::AbortHandle {
+    let tattoy_id = id.to_owned();
+    let task = match frame_ticks {
+        Some(ticks) => tokio::spawn(ticks.clone().scope(tattoy)),
+        None => tokio::spawn(tattoy),
+    };
+    let handle = task.abort_handle();
+    tattoys.spawn(async move {
+        let result = match task.await {
+            Ok(result) => Some(result),
+            Err(error) if error.is_cancelled() => None,
+            Err(error) => Some(Err(color_eyre::eyre::eyre!("{error}"))),
+        };
+        (tattoy_id, result)
+    });
+    handle
+}
+
+/// The future that runs a tattoy until Tattoy exits.
+pub type TattoyFuture = std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send>>;
+
+/// Creates a tattoy, ready to run, from everything that tattoys are given.
+pub(crate) type TattoyFactory = Box<dyn Fn(TattoyArgs) -> TattoyFuture + Send + Sync>;
+
+/// Checks, without starting a tattoy, that it has everything that it needs to start.
+pub(crate) type TattoySetupCheck =
+    Box<dyn Fn(Arc<crate::shared_state::SharedState>) -> TattoyFuture + Send + Sync>;
+
+/// Everything that a tattoy is given when it's created.
+#[non_exhaustive]
+pub struct TattoyArgs {
+    /// The channel of protocol messages from the rest of Tattoy.
+    pub protocol_tx: tokio::sync::broadcast::Sender<Protocol>,
+    /// The channel for sending rendered frames to the compositor.
+    pub output: tokio::sync::mpsc::Sender<FrameUpdate>,
+    /// Shared app state.
+    pub state: Arc<crate::shared_state::SharedState>,
+}
+
+/// A tattoy that can be created.
+struct RegisteredTattoy {
+    /// A short description of what the tattoy does.
+    description: String,
+    /// Creates the tattoy.
+    factory: TattoyFactory,
+    /// Checks that the tattoy can start, like that its files load.
+    setup_check: Option<TattoySetupCheck>,
+}
+
+/// Everything that a user might want to know about a registered tattoy.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TattoyInfo {
+    /// The name used to enable the tattoy, with `--use` for example.
+    pub name: String,
+    /// A short description of what the tattoy does.
+    pub description: String,
+    /// The tattoy's section of the default config, if it has one.
+    pub config: Option<toml::Table>,
+}
+
+impl std::fmt::Display for TattoyInfo {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(formatter, "{}: {}", self.name, self.description)?;
+        for (key, value) in self.config.iter().flatten() {
+            writeln!(formatter, "    {key} = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// All the tattoys that can be created, by name.
+///
+/// Tattoys that are registered with the same name as a built-in replace the built-in.
+#[derive(Default)]
+pub struct TattoyRegistry {
+    /// Each tattoy, by name.
+    tattoys: std::collections::BTreeMap<String, RegisteredTattoy>,
+    /// The compositor's final pass, if one has been set. It's kept with the tattoys, as it's the
+    /// other way that embedders can add effects.
+    pixel_shader: Option<crate::pixel_shader::PixelShader>,
+}
+
+impl TattoyRegistry {
+    /// A registry of all the tattoys that come with Tattoy.
+    #[inline]
+    #[must_use]
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::default();
+        registry.register(
+            "scrollbar",
+            "A scrollbar that shows when scrolling through the terminal's history.",
+            |args| {
+                Box::pin(crate::tattoys::scrollbar::Scrollbar::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "random_walker",
+            "A single pixel that randomly wanders around the terminal.",
+            |args| {
+                Box::pin(crate::tattoys::random_walker::RandomWalker::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "minimap",
+            "A minimap of the whole scrollback, shown when hovering over the right edge.",
+            |args| {
+                Box::pin(crate::tattoys::minimap::Minimap::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "smokey_cursor",
+            "A smoke simulation of particles that billow out of the cursor.",
+            |args| {
+                Box::pin(crate::tattoys::smokey_cursor::main::SmokeyCursor::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "shaders",
+            "Shadertoy-like GLSL shaders, rendered behind or over the terminal.",
+            |args| {
+                Box::pin(crate::tattoys::shaders::main::Shaders::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "background_image",
+            "An image, drawn behind the terminal.",
+            |args| {
+                Box::pin(crate::tattoys::background_image::BackgroundImage::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "gradient",
+            "A colour gradient, drawn behind the terminal.",
+            |args| {
+                Box::pin(crate::tattoys::gradient::Gradient::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "cursor_trail",
+            "A fading trail that follows the cursor.",
+            |args| {
+                Box::pin(crate::tattoys::cursor_trail::CursorTrail::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "cursor_flash",
+            "A fading highlight around the cursor when it jumps a long way.",
+            |args| {
+                Box::pin(crate::tattoys::cursor_flash::CursorFlash::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "cursor",
+            "A custom, animated cursor that replaces the terminal's own cursor.",
+            |args| {
+                Box::pin(crate::tattoys::cursor::Cursor::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "clock",
+            "The time, and optionally the date, in a corner of the terminal.",
+            |args| {
+                Box::pin(crate::tattoys::clock::Clock::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "screensaver",
+            "A full screen effect that takes over the terminal whilst it's idle.",
+            |args| {
+                Box::pin(crate::tattoys::screensaver::Screensaver::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register(
+            "ripple",
+            "Rings that ripple out from the cursor whenever a character is typed.",
+            |args| {
+                Box::pin(crate::tattoys::ripple::Ripple::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        registry.register("reveal", "Fades in new text as it first appears.", |args| {
+            Box::pin(crate::tattoys::reveal::Reveal::start(
+                args.protocol_tx,
+                args.output,
+                args.state,
+            ))
+        });
+        registry.set_setup_check("shaders", |state| {
+            Box::pin(crate::tattoys::shaders::main::Shaders::check_setup(state))
+        });
+        registry.set_setup_check("background_image", |state| {
+            Box::pin(crate::tattoys::background_image::BackgroundImage::check_setup(state))
+        });
+        #[cfg(feature = "audio")]
+        registry.register(
+            "audio",
+            "A music visualiser, driven by the system's audio input.",
+            |args| {
+                Box::pin(crate::tattoys::audio::Audio::start(
+                    args.protocol_tx,
+                    args.output,
+                    args.state,
+                ))
+            },
+        );
+        #[cfg(feature = "script")]
+        registry.register("script", "A tattoy from a Rhai script.", |args| {
+            Box::pin(crate::tattoys::script::Script::start(
+                args.protocol_tx,
+                args.output,
+                args.state,
+            ))
+        });
+        #[cfg(feature = "script")]
+        registry.set_setup_check("script", |state| {
+            Box::pin(crate::tattoys::script::Script::check_setup(state))
+        });
+        #[cfg(feature = "wasm")]
+        registry.register("wasm", "A tattoy from a WebAssembly plugin.", |args| {
+            Box::pin(crate::tattoys::wasm::Wasm::start(
+                args.protocol_tx,
+                args.output,
+                args.state,
+            ))
+        });
+        #[cfg(feature = "wasm")]
+        registry.set_setup_check("wasm", |state| {
+            Box::pin(crate::tattoys::wasm::Wasm::check_setup(state))
+        });
+        registry
+    }
+
+    /// Register a tattoy. Replaces any existing tattoy with the same name.
+    #[inline]
+    pub fn register<F>(&mut self, name: &str, description: &str, factory: F)
+    where
+        F: Fn(TattoyArgs) -> TattoyFuture + Send + Sync + 'static,
+    {
+        self.tattoys.insert(
+            name.to_owned(),
+            RegisteredTattoy {
+                description: description.to_owned(),
+                factory: Box::new(factory),
+                setup_check: None,
+            },
+        );
+    }
+
+    /// Set a check that the named tattoy has everything that it needs to start, like any files
+    /// that it loads. It's run by `--validate`, without starting the tattoy. Does nothing if
+    /// there's no tattoy with the name.
+    #[inline]
+    pub fn set_setup_check<F>(&mut self, name: &str, check: F)
+    where
+        F: Fn(Arc<crate::shared_state::SharedState>) -> TattoyFuture + Send + Sync + 'static,
+    {
+        if let Some(tattoy) = self.tattoys.get_mut(name) {
+            tattoy.setup_check = Some(Box::new(check));
+        }
+    }
+
+    /// Check that the named tattoy can start, without starting it. Returns `None` if there's no
+    /// tattoy with the name.
+    pub(crate) async fn check_setup(
+        &self,
+        name: &str,
+        state: &Arc<crate::shared_state::SharedState>,
+    ) -> Option<Result<()>> {
+        let tattoy = self.tattoys.get(name)?;
+        let Some(check) = tattoy.setup_check.as_ref() else {
+            return Some(Ok(()));
+        };
+
+        Some(check(Arc::clone(state)).await)
+    }
+
+    /// Transform every cell of every frame with a closure, as the compositor's final pass.
+    /// Replaces any existing shader. It's called for every cell of every frame, so it needs to be
+    /// cheap.
+    #[inline]
+    pub fn set_pixel_shader<F>(&mut self, shader: F)
+    where
+        F: Fn(crate::pixel_shader::CellContext<'_>) -> crate::pixel_shader::CellOutput
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.pixel_shader = Some(crate::pixel_shader::PixelShader::new(shader));
+    }
+
+    /// Take the pixel shader, to give to the compositor.
+    pub(crate) const fn take_pixel_shader(&mut self) -> Option<crate::pixel_shader::PixelShader> {
+        self.pixel_shader.take()
+    }
+
+    /// The names of all the registered tattoys.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.tattoys.keys().map(String::as_str)
+    }
+
+    /// Create the named tattoy, if it's registered.
+    pub(crate) fn create_instance(&self, name: &str, args: TattoyArgs) -> Option<TattoyFuture> {
+        self.tattoys.get(name).map(|tattoy| (tattoy.factory)(args))
+    }
+
+    /// Describe all the registered tattoys, along with their default config.
+    pub(crate) fn describe_tattoys(&self) -> Vec<TattoyInfo> {
+        self.tattoys
+            .iter()
+            .map(|(name, tattoy)| TattoyInfo {
+                name: name.clone(),
+                description: tattoy.description.clone(),
+                config: crate::config::Config::default_section(config_section(name)),
+            })
+            .collect()
+    }
+}
+
+/// The name of a tattoy's section in the config. It's usually the same as the tattoy's name.
+fn config_section(name: &str) -> &str {
+    match name {
+        "shaders" => "shader",
+        _ => name,
+    }
+}
+
+/// Whether a tattoy should be started. The scrollbar is always started. Other tattoys are
+/// started when they're enabled on the command line, or, for the built-ins, in the config.
+pub(crate) async fn is_enabled(
+    name: &str,
+    enabled_tattoys: &[String],
+    state: &crate::shared_state::SharedState,
+) -> bool {
+    if name == "scrollbar" || enabled_tattoys.iter().any(|enabled| enabled == name) {
+        return true;
+    }
+
+    let config = state.config.read().await;
+    match name {
+        "minimap" => config.minimap.enabled,
+        "smokey_cursor" => config.smokey_cursor.enabled,
+        "shaders" => config.shader.enabled,
+        "wasm" => config.wasm.enabled,
+        "background_image" => config.background_image.enabled,
+        "gradient" => config.gradient.enabled,
+        "cursor_trail" => config.cursor_trail.enabled,
+        "cursor_flash" => config.cursor_flash.enabled,
+        "cursor" => config.cursor.enabled,
+        "ripple" => config.ripple.enabled,
+        "reveal" => config.reveal.enabled,
+        "clock" => config.clock.enabled,
+        "screensaver" => config.screensaver.enabled,
+        "script" => config.script.enabled,
+        "audio" => config.audio.enabled,
+        _ => false,
+    }
+}
+
+/// All the running tattoys, so that they can be started and stopped whenever the config changes.
+struct Loader {
+    /// All the tattoys that can be started.
+    registry: TattoyRegistry,
+    /// The tattoys enabled on the command line, which are always running.
+    enabled_tattoys: Vec<String>,
+    /// The channel of protocol messages from the rest of Tattoy.
+    protocol_tx: tokio::sync::broadcast::Sender<Protocol>,
+    /// The channel for sending rendered frames to the compositor.
+    output: tokio::sync::mpsc::Sender<FrameUpdate>,
+    /// Shared app state.
+    state: Arc<crate::shared_state::SharedState>,
+    /// The tasks of all the tattoys, for catching them when they exit.
+    tasks: tokio::task::JoinSet<TattoyResult>,
+    /// The clock that steps the tattoys' frames, when they aren't rendering in real time.
+    frame_ticks: Option<crate::frame_clock::FrameTicks>,
+    /// Stops each running tattoy, by name.
+    running: std::collections::HashMap<String, tokio::task::AbortHandle>,
+    /// The number of times that each tattoy has failed.
+    failures: std::collections::HashMap<String, u8>,
+    /// The tattoys that failed too many times. They aren't started again until they're disabled
+    /// and re-enabled in the config.
+    disabled: std::collections::HashSet<String>,
+}
+
+impl Loader {
+    /// Start every enabled tattoy that isn't running, and stop every running tattoy that isn't
+    /// enabled anymore. Tattoys that keep running get the new config themselves.
+    async fn sync_with_config(&mut self) {
+        let names: Vec<String> = self.registry.names().map(str::to_owned).collect();
+        for name in names {
+            let is_enabled = is_enabled(&name, &self.enabled_tattoys, &self.state).await;
+            if !is_enabled {
+                self.failures.remove(&name);
+                self.disabled.remove(&name);
+            }
+            if self.disabled.contains(&name) {
+                continue;
+            }
+
+            let is_running = self.running.contains_key(&name);
+            if is_enabled && !is_running {
+                self.start(&name);
+            }
+            if !is_enabled && is_running {
+                self.stop(&name).await;
+            }
+        }
+    }
+
+    /// Start the named tattoy.
+    fn start(&mut self, name: &str) {
+        let args = TattoyArgs {
+            protocol_tx: self.protocol_tx.clone(),
+            output: self.output.clone(),
+            state: Arc::clone(&self.state),
+        };
+        if let Some(tattoy) = self.registry.create_instance(name, args) {
+            tracing::info!("Starting '{name}' tattoy...");
+            let handle = spawn_tattoy(&mut self.tasks, name, tattoy, self.frame_ticks.as_ref());
+            self.running.insert(name.to_owned(), handle);
+        }
+    }
+
+    /// Stop the named tattoy, and clear its last frame from the compositor. A surface on layer 0
+    /// is never composited.
+    async fn stop(&mut self, name: &str) {
+        let Some(handle) = self.running.remove(name) else {
+            return;
+        };
+        tracing::info!("Stopping '{name}' tattoy...");
+        handle.abort();
+
+        let blank = crate::surface::Surface::new(name.to_owned(), 0, 0, 0);
+        let result = self.output.send(FrameUpdate::TattoySurface(blank)).await;
+        if let Err(error) = result {
+            tracing::error!("Couldn't clear the '{name}' tattoy's last frame: {error:?}");
+        }
+    }
+
+    /// Let the user know that their config couldn't be reloaded.
+    fn handle_config_error(&self, error: &str) {
+        crate::tattoys::notice::Notice::show_config_error(
+            error,
+            &self.protocol_tx,
+            self.output.clone(),
+            Arc::clone(&self.state),
+        );
+    }
+
+    /// Report a tattoy that has exited. A tattoy that failed, including by panicking, is
+    /// restarted, unless it's failed too many times, in which case it's disabled and the user is
+    /// shown a notice.
+    fn handle_exit(&mut self, id: String, result: Option<Result<()>>) {
+        let Some(result) = result else {
+            tracing::debug!("The '{id}' tattoy was stopped");
+            return;
+        };
+
+        self.running.remove(&id);
+        let Err(error) = result else {
+            tracing::error!("The '{id}' tattoy exited without error");
+            return;
+        };
+
+        let failures = self.failures.entry(id.clone()).or_default();
+        *failures = failures.saturating_add(1);
+        tracing::error!("The '{id}' tattoy failed ({failures} times): {error:?}");
+        if *failures < MAX_TATTOY_FAILURES {
+            self.start(&id);
+            return;
+        }
+
+        tracing::error!("Disabling the '{id}' tattoy after too many failures");
+        self.disabled.insert(id.clone());
+        crate::tattoys::notice::Notice::show_failure(
+            &id,
+            &error.to_string(),
+            &self.protocol_tx,
+            self.output.clone(),
+            Arc::clone(&self.state),
+        );
+    }
+}
+
+/// Start the main loader thread. Tattoys are started and stopped as they're enabled and disabled
+/// in the config, like when switching config profiles.
 pub(crate) fn start_tattoys(
+    registry: TattoyRegistry,
     enabled_tattoys: Vec<String>,
     input: tokio::sync::broadcast::Sender<Protocol>,
     output: tokio::sync::mpsc::Sender<FrameUpdate>,
     state: Arc<crate::shared_state::SharedState>,
+    frame_ticks: Option<crate::frame_clock::FrameTicks>,
 ) -> std::thread::JoinHandle<Result<(), color_eyre::eyre::Error>> {
     let tokio_runtime = tokio::runtime::Handle::current();
     std::thread::spawn(move || -> Result<()> {
         tokio_runtime.block_on(async {
-            let mut tattoy_futures = tokio::task::JoinSet::new();
-
-            tracing::info!("Starting 'scrollbar' tattoy...");
-            tattoy_futures.spawn(crate::tattoys::scrollbar::Scrollbar::start(
-                input.clone(),
-                output.clone(),
-            ));
-
-            if enabled_tattoys.contains(&"random_walker".to_owned()) {
-                tracing::info!("Starting 'random_walker' tattoy...");
-                tattoy_futures.spawn(crate::tattoys::random_walker::RandomWalker::start(
-                    input.clone(),
-                    output.clone(),
-                ));
-            }
-
-            if enabled_tattoys.contains(&"minimap".to_owned())
-                || state.config.read().await.minimap.enabled
-            {
-                tracing::info!("Starting 'minimap' tattoy...");
-                tattoy_futures.spawn(crate::tattoys::minimap::Minimap::start(
-                    input.clone(),
-                    output.clone(),
-                    Arc::clone(&state),
-                ));
-            }
-
-            if enabled_tattoys.contains(&"smokey_cursor".to_owned())
-                || state.config.read().await.smokey_cursor.enabled
-            {
-                tracing::info!("Starting 'smokey_cursor' tattoy...");
-                tattoy_futures.spawn(crate::tattoys::smokey_cursor::main::SmokeyCursor::start(
-                    input.clone(),
-                    output.clone(),
-                ));
-            }
+            let mut protocol_rx = input.subscribe();
+            let mut loader = Loader {
+                registry,
+                enabled_tattoys,
+                protocol_tx: input,
+                output,
+                state,
+                tasks: tokio::task::JoinSet::new(),
+                frame_ticks,
+                running: std::collections::HashMap::new(),
+                failures: std::collections::HashMap::new(),
+                disabled: std::collections::HashSet::new(),
+            };
+            loader.sync_with_config().await;
 
-            if enabled_tattoys.contains(&"shaders".to_owned())
-                || state.config.read().await.shader.enabled
-            {
-                tracing::info!("Starting 'shaders' tattoy...");
-                tattoy_futures.spawn(crate::tattoys::shaders::main::Shaders::start(
-                    input.clone(),
-                    output.clone(),
-                    Arc::clone(&state),
-                ));
+            #[expect(
+                clippy::integer_division_remainder_used,
+                reason = "This is caused by the `tokio::select!`"
+            )]
+            loop {
+                tokio::select! {
+                    Some(exited) = loader.tasks.join_next() => match exited {
+                        Ok((id, result)) => loader.handle_exit(id, result),
+                        Err(spawn_error) => tracing::error!("Error spawning a tattoy: {spawn_error:?}"),
+                    },
+                    message = protocol_rx.recv() => match message {
+                        Ok(Protocol::End) | Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                        Ok(Protocol::Config(_)) => loader.sync_with_config().await,
+                        Ok(Protocol::ConfigReloaded(Err(error))) => loader.handle_config_error(&error),
+                        Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => (),
+                    },
+                }
             }
 
-            while let Some(starting) = tattoy_futures.join_next().await {
-                match starting {
-                    Ok(result) => match result {
-                        Ok(()) => tracing::error!("A tattoy exited without error"),
-                        Err(error) => tracing::error!("A tattoy exited: {error:?}"),
-                    },
+            while let Some(exited) = loader.tasks.join_next().await {
+                match exited {
+                    Ok((id, result)) => loader.handle_exit(id, result),
                     Err(spawn_error) => tracing::error!("Error spawning a tattoy: {spawn_error:?}"),
                 }
             }
@@ -81,3 +619,114 @@ pub(crate) fn start_tattoys(
         })
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args() -> TattoyArgs {
+        let (protocol_tx, _) = tokio::sync::broadcast::channel(1);
+        let (output, _) = tokio::sync::mpsc::channel(1);
+        TattoyArgs {
+            protocol_tx,
+            output,
+            state: Arc::new(crate::shared_state::SharedState::default()),
+        }
+    }
+
+    #[tokio::test]
+    async fn registered_tattoys_replace_builtins() {
+        let mut registry = TattoyRegistry::with_builtins();
+        assert!(registry.create_instance("nope", args()).is_none());
+
+        registry.register("minimap", "Custom minimap", |_| {
+            Box::pin(async { color_eyre::eyre::bail!("custom") })
+        });
+        registry.register("custom", "Custom", |_| Box::pin(async { Ok(()) }));
+
+        let minimap = registry.create_instance("minimap", args()).unwrap();
+        assert_eq!(minimap.await.unwrap_err().to_string(), "custom");
+        let custom = registry.create_instance("custom", args()).unwrap();
+        assert!(custom.await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failing_tattoys_are_restarted_then_disabled() {
+        let starts = Arc::new(std::sync::atomic::AtomicU8::new(0));
+        let mut registry = TattoyRegistry::default();
+        let counter = Arc::clone(&starts);
+        registry.register("broken", "Broken", move |_| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Box::pin(async { color_eyre::eyre::bail!("broken") })
+        });
+
+        let (protocol_tx, _) = tokio::sync::broadcast::channel(16);
+        let (output, mut output_rx) = tokio::sync::mpsc::channel(16);
+        let state = Arc::new(crate::shared_state::SharedState::default());
+        state.set_tty_size(80, 24).await;
+        let loader = start_tattoys(
+            registry,
+            vec!["broken".to_owned()],
+            protocol_tx.clone(),
+            output,
+            state,
+            None,
+        );
+
+        let Some(FrameUpdate::TattoySurface(notice)) = output_rx.recv().await else {
+            panic!("Expected the notice's frame");
+        };
+        assert_eq!(notice.id, "notice_broken");
+        assert_eq!(
+            starts.load(std::sync::atomic::Ordering::Relaxed),
+            MAX_TATTOY_FAILURES
+        );
+
+        protocol_tx.send(Protocol::End).unwrap();
+        loader.join().unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn setup_checks_are_run_without_starting_tattoys() {
+        let state = Arc::new(crate::shared_state::SharedState::default());
+        *state.config_path.write().await = std::env::temp_dir().join("tattoy-missing-config");
+        let mut registry = TattoyRegistry::with_builtins();
+        registry.register("custom", "Custom", |_| {
+            panic!("Tattoys shouldn't be created to check them")
+        });
+        registry.set_setup_check("custom", |_| {
+            Box::pin(async { color_eyre::eyre::bail!("custom problem") })
+        });
+
+        assert!(registry.check_setup("nope", &state).await.is_none());
+        assert!(registry.check_setup("clock", &state).await.unwrap().is_ok());
+        let custom = registry.check_setup("custom", &state).await.unwrap();
+        assert_eq!(custom.unwrap_err().to_string(), "custom problem");
+        let mut names = vec!["shaders", "background_image"];
+        if cfg!(feature = "wasm") {
+            names.push("wasm");
+        }
+        for name in names {
+            let result = registry.check_setup(name, &state).await.unwrap();
+            assert!(
+                result.is_err(),
+                "The '{name}' tattoy's missing file wasn't found"
+            );
+        }
+    }
+
+    #[test]
+    fn tattoys_describe_themselves() {
+        let mut registry = TattoyRegistry::with_builtins();
+        registry.register("custom", "Custom", |_| Box::pin(async { Ok(()) }));
+        let tattoys = registry.describe_tattoys();
+
+        let shaders = tattoys.iter().find(|info| info.name == "shaders").unwrap();
+        assert!(!shaders.description.is_empty());
+        let config = shaders.config.as_ref().unwrap();
+        assert_eq!(config.get("enabled"), Some(&toml::Value::Boolean(false)));
+
+        let custom = tattoys.iter().find(|info| info.name == "custom").unwrap();
+        assert_eq!(custom.config, None);
+    }
+}
@@ -0,0 +1,284 @@
+//! Predictively echo typed characters, to hide the latency of slow connections.
+//!
+//! Every printable character that's typed is predicted to appear at the PTY's cursor, before the
+//! PTY itself has echoed it. Predictions are confirmed, and forgotten, once the PTY's output
+//! contains them. Any other output that moves the cursor elsewhere means the predictions were
+//! wrong, so they're all dropped.
+//!
+//! Because applications don't always echo what's typed, passwords for example, predictions are
+//! only shown once the PTY has echoed at least one of the characters typed on the current line.
+//! They're never shown in the alternate screen, because full screen applications rarely echo
+//! input at the cursor.
+
+use std::collections::VecDeque;
+
+use termwiz::cell::Cell;
+
+/// The user's config for local echo.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Show typed characters before the PTY has echoed them.
+    pub enabled: bool,
+    /// How long, in seconds, to wait for the PTY to echo a character before assuming that it
+    /// won't, and dropping all the predictions.
+    pub timeout: f32,
+    /// Underline predicted characters that haven't been echoed yet.
+    pub underline: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout: 1.0,
+            underline: true,
+        }
+    }
+}
+
+/// A character that's been typed, but not yet echoed by the PTY.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Prediction {
+    /// The column where the character is expected to appear.
+    x: usize,
+    /// The row where the character is expected to appear.
+    y: usize,
+    /// The typed character.
+    character: char,
+}
+
+/// The predictions for the characters that have been typed.
+#[derive(Default)]
+pub(crate) struct LocalEcho {
+    /// The typed characters that haven't been echoed yet, in the order that they were typed.
+    predictions: VecDeque<Prediction>,
+    /// Whether the PTY has echoed any of the characters typed on the current line.
+    is_echoing: bool,
+    /// When a character was last typed.
+    last_input: Option<std::time::Instant>,
+}
+
+impl LocalEcho {
+    /// Predict the effect of the user's input on the PTY's screen. Returns whether the shown
+    /// predictions changed.
+    pub fn handle_input(
+        &mut self,
+        event: &termwiz::input::InputEvent,
+        pty: &termwiz::surface::Surface,
+        now: std::time::Instant,
+    ) -> bool {
+        let termwiz::input::InputEvent::Key(key) = event else {
+            if matches!(
+                event,
+                termwiz::input::InputEvent::Paste(_) | termwiz::input::InputEvent::Resized { .. }
+            ) {
+                return self.clear();
+            }
+            return false;
+        };
+
+        let is_unmodified =
+            key.modifiers.is_empty() || key.modifiers == termwiz::input::Modifiers::SHIFT;
+        #[expect(
+            clippy::wildcard_enum_match_arm,
+            reason = "Only a few keys are predicted"
+        )]
+        match key.key {
+            termwiz::input::KeyCode::Char(character)
+                if is_unmodified && !character.is_control() =>
+            {
+                self.predict(character, pty, now)
+            }
+            termwiz::input::KeyCode::Backspace if is_unmodified => {
+                self.predictions.pop_back().is_some() && self.is_echoing
+            }
+            termwiz::input::KeyCode::Enter => {
+                let is_changed = self.clear();
+                self.is_echoing = false;
+                is_changed
+            }
+            _ => self.clear(),
+        }
+    }
+
+    /// Predict that the character will appear after the previous prediction, or at the cursor if
+    /// there aren't any.
+    fn predict(
+        &mut self,
+        character: char,
+        pty: &termwiz::surface::Surface,
+        now: std::time::Instant,
+    ) -> bool {
+        let (x, y) = self
+            .predictions
+            .back()
+            .map_or_else(|| pty.cursor_position(), |last| (last.x + 1, last.y));
+
+        // Wrapping onto the next line is too hard to predict.
+        if x >= pty.dimensions().0 {
+            return self.clear();
+        }
+
+        self.predictions.push_back(Prediction { x, y, character });
+        self.last_input = Some(now);
+        self.is_echoing
+    }
+
+    /// Forget all the predictions. Returns whether any were being shown.
+    fn clear(&mut self) -> bool {
+        let was_shown = self.is_echoing && !self.predictions.is_empty();
+        self.predictions.clear();
+        was_shown
+    }
+
+    /// Compare the predictions to the PTY's latest screen. Predictions that the PTY has echoed are
+    /// confirmed. If the PTY's cursor isn't where the next prediction should be, then some other
+    /// output arrived and all the remaining predictions are dropped.
+    pub fn reconcile(&mut self, pty: &termwiz::surface::Surface) {
+        let cursor = pty.cursor_position();
+        let cells = pty.screen_cells();
+        while let Some(prediction) = self.predictions.front() {
+            let is_echoed = cells
+                .get(prediction.y)
+                .and_then(|line| line.get(prediction.x))
+                .is_some_and(|cell| cell.str().chars().eq([prediction.character]))
+                && cursor != (prediction.x, prediction.y);
+            if !is_echoed {
+                break;
+            }
+            self.predictions.pop_front();
+            self.is_echoing = true;
+        }
+
+        if let Some(prediction) = self.predictions.front() {
+            if cursor != (prediction.x, prediction.y) {
+                self.predictions.clear();
+            }
+        }
+    }
+
+    /// Drop the predictions if the PTY hasn't echoed them in time, it probably isn't going to.
+    /// Returns whether the shown predictions changed.
+    pub fn expire(&mut self, now: std::time::Instant, config: &Config) -> bool {
+        let is_expired = self.last_input.is_some_and(|last_input| {
+            now.saturating_duration_since(last_input).as_secs_f32() > config.timeout
+        });
+        if !is_expired || self.predictions.is_empty() {
+            return false;
+        }
+
+        let is_changed = self.clear();
+        self.is_echoing = false;
+        is_changed
+    }
+
+    /// Draw the predicted characters over the PTY.
+    pub fn render(&self, frame: &mut [&mut [Cell]], config: &Config) {
+        if !self.is_echoing {
+            return;
+        }
+
+        for prediction in &self.predictions {
+            let Some(cell) = frame
+                .get_mut(prediction.y)
+                .and_then(|line| line.get_mut(prediction.x))
+            else {
+                continue;
+            };
+            let mut attributes = cell.attrs().clone();
+            if config.underline {
+                attributes.set_underline(termwiz::cell::Underline::Single);
+            }
+            *cell = Cell::new(prediction.character, attributes);
+        }
+    }
+
+    /// Where the cursor would be if the predictions were echoed. `None` when no predictions are
+    /// shown.
+    pub fn cursor_position(&self) -> Option<(usize, usize)> {
+        if !self.is_echoing {
+            return None;
+        }
+        self.predictions
+            .back()
+            .map(|prediction| (prediction.x + 1, prediction.y))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::indexing_slicing, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    fn key(character: char) -> termwiz::input::InputEvent {
+        termwiz::input::InputEvent::Key(termwiz::input::KeyEvent {
+            key: termwiz::input::KeyCode::Char(character),
+            modifiers: termwiz::input::Modifiers::NONE,
+        })
+    }
+
+    fn prompt() -> termwiz::surface::Surface {
+        let mut pty = termwiz::surface::Surface::new(10, 2);
+        pty.add_change("$ ");
+        pty
+    }
+
+    #[test]
+    fn predictions_are_shown_once_the_pty_echoes() {
+        let mut local_echo = LocalEcho::default();
+        let mut pty = prompt();
+        let now = std::time::Instant::now();
+
+        assert!(!local_echo.handle_input(&key('a'), &pty, now));
+        assert_eq!(local_echo.cursor_position(), None);
+
+        pty.add_change("a");
+        local_echo.reconcile(&pty);
+        assert!(local_echo.predictions.is_empty());
+
+        assert!(local_echo.handle_input(&key('b'), &pty, now));
+        assert_eq!(local_echo.cursor_position(), Some((4, 0)));
+
+        let mut surface = prompt();
+        let mut frame = surface.screen_cells();
+        local_echo.render(&mut frame, &Config::default());
+        assert_eq!(frame[0][3].str(), "b");
+        assert_eq!(
+            frame[0][3].attrs().underline(),
+            termwiz::cell::Underline::Single
+        );
+    }
+
+    #[test]
+    fn characters_that_arent_echoed_are_never_shown() {
+        let mut local_echo = LocalEcho::default();
+        let pty = prompt();
+        let now = std::time::Instant::now();
+
+        local_echo.handle_input(&key('a'), &pty, now);
+        local_echo.handle_input(&key('b'), &pty, now);
+        local_echo.reconcile(&pty);
+        assert_eq!(local_echo.cursor_position(), None);
+
+        let later = now + std::time::Duration::from_secs(2);
+        local_echo.expire(later, &Config::default());
+        assert!(local_echo.predictions.is_empty());
+    }
+
+    #[test]
+    fn other_output_drops_the_predictions() {
+        let mut local_echo = LocalEcho::default();
+        let mut pty = prompt();
+        let now = std::time::Instant::now();
+
+        local_echo.handle_input(&key('a'), &pty, now);
+        pty.add_change("a");
+        local_echo.reconcile(&pty);
+        local_echo.handle_input(&key('b'), &pty, now);
+
+        pty.add_change("\r\nsomething else");
+        local_echo.reconcile(&pty);
+        assert_eq!(local_echo.cursor_position(), None);
+    }
+}
@@ -0,0 +1,153 @@
+//! Masks clip a tattoy so that it only renders in part of the terminal. For example, smoke only
+//! in the top half of the screen, or a gradient only behind the prompt.
+
+/// A rectangle of the terminal, in cell coordinates.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Rect {
+    /// The column of the rectangle's left edge.
+    pub x: usize,
+    /// The row of the rectangle's top edge.
+    pub y: usize,
+    /// The number of columns in the rectangle.
+    pub width: usize,
+    /// The number of rows in the rectangle.
+    pub height: usize,
+}
+
+impl Rect {
+    /// Is the cell inside the rectangle?
+    pub const fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x
+            && y >= self.y
+            && x < self.x.saturating_add(self.width)
+            && y < self.y.saturating_add(self.height)
+    }
+
+    /// The part of the terminal that's inside both rectangles. It's empty when they don't
+    /// overlap.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self
+            .x
+            .saturating_add(self.width)
+            .min(other.x.saturating_add(other.width));
+        let bottom = self
+            .y
+            .saturating_add(self.height)
+            .min(other.y.saturating_add(other.height));
+        Self {
+            x,
+            y,
+            width: right.saturating_sub(x),
+            height: bottom.saturating_sub(y),
+        }
+    }
+}
+
+/// The cells of the terminal that a tattoy is allowed to render to. The compositor ignores
+/// everything that the tattoy renders outside of it.
+#[derive(Clone)]
+pub(crate) enum Mask {
+    /// Only render inside the rectangle.
+    Rect(Rect),
+    /// Only render to the cells for which the predicate, given the cell's column and row, is
+    /// true.
+    Predicate(std::sync::Arc<dyn Fn(usize, usize) -> bool + Send + Sync>),
+}
+
+impl Mask {
+    /// Is the tattoy allowed to render to the cell?
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        match self {
+            Self::Rect(rect) => rect.contains(x, y),
+            Self::Predicate(predicate) => predicate(x, y),
+        }
+    }
+
+    /// The cells that both masks allow. A missing mask allows every cell.
+    pub fn intersection(first: Option<&Self>, second: Option<&Self>) -> Option<Self> {
+        match (first, second) {
+            (None, None) => None,
+            (Some(mask), None) | (None, Some(mask)) => Some(mask.clone()),
+            (Some(Self::Rect(first)), Some(Self::Rect(second))) => {
+                Some(Self::Rect(first.intersection(second)))
+            }
+            (Some(first), Some(second)) => {
+                let (first, second) = (first.clone(), second.clone());
+                Some(Self::Predicate(std::sync::Arc::new(move |x, y| {
+                    first.contains(x, y) && second.contains(x, y)
+                })))
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Mask {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rect(rect) => formatter.debug_tuple("Rect").field(rect).finish(),
+            Self::Predicate(_) => formatter.write_str("Predicate"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rectangles() {
+        let mask = Mask::Rect(Rect {
+            x: 2,
+            y: 1,
+            width: 3,
+            height: 2,
+        });
+        assert!(mask.contains(2, 1));
+        assert!(mask.contains(4, 2));
+        assert!(!mask.contains(1, 1));
+        assert!(!mask.contains(5, 1));
+        assert!(!mask.contains(2, 3));
+    }
+
+    #[test]
+    fn predicates() {
+        let mask = Mask::Predicate(std::sync::Arc::new(|x, y| x == y));
+        assert!(mask.contains(3, 3));
+        assert!(!mask.contains(3, 4));
+    }
+
+    #[test]
+    fn intersections() {
+        let rect = Mask::Rect(Rect {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        });
+        let other_rect = Mask::Rect(Rect {
+            x: 2,
+            y: 2,
+            width: 4,
+            height: 4,
+        });
+        let diagonal = Mask::Predicate(std::sync::Arc::new(|x, y| x == y));
+
+        assert!(Mask::intersection(None, None).is_none());
+        assert!(matches!(
+            Mask::intersection(Some(&rect), Some(&other_rect)),
+            Some(Mask::Rect(Rect {
+                x: 2,
+                y: 2,
+                width: 2,
+                height: 2
+            }))
+        ));
+
+        let both = Mask::intersection(Some(&rect), Some(&diagonal)).unwrap();
+        assert!(both.contains(3, 3));
+        assert!(!both.contains(4, 4));
+        assert!(!both.contains(3, 2));
+    }
+}
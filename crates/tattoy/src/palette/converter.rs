@@ -6,19 +6,77 @@ use color_eyre::{eyre::ContextCompat as _, Result};
 /// the palette when no other index or true colour is specified.
 const DEFAULT_TEXT_PALETTE_INDEX: u8 = 15;
 
+/// The bright versions of the first 8 palette colours are the 8 colours after them.
+const BRIGHT_COLOURS_OFFSET: u8 = 8;
+
+/// How bright faint (SGR 2) text is compared to normal text.
+const FAINT_BRIGHTNESS: f32 = 0.66;
+
 /// A single palette colour.
 type PaletteColour = (u8, u8, u8);
 
+/// A colour in the CIELAB colour space.
+type Lab = (f32, f32, f32);
+
 /// A hash of palette indexes to true colour values.
 pub type PaletteHashMap = std::collections::HashMap<String, PaletteColour>;
 
 /// Convenience type for the palette hash.
 pub(crate) struct Palette {
-    /// The palette hash.
-    pub map: PaletteHashMap,
+    /// The palette hash. It's private, so that the CIELAB colours always match it.
+    map: PaletteHashMap,
+    /// Show bold text, that uses one of the first 8 colours, with the bright version of the
+    /// colour instead.
+    pub bold_is_bright: bool,
+    /// Every palette colour in the CIELAB colour space, by index, for finding the nearest colour.
+    /// They're converted the first time that they're needed, and again whenever a colour in the
+    /// map is changed.
+    lab: std::sync::OnceLock<Vec<(u8, Lab)>>,
 }
 
 impl Palette {
+    /// Instantiate
+    pub const fn new(map: PaletteHashMap, bold_is_bright: bool) -> Self {
+        Self {
+            map,
+            bold_is_bright,
+            lab: std::sync::OnceLock::new(),
+        }
+    }
+
+    /// The palette hash, of palette indexes to true colour values.
+    pub const fn map(&self) -> &PaletteHashMap {
+        &self.map
+    }
+
+    /// Set the true colour of a palette index.
+    pub fn set_colour(&mut self, index: u8, colour: PaletteColour) {
+        self.map.insert(index.to_string(), colour);
+        self.lab = std::sync::OnceLock::new();
+    }
+
+    /// The standard xterm palette, for when the user hasn't parsed their own terminal's palette.
+    pub fn standard() -> Self {
+        let map = (0..=255)
+            .map(|index| {
+                (
+                    index.to_string(),
+                    crate::colour_depth::standard_colour(index),
+                )
+            })
+            .collect();
+        Self::new(map, false)
+    }
+
+    /// The true colour of a palette index. Falls back to the standard palette's colour, if the
+    /// palette is missing the index.
+    pub fn colour(&self, index: u8) -> PaletteColour {
+        self.map
+            .get(&index.to_string())
+            .copied()
+            .unwrap_or_else(|| crate::colour_depth::standard_colour(index))
+    }
+
     /// Convert a palette index to a Termwiz-compatible true colour.
     pub fn true_colour_from_index(&self, index: u8) -> termwiz::color::ColorAttribute {
         #[expect(
@@ -34,6 +92,33 @@ impl Palette {
         termwiz::color::ColorAttribute::TrueColorWithPaletteFallback(srgba, index)
     }
 
+    /// The index of the palette colour that looks closest to the true colour. It uses perceptual
+    /// distance, so it's much better than naive RGB distance at downsampling to 16 or 256 colours.
+    /// When several palette colours are just as close, the lowest index is used. Only the first
+    /// `palette_size` colours are considered.
+    pub fn nearest_index(&self, rgb: PaletteColour, palette_size: u16) -> u8 {
+        let target = to_lab(rgb);
+        self.lab_colours()
+            .iter()
+            .filter(|(index, _)| u16::from(*index) < palette_size)
+            .map(|(index, lab)| (*index, lab_distance(target, *lab)))
+            .min_by(|left, right| left.1.total_cmp(&right.1).then(left.0.cmp(&right.0)))
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// Every palette colour in the CIELAB colour space, converted the first time it's needed.
+    fn lab_colours(&self) -> &[(u8, Lab)] {
+        self.lab.get_or_init(|| {
+            let mut lab: Vec<(u8, Lab)> = self
+                .map
+                .iter()
+                .filter_map(|(index, colour)| Some((index.parse::<u8>().ok()?, to_lab(*colour))))
+                .collect();
+            lab.sort_by_key(|(index, _)| *index);
+            lab
+        })
+    }
+
     /// Print all the true colour versions of the terminal's palette as found in the screenshot.
     #[expect(
         clippy::print_stdout,
@@ -63,6 +148,36 @@ impl Palette {
     pub fn cell_attributes_to_true_colour(&self, attributes: &mut termwiz::cell::CellAttributes) {
         self.convert_fg_to_true_colour(attributes);
         self.convert_bg_to_true_colour(attributes);
+        Self::darken_faint_text(attributes);
+    }
+
+    /// Faint text is shown by darkening its true colour. The text's intensity is then made
+    /// normal, so that the user's terminal doesn't make it even fainter.
+    fn darken_faint_text(attributes: &mut termwiz::cell::CellAttributes) {
+        if attributes.intensity() != termwiz::cell::Intensity::Half {
+            return;
+        }
+
+        let darken = |colour: termwiz::color::SrgbaTuple| {
+            termwiz::color::SrgbaTuple(
+                colour.0 * FAINT_BRIGHTNESS,
+                colour.1 * FAINT_BRIGHTNESS,
+                colour.2 * FAINT_BRIGHTNESS,
+                colour.3,
+            )
+        };
+        let darker = match attributes.foreground() {
+            termwiz::color::ColorAttribute::TrueColorWithPaletteFallback(colour, index) => {
+                termwiz::color::ColorAttribute::TrueColorWithPaletteFallback(darken(colour), index)
+            }
+            termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(colour) => {
+                termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(darken(colour))
+            }
+            termwiz::color::ColorAttribute::PaletteIndex(_)
+            | termwiz::color::ColorAttribute::Default => return,
+        };
+        attributes.set_foreground(darker);
+        attributes.set_intensity(termwiz::cell::Intensity::Normal);
     }
 
     /// Convert text palette indexes to true colour values.
@@ -76,10 +191,16 @@ impl Palette {
             return;
         }
 
-        let termwiz::color::ColorAttribute::PaletteIndex(index) = attributes.foreground() else {
+        let termwiz::color::ColorAttribute::PaletteIndex(mut index) = attributes.foreground()
+        else {
             return;
         };
 
+        let is_bold = attributes.intensity() == termwiz::cell::Intensity::Bold;
+        if self.bold_is_bright && is_bold && index < BRIGHT_COLOURS_OFFSET {
+            index += BRIGHT_COLOURS_OFFSET;
+        }
+
         let colour_attribute = self.true_colour_from_index(index);
         attributes.set_foreground(colour_attribute);
     }
@@ -98,3 +219,124 @@ impl Palette {
         attributes.set_background(colour_attribute);
     }
 }
+
+/// How different two colours look. It's the CIE76 distance, the straight line distance between
+/// the colours in the CIELAB colour space.
+fn lab_distance(left: Lab, right: Lab) -> f32 {
+    (left.0 - right.0)
+        .hypot(left.1 - right.1)
+        .hypot(left.2 - right.2)
+}
+
+/// Convert an sRGB colour to CIELAB, with a D65 white point.
+fn to_lab(colour: PaletteColour) -> Lab {
+    /// The D65 white point in the XYZ colour space.
+    const WHITE: (f32, f32, f32) = (0.950_47, 1.0, 1.088_83);
+
+    let linear = |channel: u8| crate::colour_vision::to_linear(f32::from(channel) / 255.0);
+    let (red, green, blue) = (linear(colour.0), linear(colour.1), linear(colour.2));
+    let x = (0.412_4 * red + 0.357_6 * green + 0.180_5 * blue) / WHITE.0;
+    let y = (0.212_6 * red + 0.715_2 * green + 0.072_2 * blue) / WHITE.1;
+    let z = (0.019_3 * red + 0.119_2 * green + 0.950_5 * blue) / WHITE.2;
+
+    let compand = |value: f32| {
+        if value > 0.008_856 {
+            value.cbrt()
+        } else {
+            7.787 * value + 16.0 / 116.0
+        }
+    };
+    let (x, y, z) = (compand(x), compand(y), compand(z));
+    (116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z))
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exact_colours_are_their_own_index() {
+        let palette = Palette::standard();
+        assert_eq!(palette.nearest_index((0, 0, 0), 256), 0);
+        assert_eq!(palette.nearest_index((205, 0, 205), 256), 5);
+        assert_eq!(palette.nearest_index((255, 135, 0), 256), 208);
+        // 196 is also pure red, so the lowest index is used.
+        assert_eq!(palette.nearest_index((255, 0, 0), 256), 9);
+    }
+
+    #[test]
+    fn nearby_colours_are_quantized() {
+        let palette = Palette::standard();
+        assert_eq!(palette.nearest_index((250, 5, 5), 256), 9);
+        assert_eq!(palette.nearest_index((130, 130, 130), 256), 244);
+        assert_eq!(palette.nearest_index((0, 0, 120), 256), 18);
+        assert_eq!(palette.nearest_index((100, 60, 20), 256), 94);
+    }
+
+    #[test]
+    fn changed_colours_are_quantized() {
+        let mut palette = Palette::standard();
+        assert_eq!(palette.nearest_index((250, 5, 5), 256), 9);
+        palette.set_colour(1, (250, 5, 5));
+        assert_eq!(palette.nearest_index((250, 5, 5), 256), 1);
+    }
+
+    fn resolve_with(
+        palette: &Palette,
+        index: u8,
+        intensity: termwiz::cell::Intensity,
+    ) -> termwiz::cell::CellAttributes {
+        let mut attributes = termwiz::cell::CellAttributes::default();
+        attributes
+            .set_foreground(termwiz::color::ColorAttribute::PaletteIndex(index))
+            .set_intensity(intensity);
+        palette.cell_attributes_to_true_colour(&mut attributes);
+        attributes
+    }
+
+    fn resolve(intensity: termwiz::cell::Intensity) -> termwiz::cell::CellAttributes {
+        resolve_with(&Palette::standard(), 1, intensity)
+    }
+
+    fn red(attributes: &termwiz::cell::CellAttributes) -> f32 {
+        crate::opaque_cell::OpaqueCell::extract_colour(attributes.foreground())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn faint_text_is_darker() {
+        let normal = resolve(termwiz::cell::Intensity::Normal);
+        let faint = resolve(termwiz::cell::Intensity::Half);
+        assert!((red(&faint) - red(&normal) * FAINT_BRIGHTNESS).abs() < 0.001);
+        assert_eq!(faint.intensity(), termwiz::cell::Intensity::Normal);
+    }
+
+    #[test]
+    fn bold_text_isnt_darker() {
+        let normal = resolve(termwiz::cell::Intensity::Normal);
+        let bold = resolve(termwiz::cell::Intensity::Bold);
+        assert!((red(&bold) - red(&normal)).abs() < 0.001);
+        assert_eq!(bold.intensity(), termwiz::cell::Intensity::Bold);
+    }
+
+    #[test]
+    fn bold_can_be_bright() {
+        let mut palette = Palette::standard();
+        palette.bold_is_bright = true;
+        let bold = resolve_with(&palette, 1, termwiz::cell::Intensity::Bold);
+        assert_eq!(red(&bold), 1.0);
+        let bright = resolve_with(&palette, 9, termwiz::cell::Intensity::Bold);
+        assert_eq!(red(&bright), 1.0);
+        let normal = resolve_with(&palette, 1, termwiz::cell::Intensity::Normal);
+        assert!(red(&normal) < 1.0);
+    }
+
+    #[test]
+    fn perceptual_distance_is_symmetric_and_zero_for_the_same_colour() {
+        let (red, green) = (to_lab((255, 0, 0)), to_lab((0, 255, 0)));
+        assert!(lab_distance(red, red) < f32::EPSILON);
+        assert!((lab_distance(red, green) - lab_distance(green, red)).abs() < 0.001);
+    }
+}
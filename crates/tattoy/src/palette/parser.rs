@@ -188,7 +188,7 @@ impl Parser {
         }
 
         let path = Self::palette_config_path(state).await;
-        let data = toml::to_string(&palette.map)?;
+        let data = toml::to_string(palette.map())?;
         std::fs::write(path.clone(), data)?;
 
         println!("Palette saved to: {}", path.display());
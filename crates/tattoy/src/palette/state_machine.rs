@@ -63,9 +63,10 @@ impl Machine {
 
         let mut machine = Self {
             state: State::LookingForRedish,
-            palette: crate::palette::converter::Palette {
-                map: std::collections::HashMap::new(),
-            },
+            palette: crate::palette::converter::Palette::new(
+                std::collections::HashMap::new(),
+                false,
+            ),
             current_colour: xcap::image::Rgba::<u8>([0, 0, 0, 0]),
             palette_index: 0,
             row_index: 0,
@@ -208,8 +209,8 @@ impl Machine {
 
                 // TODO: I feel like there should be a way to get this (inserting of a palette
                 // colour) into the [`ParserState::CollectingRow`] step 🤔
-                self.palette.map.insert(
-                    self.palette_index.to_string(),
+                self.palette.set_colour(
+                    self.palette_index,
                     (
                         self.current_colour[0],
                         self.current_colour[1],
@@ -232,8 +233,8 @@ impl Machine {
                         self.current_colour
                     );
 
-                    self.palette.map.insert(
-                        self.palette_index.to_string(),
+                    self.palette.set_colour(
+                        self.palette_index,
                         (
                             self.current_colour[0],
                             self.current_colour[1],
@@ -266,9 +267,9 @@ mod test {
         let screenshot = xcap::image::open(path).unwrap();
         let palette = Machine::parse_screenshot(&screenshot.into_rgba8()).unwrap();
 
-        assert_eq!(palette.map["0"], (14, 13, 21));
-        assert_eq!(palette.map["128"], (175, 0, 215));
-        assert_eq!(palette.map["255"], (238, 238, 238));
+        assert_eq!(palette.map()["0"], (14, 13, 21));
+        assert_eq!(palette.map()["128"], (175, 0, 215));
+        assert_eq!(palette.map()["255"], (238, 238, 238));
     }
 
     #[test]
@@ -293,8 +294,8 @@ mod test {
         let screenshot = xcap::image::open(path).unwrap();
         let palette = Machine::parse_screenshot(&screenshot.into_rgba8()).unwrap();
 
-        assert_eq!(palette.map["0"], (1, 1, 0));
-        assert_eq!(palette.map["128"], (175, 0, 215));
-        assert_eq!(palette.map["255"], (238, 237, 238));
+        assert_eq!(palette.map()["0"], (1, 1, 0));
+        assert_eq!(palette.map()["128"], (175, 0, 215));
+        assert_eq!(palette.map()["255"], (238, 237, 238));
     }
 }
@@ -0,0 +1,236 @@
+//! A low level hook for transforming every cell of the final composited frame with Rust code.
+//!
+//! The shader is the compositor's very last pass, after colour grading, and before the frame
+//! is quantized for terminals without true colour. So it sees the colours exactly as they'd be
+//! shown, and can do anything to them.
+//!
+//! It's the most flexible way to write an effect, but also the most expensive. The closure is
+//! called for every cell of every frame, and the whole frame is copied first, so that every cell
+//! sees its neighbours as they were before the pass. At 30 frames a second, a 200×50 terminal
+//! calls the closure 300,000 times a second. It also runs on the render loop itself, so a slow
+//! shader slows down everything, even the PTY's own output. Keep shaders cheap: avoid
+//! allocating, and only sample the neighbours that are really needed.
+//!
+//! Embedders set a shader with `TattoyRegistry::set_pixel_shader()`.
+
+use termwiz::cell::Cell;
+
+/// Everything that a shader knows about the cell it's shading.
+pub struct CellContext<'frame> {
+    /// The cell's column.
+    pub x: usize,
+    /// The cell's row.
+    pub y: usize,
+    /// How long the shader has been running for, for animating.
+    pub time: std::time::Duration,
+    /// The cell's foreground colour. Palette colours are resolved with the user's parsed
+    /// palette. `None` when it's the terminal's own default colour, or a palette colour when
+    /// there's no parsed palette.
+    pub foreground: Option<crate::surface::Colour>,
+    /// The cell's background colour. Palette colours are resolved with the user's parsed
+    /// palette. `None` when it's the terminal's own default colour, or a palette colour when
+    /// there's no parsed palette.
+    pub background: Option<crate::surface::Colour>,
+    /// The whole frame, as it was before the shader started, for sampling neighbours.
+    frame: &'frame [Vec<Cell>],
+    /// The user's parsed palette, for resolving the neighbours' palette colours.
+    palette: Option<&'frame crate::palette::converter::Palette>,
+}
+
+impl CellContext<'_> {
+    /// The colours of a neighbouring cell, offset by the given columns and rows, as they were
+    /// before the shader started. `None` when the offset is outside the frame.
+    #[inline]
+    #[must_use]
+    pub fn neighbour(&self, x_offset: isize, y_offset: isize) -> Option<CellOutput> {
+        let x = self.x.checked_add_signed(x_offset)?;
+        let y = self.y.checked_add_signed(y_offset)?;
+        self.frame
+            .get(y)?
+            .get(x)
+            .map(|cell| colours(cell, self.palette))
+    }
+}
+
+/// The new colours for a cell. `None` is the terminal's default colour.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[expect(
+    clippy::exhaustive_structs,
+    reason = "Shaders construct it, and a cell only has the 2 colours"
+)]
+pub struct CellOutput {
+    /// The new foreground colour.
+    pub foreground: Option<crate::surface::Colour>,
+    /// The new background colour.
+    pub background: Option<crate::surface::Colour>,
+}
+
+/// The closure that shades each cell.
+type Shader = Box<dyn Fn(CellContext<'_>) -> CellOutput + Send + Sync>;
+
+/// A per cell transform of the final composited frame.
+pub(crate) struct PixelShader {
+    /// Shades each cell.
+    shader: Shader,
+    /// When the shader was set, so that it can animate.
+    pub started: std::time::Instant,
+}
+
+impl PixelShader {
+    /// Instantiate
+    pub fn new(shader: impl Fn(CellContext<'_>) -> CellOutput + Send + Sync + 'static) -> Self {
+        Self {
+            shader: Box::new(shader),
+            started: std::time::Instant::now(),
+        }
+    }
+
+    /// Shade every cell of the frame. Colours that the shader doesn't change are left exactly as
+    /// they were, so palette colours aren't lost.
+    pub fn apply(
+        &self,
+        frame: &mut [&mut [Cell]],
+        time: std::time::Duration,
+        palette: Option<&crate::palette::converter::Palette>,
+    ) {
+        let before: crate::renderer::CompositedFrame =
+            frame.iter().map(|line| line.to_vec()).collect();
+
+        for (y, line) in frame.iter_mut().enumerate() {
+            for (x, cell) in line.iter_mut().enumerate() {
+                let original = colours(cell, palette);
+                let context = CellContext {
+                    x,
+                    y,
+                    time,
+                    foreground: original.foreground,
+                    background: original.background,
+                    frame: &before,
+                    palette,
+                };
+                let output = (self.shader)(context);
+
+                if output.foreground != original.foreground {
+                    cell.attrs_mut()
+                        .set_foreground(colour_attribute(output.foreground));
+                }
+                if output.background != original.background {
+                    cell.attrs_mut()
+                        .set_background(colour_attribute(output.background));
+                }
+            }
+        }
+    }
+}
+
+/// The resolved colours of a cell. Palette colours are resolved with the palette, if there is one.
+fn colours(cell: &Cell, palette: Option<&crate::palette::converter::Palette>) -> CellOutput {
+    let colour = |attribute| {
+        let resolved = match (attribute, palette) {
+            (termwiz::color::ColorAttribute::PaletteIndex(index), Some(palette)) => {
+                palette.true_colour_from_index(index)
+            }
+            _ => attribute,
+        };
+        let termwiz::color::SrgbaTuple(red, green, blue, alpha) =
+            crate::opaque_cell::OpaqueCell::extract_colour(resolved)?;
+        Some((red, green, blue, alpha))
+    };
+    CellOutput {
+        foreground: colour(cell.attrs().foreground()),
+        background: colour(cell.attrs().background()),
+    }
+}
+
+/// The colour attribute for a colour from a shader.
+const fn colour_attribute(
+    colour: Option<crate::surface::Colour>,
+) -> termwiz::color::ColorAttribute {
+    match colour {
+        Some(colour) => crate::surface::Surface::make_colour_attribute(colour),
+        None => termwiz::color::ColorAttribute::Default,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frame(backgrounds: &[Option<crate::surface::Colour>]) -> crate::renderer::CompositedFrame {
+        let line = backgrounds
+            .iter()
+            .map(|background| {
+                let mut cell = Cell::default();
+                cell.attrs_mut()
+                    .set_background(colour_attribute(*background));
+                cell
+            })
+            .collect();
+        vec![line]
+    }
+
+    fn backgrounds(
+        frame: &crate::renderer::CompositedFrame,
+    ) -> Vec<Option<crate::surface::Colour>> {
+        frame
+            .iter()
+            .flatten()
+            .map(|cell| colours(cell, None).background)
+            .collect()
+    }
+
+    #[test]
+    fn shaders_see_the_frame_as_it_was_before_shading() {
+        let red = Some(crate::surface::RED);
+        let white = Some(crate::surface::WHITE);
+        let mut cells = frame(&[red, white, None]);
+        let mut lines: Vec<&mut [Cell]> = cells.iter_mut().map(Vec::as_mut_slice).collect();
+
+        // Shift every background one cell to the right.
+        let shader = PixelShader::new(|context| CellOutput {
+            foreground: context.foreground,
+            background: context.neighbour(-1, 0).and_then(|left| left.background),
+        });
+        shader.apply(&mut lines, std::time::Duration::ZERO, None);
+
+        assert_eq!(backgrounds(&cells), [None, red, white]);
+    }
+
+    #[test]
+    fn shaders_know_where_and_when_each_cell_is() {
+        let mut cells = frame(&[None, None]);
+        let mut lines: Vec<&mut [Cell]> = cells.iter_mut().map(Vec::as_mut_slice).collect();
+
+        // Fade the second column in over 4 seconds.
+        let shader = PixelShader::new(|context| {
+            let fade = (context.time.as_secs_f32() / 4.0).min(1.0);
+            let is_faded = context.x == 1 && context.y == 0;
+            CellOutput {
+                foreground: context.foreground,
+                background: is_faded
+                    .then_some((1.0, 1.0, 1.0, fade))
+                    .or(context.background),
+            }
+        });
+        shader.apply(&mut lines, std::time::Duration::from_secs(2), None);
+
+        assert_eq!(backgrounds(&cells), [None, Some((1.0, 1.0, 1.0, 0.5))]);
+    }
+
+    #[test]
+    fn palette_colours_are_resolved_with_the_palette() {
+        let mut cell = Cell::default();
+        cell.attrs_mut()
+            .set_background(termwiz::color::ColorAttribute::PaletteIndex(1));
+        let palette = crate::palette::converter::Palette::new(
+            std::collections::HashMap::from([("1".to_owned(), (255, 0, 0))]),
+            false,
+        );
+
+        assert_eq!(
+            colours(&cell, Some(&palette)).background,
+            Some(crate::surface::RED)
+        );
+        assert_eq!(colours(&cell, None).background, None);
+    }
+}
@@ -0,0 +1,219 @@
+//! Mirror all the raw output of the PTY to a log file, for auditing and debugging.
+//!
+//! The bytes come from the shadow terminal's tee, so they're exactly what the application wrote,
+//! escape codes and all. Writing happens on its own blocking task, so a slow disk never holds up
+//! rendering. If the task can't keep up then the tee drops bytes rather than waiting.
+//!
+//! The log is rotated once it gets too big or too old. The current log is always at the
+//! configured path, the previous one at `<path>.1`, the one before that at `<path>.2`, and so on.
+
+use std::io::Write as _;
+
+use color_eyre::eyre::Result;
+
+/// How many payloads of PTY output can be waiting to be written before new ones are dropped.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// The number of bytes in a megabyte.
+const BYTES_PER_MEGABYTE: u64 = 1024 * 1024;
+
+/// The user's config for logging the PTY's output.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Where to write the PTY's output. Logging is disabled when it isn't set.
+    pub path: Option<std::path::PathBuf>,
+    /// Start a new log once the current one reaches this size, in megabytes. 0 disables it.
+    pub max_size: u64,
+    /// Start a new log once the current one is this old, in seconds. 0 disables it.
+    pub max_age: u64,
+    /// The number of previous logs to keep. 0 keeps none, so that rotating just empties the log.
+    pub max_files: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_size: 10,
+            max_age: 0,
+            max_files: 5,
+        }
+    }
+}
+
+/// A log file of the PTY's output, that rotates itself.
+pub(crate) struct PtyLog {
+    /// Where the current log is.
+    path: std::path::PathBuf,
+    /// The current log.
+    writer: std::io::BufWriter<std::fs::File>,
+    /// The biggest, in bytes, that a log can be. 0 is unlimited.
+    max_bytes: u64,
+    /// The oldest that a log can be.
+    max_age: Option<std::time::Duration>,
+    /// The number of previous logs to keep.
+    max_files: usize,
+    /// The number of bytes written to the current log.
+    written: u64,
+    /// When the current log was started.
+    opened: std::time::Instant,
+}
+
+impl PtyLog {
+    /// Open the log, adding to it if it already exists.
+    pub fn open(path: std::path::PathBuf, config: &Config) -> Result<Self> {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let file = Self::open_file(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            writer: std::io::BufWriter::new(file),
+            max_bytes: config.max_size.saturating_mul(BYTES_PER_MEGABYTE),
+            max_age: (config.max_age > 0).then(|| std::time::Duration::from_secs(config.max_age)),
+            max_files: config.max_files,
+            written,
+            opened: std::time::Instant::now(),
+        })
+    }
+
+    /// Open the log file for appending.
+    fn open_file(path: &std::path::Path) -> Result<std::fs::File> {
+        Ok(std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?)
+    }
+
+    /// Write some PTY output to the log, rotating it first if needed.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.is_rotation_due(std::time::Instant::now()) {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(bytes)?;
+        self.written = self.written.saturating_add(bytes.len().try_into()?);
+        Ok(())
+    }
+
+    /// Write any buffered output to the file.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Is the current log too big or too old?
+    fn is_rotation_due(&self, now: std::time::Instant) -> bool {
+        let is_too_big = self.max_bytes > 0 && self.written >= self.max_bytes;
+        let is_too_old = self
+            .max_age
+            .is_some_and(|max_age| now.saturating_duration_since(self.opened) >= max_age);
+        is_too_big || is_too_old
+    }
+
+    /// The path of a previous log. 1 is the most recent.
+    fn rotated_path(&self, number: usize) -> std::path::PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{number}"));
+        path.into()
+    }
+
+    /// Move each previous log along by one, dropping the oldest, and start a new log.
+    fn rotate(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        if self.max_files > 0 {
+            for number in (1..self.max_files).rev() {
+                let from = self.rotated_path(number);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated_path(number + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        } else {
+            std::fs::remove_file(&self.path)?;
+        }
+
+        self.writer = std::io::BufWriter::new(Self::open_file(&self.path)?);
+        self.written = 0;
+        self.opened = std::time::Instant::now();
+        tracing::debug!("Rotated the PTY log: {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Start the task that writes the PTY's output to the log. Returns the channel to give to the
+/// shadow terminal's tee, or `None` if logging isn't enabled. The task finishes once the shadow
+/// terminal drops its end of the channel.
+pub(crate) fn start(
+    config: &Config,
+) -> Result<
+    Option<(
+        tokio::sync::mpsc::Sender<Vec<u8>>,
+        tokio::task::JoinHandle<Result<()>>,
+    )>,
+> {
+    let Some(path) = config.path.clone() else {
+        return Ok(None);
+    };
+
+    tracing::debug!("Logging the PTY's output to: {}", path.display());
+    let mut log = PtyLog::open(path, config)?;
+    let (tee_tx, mut tee_rx) = tokio::sync::mpsc::channel::<Vec<u8>>(CHANNEL_CAPACITY);
+    let handle = tokio::task::spawn_blocking(move || -> Result<()> {
+        while let Some(bytes) = tee_rx.blocking_recv() {
+            log.write(&bytes)?;
+            while let Ok(more) = tee_rx.try_recv() {
+                log.write(&more)?;
+            }
+            log.flush()?;
+        }
+
+        log.flush()
+    });
+
+    Ok(Some((tee_tx, handle)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn logs_rotate_once_they_are_too_big() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("pty.log");
+        let config = Config {
+            max_files: 2,
+            ..Config::default()
+        };
+        let mut log = PtyLog::open(path.clone(), &config).unwrap();
+        log.max_bytes = 4;
+
+        for bytes in ["one\n", "two\n", "three\n", "four\n"] {
+            log.write(bytes.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        let read = |path: std::path::PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(path.clone()), "four\n");
+        assert_eq!(read(log.rotated_path(1)), "three\n");
+        assert_eq!(read(log.rotated_path(2)), "two\n");
+        assert!(!log.rotated_path(3).exists());
+    }
+
+    #[test]
+    fn logs_rotate_once_they_are_too_old() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("pty.log");
+        let mut log = PtyLog::open(path, &Config::default()).unwrap();
+        assert!(!log.is_rotation_due(std::time::Instant::now()));
+
+        log.max_age = Some(std::time::Duration::from_secs(1));
+        let later = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        assert!(log.is_rotation_due(later));
+    }
+}
@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 use color_eyre::eyre::{ContextCompat as _, Result};
 use termwiz::cell::Cell;
+use tracing::Instrument as _;
 
 use termwiz::surface::Surface as TermwizSurface;
 use termwiz::surface::{Change as TermwizChange, Position as TermwizPosition};
@@ -35,6 +36,29 @@ pub const MILLIS_PER_SECOND: f32 = 1_000.0;
 /// buffer of frames is for extreme conditions. 100 frames should give about 3 seconds of grace.
 const MAX_FRAME_BACKLOG: usize = 100;
 
+/// How long idle dimming takes to fade down to its full amount.
+const IDLE_DIMMING_FADE: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// How often to check whether the terminal has become idle.
+const IDLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// How often to check whether blinking text needs to change between visible and hidden.
+const BLINK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Shown in the top right corner when the terminal is too small for any of the tattoys.
+const TOO_SMALL_INDICATOR: char = '…';
+
+/// Fading the tattoys out, or back in, when the alternate screen is toggled.
+pub(crate) struct Fade {
+    /// How far the tattoys have faded out.
+    amount: crate::animation::Tween<f32>,
+    /// When the previous frame of the fade was rendered.
+    last_tick: std::time::Instant,
+}
+
+/// The cells of a composited frame, row by row.
+pub(crate) type CompositedFrame = Vec<Vec<Cell>>;
+
 /// `Render`
 #[derive(Default)]
 pub(crate) struct Renderer {
@@ -48,6 +72,34 @@ pub(crate) struct Renderer {
     pub tattoys: std::collections::HashMap<String, crate::surface::Surface>,
     /// A shadow version of the user's conventional terminal
     pub pty: TermwizSurface,
+    /// The number of frames that have been composited, for profiling.
+    pub frame: u64,
+    /// Somewhere to also write every composited frame, for piping into other tools.
+    pub frame_sink: Option<crate::frame_sink::FrameSink>,
+    /// How much the last frame was dimmed by, because the terminal is idle.
+    pub idle_dimming: f32,
+    /// The current shake of the whole terminal, if it's shaking.
+    pub shake: Option<crate::shake::Shake>,
+    /// Whether the user has hidden all the tattoys, leaving just the PTY.
+    pub are_tattoys_hidden: bool,
+    /// How far the tattoys have faded out, from 0.0, fully shown, to 1.0, fully hidden.
+    pub tattoy_fade: f32,
+    /// The current fade of the tattoys, if they're fading.
+    pub fade: Option<Fade>,
+    /// When the PTY's text first blinked, so that all blinking text blinks in time.
+    pub blink_started: Option<std::time::Instant>,
+    /// Whether each speed of the PTY's blinking text was visible in the last frame.
+    pub blink_phase: crate::blink::Phase,
+    /// Whether the last frame had any blinking text in it.
+    pub is_blinking: bool,
+    /// Typed characters that are shown before the PTY has echoed them.
+    pub local_echo: crate::local_echo::LocalEcho,
+    /// A per cell transform of the final frame, see `crate::pixel_shader`.
+    pub pixel_shader: Option<crate::pixel_shader::PixelShader>,
+    /// The user's parsed palette, if they have one, for resolving palette colours.
+    pub palette: Option<crate::palette::converter::Palette>,
+    /// Quantizes the final frame for terminals without true colour.
+    pub quantizer: crate::colour_depth::Quantizer,
 }
 
 impl Renderer {
@@ -62,6 +114,20 @@ impl Renderer {
             height,
             tattoys: std::collections::HashMap::default(),
             pty: TermwizSurface::new(width.into(), height.into()),
+            frame: 0,
+            frame_sink: None,
+            idle_dimming: 0.0,
+            shake: None,
+            are_tattoys_hidden: false,
+            tattoy_fade: 0.0,
+            fade: None,
+            blink_started: None,
+            blink_phase: crate::blink::Phase::default(),
+            is_blinking: false,
+            local_echo: crate::local_echo::LocalEcho::default(),
+            pixel_shader: None,
+            palette: None,
+            quantizer: crate::colour_depth::Quantizer::default(),
         };
 
         Ok(renderer)
@@ -71,6 +137,8 @@ impl Renderer {
     pub fn start(
         state: Arc<SharedState>,
         protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        frame_sink: Option<crate::frame_sink::FrameSink>,
+        pixel_shader: Option<crate::pixel_shader::PixelShader>,
     ) -> (
         tokio::task::JoinHandle<Result<()>>,
         tokio::sync::mpsc::Sender<FrameUpdate>,
@@ -81,6 +149,8 @@ impl Renderer {
             // the `?` syntax.
             match Self::new(Arc::clone(&state)) {
                 Ok(mut renderer) => {
+                    renderer.frame_sink = frame_sink;
+                    renderer.pixel_shader = pixel_shader;
                     let result = renderer.run(surfaces_rx, protocol_tx.clone()).await;
 
                     if let Err(error) = result {
@@ -156,6 +226,12 @@ impl Renderer {
         let mut copy_of_users_terminal = Self::get_termwiz_terminal()?;
         copy_of_users_terminal.set_raw_mode()?;
         let mut composited_terminal = BufferedTerminal::new(copy_of_users_terminal)?;
+        let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        let frame_rate = self.state.config.read().await.frame_rate.max(1);
+        let mut shake_tick = tokio::time::interval(std::time::Duration::from_secs(1) / frame_rate);
+        let mut blink_check = tokio::time::interval(BLINK_CHECK_INTERVAL);
+        let mut fade_tick = tokio::time::interval(std::time::Duration::from_secs(1) / frame_rate);
+        self.reload_palette().await;
 
         tracing::debug!("Starting render loop");
         #[expect(
@@ -173,8 +249,50 @@ impl Renderer {
                     }
                     self.handle_frame_update(&mut surfaces, &mut composited_terminal, &protocol_tx).await?;
                 },
+                _ = idle_check.tick() => {
+                    self.check_idle_dimming(&mut composited_terminal).await?;
+                    self.expire_local_echo(&mut composited_terminal).await?;
+                },
+                // Shaking needs new frames, even when nothing else has changed.
+                _ = shake_tick.tick(), if self.shake.is_some() => {
+                    self.tick_shake(&mut composited_terminal).await?;
+                },
+                _ = fade_tick.tick(), if self.fade.is_some() => {
+                    self.tick_fade(&mut composited_terminal).await?;
+                },
+                _ = blink_check.tick(), if self.is_blinking => {
+                    self.check_blink(&mut composited_terminal).await?;
+                },
                 Ok(message) = protocol_rx.recv() => {
-                    Self::handle_protocol_message(&mut composited_terminal, &message);
+                    Self::handle_protocol_message(
+                        &mut composited_terminal,
+                        &message,
+                        self.is_native_cursor_replaced(),
+                    );
+                    self.handle_shake_messages(&message).await;
+                    if let crate::run::Protocol::AlternateScreen(is_alternate_screen) = message {
+                        self.start_fade(is_alternate_screen).await;
+                    }
+                    if let crate::run::Protocol::Input(input) = &message {
+                        if self.predict_local_echo(&input.event).await {
+                            self.flush_frame(&mut composited_terminal).await?;
+                        }
+                    }
+                    if matches!(message, crate::run::Protocol::Input(_)) {
+                        *self.state.last_activity.write().await = Some(std::time::Instant::now());
+                        self.check_idle_dimming(&mut composited_terminal).await?;
+                    }
+                    if matches!(message, crate::run::Protocol::ToggleTattoys) {
+                        self.are_tattoys_hidden = !self.are_tattoys_hidden;
+                        self.flush_frame(&mut composited_terminal).await?;
+                    }
+                    if matches!(message, crate::run::Protocol::PaletteChanged) {
+                        self.reload_palette().await;
+                        // Only changed cells are usually sent, so every cell is sent again to be
+                        // sure that none of them keep colours from the old palette.
+                        composited_terminal.repaint()?;
+                        self.flush_frame(&mut composited_terminal).await?;
+                    }
                     if matches!(message, crate::run::Protocol::End) {
                         break;
                     }
@@ -212,12 +330,14 @@ impl Renderer {
     fn handle_protocol_message(
         composited_terminal: &mut BufferedTerminal<impl TermwizTerminal>,
         message: &crate::run::Protocol,
+        is_native_cursor_replaced: bool,
     ) {
         #[expect(clippy::wildcard_enum_match_arm, reason = "It's our internal protocol")]
         let result = match message {
-            crate::run::Protocol::CursorVisibility(is_visible) => {
-                Self::cursor_visibility(composited_terminal, *is_visible)
-            }
+            crate::run::Protocol::CursorVisibility(is_visible) => Self::cursor_visibility(
+                composited_terminal,
+                *is_visible && !is_native_cursor_replaced,
+            ),
             _ => Ok(()),
         };
 
@@ -226,6 +346,14 @@ impl Renderer {
         }
     }
 
+    /// Whether a tattoy is drawing its own cursor, so the user's terminal's cursor shouldn't be
+    /// shown.
+    fn is_native_cursor_replaced(&self) -> bool {
+        self.state
+            .is_native_cursor_replaced
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
     /// Hide/show the cursor in the end user's terminal.
     fn cursor_visibility(
         composited_terminal: &mut BufferedTerminal<impl TermwizTerminal>,
@@ -250,6 +378,23 @@ impl Renderer {
         update: FrameUpdate,
         composited_terminal: &mut BufferedTerminal<impl TermwizTerminal + Send>,
     ) -> Result<()> {
+        self.apply_frame_update(update).await;
+
+        if backlog > 5 {
+            tracing::warn!("Backlog: {backlog}");
+        }
+
+        if backlog > 0 {
+            return Ok(());
+        }
+
+        self.frame = self.frame.wrapping_add(1);
+        let span = tracing::debug_span!("frame", frame = self.frame);
+        self.flush_frame(composited_terminal).instrument(span).await
+    }
+
+    /// Keep the latest copy of a tattoy's or the PTY's frame, ready for the next composite.
+    async fn apply_frame_update(&mut self, update: FrameUpdate) {
         match update {
             FrameUpdate::TattoySurface(surface) => {
                 let surface_id = surface.id.clone();
@@ -260,19 +405,219 @@ impl Renderer {
             }
             FrameUpdate::PTYSurface => {
                 tracing::trace!("Rendering PTY frame update");
+                *self.state.last_activity.write().await = Some(std::time::Instant::now());
                 self.get_updated_pty_frame().await;
+                self.local_echo.reconcile(&self.pty);
             }
         }
+    }
 
-        if backlog > 5 {
-            tracing::warn!("Backlog: {backlog}");
+    /// Apply the frame updates and return the composited frame, without rendering it to the
+    /// user's terminal. Because it doesn't wait on any timers, it's useful for deterministically
+    /// testing compositing and for exporting frames headlessly. Tattoys run in their own tasks,
+    /// so their frames have to be given as updates.
+    pub async fn render_frame(
+        &mut self,
+        updates: impl IntoIterator<Item = FrameUpdate> + Send,
+    ) -> Result<TermwizSurface> {
+        for update in updates {
+            self.apply_frame_update(update).await;
         }
 
-        if backlog > 0 {
-            return Ok(());
+        self.composite().await
+    }
+
+    /// Start shaking the terminal, either because it was asked to or because the bell rang.
+    async fn handle_shake_messages(&mut self, message: &crate::run::Protocol) {
+        #[expect(clippy::wildcard_enum_match_arm, reason = "It's our internal protocol")]
+        let (intensity, duration) = match message {
+            crate::run::Protocol::Shake {
+                intensity,
+                duration,
+            } => (*intensity, *duration),
+            crate::run::Protocol::Bell => {
+                let config = self.state.config.read().await.screen_shake.clone();
+                if !config.on_bell {
+                    return;
+                }
+                let Ok(duration) = std::time::Duration::try_from_secs_f32(config.duration) else {
+                    tracing::error!("Invalid screen shake duration: {}", config.duration);
+                    return;
+                };
+                (config.intensity, duration)
+            }
+            _ => return,
+        };
+
+        let rng = self.state.tattoy_rng("screen_shake").await;
+        self.shake = Some(crate::shake::Shake::new(intensity, duration, rng));
+    }
+
+    /// Render the next frame of a shake. The frame after the shake has finished puts everything
+    /// back in its place.
+    async fn tick_shake(
+        &mut self,
+        composited_terminal: &mut BufferedTerminal<impl TermwizTerminal + Send>,
+    ) -> Result<()> {
+        if self
+            .shake
+            .as_ref()
+            .is_some_and(crate::shake::Shake::is_finished)
+        {
+            self.shake = None;
+        }
+
+        self.flush_frame(composited_terminal).await
+    }
+
+    /// Start fading the tattoys out when the PTY switches to the alternate screen, and back in
+    /// when it switches back.
+    async fn start_fade(&mut self, is_alternate_screen: bool) {
+        let config = self.state.config.read().await.alternate_screen.clone();
+        let target = if config.hide_tattoys && is_alternate_screen {
+            1.0
+        } else {
+            0.0
+        };
+        if (target - self.tattoy_fade).abs() <= f32::EPSILON {
+            self.fade = None;
+            return;
+        }
+
+        let Ok(duration) = std::time::Duration::try_from_secs_f32(config.fade_duration) else {
+            tracing::error!(
+                "Invalid alternate screen fade duration: {}",
+                config.fade_duration
+            );
+            self.tattoy_fade = target;
+            return;
+        };
+        self.fade = Some(Fade {
+            amount: crate::animation::Tween::new(self.tattoy_fade, target, duration, config.easing),
+            last_tick: std::time::Instant::now(),
+        });
+    }
+
+    /// Render the next frame of the tattoys fading in or out.
+    async fn tick_fade(
+        &mut self,
+        composited_terminal: &mut BufferedTerminal<impl TermwizTerminal + Send>,
+    ) -> Result<()> {
+        if let Some(fade) = &mut self.fade {
+            let now = std::time::Instant::now();
+            self.tattoy_fade = fade
+                .amount
+                .advance(now.saturating_duration_since(fade.last_tick));
+            fade.last_tick = now;
+            if fade.amount.is_finished() {
+                self.fade = None;
+            }
+        }
+
+        self.flush_frame(composited_terminal).await
+    }
+
+    /// Re-render when blinking text needs to change between visible and hidden.
+    async fn check_blink(
+        &mut self,
+        composited_terminal: &mut BufferedTerminal<impl TermwizTerminal + Send>,
+    ) -> Result<()> {
+        let config = self.state.config.read().await.blink.clone();
+        if self.current_blink_phase(&config) != self.blink_phase {
+            self.flush_frame(composited_terminal).await?;
+        }
+
+        Ok(())
+    }
+
+    /// The phase that blinking text should be in now.
+    fn current_blink_phase(&mut self, config: &crate::blink::Config) -> crate::blink::Phase {
+        let started = *self
+            .blink_started
+            .get_or_insert_with(std::time::Instant::now);
+        crate::blink::Phase::at(started.elapsed(), config)
+    }
+
+    /// Hide the PTY's blinking text when it's in the hidden part of its blink.
+    fn blink(&mut self, frame: &mut [&mut [Cell]], config: &crate::blink::Config) {
+        self.is_blinking = config.enabled && crate::blink::has_blinking_cells(frame);
+        if !self.is_blinking {
+            self.blink_phase = crate::blink::Phase::default();
+            return;
+        }
+
+        self.blink_phase = self.current_blink_phase(config);
+        crate::blink::hide_blinking_cells(frame, self.blink_phase);
+    }
+
+    /// Predict how the user's typing will be echoed. Returns whether the shown predictions
+    /// changed.
+    async fn predict_local_echo(&mut self, event: &termwiz::input::InputEvent) -> bool {
+        let is_enabled = self.state.config.read().await.local_echo.enabled;
+        let is_predictable = is_enabled
+            && !self.state.get_is_alternate_screen().await
+            && !self.state.get_is_scrolling().await;
+        if !is_predictable {
+            return false;
+        }
+
+        self.local_echo
+            .handle_input(event, &self.pty, std::time::Instant::now())
+    }
+
+    /// Re-render when the PTY hasn't echoed the predicted characters in time.
+    async fn expire_local_echo(
+        &mut self,
+        composited_terminal: &mut BufferedTerminal<impl TermwizTerminal + Send>,
+    ) -> Result<()> {
+        let config = self.state.config.read().await.local_echo.clone();
+        if self.local_echo.expire(std::time::Instant::now(), &config) {
+            self.flush_frame(composited_terminal).await?;
         }
 
-        let new_frame = self.composite().await?;
+        Ok(())
+    }
+
+    /// Re-render when the amount of idle dimming needs to change, because there won't
+    /// necessarily be any other frames to render whilst the terminal is idle.
+    async fn check_idle_dimming(
+        &mut self,
+        composited_terminal: &mut BufferedTerminal<impl TermwizTerminal + Send>,
+    ) -> Result<()> {
+        let config = self.state.config.read().await.idle_dimming.clone();
+        let last_activity = *self
+            .state
+            .last_activity
+            .write()
+            .await
+            .get_or_insert_with(std::time::Instant::now);
+        let dimming = idle_dimming(last_activity.elapsed(), &config);
+        if (dimming - self.idle_dimming).abs() > f32::EPSILON {
+            self.flush_frame(composited_terminal).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Composite a new frame and send it to the user's actual terminal.
+    async fn flush_frame(
+        &mut self,
+        composited_terminal: &mut BufferedTerminal<impl TermwizTerminal + Send>,
+    ) -> Result<()> {
+        let config = self.state.config.read().await.idle_dimming.clone();
+        let last_activity = *self.state.last_activity.read().await;
+        self.idle_dimming = last_activity.map_or(0.0, |last_activity| {
+            idle_dimming(last_activity.elapsed(), &config)
+        });
+
+        let mut new_frame = self.render_frame(std::iter::empty()).await?;
+        if let Some(frame_sink) = &mut self.frame_sink {
+            // The frame sink is optional, so its failure doesn't stop rendering to the TTY.
+            if let Err(error) = frame_sink.write_frame(&mut new_frame) {
+                tracing::error!("Stopping the frame sink: {error:?}");
+                self.frame_sink = None;
+            }
+        }
 
         // Hide the cursor without flushing.
         composited_terminal.add_change(TermwizChange::CursorVisibility(
@@ -282,7 +627,10 @@ impl Renderer {
         let changes = composited_terminal.diff_screens(&new_frame);
         composited_terminal.add_changes(changes);
 
-        let (cursor_x, cursor_y) = self.pty.cursor_position();
+        let (cursor_x, cursor_y) = self
+            .local_echo
+            .cursor_position()
+            .unwrap_or_else(|| self.pty.cursor_position());
         composited_terminal.add_change(TermwizChange::CursorPosition {
             x: TermwizPosition::Absolute(cursor_x),
             y: TermwizPosition::Absolute(cursor_y),
@@ -293,7 +641,9 @@ impl Renderer {
 
         // This is where we actually render to the user's real terminal.
         composited_terminal.flush()?;
-        Self::cursor_visibility(composited_terminal, true)?;
+        if !self.is_native_cursor_replaced() {
+            Self::cursor_visibility(composited_terminal, true)?;
+        }
 
         Ok(())
     }
@@ -303,15 +653,63 @@ impl Renderer {
         let mut surface = TermwizSurface::new(self.width.into(), self.height.into());
         let mut frame = surface.screen_cells();
 
+        let config = self.state.config.read().await;
+        let show_too_small_indicator = config.show_too_small_indicator;
+        let blink = config.blink.clone();
+        let local_echo = config.local_echo.clone();
+        drop(config);
+
         // TODO: A failed render shouldn't crash the whole tick.
         self.render_tattoys_below(&mut frame)?;
         self.render_pty(&mut frame)?;
+        self.blink(&mut frame, &blink);
+        self.local_echo.render(&mut frame, &local_echo);
+        // Shared before the tattoys above the PTY are rendered, so that they never see
+        // themselves.
+        self.share_composited_frame(&frame).await;
         self.render_tattoys_above(&mut frame)?;
+        if show_too_small_indicator && !self.are_tattoys_hidden {
+            self.render_too_small_indicator(&mut frame);
+        }
+        if let Some(shake) = &mut self.shake {
+            crate::shake::shift_cells(&mut frame, shake.offset());
+        }
         self.colour_grade(&mut frame).await?;
+        if let Some(shader) = &self.pixel_shader {
+            shader.apply(&mut frame, shader.started.elapsed(), self.palette.as_ref());
+        }
+        self.quantize(&mut frame).await;
 
         Ok(surface)
     }
 
+    /// Load the user's parsed palette. Without one, palette colours are left for the terminal to
+    /// resolve itself.
+    async fn reload_palette(&mut self) {
+        self.palette = match crate::config::Config::load_palette(&self.state).await {
+            Ok(palette) => palette,
+            Err(error) => {
+                tracing::error!("Couldn't load the palette for the renderer: {error:?}");
+                None
+            }
+        };
+        self.quantizer.clear();
+    }
+
+    /// Share a copy of the frame with any tattoys that have asked for it.
+    async fn share_composited_frame(&self, frame: &[&mut [Cell]]) {
+        if !self
+            .state
+            .is_composited_frame_wanted
+            .load(std::sync::atomic::Ordering::Relaxed)
+        {
+            return;
+        }
+
+        let copy = frame.iter().map(|line| line.to_vec()).collect();
+        *self.state.composited_frame.write().await = Some(Arc::new(copy));
+    }
+
     /// Render all the tattoys that appear below the PTY.
     fn render_tattoys_below(&mut self, frame: &mut Vec<&mut [Cell]>) -> Result<()> {
         self.render_tattoys(frame, std::cmp::Ordering::Less)
@@ -322,28 +720,38 @@ impl Renderer {
         self.render_tattoys(frame, std::cmp::Ordering::Greater)
     }
 
-    /// Render a tattoy onto the compositor frame.
+    /// Render a tattoy onto the compositor frame. Only the cells inside the tattoy's mask are
+    /// rendered.
     fn render_tattoys(
         &mut self,
         frame: &mut Vec<&mut [Cell]>,
         comparator: std::cmp::Ordering,
     ) -> Result<()> {
+        let opacity = 1.0 - self.tattoy_fade;
+        if self.are_tattoys_hidden || opacity <= 0.0 {
+            return Ok(());
+        }
+
         let mut tattoys: Vec<&mut crate::surface::Surface> = self
             .tattoys
             .values_mut()
             .filter(|tattoy| tattoy.layer.cmp(&0) == comparator)
+            .filter(|tattoy| !Self::is_too_small(self.width, self.height, tattoy))
             .collect();
         tattoys.sort_by_key(|tattoy| tattoy.layer);
 
         for tattoy in &mut tattoys {
             let tattoy_frame_size = tattoy.surface.dimensions();
+            let mask = tattoy.mask.as_ref();
             let tattoy_cells = tattoy.surface.screen_cells();
 
             for y in 0..self.height {
                 for x in 0..self.width {
-                    if usize::from(x) < tattoy_frame_size.0 && usize::from(y) < tattoy_frame_size.1
-                    {
-                        Self::composite_cell(frame, &tattoy_cells, x.into(), y.into())?;
+                    let (column, row) = (usize::from(x), usize::from(y));
+                    let is_masked =
+                        mask.is_some_and(|tattoy_mask| !tattoy_mask.contains(column, row));
+                    if column < tattoy_frame_size.0 && row < tattoy_frame_size.1 && !is_masked {
+                        Self::composite_cell(frame, &tattoy_cells, column, row, opacity)?;
                     }
                 }
             }
@@ -352,6 +760,32 @@ impl Renderer {
         Ok(())
     }
 
+    /// Whether the terminal is too small for the tattoy. Such tattoys aren't rendered until the
+    /// terminal is resized to be big enough again.
+    const fn is_too_small(width: u16, height: u16, tattoy: &crate::surface::Surface) -> bool {
+        width < tattoy.min_size.0 || height < tattoy.min_size.1
+    }
+
+    /// Let the user know that some tattoys are hidden because the terminal is too small for them.
+    fn render_too_small_indicator(&self, frame: &mut [&mut [Cell]]) {
+        let is_any_too_small = self
+            .tattoys
+            .values()
+            .any(|tattoy| Self::is_too_small(self.width, self.height, tattoy));
+        if !is_any_too_small {
+            return;
+        }
+
+        let Some(cell) = frame
+            .first_mut()
+            .and_then(|line| line.get_mut(usize::from(self.width).saturating_sub(1)))
+        else {
+            return;
+        };
+        let attributes = cell.attrs().clone();
+        *cell = Cell::new(TOO_SMALL_INDICATOR, attributes);
+    }
+
     /// Render the PTY to the compositor frame.
     fn render_pty(&mut self, frame: &mut Vec<&mut [Cell]>) -> Result<()> {
         let pty_frame_size = self.pty.dimensions();
@@ -360,7 +794,7 @@ impl Renderer {
         for y in 0..self.height {
             for x in 0..self.width {
                 if usize::from(x) < pty_frame_size.0 && usize::from(y) < pty_frame_size.1 {
-                    Self::composite_cell(frame, &pty_cells, x.into(), y.into())?;
+                    Self::composite_cell(frame, &pty_cells, x.into(), y.into(), 1.0)?;
                 }
             }
         }
@@ -382,12 +816,14 @@ impl Renderer {
         });
     }
 
-    /// Add a single cell to the compositor frame.
+    /// Add a single cell to the compositor frame. Cells that aren't fully opaque are made more
+    /// transparent, and only have their text shown when they're mostly opaque.
     fn composite_cell(
         base: &mut Vec<&mut [Cell]>,
         frame: &[&mut [Cell]],
         x: usize,
         y: usize,
+        opacity: f32,
     ) -> Result<()> {
         let composited_cell = base
             .get_mut(y)
@@ -399,9 +835,17 @@ impl Renderer {
             .context(format!("No y coord ({y}) for cell"))?
             .get(x)
             .context(format!("No x coord ({x}) for cell"))?;
+        let faded_cell;
+        let cell_above = if opacity < 1.0 {
+            faded_cell = Self::fade_cell(cell_above, opacity);
+            &faded_cell
+        } else {
+            cell_above
+        };
 
         let character_above = cell_above.str().to_owned();
-        let is_character_above_text = !character_above.is_empty() && character_above != " ";
+        let is_character_above_text =
+            !character_above.is_empty() && character_above != " " && opacity >= 0.5;
         if is_character_above_text {
             let old_background = composited_cell.attrs().background();
             let old_foreground = composited_cell.attrs().foreground();
@@ -416,6 +860,27 @@ impl Renderer {
         Ok(())
     }
 
+    /// A copy of the cell with its colours made more transparent.
+    fn fade_cell(cell: &Cell, opacity: f32) -> Cell {
+        let fade = |colour: termwiz::color::ColorAttribute| {
+            crate::opaque_cell::OpaqueCell::extract_colour(colour).map_or(
+                colour,
+                |termwiz::color::SrgbaTuple(red, green, blue, alpha)| {
+                    termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(
+                        termwiz::color::SrgbaTuple(red, green, blue, alpha * opacity),
+                    )
+                },
+            )
+        };
+
+        let mut faded = cell.clone();
+        let foreground = fade(cell.attrs().foreground());
+        let background = fade(cell.attrs().background());
+        faded.attrs_mut().set_foreground(foreground);
+        faded.attrs_mut().set_background(background);
+        faded
+    }
+
     /// Apply colour changes, like saturation, hue, contrast, etc.
     //
     // TODO: consider including this in the final compositing layer, just for the performance
@@ -424,8 +889,10 @@ impl Renderer {
         let config = self.state.config.read().await;
 
         let saturation: f64 = config.color.saturation.into();
-        let light: f64 = config.color.brightness.into();
+        let light: f64 = (config.color.brightness + self.idle_dimming).into();
         let hue: f64 = config.color.hue.into();
+        let deficiency = config.color.vision_deficiency;
+        let vision_mode = config.color.vision_mode;
         drop(config);
 
         for line in &mut frame.iter_mut() {
@@ -437,6 +904,7 @@ impl Renderer {
                     gradable = gradable.saturate(saturation);
                     gradable = gradable.lighten(light);
                     gradable = gradable.adjust_hue_fixed(hue);
+                    gradable = crate::colour_vision::filter(gradable, deficiency, vision_mode);
                     cell.attrs_mut().set_foreground(
                         termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(gradable),
                     );
@@ -449,6 +917,7 @@ impl Renderer {
                     gradable = gradable.saturate(saturation);
                     gradable = gradable.lighten(light);
                     gradable = gradable.adjust_hue_fixed(hue);
+                    gradable = crate::colour_vision::filter(gradable, deficiency, vision_mode);
                     cell.attrs_mut().set_background(
                         termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(gradable),
                     );
@@ -458,11 +927,56 @@ impl Renderer {
 
         Ok(())
     }
+
+    /// Quantize the frame's colours for terminals that don't support true colour.
+    async fn quantize(&mut self, frame: &mut Vec<&mut [Cell]>) {
+        let configured = self.state.config.read().await.color_depth;
+        let detected = *self.state.terminal_colour_depth.read().await;
+        let depth = crate::colour_depth::ColourDepth::resolve(configured, detected);
+        if depth == crate::colour_depth::ColourDepth::TrueColour {
+            return;
+        }
+
+        let palette = self.palette.as_ref();
+        for line in &mut frame.iter_mut() {
+            for cell in line.iter_mut() {
+                let foreground = self
+                    .quantizer
+                    .quantize(cell.attrs().foreground(), depth, palette);
+                let background = self
+                    .quantizer
+                    .quantize(cell.attrs().background(), depth, palette);
+                cell.attrs_mut().set_foreground(foreground);
+                cell.attrs_mut().set_background(background);
+            }
+        }
+    }
+}
+
+/// How much to dim the terminal by, after it's been idle for the given time. It fades down to the
+/// full amount, rather than dimming all at once.
+fn idle_dimming(idle_for: std::time::Duration, config: &crate::config::IdleDimming) -> f32 {
+    if config.timeout == 0 {
+        return 0.0;
+    }
+
+    let timeout = std::time::Duration::from_secs(config.timeout);
+    let Some(dimming_for) = idle_for.checked_sub(timeout) else {
+        return 0.0;
+    };
+    let mut fade = crate::animation::Tween::new(
+        0.0,
+        config.brightness,
+        IDLE_DIMMING_FADE,
+        crate::animation::Easing::Linear,
+    );
+    fade.advance(dimming_for)
 }
 
 #[expect(
     clippy::indexing_slicing,
     clippy::unreadable_literal,
+    clippy::float_cmp,
     reason = "Tests aren't so strict"
 )]
 #[cfg(test)]
@@ -499,6 +1013,186 @@ mod test {
         cell.clone()
     }
 
+    #[tokio::test]
+    async fn rendering_a_frame_composites_the_pty_and_tattoys() {
+        let mut renderer = Renderer {
+            width: 2,
+            height: 1,
+            ..Renderer::default()
+        };
+        let mut pty = TermwizSurface::new(2, 1);
+        pty.add_change("ab");
+        *renderer.state.shadow_tty_screen.write().await = pty;
+
+        let mut tattoy = crate::surface::Surface::new("above".into(), 2, 1, 1);
+        tattoy.add_text(1, 0, "c".into(), None, None);
+        let updates = [FrameUpdate::PTYSurface, FrameUpdate::TattoySurface(tattoy)];
+
+        let mut frame = renderer.render_frame(updates).await.unwrap();
+        let cells = frame.screen_cells();
+        assert_eq!(cells[0][0].str(), "a");
+        assert_eq!(cells[0][1].str(), "c");
+    }
+
+    #[tokio::test]
+    async fn tattoys_are_hidden_whilst_the_terminal_is_too_small() {
+        let mut renderer = Renderer {
+            width: 3,
+            height: 1,
+            ..Renderer::default()
+        };
+        let mut tattoy = crate::surface::Surface::new("big".into(), 3, 1, 1);
+        tattoy.add_text(0, 0, "abc".into(), None, None);
+        tattoy.min_size = (4, 1);
+
+        let mut frame = renderer
+            .render_frame([FrameUpdate::TattoySurface(tattoy)])
+            .await
+            .unwrap();
+        let cells = frame.screen_cells();
+        assert_eq!(cells[0][0].str(), " ");
+        assert_eq!(cells[0][2].str(), TOO_SMALL_INDICATOR.to_string());
+
+        renderer.state.config.write().await.show_too_small_indicator = false;
+        let mut frame = renderer.render_frame([]).await.unwrap();
+        assert_eq!(frame.screen_cells()[0][2].str(), " ");
+
+        renderer.width = 4;
+        let mut frame = renderer.render_frame([]).await.unwrap();
+        assert_eq!(frame.screen_cells()[0][0].str(), "a");
+    }
+
+    #[tokio::test]
+    async fn pixel_shaders_are_the_final_pass() {
+        let mut renderer = Renderer {
+            width: 1,
+            height: 1,
+            ..Renderer::default()
+        };
+        renderer.pixel_shader = Some(crate::pixel_shader::PixelShader::new(|context| {
+            crate::pixel_shader::CellOutput {
+                foreground: Some(crate::surface::RED),
+                background: context.background,
+            }
+        }));
+        let mut tattoy = crate::surface::Surface::new("below".into(), 1, 1, -1);
+        tattoy.add_text(0, 0, "a".into(), None, Some(crate::surface::WHITE));
+
+        let mut frame = renderer
+            .render_frame([FrameUpdate::TattoySurface(tattoy)])
+            .await
+            .unwrap();
+        let cell = &frame.screen_cells()[0][0];
+        assert_eq!(cell.str(), "a");
+        assert_eq!(
+            cell.attrs().foreground(),
+            crate::surface::Surface::make_colour_attribute(crate::surface::RED)
+        );
+    }
+
+    #[tokio::test]
+    async fn composited_frames_are_shared_without_the_tattoys_above() {
+        let mut renderer = Renderer {
+            width: 2,
+            height: 1,
+            ..Renderer::default()
+        };
+        let mut pty = TermwizSurface::new(2, 1);
+        pty.add_change("ab");
+        *renderer.state.shadow_tty_screen.write().await = pty;
+        let mut tattoy = crate::surface::Surface::new("above".into(), 2, 1, 1);
+        tattoy.add_text(1, 0, "c".into(), None, None);
+        let updates = [FrameUpdate::PTYSurface, FrameUpdate::TattoySurface(tattoy)];
+
+        renderer.render_frame(updates).await.unwrap();
+        assert!(renderer.state.composited_frame.read().await.is_none());
+
+        renderer
+            .state
+            .is_composited_frame_wanted
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        renderer.render_frame(std::iter::empty()).await.unwrap();
+        let shared = renderer
+            .state
+            .composited_frame
+            .read()
+            .await
+            .clone()
+            .unwrap();
+        assert_eq!(shared[0][0].str(), "a");
+        assert_eq!(shared[0][1].str(), "b");
+    }
+
+    #[tokio::test]
+    async fn hidden_tattoys_arent_composited() {
+        let mut renderer = Renderer {
+            width: 1,
+            height: 1,
+            are_tattoys_hidden: true,
+            ..Renderer::default()
+        };
+        let mut tattoy = crate::surface::Surface::new("above".into(), 1, 1, 1);
+        tattoy.add_text(0, 0, "c".into(), None, None);
+
+        let mut frame = renderer
+            .render_frame([FrameUpdate::TattoySurface(tattoy)])
+            .await
+            .unwrap();
+        assert_eq!(frame.screen_cells()[0][0].str(), " ");
+    }
+
+    #[tokio::test]
+    async fn faded_tattoys_are_composited_more_transparently() {
+        let mut renderer = Renderer {
+            width: 1,
+            height: 1,
+            tattoy_fade: 0.75,
+            ..Renderer::default()
+        };
+        let mut tattoy = crate::surface::Surface::new("above".into(), 1, 1, 1);
+        tattoy.add_text(0, 0, "c".into(), Some(crate::surface::RED), None);
+        renderer.tattoys.insert(tattoy.id.clone(), tattoy);
+
+        let mut frame = renderer.render_frame(std::iter::empty()).await.unwrap();
+        let cell = frame.screen_cells()[0][0].clone();
+        assert_eq!(cell.str(), " ");
+        assert_ne!(
+            cell.attrs().background(),
+            termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(
+                termwiz::color::SrgbaTuple(1.0, 0.0, 0.0, 1.0)
+            )
+        );
+
+        renderer.tattoy_fade = 1.0;
+        let mut frame = renderer.render_frame(std::iter::empty()).await.unwrap();
+        assert_eq!(
+            frame.screen_cells()[0][0].attrs().background(),
+            termwiz::color::ColorAttribute::Default
+        );
+    }
+
+    #[test]
+    fn idle_dimming_fades_in_after_the_timeout() {
+        let config = crate::config::IdleDimming {
+            timeout: 10,
+            brightness: -0.3,
+        };
+        let dimming =
+            |seconds: f32| idle_dimming(std::time::Duration::from_secs_f32(seconds), &config);
+        assert_eq!(dimming(9.0), 0.0);
+        assert!((dimming(11.5) - -0.15).abs() < 0.001);
+        assert_eq!(dimming(60.0), -0.3);
+
+        let disabled = crate::config::IdleDimming {
+            timeout: 0,
+            ..config
+        };
+        assert_eq!(
+            idle_dimming(std::time::Duration::from_secs(60), &disabled),
+            0.0
+        );
+    }
+
     #[tokio::test]
     async fn blending_text() {
         let mut renderer = Renderer {
@@ -18,7 +18,8 @@ use crate::shared_state::SharedState;
 //
 /// There a are 2 "screens" or "surfaces" to manage in Tattoy. The fancy special affects screen
 /// and the traditional PTY.
-pub(crate) enum FrameUpdate {
+#[non_exhaustive]
+pub enum FrameUpdate {
     /// A frame of a tattoy TTY screen
     TattoySurface(crate::surface::Surface),
     /// A frame of a PTY terminal has been updated in the shared state
@@ -28,7 +29,7 @@ pub(crate) enum FrameUpdate {
 /// Commands to control the various tasks/threads
 #[non_exhaustive]
 #[derive(Clone, Debug)]
-pub(crate) enum Protocol {
+pub enum Protocol {
     /// Output from the PTY.
     Output(shadow_terminal::output::Output),
     /// The entire application is exiting.
@@ -46,6 +47,32 @@ pub(crate) enum Protocol {
     CursorVisibility(bool),
     /// Tattoy's configuration.
     Config(crate::config::Config),
+    /// Reload the config and palette files now, rather than waiting for the file watcher, which
+    /// isn't reliable on some filesystems.
+    ReloadConfig,
+    /// The result of a `ReloadConfig` or `SwitchProfile`. Also sent with the error when the config
+    /// file changed but couldn't be loaded. Failures are shown to the user in a notice.
+    ConfigReloaded(std::result::Result<(), String>),
+    /// Reload the config with the named profile, from the config directory's `profiles` directory,
+    /// laid over the main config. The current profile is kept if the new one doesn't load.
+    SwitchProfile(String),
+    /// The terminal's palette was reloaded. Any colours that were resolved from the old palette
+    /// are stale, so they need resolving again.
+    PaletteChanged,
+    /// Hide all the tattoys if they're shown, or show them if they're hidden.
+    ToggleTattoys,
+    /// The application running in the PTY rang the bell.
+    Bell,
+    /// The PTY switched to, `true`, or back from, `false`, the alternate screen. Full screen
+    /// applications, like Vim, use the alternate screen.
+    AlternateScreen(bool),
+    /// Shake the whole terminal.
+    Shake {
+        /// The furthest, in cells, that the terminal is shaken.
+        intensity: f32,
+        /// How long the shake lasts.
+        duration: std::time::Duration,
+    },
 }
 
 // TODO:
@@ -53,9 +80,34 @@ pub(crate) enum Protocol {
 // return its error, and then check them all at the end?
 //
 /// Main entrypoint
-pub(crate) async fn run(state_arc: &std::sync::Arc<SharedState>) -> Result<()> {
+pub(crate) async fn run(
+    state_arc: &std::sync::Arc<SharedState>,
+    mut registry: crate::loader::TattoyRegistry,
+) -> Result<()> {
     let cli_args = setup(state_arc).await?;
 
+    if cli_args.validate {
+        return validate_setup(state_arc, &registry, &cli_args.enabled_tattoys).await;
+    }
+
+    if cli_args.list_tattoys {
+        list_tattoys(&registry);
+        return Ok(());
+    }
+
+    if let Some(recording) = cli_args.export.clone() {
+        let options = crate::export::Options {
+            recording,
+            output: cli_args.export_to.clone(),
+            frame_rate: cli_args.export_frame_rate,
+            duration: cli_args.export_duration,
+            columns: cli_args.export_columns,
+            rows: cli_args.export_rows,
+        };
+        return crate::export::export(state_arc, registry, cli_args.enabled_tattoys, &options)
+            .await;
+    }
+
     if cli_args.capture_palette {
         crate::palette::parser::Parser::run(state_arc, None).await?;
         return Ok(());
@@ -68,26 +120,48 @@ pub(crate) async fn run(state_arc: &std::sync::Arc<SharedState>) -> Result<()> {
 
     let (protocol_tx, _) = tokio::sync::broadcast::channel(1024);
 
-    let (renderer, surfaces_tx) = Renderer::start(Arc::clone(state_arc), protocol_tx.clone());
+    let frame_rate = state_arc.config.read().await.frame_rate;
+    let frame_sink = cli_args
+        .frame_sink
+        .as_ref()
+        .map(|path| crate::frame_sink::FrameSink::open(path, cli_args.frame_format, frame_rate));
+    state_arc
+        .is_tui_started
+        .store(true, std::sync::atomic::Ordering::Release);
+    let (renderer, surfaces_tx) = Renderer::start(
+        Arc::clone(state_arc),
+        protocol_tx.clone(),
+        frame_sink,
+        registry.take_pixel_shader(),
+    );
 
     let config_handle = crate::config::Config::watch(Arc::clone(state_arc), protocol_tx.clone());
+    #[cfg(unix)]
+    crate::signals::start(protocol_tx.clone())?;
     let input_thread_handle = Input::start(protocol_tx.clone());
     let tattoys_handle = crate::loader::start_tattoys(
+        registry,
         cli_args.enabled_tattoys.clone(),
         protocol_tx.clone(),
         surfaces_tx.clone(),
         Arc::clone(state_arc),
+        None,
     );
 
+    let pty_log_config = state_arc.config.read().await.pty_log.clone();
+    let (pty_log_tx, pty_log_handle) = crate::pty_log::start(&pty_log_config)?.unzip();
+
     let users_tty_size = crate::renderer::Renderer::get_users_tty_size()?;
+    let status_rows = state_arc.config.read().await.status_rows;
     crate::terminal_proxy::TerminalProxy::start(
         state_arc,
         surfaces_tx,
         protocol_tx.clone(),
         shadow_terminal::shadow_terminal::Config {
             width: users_tty_size.cols.try_into()?,
-            height: users_tty_size.rows.try_into()?,
+            height: crate::config::Config::pty_height(users_tty_size.rows.try_into()?, status_rows),
             command: get_startup_command(state_arc, cli_args).await?,
+            tee_tx: pty_log_tx,
             ..Default::default()
         },
     )
@@ -115,6 +189,12 @@ pub(crate) async fn run(state_arc: &std::sync::Arc<SharedState>) -> Result<()> {
     renderer.await??;
     tracing::trace!("Left renderer task 🟢");
 
+    if let Some(handle) = pty_log_handle {
+        tracing::trace!("Awaiting PTY log task 🔴");
+        handle.await??;
+        tracing::trace!("Left PTY log task 🟢");
+    }
+
     tracing::trace!("Awaiting config watcher task 🔴");
     config_handle.await??;
     tracing::trace!("Left config watcher task 🟢");
@@ -123,6 +203,30 @@ pub(crate) async fn run(state_arc: &std::sync::Arc<SharedState>) -> Result<()> {
     Ok(())
 }
 
+/// Check the user's setup and report the results, without starting the terminal.
+#[expect(clippy::print_stdout, reason = "The report is for the user")]
+async fn validate_setup(
+    state: &std::sync::Arc<SharedState>,
+    registry: &crate::loader::TattoyRegistry,
+    enabled_tattoys: &[String],
+) -> Result<()> {
+    let report = crate::config::Config::validate_setup(state, registry, enabled_tattoys).await;
+    if !report.is_ok() {
+        color_eyre::eyre::bail!("Tattoy's setup isn't valid:\n{report}");
+    }
+
+    println!("{report}");
+    Ok(())
+}
+
+/// Print all the tattoys that can be used.
+#[expect(clippy::print_stdout, reason = "The list is for the user")]
+fn list_tattoys(registry: &crate::loader::TattoyRegistry) {
+    for info in registry.describe_tattoys() {
+        println!("{info}");
+    }
+}
+
 /// Get the command that Tattoy will use to startup, usually something like `bash`.
 async fn get_startup_command(
     state: &std::sync::Arc<SharedState>,
@@ -163,12 +267,22 @@ async fn setup(state: &std::sync::Arc<SharedState>) -> Result<CliArgs> {
     let mut main_config_file = state.main_config_file.write().await;
     (*main_config_file).clone_from(&cli_args.main_config);
     drop(main_config_file);
+    (*state.profile.write().await).clone_from(&cli_args.profile);
 
     crate::config::Config::setup_directory(cli_args.config_dir.clone(), state).await?;
+    if cli_args.validate {
+        // Validation loads the config itself, so that it can report any problems with it.
+        return Ok(cli_args);
+    }
     crate::config::Config::load_config_into_shared_state(state).await?;
 
     setup_logging(cli_args.clone(), state).await?;
 
+    // This has to be detected before `COLORTERM` is overridden.
+    let colour_depth = crate::colour_depth::ColourDepth::detect();
+    tracing::debug!("Detected the terminal's colour depth as: {colour_depth:?}");
+    *state.terminal_colour_depth.write().await = colour_depth;
+
     // Assuming true colour makes Tattoy simpler.
     // * I think it's safe to assume that the vast majority of people using Tattoy will have a
     //   true color terminal anyway.
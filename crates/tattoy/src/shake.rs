@@ -0,0 +1,142 @@
+//! Shake the whole terminal, for example when the bell rings. Every cell of the composited frame
+//! is offset by a random amount, which decays over the duration of the shake.
+
+use rand::Rng as _;
+use termwiz::cell::Cell;
+
+/// The user's config for shaking the terminal.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Shake the terminal whenever the bell rings.
+    pub on_bell: bool,
+    /// The furthest, in cells, that the terminal is shaken.
+    pub intensity: f32,
+    /// How long, in seconds, the shake lasts.
+    pub duration: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            on_bell: false,
+            intensity: 1.0,
+            duration: 0.3,
+        }
+    }
+}
+
+/// A single shake of the terminal.
+pub(crate) struct Shake {
+    /// How far, in cells, the terminal can currently be shaken. It decays to nothing.
+    amplitude: crate::animation::Tween<f32>,
+    /// When the previous offset was made.
+    last_tick: std::time::Instant,
+    /// The source of the shake's randomness.
+    rng: rand::rngs::StdRng,
+}
+
+impl Shake {
+    /// Start a new shake.
+    pub fn new(intensity: f32, duration: std::time::Duration, rng: rand::rngs::StdRng) -> Self {
+        Self {
+            amplitude: crate::animation::Tween::new(
+                intensity.max(0.0),
+                0.0,
+                duration,
+                crate::animation::Easing::Linear,
+            ),
+            last_tick: std::time::Instant::now(),
+            rng,
+        }
+    }
+
+    /// Has the shake finished?
+    pub fn is_finished(&self) -> bool {
+        self.amplitude.is_finished()
+    }
+
+    /// A random offset, in columns and rows, for the current frame.
+    pub fn offset(&mut self) -> (isize, isize) {
+        let now = std::time::Instant::now();
+        let amplitude = self
+            .amplitude
+            .advance(now.saturating_duration_since(self.last_tick));
+        self.last_tick = now;
+        offset(&mut self.rng, amplitude)
+    }
+}
+
+/// A random offset, in columns and rows, no further than the amplitude.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    reason = "Shakes are only ever a few cells"
+)]
+fn offset(rng: &mut rand::rngs::StdRng, amplitude: f32) -> (isize, isize) {
+    let amount = amplitude.max(0.0).round() as isize;
+    if amount == 0 {
+        return (0, 0);
+    }
+    (
+        rng.gen_range(-amount..=amount),
+        rng.gen_range(-amount..=amount),
+    )
+}
+
+/// Move every cell of the frame by the offset. Cells that are moved in from beyond the edges of
+/// the frame are blank.
+pub(crate) fn shift_cells(frame: &mut [&mut [Cell]], offset: (isize, isize)) {
+    if offset == (0, 0) {
+        return;
+    }
+
+    let original: Vec<Vec<Cell>> = frame.iter().map(|line| line.to_vec()).collect();
+    for (y, line) in frame.iter_mut().enumerate() {
+        for (x, cell) in line.iter_mut().enumerate() {
+            let source = x
+                .checked_add_signed(-offset.0)
+                .zip(y.checked_add_signed(-offset.1))
+                .and_then(|(source_x, source_y)| original.get(source_y)?.get(source_x));
+            *cell = source.cloned().unwrap_or_default();
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::indexing_slicing, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offsets_are_within_the_amplitude() {
+        let mut rng = rand::SeedableRng::seed_from_u64(0);
+        assert_eq!(offset(&mut rng, 0.0), (0, 0));
+        assert_eq!(offset(&mut rng, -1.0), (0, 0));
+        for _ in 0..10 {
+            let (x, y) = offset(&mut rng, 1.5);
+            assert!(x.abs() <= 2 && y.abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn shakes_finish() {
+        let rng = rand::SeedableRng::seed_from_u64(0);
+        let mut shake = Shake::new(3.0, std::time::Duration::ZERO, rng);
+        assert_eq!(shake.offset(), (0, 0));
+        assert!(shake.is_finished());
+    }
+
+    #[test]
+    fn shifting_fills_edges_with_blanks() {
+        let mut surface = termwiz::surface::Surface::new(3, 2);
+        surface.add_change("abc");
+        let mut frame = surface.screen_cells();
+        shift_cells(&mut frame, (1, 1));
+
+        assert_eq!(frame[0][0].str(), " ");
+        assert_eq!(frame[1][0].str(), " ");
+        assert_eq!(frame[1][1].str(), "a");
+        assert_eq!(frame[1][2].str(), "b");
+    }
+}
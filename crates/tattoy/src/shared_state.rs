@@ -23,11 +23,13 @@ pub struct TTYSize {
 /// All the shared data the app uses
 #[derive(Default)]
 #[non_exhaustive]
-pub(crate) struct SharedState {
+pub struct SharedState {
     /// Location of the config directory.
     pub config_path: tokio::sync::RwLock<std::path::PathBuf>,
     /// Name of the main config file.
     pub main_config_file: tokio::sync::RwLock<std::path::PathBuf>,
+    /// The config profile that's laid over the main config, if there is one.
+    pub profile: tokio::sync::RwLock<Option<String>>,
     /// User config
     pub config: tokio::sync::RwLock<crate::config::Config>,
     /// Just the size of the user's terminal. All the tattoys and shadow TTY should follow this
@@ -53,10 +55,31 @@ pub(crate) struct SharedState {
     pub pty_sequence: tokio::sync::RwLock<usize>,
     /// Is the application logging?
     pub is_logging: tokio::sync::RwLock<bool>,
+    /// The last time that there was any input or output. `None` until the first check. It's what
+    /// idle effects, like dimming and the screensaver, measure idleness from.
+    pub last_activity: tokio::sync::RwLock<Option<std::time::Instant>>,
+    /// The colour depth of the user's terminal, detected when Tattoy starts.
+    pub terminal_colour_depth: tokio::sync::RwLock<crate::colour_depth::ColourDepth>,
+    /// The latest composited frame, for tattoys that react to what's on the screen. It's only
+    /// kept up to date once a tattoy has asked for it, see `is_composited_frame_wanted`.
+    pub composited_frame: tokio::sync::RwLock<Option<Arc<crate::renderer::CompositedFrame>>>,
+    /// Whether any tattoy has asked for the composited frame. Copying every frame isn't free, so
+    /// it's only done when it's wanted.
+    pub is_composited_frame_wanted: std::sync::atomic::AtomicBool,
+    /// Whether a tattoy draws its own cursor, in which case the user's terminal's own cursor is
+    /// kept hidden.
+    pub is_native_cursor_replaced: std::sync::atomic::AtomicBool,
+    /// Whether Tattoy has taken over the user's terminal. Commands like `--validate` exit without
+    /// ever starting it, so there's no screen to reset.
+    pub is_tui_started: std::sync::atomic::AtomicBool,
 }
 
 impl SharedState {
     /// Initialise the shared state
+    ///
+    /// # Errors
+    /// When the size of the user's terminal can't be found.
+    #[inline]
     pub async fn init() -> Result<Arc<Self>> {
         let tty_size = Renderer::get_users_tty_size()?;
         let state = Self::default();
@@ -68,38 +91,57 @@ impl SharedState {
     }
 
     /// Get a read lock and return the current TTY size
+    #[inline]
     pub async fn get_tty_size(&self) -> TTYSize {
         let tty_size = self.tty_size.read().await;
         *tty_size
     }
 
     /// Get a write lock and set the a new TTY size
+    #[inline]
     pub async fn set_tty_size(&self, width: u16, height: u16) {
         let mut tty_size = self.tty_size.write().await;
         *tty_size = TTYSize { width, height };
     }
 
+    /// Derive a random number generator for a tattoy. It's seeded from the user's `random_seed`
+    /// and the tattoy's ID. Deriving from the ID, rather than drawing from one shared generator,
+    /// means that the order in which tattoys start, which isn't deterministic, doesn't change
+    /// the numbers that they get.
+    #[inline]
+    pub async fn tattoy_rng(&self, id: &str) -> rand::rngs::StdRng {
+        let seed = self.config.read().await.random_seed;
+        crate::utils::seeded_rng(seed, id)
+    }
+
     /// Get a read lock and return whether the user is currently scrolling.
+    #[inline]
     pub async fn get_is_scrolling(&self) -> bool {
         let is_scrolling = self.is_scrolling.read().await;
         *is_scrolling
     }
 
     /// Get a write lock and set the scrolling state.
+    #[inline]
     pub async fn set_is_scrolling(&self, value: bool) {
         let mut is_scrolling = self.is_scrolling.write().await;
         *is_scrolling = value;
     }
 
     /// Get a read lock and return whether the alternate screen is currently active.
+    #[inline]
     pub async fn get_is_alternate_screen(&self) -> bool {
         let is_alternate_screen = self.is_alternate_screen.read().await;
         *is_alternate_screen
     }
 
-    /// Get a write lock and set whether the alternate screen is active or not.
-    pub async fn set_is_alternate_screen(&self, value: bool) {
+    /// Get a write lock and set whether the alternate screen is active or not. Returns whether
+    /// it changed.
+    #[inline]
+    pub async fn set_is_alternate_screen(&self, value: bool) -> bool {
         let mut is_alternate_screen = self.is_alternate_screen.write().await;
+        let is_changed = *is_alternate_screen != value;
         *is_alternate_screen = value;
+        is_changed
     }
 }
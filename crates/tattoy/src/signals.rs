@@ -0,0 +1,54 @@
+//! Control Tattoy with Unix signals, for scripting and daemon-like usage.
+//!
+//! * `SIGUSR1` reloads the config and palette.
+//! * `SIGUSR2` toggles all the tattoys on and off.
+//!
+//! `SIGHUP` isn't used, because it means that the user's terminal hung up, which should still
+//! end Tattoy as normal.
+
+use color_eyre::eyre::Result;
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Listen for signals and send the protocol message for each one, until Tattoy exits.
+pub(crate) fn start(
+    protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+) -> Result<tokio::task::JoinHandle<()>> {
+    let mut user_defined_1 = signal(SignalKind::user_defined1())?;
+    let mut user_defined_2 = signal(SignalKind::user_defined2())?;
+    let mut protocol_rx = protocol_tx.subscribe();
+
+    Ok(tokio::spawn(async move {
+        #[expect(
+            clippy::integer_division_remainder_used,
+            reason = "This is caused by the `tokio::select!`"
+        )]
+        loop {
+            tokio::select! {
+                Some(()) = user_defined_1.recv() => {
+                    tracing::debug!("Received SIGUSR1, reloading the config");
+                    send(&protocol_tx, crate::run::Protocol::ReloadConfig);
+                }
+                Some(()) = user_defined_2.recv() => {
+                    tracing::debug!("Received SIGUSR2, toggling the tattoys");
+                    send(&protocol_tx, crate::run::Protocol::ToggleTattoys);
+                }
+                Ok(message) = protocol_rx.recv() => {
+                    if matches!(message, crate::run::Protocol::End) {
+                        break;
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// Send a protocol message on behalf of a signal.
+fn send(
+    protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+    message: crate::run::Protocol,
+) {
+    let result = protocol_tx.send(message);
+    if let Err(error) = result {
+        tracing::error!("Couldn't send protocol message for signal: {error:?}");
+    }
+}
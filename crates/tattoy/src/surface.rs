@@ -7,7 +7,7 @@ use termwiz::surface::Change as TermwizChange;
 use termwiz::surface::Position as TermwizPosition;
 
 /// An RGB colour
-pub(crate) type Colour = (f32, f32, f32, f32);
+pub type Colour = (f32, f32, f32, f32);
 
 /// A default pure white.
 pub const WHITE: Colour = (1.0, 1.0, 1.0, 1.0);
@@ -20,7 +20,8 @@ pub const RED: Colour = (1.0, 0.0, 0.0, 1.0);
 
 /// `Surface`
 #[derive(Clone)]
-pub(crate) struct Surface {
+#[non_exhaustive]
+pub struct Surface {
     /// The unique ID of the tattoy to which this surface belongs.
     pub id: String,
     /// The terminal's width
@@ -33,11 +34,16 @@ pub(crate) struct Surface {
     pub layer: i16,
     /// A surface of terminal cells
     pub surface: termwiz::surface::Surface,
+    /// The only cells that the compositor renders from this surface.
+    pub mask: Option<crate::mask::Mask>,
+    /// The smallest terminal, in columns and rows, that the compositor renders this surface to.
+    pub min_size: (u16, u16),
 }
 
 impl Surface {
     /// Create a Compositor/Tattoy
     #[must_use]
+    #[inline]
     pub fn new(id: String, width: usize, height: usize, layer: i16) -> Self {
         Self {
             id,
@@ -45,10 +51,16 @@ impl Surface {
             height,
             layer,
             surface: termwiz::surface::Surface::new(width, height),
+            mask: None,
+            min_size: (1, 1),
         }
     }
 
     /// Add a pixel ("▀", "▄") to a tattoy surface
+    ///
+    /// # Errors
+    /// When the pixel is outside of the surface.
+    #[inline]
     pub fn add_pixel(&mut self, x: usize, y: usize, colour: Colour) -> Result<()> {
         let (col, row) = self.coords_to_tty(x, y)?;
         self.surface.add_change(TermwizChange::CursorPosition {
@@ -88,6 +100,7 @@ impl Surface {
     }
 
     /// Overlay text at a given coord with the given colours.
+    #[inline]
     pub fn add_text(
         &mut self,
         x: usize,
@@ -117,6 +130,7 @@ impl Surface {
 
     /// Make a Termwiz colour attribute
     #[must_use]
+    #[inline]
     pub const fn make_colour_attribute(colour: Colour) -> termwiz::color::ColorAttribute {
         termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(termwiz::color::SrgbaTuple(
             colour.0, colour.1, colour.2, colour.3,
@@ -125,6 +139,7 @@ impl Surface {
 
     /// Make a Termwiz background colour
     #[must_use]
+    #[inline]
     pub const fn make_bg_colour(colour: Colour) -> TermwizChange {
         let colour_attribute = Self::make_colour_attribute(colour);
         TermwizChange::Attribute(termwiz::cell::AttributeChange::Background(colour_attribute))
@@ -134,6 +149,7 @@ impl Surface {
     /// terminal displays when nothing else has been set. It's often what's used on a GUI terminal
     /// to make it's background transparent.
     #[must_use]
+    #[inline]
     pub const fn make_default_bg_colour() -> TermwizChange {
         let colour_attribute = termwiz::color::ColorAttribute::Default;
         TermwizChange::Attribute(termwiz::cell::AttributeChange::Background(colour_attribute))
@@ -141,6 +157,7 @@ impl Surface {
 
     /// Make a Termwiz background colour
     #[must_use]
+    #[inline]
     pub const fn make_fg_colour(colour: Colour) -> TermwizChange {
         let colour_attribute = Self::make_colour_attribute(colour);
         TermwizChange::Attribute(termwiz::cell::AttributeChange::Foreground(colour_attribute))
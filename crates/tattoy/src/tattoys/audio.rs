@@ -0,0 +1,427 @@
+//! A music visualiser, driven by the level of the system's audio input.
+//!
+//! Audio is captured on its own thread, which measures the loudness of each buffer of samples
+//! and shares it with the tattoy. The tattoy smooths the level and looks for beats, which are
+//! sudden jumps above the recent average, then draws either scrolling bars or a background that
+//! pulses to the beat. If there's no audio input device then the tattoy just logs a warning and
+//! stops.
+//!
+//! Audio capture is only available when Tattoy is built with the `audio` feature.
+
+/// How the audio is visualised.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Visualisation {
+    /// Bars along the bottom of the terminal, one per column, that scroll from right to left.
+    #[default]
+    Bars,
+    /// The whole background pulses with the beat.
+    Pulse,
+}
+
+/// All the user config for the audio tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+#[cfg_attr(
+    not(feature = "audio"),
+    expect(dead_code, reason = "Only the `audio` feature captures audio")
+)]
+pub(crate) struct Config {
+    /// Enable/disable the audio visualiser
+    pub enabled: bool,
+    /// How the audio is visualised.
+    pub visualisation: Visualisation,
+    /// The colour of the visualisation, as red, green, blue and alpha, each from 0.0 to 1.0.
+    pub colour: crate::surface::Colour,
+    /// How much to amplify the audio level by. Microphones are usually quite quiet.
+    pub sensitivity: f32,
+    /// The compositing layer. Negative values are below the terminal's text, positive values are
+    /// above it.
+    pub layer: i16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            visualisation: Visualisation::default(),
+            colour: (0.2, 0.6, 1.0, 0.6),
+            sensitivity: 4.0,
+            layer: -10,
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+pub(crate) use host::Audio;
+
+/// The audio capture and visualiser, which need the `cpal` dependency.
+#[cfg(feature = "audio")]
+mod host {
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use color_eyre::eyre::{ContextCompat as _, Result};
+    use cpal::traits::{DeviceTrait as _, HostTrait as _, StreamTrait as _};
+
+    use super::{Config, Visualisation};
+    use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+    /// How long, in seconds, the level takes to fall most of the way back down after a peak.
+    const RELEASE: f32 = 0.3;
+
+    /// How long, in seconds, the average level that beats are compared to covers.
+    const AVERAGE_WINDOW: f32 = 1.0;
+
+    /// How many times louder than the average the audio has to be to count as a beat.
+    const BEAT_THRESHOLD: f32 = 1.5;
+
+    /// The quietest level that can count as a beat, so that background noise doesn't.
+    const MINIMUM_BEAT: f32 = 0.1;
+
+    /// How long, in seconds, a beat takes to fade away.
+    const BEAT_DECAY: f32 = 0.15;
+
+    /// How often the capture thread checks whether it should stop.
+    const STOP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+    /// The audio level, smoothed, and the beats found in it.
+    #[derive(Debug, Default)]
+    struct Meter {
+        /// The smoothed level, from 0.0 to 1.0. It rises straight away but falls slowly.
+        level: f32,
+        /// The average level over the recent past.
+        average: f32,
+        /// How strong the latest beat still is, from 1.0 when it hits, fading to 0.0.
+        beat: f32,
+    }
+
+    impl Meter {
+        /// Update the meter with the latest raw level from the capture thread.
+        fn update(&mut self, raw: f32, delta: std::time::Duration, sensitivity: f32) {
+            let amplified = (raw * sensitivity).clamp(0.0, 1.0);
+            let seconds = delta.as_secs_f32();
+
+            let release = (-seconds / RELEASE).exp();
+            self.level = if amplified > self.level {
+                amplified
+            } else {
+                self.level.mul_add(release, amplified * (1.0 - release))
+            };
+
+            let is_beat = amplified >= MINIMUM_BEAT && amplified > self.average * BEAT_THRESHOLD;
+            self.beat = if is_beat {
+                1.0
+            } else {
+                self.beat * (-seconds / BEAT_DECAY).exp()
+            };
+
+            let catch_up = (seconds / AVERAGE_WINDOW).min(1.0);
+            self.average = (amplified - self.average).mul_add(catch_up, self.average);
+        }
+    }
+
+    /// The thread that captures audio. Capturing stops when this is dropped.
+    struct Capture {
+        /// The loudness of the latest buffer of samples, as the bits of an `f32`.
+        level: Arc<AtomicU32>,
+        /// Tells the capture thread to stop.
+        is_stopped: Arc<AtomicBool>,
+        /// The capture thread.
+        thread: std::thread::JoinHandle<()>,
+    }
+
+    impl Capture {
+        /// Start capturing from the default audio input device. Fails if there isn't one, or it
+        /// can't be opened.
+        async fn start() -> Result<Self> {
+            let level = Arc::new(AtomicU32::new(0.0_f32.to_bits()));
+            let is_stopped = Arc::new(AtomicBool::new(false));
+            let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<Result<()>>();
+
+            let thread_level = Arc::clone(&level);
+            let thread_is_stopped = Arc::clone(&is_stopped);
+            let thread = std::thread::spawn(move || {
+                // Streams can't always be sent between threads, so it's made and kept here.
+                let (maybe_stream, report) = match open_stream(thread_level) {
+                    Ok(stream) => (Some(stream), Ok(())),
+                    Err(error) => (None, Err(error)),
+                };
+                if ready_tx.send(report).is_err() {
+                    tracing::error!("Audio visualiser stopped before capture started");
+                }
+                let Some(stream) = maybe_stream else {
+                    return;
+                };
+
+                while !thread_is_stopped.load(Ordering::Relaxed) {
+                    std::thread::park_timeout(STOP_CHECK_INTERVAL);
+                }
+                drop(stream);
+                tracing::debug!("Audio capture stopped");
+            });
+
+            ready_rx.await??;
+            Ok(Self {
+                level,
+                is_stopped,
+                thread,
+            })
+        }
+
+        /// The loudness of the latest buffer of samples.
+        fn level(&self) -> f32 {
+            f32::from_bits(self.level.load(Ordering::Relaxed))
+        }
+    }
+
+    impl Drop for Capture {
+        fn drop(&mut self) {
+            self.is_stopped.store(true, Ordering::Relaxed);
+            self.thread.thread().unpark();
+        }
+    }
+
+    /// Open and start a stream from the default audio input device.
+    fn open_stream(level: Arc<AtomicU32>) -> Result<cpal::Stream> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .context("No audio input device")?;
+        let supported = device.default_input_config()?;
+        let sample_format = supported.sample_format();
+        let config = supported.config();
+        tracing::debug!(
+            "Capturing audio from {:?}: {config:?} {sample_format:?}",
+            device.name()
+        );
+
+        let stream = if sample_format == cpal::SampleFormat::F32 {
+            build_stream::<f32>(&device, &config, level)?
+        } else if sample_format == cpal::SampleFormat::I16 {
+            build_stream::<i16>(&device, &config, level)?
+        } else if sample_format == cpal::SampleFormat::U16 {
+            build_stream::<u16>(&device, &config, level)?
+        } else {
+            color_eyre::eyre::bail!("Unsupported audio sample format: {sample_format:?}");
+        };
+        stream.play()?;
+        Ok(stream)
+    }
+
+    /// Build a stream that shares the loudness of each buffer of samples.
+    fn build_stream<T>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        level: Arc<AtomicU32>,
+    ) -> Result<cpal::Stream>
+    where
+        T: cpal::SizedSample,
+        f32: cpal::FromSample<T>,
+    {
+        Ok(device.build_input_stream(
+            config,
+            move |samples: &[T], _: &cpal::InputCallbackInfo| {
+                level.store(loudness(samples).to_bits(), Ordering::Relaxed);
+            },
+            |error| tracing::error!("Capturing audio: {error}"),
+            None,
+        )?)
+    }
+
+    /// The root mean square of the samples, which is a good measure of how loud they sound.
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        reason = "Audio buffers are far too small to lose precision"
+    )]
+    fn loudness<T>(samples: &[T]) -> f32
+    where
+        T: cpal::Sample,
+        f32: cpal::FromSample<T>,
+    {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let sum_of_squares: f32 = samples
+            .iter()
+            .map(|sample| {
+                let value = sample.to_sample::<f32>();
+                value * value
+            })
+            .sum();
+        (sum_of_squares / samples.len() as f32).sqrt()
+    }
+
+    /// `Audio`
+    pub(crate) struct Audio {
+        /// The base Tattoy struct
+        tattoy: Tattoyer,
+        /// The user's config for the audio visualiser.
+        config: Config,
+        /// The audio capture thread.
+        capture: Capture,
+        /// The smoothed audio level.
+        meter: Meter,
+        /// The most recent levels, oldest first, one for each column of bars.
+        history: VecDeque<f32>,
+        /// When the previous frame was rendered.
+        last_tick: std::time::Instant,
+    }
+
+    impl Audio {
+        /// Our main entrypoint.
+        pub(crate) async fn start(
+            protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+            output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+            state: Arc<crate::shared_state::SharedState>,
+        ) -> Result<()> {
+            let capture = match Capture::start().await {
+                Ok(capture) => capture,
+                Err(error) => {
+                    tracing::warn!("Not starting the audio visualiser: {error:?}");
+                    return Ok(());
+                }
+            };
+
+            let rng = state.tattoy_rng("audio").await;
+            let config = state.config.read().await.audio.clone();
+            let tattoy = Tattoyer::new(
+                "audio".to_owned(),
+                config.layer,
+                output,
+                rng,
+                &*state.config.read().await,
+            );
+            let audio = Self {
+                tattoy,
+                config,
+                capture,
+                meter: Meter::default(),
+                history: VecDeque::new(),
+                last_tick: std::time::Instant::now(),
+            };
+            audio.run(&protocol_tx).await
+        }
+
+        /// Draw a scrolling bar for each of the recent levels.
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_precision_loss,
+            reason = "Terminals are far too small to lose precision"
+        )]
+        fn draw_bars(&mut self) {
+            let width = usize::from(self.tattoy.width);
+            let height = f32::from(self.tattoy.height);
+            self.history.push_back(self.meter.level);
+            while self.history.len() > width {
+                self.history.pop_front();
+            }
+
+            let (red, green, blue, alpha) = self.config.colour;
+            let style = crate::draw::Style {
+                colour: (red, green, blue, alpha.max(self.meter.beat)),
+                ..crate::draw::Style::default()
+            };
+            let offset = width - self.history.len();
+            for (column, level) in self.history.iter().enumerate() {
+                let bar_height = level * height;
+                let shape = crate::draw::Shape::Rect {
+                    x: (offset + column) as f32,
+                    y: height - bar_height,
+                    width: 1.0,
+                    height: bar_height,
+                };
+                crate::draw::fill(&mut self.tattoy.surface, &shape, &style);
+            }
+        }
+
+        /// Fill the whole background, brighter on each beat.
+        fn draw_pulse(&mut self) {
+            let (red, green, blue, alpha) = self.config.colour;
+            let strength = self.meter.level.max(self.meter.beat);
+            let style = crate::draw::Style {
+                colour: (red, green, blue, alpha * strength),
+                ..crate::draw::Style::default()
+            };
+            let shape = crate::draw::Shape::Rect {
+                x: 0.0,
+                y: 0.0,
+                width: f32::from(self.tattoy.width),
+                height: f32::from(self.tattoy.height),
+            };
+            crate::draw::fill(&mut self.tattoy.surface, &shape, &style);
+        }
+    }
+
+    impl Tattoy for Audio {
+        fn tattoyer(&mut self) -> &mut Tattoyer {
+            &mut self.tattoy
+        }
+
+        fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+            if let crate::run::Protocol::Config(config) = message {
+                self.config = config.audio.clone();
+                self.tattoy.layer = self.config.layer;
+            }
+        }
+
+        async fn render(&mut self) -> Result<()> {
+            let now = std::time::Instant::now();
+            let delta = now.saturating_duration_since(self.last_tick);
+            self.last_tick = now;
+            self.meter
+                .update(self.capture.level(), delta, self.config.sensitivity);
+
+            if !self.tattoy.is_ready() {
+                return Ok(());
+            }
+
+            self.tattoy.initialise_surface();
+            match self.config.visualisation {
+                Visualisation::Bars => self.draw_bars(),
+                Visualisation::Pulse => self.draw_pulse(),
+            }
+
+            self.tattoy.send_output();
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        const FRAME: std::time::Duration = std::time::Duration::from_millis(16);
+
+        #[test]
+        fn levels_rise_quickly_and_fall_slowly() {
+            let mut meter = Meter::default();
+            meter.update(0.5, FRAME, 1.0);
+            assert!((meter.level - 0.5).abs() < f32::EPSILON);
+
+            meter.update(0.0, FRAME, 1.0);
+            assert!(meter.level > 0.4);
+            assert!(meter.level < 0.5);
+        }
+
+        #[test]
+        fn sudden_jumps_are_beats() {
+            let mut meter = Meter::default();
+            for _ in 0..200 {
+                meter.update(0.2, FRAME, 1.0);
+            }
+            assert!(meter.beat < 0.1);
+
+            meter.update(0.8, FRAME, 1.0);
+            assert!((meter.beat - 1.0).abs() < f32::EPSILON);
+        }
+
+        #[test]
+        fn silence_is_quiet() {
+            assert!(loudness::<f32>(&[]).abs() < f32::EPSILON);
+            assert!((loudness(&[0.5_f32, -0.5]) - 0.5).abs() < f32::EPSILON);
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! A static image behind the terminal, like a wallpaper.
+//!
+//! The image is downsampled to the terminal's cells, with half blocks giving it 2 pixels per cell
+//! vertically. It's only refitted when the terminal's size changes.
+//!
+//! Animated GIFs are played using the delays of each of their frames. All the frames are decoded
+//! when the image is loaded, so that playing them fits into the frame budget.
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// How the image is fitted to the terminal.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Fit {
+    /// Stretch the image to fill the whole terminal, ignoring its aspect ratio.
+    Stretch,
+    /// Scale the image to fit inside the terminal, keeping its aspect ratio, and centre it.
+    #[default]
+    Contain,
+    /// Repeat the image, at its original size, from the top left.
+    Tile,
+}
+
+/// All the user config for the background image tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the background image
+    pub enabled: bool,
+    /// The path to the image. Is relative to Tattoy's config directory.
+    pub path: std::path::PathBuf,
+    /// How the image is fitted to the terminal.
+    pub fit: Fit,
+    /// The number of times to play an animated GIF. 0 plays it forever.
+    pub loop_count: u32,
+    /// How much faster than normal to play an animated GIF. From 0.0, which pauses it, up to
+    /// 100.0.
+    pub speed: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "background.png".into(),
+            fit: Fit::default(),
+            loop_count: 0,
+            speed: 1.0,
+        }
+    }
+}
+
+/// The fastest that a GIF can be played. Much faster and every frame would be skipped anyway.
+const MAXIMUM_SPEED: f32 = 100.0;
+
+/// GIFs with frame delays shorter than this are played at this delay instead, just like browsers.
+const MINIMUM_FRAME_DELAY: std::time::Duration = std::time::Duration::from_millis(20);
+
+/// The playback state of an animated image.
+#[derive(Debug, Default)]
+struct Animation {
+    /// How long each frame is shown for.
+    delays: Vec<std::time::Duration>,
+    /// The index of the frame that's currently shown.
+    current: usize,
+    /// How long the current frame has been shown for.
+    elapsed: std::time::Duration,
+    /// The number of times that the animation has played through.
+    loops: u32,
+    /// The number of times to play the animation. 0 plays it forever.
+    loop_count: u32,
+    /// How much faster than normal to play the animation.
+    speed: f32,
+}
+
+impl Animation {
+    /// Move the animation on by the given time. Returns whether the frame changed.
+    fn advance(&mut self, delta: std::time::Duration) -> bool {
+        if self.delays.len() < 2 || self.is_finished() {
+            return false;
+        }
+
+        let previous = self.current;
+        let scaled = delta.as_secs_f32() * self.speed;
+        self.elapsed += std::time::Duration::try_from_secs_f32(scaled).unwrap_or_default();
+        while let Some(delay) = self.delays.get(self.current) {
+            let delay = (*delay).max(MINIMUM_FRAME_DELAY);
+            if self.elapsed < delay {
+                break;
+            }
+            self.elapsed -= delay;
+
+            if self.current + 1 < self.delays.len() {
+                self.current += 1;
+                continue;
+            }
+            self.loops += 1;
+            if self.is_finished() {
+                break;
+            }
+            self.current = 0;
+        }
+
+        self.current != previous
+    }
+
+    /// Check that the speed from the user's config can be played.
+    fn validate_speed(speed: f32) -> Result<f32> {
+        if !(0.0..=MAXIMUM_SPEED).contains(&speed) {
+            color_eyre::eyre::bail!(
+                "The background image's speed must be from 0.0 to {MAXIMUM_SPEED}, not {speed}"
+            );
+        }
+        Ok(speed)
+    }
+
+    /// Whether the animation has played for all its loops, and so stays on its last frame.
+    const fn is_finished(&self) -> bool {
+        self.loop_count != 0 && self.loops >= self.loop_count
+    }
+}
+
+/// `BackgroundImage`
+pub(crate) struct BackgroundImage {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// Every frame of the original image. Still images just have the one frame.
+    frames: Vec<image::RgbaImage>,
+    /// Every frame, fitted to the terminal. They're all fitted at once, only when the terminal's
+    /// size changes, so that playing an animation is cheap.
+    fitted: Vec<image::RgbaImage>,
+    /// The playback state of an animated image.
+    animation: Animation,
+    /// How the image is fitted to the terminal.
+    fit: Fit,
+    /// The size of the terminal that the image was last fitted to.
+    fitted_size: Option<(u16, u16)>,
+    /// Whether the current frame has been accepted by the renderer.
+    is_rendered: bool,
+    /// When the previous frame was rendered.
+    last_tick: Option<std::time::Instant>,
+}
+
+impl BackgroundImage {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<Self> {
+        let rng = state.tattoy_rng("background_image").await;
+        let tattoy = Tattoyer::new(
+            "background_image".to_owned(),
+            -100,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        let config = state.config.read().await.background_image.clone();
+        let speed = Animation::validate_speed(config.speed)?;
+        let path = state.config_path.read().await.join(config.path);
+        tracing::info!("Loading background image from: {path:?}");
+        let (frames, delays) = load_frames(&path)
+            .map_err(|error| color_eyre::eyre::eyre!("Couldn't load {path:?}: {error}"))?;
+
+        Ok(Self {
+            tattoy,
+            frames,
+            fitted: Vec::new(),
+            animation: Animation {
+                delays,
+                loop_count: config.loop_count,
+                speed,
+                ..Animation::default()
+            },
+            fit: config.fit,
+            fitted_size: None,
+            is_rendered: false,
+            last_tick: None,
+        })
+    }
+
+    /// Check that the image loads, and that it can be played at its speed, without starting the
+    /// tattoy.
+    pub(crate) async fn check_setup(
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        let config = state.config.read().await.background_image.clone();
+        Animation::validate_speed(config.speed)?;
+        let path = state.config_path.read().await.join(config.path);
+        load_frames(&path)
+            .map_err(|error| color_eyre::eyre::eyre!("Couldn't load {path:?}: {error}"))?;
+
+        Ok(())
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await?.run(&protocol_tx).await
+    }
+}
+
+impl Tattoy for BackgroundImage {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    /// Only render when the terminal's size, or the animation's frame, has changed. The
+    /// compositor keeps the last frame.
+    async fn render(&mut self) -> Result<()> {
+        let now = std::time::Instant::now();
+        let delta = self
+            .last_tick
+            .replace(now)
+            .map_or(std::time::Duration::ZERO, |last_tick| now - last_tick);
+        let is_new_frame = self.animation.advance(delta);
+
+        let size = (self.tattoy.width, self.tattoy.height);
+        if !self.tattoy.is_ready() {
+            return Ok(());
+        }
+        let is_resized = self.fitted_size != Some(size);
+        if !is_resized && !is_new_frame && self.is_rendered {
+            return Ok(());
+        }
+        if is_resized {
+            self.fitted_size = Some(size);
+            self.fitted = self
+                .frames
+                .iter()
+                .map(|frame| fit_image(frame, self.fit, size.0.into(), u32::from(size.1) * 2))
+                .collect();
+        }
+
+        let Some(fitted) = self.fitted.get(self.animation.current) else {
+            return Ok(());
+        };
+        self.tattoy.initialise_surface();
+        for (x, y, pixel) in fitted.enumerate_pixels() {
+            if pixel.0[3] == 0 {
+                continue;
+            }
+            let [red, green, blue, alpha] = pixel.0.map(|channel| f32::from(channel) / 255.0);
+            self.tattoy.surface.add_pixel(
+                usize::try_from(x)?,
+                usize::try_from(y)?,
+                (red, green, blue, alpha),
+            )?;
+        }
+
+        self.is_rendered = self.tattoy.send_output();
+
+        Ok(())
+    }
+}
+
+/// Decode every frame of the image upfront, so that playing an animation doesn't have to. Along
+/// with each frame's delay.
+fn load_frames(
+    path: &std::path::Path,
+) -> Result<(Vec<image::RgbaImage>, Vec<std::time::Duration>)> {
+    let is_gif = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+    if !is_gif {
+        return Ok((vec![image::open(path)?.into_rgba8()], Vec::new()));
+    }
+
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let decoder = image::codecs::gif::GifDecoder::new(reader)?;
+    let frames = image::AnimationDecoder::into_frames(decoder).collect_frames()?;
+    let delays = frames
+        .iter()
+        .map(|frame| std::time::Duration::from(frame.delay()))
+        .collect();
+    let images = frames.into_iter().map(image::Frame::into_buffer).collect();
+    Ok((images, delays))
+}
+
+/// Fit the image to the given number of pixels.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    reason = "Image dimensions are far too small to lose precision"
+)]
+fn fit_image(image: &image::RgbaImage, fit: Fit, width: u32, height: u32) -> image::RgbaImage {
+    let filter = image::imageops::FilterType::Triangle;
+    match fit {
+        Fit::Stretch => image::imageops::resize(image, width, height, filter),
+        Fit::Contain => {
+            let scale = (width as f32 / image.width().max(1) as f32)
+                .min(height as f32 / image.height().max(1) as f32);
+            let scaled_width = ((image.width() as f32 * scale).round() as u32).clamp(1, width);
+            let scaled_height = ((image.height() as f32 * scale).round() as u32).clamp(1, height);
+            let scaled = image::imageops::resize(image, scaled_width, scaled_height, filter);
+            let mut canvas = image::RgbaImage::new(width, height);
+            image::imageops::replace(
+                &mut canvas,
+                &scaled,
+                i64::from((width - scaled_width).div_euclid(2)),
+                i64::from((height - scaled_height).div_euclid(2)),
+            );
+            canvas
+        }
+        Fit::Tile => {
+            let mut canvas = image::RgbaImage::new(width, height);
+            image::imageops::tile(&mut canvas, image);
+            canvas
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn two_by_one() -> image::RgbaImage {
+        image::RgbaImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn stretching() {
+        let fitted = fit_image(&two_by_one(), Fit::Stretch, 4, 4);
+        assert_eq!(fitted.dimensions(), (4, 4));
+        assert_eq!(fitted.get_pixel(0, 3).0[3], 255);
+    }
+
+    #[test]
+    fn containing_keeps_aspect_ratio() {
+        let fitted = fit_image(&two_by_one(), Fit::Contain, 4, 4);
+        assert_eq!(fitted.dimensions(), (4, 4));
+        assert_eq!(fitted.get_pixel(0, 0).0[3], 0);
+        assert_eq!(fitted.get_pixel(0, 1).0, [255, 0, 0, 255]);
+        assert_eq!(fitted.get_pixel(3, 2).0, [0, 0, 255, 255]);
+        assert_eq!(fitted.get_pixel(0, 3).0[3], 0);
+    }
+
+    fn animation(loop_count: u32) -> Animation {
+        Animation {
+            delays: vec![std::time::Duration::from_millis(100); 3],
+            loop_count,
+            speed: 1.0,
+            ..Animation::default()
+        }
+    }
+
+    #[test]
+    fn animations_honour_frame_delays() {
+        let mut animation = animation(0);
+        assert!(!animation.advance(std::time::Duration::from_millis(50)));
+        assert!(animation.advance(std::time::Duration::from_millis(50)));
+        assert_eq!(animation.current, 1);
+        assert!(animation.advance(std::time::Duration::from_millis(250)));
+        assert_eq!(animation.current, 0);
+        assert_eq!(animation.loops, 1);
+    }
+
+    #[test]
+    fn animations_stop_after_their_loop_count() {
+        let mut animation = animation(1);
+        animation.advance(std::time::Duration::from_secs(10));
+        assert_eq!(animation.current, 2);
+        assert!(!animation.advance(std::time::Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn animation_speed() {
+        let mut animation = animation(0);
+        animation.speed = 2.0;
+        animation.advance(std::time::Duration::from_millis(100));
+        assert_eq!(animation.current, 2);
+    }
+
+    #[test]
+    fn unplayable_speeds_are_rejected() {
+        assert!(Animation::validate_speed(0.0).is_ok());
+        assert!(Animation::validate_speed(MAXIMUM_SPEED).is_ok());
+        for speed in [-1.0, f32::INFINITY, f32::NAN, MAXIMUM_SPEED + 1.0] {
+            assert!(Animation::validate_speed(speed).is_err(), "{speed}");
+        }
+
+        let mut animation = animation(0);
+        animation.speed = f32::INFINITY;
+        assert!(!animation.advance(std::time::Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn tiling() {
+        let fitted = fit_image(&two_by_one(), Fit::Tile, 3, 2);
+        assert_eq!(fitted.get_pixel(2, 1).0, [255, 0, 0, 255]);
+    }
+}
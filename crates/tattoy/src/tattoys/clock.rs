@@ -0,0 +1,467 @@
+//! A clock in a corner of the terminal, with the date too if wanted.
+//!
+//! The clock only changes once a second, so it uses its own frame interval rather than rendering
+//! at the full frame rate. It's drawn below the terminal's text by default, so that it never
+//! hides anything, though it's best placed in a corner that's usually empty.
+//!
+//! When there are status rows reserved at the bottom of the terminal, and the clock fits in
+//! them, the clock goes in the corner of the status rows instead. Then it never shares space with
+//! the PTY at all.
+
+use std::fmt::Write as _;
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// How long to wait between frames. The clock doesn't show anything shorter than a second.
+const FRAME_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The gap, in columns, between the clock and the side of the terminal.
+const MARGIN: usize = 1;
+
+/// The height, in rows, of the large font.
+const LARGE_HEIGHT: usize = 3;
+
+/// The gap, in columns, between each character of the large font.
+const LARGE_SPACING: usize = 1;
+
+/// The segments of a large digit, as `(x, y, width, height)`, in the order a to g of a seven
+/// segment display. Digits are 3 columns wide. Segments are half a row thick, so that they're
+/// drawn with half blocks.
+const SEGMENTS: [(f32, f32, f32, f32); 7] = [
+    (0.0, 0.0, 3.0, 0.5),
+    (2.0, 0.0, 1.0, 1.5),
+    (2.0, 1.0, 1.0, 1.5),
+    (0.0, 2.0, 3.0, 0.5),
+    (0.0, 1.0, 1.0, 1.5),
+    (0.0, 0.0, 1.0, 1.5),
+    (0.0, 1.0, 3.0, 0.5),
+];
+
+/// The segments that are lit for each digit, one bit per segment, with segment a as the lowest
+/// bit.
+const DIGITS: [u8; 10] = [
+    0b011_1111, 0b000_0110, 0b101_1011, 0b100_1111, 0b110_0110, 0b110_1101, 0b111_1101, 0b000_0111,
+    0b111_1111, 0b110_1111,
+];
+
+/// A corner of the terminal.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Corner {
+    /// The top left corner.
+    TopLeft,
+    /// The top right corner.
+    #[default]
+    TopRight,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+/// How the clock's time is drawn.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Font {
+    /// Normal text.
+    #[default]
+    Small,
+    /// Digits 3 rows tall, drawn with block characters. It only has digits, ":" and "-".
+    Large,
+}
+
+/// All the user config for the clock tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the clock
+    pub enabled: bool,
+    /// The corner of the terminal that the clock is in.
+    pub corner: Corner,
+    /// How the time is drawn.
+    pub font: Font,
+    /// The format of the time, as a `strftime` string, like "%H:%M:%S".
+    pub time_format: String,
+    /// Show the date under the time.
+    pub show_date: bool,
+    /// The format of the date, as a `strftime` string, like "%Y-%m-%d".
+    pub date_format: String,
+    /// The colour of the clock, as red, green, blue and alpha, each from 0.0 to 1.0.
+    pub colour: crate::surface::Colour,
+    /// The compositing layer. Negative values are below the terminal's text, positive values are
+    /// above it.
+    pub layer: i16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            corner: Corner::default(),
+            font: Font::default(),
+            time_format: "%H:%M".into(),
+            show_date: false,
+            date_format: "%Y-%m-%d".into(),
+            colour: (1.0, 1.0, 1.0, 0.8),
+            layer: -1,
+        }
+    }
+}
+
+/// What the clock last rendered, so that nothing is sent until the clock changes.
+#[derive(PartialEq, Eq)]
+struct Rendered {
+    /// The time and, if shown, the date.
+    lines: Vec<String>,
+    /// The size of the terminal.
+    tty_size: (u16, u16),
+}
+
+/// `Clock`
+pub(crate) struct Clock {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// The user's config for the clock.
+    config: Config,
+    /// What was last rendered.
+    rendered: Option<Rendered>,
+}
+
+impl Clock {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Self {
+        let rng = state.tattoy_rng("clock").await;
+        let config = state.config.read().await.clock.clone();
+        let mut tattoy = Tattoyer::new(
+            "clock".to_owned(),
+            config.layer,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        tattoy.frame_interval = Some(FRAME_INTERVAL);
+        Self {
+            tattoy,
+            config,
+            rendered: None,
+        }
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await.run(&protocol_tx).await
+    }
+
+    /// Draw the clock with its top left corner at the given cell.
+    fn draw(&mut self, lines: &[String], (left, top): (usize, usize), width: usize) {
+        let colour = self.config.colour;
+        let is_right = matches!(self.config.corner, Corner::TopRight | Corner::BottomRight);
+        let align = |line_width: usize| {
+            if is_right {
+                left + width.saturating_sub(line_width)
+            } else {
+                left
+            }
+        };
+
+        let mut row = top;
+        let mut small_lines = lines;
+        if self.config.font == Font::Large {
+            if let Some((time, rest)) = lines.split_first() {
+                let time_origin = (align(large_width(time)), top);
+                draw_large(&mut self.tattoy.surface, time, time_origin, colour);
+                row += LARGE_HEIGHT;
+                small_lines = rest;
+            }
+        }
+
+        for line in small_lines {
+            let x = align(line.chars().count());
+            self.tattoy
+                .surface
+                .add_text(x, row, line.clone(), None, Some(colour));
+            row += 1;
+        }
+    }
+}
+
+impl Tattoy for Clock {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        if let crate::run::Protocol::Config(config) = message {
+            self.config = config.clock.clone();
+            self.tattoy.layer = self.config.layer;
+            self.rendered = None;
+        }
+    }
+
+    /// Only render when the clock has changed, the compositor keeps the last frame.
+    async fn render(&mut self) -> Result<()> {
+        if !self.tattoy.is_ready() {
+            return Ok(());
+        }
+
+        let Some(clock_lines) = lines(&self.config, &chrono::Local::now()) else {
+            return Ok(());
+        };
+        let rendered = Rendered {
+            lines: clock_lines,
+            tty_size: (self.tattoy.width, self.tattoy.height),
+        };
+        if self.rendered.as_ref() == Some(&rendered) {
+            return Ok(());
+        }
+
+        self.tattoy.initialise_surface();
+        let clock_size = size(self.config.font, &rendered.lines);
+        let tty = crate::mask::Rect {
+            x: 0,
+            y: 0,
+            width: self.tattoy.width.into(),
+            height: self.tattoy.height.into(),
+        };
+        let area = area(self.tattoy.status_area(), clock_size, tty);
+        if let Some((x, y)) = origin(self.config.corner, clock_size, (area.width, area.height)) {
+            self.draw(&rendered.lines, (area.x + x, area.y + y), clock_size.0);
+        }
+        if self.tattoy.send_output() {
+            self.rendered = Some(rendered);
+        }
+
+        Ok(())
+    }
+}
+
+/// The text of the clock, one line for the time and one for the date if it's shown. `None` if
+/// either of the formats is invalid.
+fn lines<Tz>(config: &Config, now: &chrono::DateTime<Tz>) -> Option<Vec<String>>
+where
+    Tz: chrono::TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    let mut lines = vec![format_time(now, &config.time_format)?];
+    if config.show_date {
+        lines.push(format_time(now, &config.date_format)?);
+    }
+    Some(lines)
+}
+
+/// The size, in columns and rows, of the whole clock.
+fn size(font: Font, lines: &[String]) -> (usize, usize) {
+    let small_width = |small_lines: &[String]| {
+        small_lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    match font {
+        Font::Small => (small_width(lines), lines.len()),
+        Font::Large => {
+            let Some((time, rest)) = lines.split_first() else {
+                return (0, 0);
+            };
+            (
+                large_width(time).max(small_width(rest)),
+                LARGE_HEIGHT + rest.len(),
+            )
+        }
+    }
+}
+
+/// Format the time with a `strftime` format. `None` if the format is invalid.
+fn format_time<Tz>(now: &chrono::DateTime<Tz>, format: &str) -> Option<String>
+where
+    Tz: chrono::TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    let mut text = String::new();
+    if write!(text, "{}", now.format(format)).is_err() {
+        tracing::error!("Invalid clock format: {format}");
+        return None;
+    }
+    Some(text)
+}
+
+/// The part of the terminal that the clock goes in. It's the status rows if the clock fits in
+/// them, otherwise it's the whole terminal.
+fn area(
+    status_area: Option<crate::mask::Rect>,
+    (_, clock_height): (usize, usize),
+    tty: crate::mask::Rect,
+) -> crate::mask::Rect {
+    status_area
+        .filter(|status| status.height >= clock_height)
+        .unwrap_or(tty)
+}
+
+/// Where the top left of the clock goes, so that it's in the corner. `None` if the terminal is
+/// too small for it.
+fn origin(
+    corner: Corner,
+    (width, height): (usize, usize),
+    (tty_width, tty_height): (usize, usize),
+) -> Option<(usize, usize)> {
+    let right = tty_width.checked_sub(width + MARGIN)?;
+    let bottom = tty_height.checked_sub(height)?;
+    if right < MARGIN {
+        return None;
+    }
+
+    Some(match corner {
+        Corner::TopLeft => (MARGIN, 0),
+        Corner::TopRight => (right, 0),
+        Corner::BottomLeft => (MARGIN, bottom),
+        Corner::BottomRight => (right, bottom),
+    })
+}
+
+/// The width, in columns, of a character in the large font. Characters that the font doesn't
+/// have are blank.
+const fn large_character_width(character: char) -> usize {
+    match character {
+        '0'..='9' | '-' => 3,
+        _ => 1,
+    }
+}
+
+/// The width, in columns, of text in the large font.
+fn large_width(text: &str) -> usize {
+    let characters = text.chars().map(large_character_width);
+    let count = text.chars().count();
+    characters.sum::<usize>() + count.saturating_sub(1) * LARGE_SPACING
+}
+
+/// Draw text in the large font, with its top left corner at the given cell.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "Terminals are far too small to lose precision"
+)]
+fn draw_large(
+    surface: &mut crate::surface::Surface,
+    text: &str,
+    (left, top): (usize, usize),
+    colour: crate::surface::Colour,
+) {
+    let mut shapes = Vec::new();
+    let mut x = left as f32;
+    let y = top as f32;
+    let segment =
+        |segment_x: f32, segment_y: f32, width: f32, height: f32| crate::draw::Shape::Rect {
+            x: segment_x,
+            y: segment_y,
+            width,
+            height,
+        };
+
+    for character in text.chars() {
+        let segments = match character {
+            '0'..='9' => character
+                .to_digit(10)
+                .and_then(|digit| DIGITS.get(usize::try_from(digit).ok()?))
+                .copied()
+                .unwrap_or(0),
+            '-' => 0b100_0000,
+            ':' => {
+                shapes.push(segment(x, y + 0.5, 1.0, 0.5));
+                shapes.push(segment(x, y + 1.5, 1.0, 0.5));
+                0
+            }
+            _ => 0,
+        };
+        for (bit, (segment_x, segment_y, width, height)) in SEGMENTS.iter().enumerate() {
+            if segments & (1 << bit) != 0 {
+                shapes.push(segment(x + segment_x, y + segment_y, *width, *height));
+            }
+        }
+        x += (large_character_width(character) + LARGE_SPACING) as f32;
+    }
+
+    // Half blocks are the only way to draw the half row thick segments.
+    let style = crate::draw::Style {
+        colour,
+        is_antialiased: true,
+        ..crate::draw::Style::default()
+    };
+    crate::draw::fill_all(surface, &shapes, &style);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clocks_go_in_corners() {
+        let tty_size = (20, 10);
+        assert_eq!(origin(Corner::TopLeft, (5, 1), tty_size), Some((1, 0)));
+        assert_eq!(origin(Corner::TopRight, (5, 1), tty_size), Some((14, 0)));
+        assert_eq!(origin(Corner::BottomLeft, (5, 2), tty_size), Some((1, 8)));
+        assert_eq!(origin(Corner::BottomRight, (5, 2), tty_size), Some((14, 8)));
+        assert_eq!(origin(Corner::TopRight, (19, 1), tty_size), None);
+    }
+
+    #[test]
+    fn clocks_go_in_the_status_rows_when_they_fit() {
+        let tty = crate::mask::Rect {
+            x: 0,
+            y: 0,
+            width: 20,
+            height: 10,
+        };
+        let status = crate::mask::Rect {
+            y: 8,
+            height: 2,
+            ..tty
+        };
+        assert_eq!(area(None, (5, 1), tty), tty);
+        assert_eq!(area(Some(status), (5, 2), tty), status);
+        assert_eq!(area(Some(status), (5, 3), tty), tty);
+    }
+
+    #[test]
+    fn times_and_dates_are_formatted() {
+        let now = chrono::NaiveDate::from_ymd_opt(2026, 10, 14)
+            .and_then(|date| date.and_hms_opt(9, 5, 30))
+            .unwrap()
+            .and_utc();
+        let config = Config {
+            time_format: "%H:%M:%S".into(),
+            show_date: true,
+            ..Config::default()
+        };
+        let clock = lines(&config, &now).unwrap();
+        assert_eq!(clock, ["09:05:30", "2026-10-14"]);
+        assert_eq!(size(Font::Small, &clock), (10, 2));
+        assert_eq!(size(Font::Large, &clock), (27, 4));
+
+        let invalid = Config {
+            time_format: "%Q".into(),
+            ..Config::default()
+        };
+        assert_eq!(lines(&invalid, &now), None);
+    }
+
+    #[test]
+    fn large_digits_are_drawn_with_blocks() {
+        let mut surface = crate::surface::Surface::new("test".into(), 7, 3, -1);
+        draw_large(&mut surface, "10", (0, 0), crate::surface::WHITE);
+
+        assert_eq!(
+            crate::draw::ascii(&surface),
+            ["  █ █▀█", "  █ █ █", "  ▀ ▀▀▀"]
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! A custom, animated cursor that replaces the user's terminal's own cursor.
+//!
+//! The glyph follows the cursor shape that the application asked for, unless the user has chosen
+//! their own. It's hidden whenever the application hides its cursor, and whilst scrolling,
+//! because the cursor isn't where it appears to be then. Like most terminals, the animation
+//! restarts whenever the cursor moves, so that it's always easy to find. Note that the glyph is
+//! drawn over the character under the cursor, so a block hides it.
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// The opacity of a pulsing cursor at the faintest point of its pulse.
+const PULSE_MINIMUM_OPACITY: f32 = 0.2;
+
+/// How the cursor is animated.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Animation {
+    /// Always fully shown.
+    Steady,
+    /// Shown for the first half of every period, then hidden for the second half.
+    Blink,
+    /// Smoothly fades out and back in again every period, like breathing.
+    #[default]
+    Pulse,
+}
+
+/// All the user config for the cursor tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the custom cursor
+    pub enabled: bool,
+    /// The character that's drawn as the cursor, like "_". By default it's a block, underline or
+    /// bar, depending on the cursor shape that the application asked for.
+    pub glyph: Option<char>,
+    /// The colour of the cursor, as red, green, blue and alpha, each from 0.0 to 1.0.
+    pub colour: crate::surface::Colour,
+    /// How the cursor is animated.
+    pub animation: Animation,
+    /// How long, in seconds, each cycle of the animation takes.
+    pub period: f32,
+    /// The compositing layer. It should be above the terminal's text, so that it isn't hidden.
+    pub layer: i16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            glyph: None,
+            colour: (1.0, 1.0, 1.0, 0.9),
+            animation: Animation::default(),
+            period: 1.2,
+            layer: 10,
+        }
+    }
+}
+
+/// What the cursor last rendered, so that nothing is sent until the cursor changes.
+#[derive(PartialEq)]
+struct Rendered {
+    /// The cursor's column and row.
+    position: (usize, usize),
+    /// The glyph drawn as the cursor.
+    glyph: char,
+    /// How visible the cursor is, at this point of its animation.
+    opacity: f32,
+}
+
+/// Keeps the user's terminal's own cursor hidden until it's dropped, even when the tattoy is
+/// stopped part way through a frame.
+struct NativeCursorReplaced(std::sync::Arc<crate::shared_state::SharedState>);
+
+impl NativeCursorReplaced {
+    /// Start hiding the user's terminal's own cursor.
+    fn new(state: std::sync::Arc<crate::shared_state::SharedState>) -> Self {
+        state
+            .is_native_cursor_replaced
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        Self(state)
+    }
+}
+
+impl Drop for NativeCursorReplaced {
+    fn drop(&mut self) {
+        self.0
+            .is_native_cursor_replaced
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// `Cursor`
+pub(crate) struct Cursor {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// The user's config for the cursor.
+    config: Config,
+    /// The cursor's position in the previous frame.
+    position: Option<(usize, usize)>,
+    /// When the cursor last moved, which is when its animation restarted.
+    moved: std::time::Instant,
+    /// What was last rendered. `None` when the cursor was hidden.
+    rendered: Option<Rendered>,
+}
+
+impl Cursor {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: &crate::shared_state::SharedState,
+    ) -> Self {
+        let rng = state.tattoy_rng("cursor").await;
+        let config = state.config.read().await.cursor.clone();
+        let tattoy = Tattoyer::new(
+            "cursor".to_owned(),
+            config.layer,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        Self {
+            tattoy,
+            config,
+            position: None,
+            moved: std::time::Instant::now(),
+            rendered: None,
+        }
+    }
+
+    /// Our main entrypoint. The user's terminal's own cursor is hidden for as long as the
+    /// tattoy is running.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        let cursor = Self::new(output, &state).await;
+        let _replaced = NativeCursorReplaced::new(state);
+        cursor.run(&protocol_tx).await
+    }
+
+    /// Restart the animation whenever the cursor moves.
+    fn track_cursor(&mut self) -> (usize, usize) {
+        let position = self.tattoy.screen.surface.cursor_position();
+        if self.position != Some(position) {
+            self.position = Some(position);
+            self.moved = std::time::Instant::now();
+        }
+        position
+    }
+}
+
+impl Tattoy for Cursor {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        if let crate::run::Protocol::Config(config) = message {
+            self.config = config.cursor.clone();
+            self.tattoy.layer = self.config.layer;
+            self.rendered = None;
+        }
+    }
+
+    /// Only render when the cursor has changed, the compositor keeps the last frame.
+    async fn render(&mut self) -> Result<()> {
+        if !self.tattoy.is_ready() {
+            return Ok(());
+        }
+
+        let position = self.track_cursor();
+        let is_shown = self.tattoy.is_cursor_visible && !self.tattoy.is_scrolling();
+        let rendered = is_shown.then(|| Rendered {
+            position,
+            glyph: self
+                .config
+                .glyph
+                .unwrap_or_else(|| shape_glyph(self.tattoy.cursor_shape)),
+            opacity: opacity(
+                self.config.animation,
+                self.config.period,
+                self.moved.elapsed().as_secs_f32(),
+            ),
+        });
+        if rendered == self.rendered {
+            return Ok(());
+        }
+
+        self.tattoy.initialise_surface();
+        if let Some(cursor) = &rendered {
+            let (red, green, blue, alpha) = self.config.colour;
+            let colour = (red, green, blue, alpha * cursor.opacity);
+            let (x, y) = cursor.position;
+            self.tattoy
+                .surface
+                .add_text(x, y, cursor.glyph.into(), None, Some(colour));
+        }
+        if self.tattoy.send_output() {
+            self.rendered = rendered;
+        }
+
+        Ok(())
+    }
+}
+
+/// The glyph that best matches the cursor shape that the application asked for.
+const fn shape_glyph(shape: termwiz::surface::CursorShape) -> char {
+    match shape {
+        termwiz::surface::CursorShape::Default
+        | termwiz::surface::CursorShape::BlinkingBlock
+        | termwiz::surface::CursorShape::SteadyBlock => '█',
+        termwiz::surface::CursorShape::BlinkingUnderline
+        | termwiz::surface::CursorShape::SteadyUnderline => '▁',
+        termwiz::surface::CursorShape::BlinkingBar | termwiz::surface::CursorShape::SteadyBar => {
+            '▏'
+        }
+    }
+}
+
+/// How visible the cursor is, the given number of seconds after its animation started.
+fn opacity(animation: Animation, period: f32, elapsed: f32) -> f32 {
+    if period <= 0.0 {
+        return 1.0;
+    }
+
+    let phase = (elapsed / period).fract();
+    match animation {
+        Animation::Steady => 1.0,
+        Animation::Blink => {
+            if phase < 0.5 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        Animation::Pulse => {
+            let wave = (phase * std::f32::consts::TAU).cos().mul_add(0.5, 0.5);
+            wave.mul_add(1.0 - PULSE_MINIMUM_OPACITY, PULSE_MINIMUM_OPACITY)
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cursors_are_animated() {
+        assert_eq!(opacity(Animation::Steady, 1.0, 0.7), 1.0);
+
+        assert_eq!(opacity(Animation::Blink, 1.0, 0.25), 1.0);
+        assert_eq!(opacity(Animation::Blink, 1.0, 0.75), 0.0);
+        assert_eq!(opacity(Animation::Blink, 1.0, 1.25), 1.0);
+
+        let pulse = |elapsed| opacity(Animation::Pulse, 2.0, elapsed);
+        assert!((pulse(0.0) - 1.0).abs() < 0.001);
+        assert!((pulse(1.0) - PULSE_MINIMUM_OPACITY).abs() < 0.001);
+        assert!(pulse(0.5) < pulse(0.0) && pulse(0.5) > pulse(1.0));
+
+        // A period of zero would never animate, so the cursor is just shown.
+        assert_eq!(opacity(Animation::Blink, 0.0, 0.75), 1.0);
+    }
+
+    #[test]
+    fn glyphs_follow_the_cursor_shape() {
+        assert_eq!(shape_glyph(termwiz::surface::CursorShape::Default), '█');
+        assert_eq!(
+            shape_glyph(termwiz::surface::CursorShape::BlinkingUnderline),
+            '▁'
+        );
+        assert_eq!(shape_glyph(termwiz::surface::CursorShape::SteadyBar), '▏');
+    }
+}
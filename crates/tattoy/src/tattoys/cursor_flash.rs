@@ -0,0 +1,255 @@
+//! Flash a fading highlight around the cursor whenever it jumps a long way, like when switching
+//! between applications, so that it's easy to find.
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// All the user config for the cursor flash tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the cursor flash
+    pub enabled: bool,
+    /// How far, in cells, the cursor has to jump between frames to trigger a flash.
+    pub distance: f32,
+    /// The colour of the flash, as red, green, blue and alpha, each from 0.0 to 1.0.
+    pub colour: crate::surface::Colour,
+    /// How long, in seconds, the flash takes to fade away.
+    pub duration: f32,
+    /// How far, in rows, the flash spreads out from the cursor. Cells are about twice as tall as
+    /// they are wide, so it spreads twice as many columns.
+    pub radius: f32,
+    /// How the flash fades.
+    pub easing: crate::animation::Easing,
+    /// The compositing layer. Negative values are below the terminal's text, positive values are
+    /// above it.
+    pub layer: i16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            distance: 10.0,
+            colour: (1.0, 1.0, 0.5, 0.8),
+            duration: 0.6,
+            radius: 2.0,
+            easing: crate::animation::Easing::EaseOut,
+            layer: -5,
+        }
+    }
+}
+
+/// A single flash, at the cursor's new position.
+struct Flash {
+    /// Where the cursor jumped to.
+    position: (usize, usize),
+    /// The opacity of the flash, which fades to nothing.
+    opacity: crate::animation::Tween<f32>,
+}
+
+/// `CursorFlash`
+pub(crate) struct CursorFlash {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// The user's config for the cursor flash.
+    config: Config,
+    /// The cursor's position in the previous frame.
+    previous: Option<(usize, usize)>,
+    /// The current flash, if there is one.
+    flash: Option<Flash>,
+    /// When the previous frame was rendered.
+    last_tick: std::time::Instant,
+}
+
+impl CursorFlash {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Self {
+        let rng = state.tattoy_rng("cursor_flash").await;
+        let config = state.config.read().await.cursor_flash.clone();
+        let tattoy = Tattoyer::new(
+            "cursor_flash".to_owned(),
+            config.layer,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        Self {
+            tattoy,
+            config,
+            previous: None,
+            flash: None,
+            last_tick: std::time::Instant::now(),
+        }
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await.run(&protocol_tx).await
+    }
+
+    /// Start a new flash if the cursor has jumped far enough since the previous frame.
+    fn track_cursor(&mut self) {
+        // The cursor isn't where it appears to be whilst scrolling.
+        if self.tattoy.is_scrolling() {
+            self.previous = None;
+            return;
+        }
+
+        let cursor = self.tattoy.screen.surface.cursor_position();
+        let is_jump = self
+            .previous
+            .is_some_and(|previous| distance(previous, cursor) >= self.config.distance);
+        if is_jump {
+            let Ok(duration) = std::time::Duration::try_from_secs_f32(self.config.duration) else {
+                tracing::error!("Invalid cursor flash duration: {}", self.config.duration);
+                return;
+            };
+            self.flash = Some(Flash {
+                position: cursor,
+                opacity: crate::animation::Tween::new(1.0, 0.0, duration, self.config.easing),
+            });
+        }
+        self.previous = Some(cursor);
+    }
+}
+
+impl Tattoy for CursorFlash {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        if let crate::run::Protocol::Config(config) = message {
+            self.config = config.cursor_flash.clone();
+            self.tattoy.layer = self.config.layer;
+        }
+    }
+
+    /// Only render whilst there's a flash, the compositor keeps the last frame.
+    async fn render(&mut self) -> Result<()> {
+        let now = std::time::Instant::now();
+        let delta = now.saturating_duration_since(self.last_tick);
+        self.last_tick = now;
+
+        if !self.tattoy.is_ready() {
+            return Ok(());
+        }
+        self.track_cursor();
+
+        let Some(flash) = &mut self.flash else {
+            return Ok(());
+        };
+        let opacity = flash.opacity.advance(delta);
+        let position = flash.position;
+        let is_finished = flash.opacity.is_finished();
+
+        self.tattoy.initialise_surface();
+        let width = usize::from(self.tattoy.width);
+        let height = usize::from(self.tattoy.height);
+        let (red, green, blue, alpha) = self.config.colour;
+        for (x, y, strength) in highlight(position, self.config.radius) {
+            if x >= width || y >= height {
+                continue;
+            }
+            let cell_opacity = alpha * opacity * strength;
+            self.tattoy.surface.add_text(
+                x,
+                y,
+                " ".into(),
+                Some((red, green, blue, cell_opacity)),
+                None,
+            );
+        }
+
+        // The final, invisible, frame has to reach the compositor, otherwise the flash would be
+        // left on the screen. So it keeps being resent until it's accepted.
+        if self.tattoy.send_output() && is_finished {
+            self.flash = None;
+        }
+
+        Ok(())
+    }
+}
+
+/// The distance, in cells, between 2 positions.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "Terminals are far too small to lose precision"
+)]
+fn distance(from: (usize, usize), to: (usize, usize)) -> f32 {
+    let columns = from.0.abs_diff(to.0) as f32;
+    let rows = from.1.abs_diff(to.1) as f32;
+    columns.hypot(rows)
+}
+
+/// Every cell around the position that's highlighted, with how strongly it's highlighted. The
+/// position itself is the strongest, fading to nothing at the radius.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_possible_wrap,
+    reason = "Terminals are far too small to lose precision"
+)]
+fn highlight(position: (usize, usize), radius: f32) -> Vec<(usize, usize, f32)> {
+    let radius = radius.max(0.0);
+    let rows = radius.ceil() as isize;
+    let columns = rows * 2;
+    let mut cells = Vec::new();
+    for row_offset in -rows..=rows {
+        for column_offset in -columns..=columns {
+            let Some((x, y)) = position
+                .0
+                .checked_add_signed(column_offset)
+                .zip(position.1.checked_add_signed(row_offset))
+            else {
+                continue;
+            };
+            let reach = (column_offset as f32 / 2.0).hypot(row_offset as f32);
+            let strength = 1.0 - reach / (radius + 1.0);
+            if strength > 0.0 {
+                cells.push((x, y, strength));
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn jumps_are_measured_in_cells() {
+        assert_eq!(distance((0, 0), (0, 0)), 0.0);
+        assert_eq!(distance((1, 2), (4, 6)), 5.0);
+    }
+
+    #[test]
+    fn highlights_fade_out_from_the_cursor() {
+        let cells = highlight((0, 0), 1.0);
+        let strength = |position: (usize, usize)| {
+            cells
+                .iter()
+                .find(|(x, y, _)| (*x, *y) == position)
+                .map(|(_, _, strength)| *strength)
+        };
+
+        assert_eq!(strength((0, 0)), Some(1.0));
+        assert!(strength((2, 0)) < strength((1, 0)));
+        assert!(strength((0, 1)) < strength((0, 0)));
+        assert_eq!(strength((5, 0)), None);
+    }
+}
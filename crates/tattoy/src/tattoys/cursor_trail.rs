@@ -0,0 +1,244 @@
+//! A fading trail behind the moving cursor.
+
+use std::collections::VecDeque;
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// How the trail fades from the cursor to the end of the trail.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Fade {
+    /// The opacity drops evenly along the trail.
+    #[default]
+    Linear,
+    /// The opacity drops quickly at first and then lingers.
+    Quadratic,
+    /// Only the part of the trail closest to the cursor is really visible.
+    Exponential,
+}
+
+impl Fade {
+    /// The opacity of a part of the trail, from its age. An age of 0.0 is at the cursor and 1.0
+    /// is at the end of the trail.
+    fn opacity(self, age: f32) -> f32 {
+        let remaining = (1.0 - age).clamp(0.0, 1.0);
+        match self {
+            Self::Linear => remaining,
+            Self::Quadratic => remaining * remaining,
+            Self::Exponential => (-5.0 * age).exp() * remaining,
+        }
+    }
+}
+
+/// All the user config for the cursor trail tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the cursor trail
+    pub enabled: bool,
+    /// The number of frames that the trail lasts for.
+    pub length: usize,
+    /// The colour of the trail, as red, green, blue and alpha, each from 0.0 to 1.0.
+    pub colour: crate::surface::Colour,
+    /// How the trail fades.
+    pub fade: Fade,
+    /// The compositing layer. Negative values are below the terminal's text, positive values are
+    /// above it.
+    pub layer: i16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length: 15,
+            colour: (0.5, 0.5, 1.0, 0.5),
+            fade: Fade::default(),
+            layer: -5,
+        }
+    }
+}
+
+/// `CursorTrail`
+pub(crate) struct CursorTrail {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// The user's config for the cursor trail.
+    config: Config,
+    /// The cursor's position in each of the most recent frames, newest first.
+    positions: VecDeque<(usize, usize)>,
+    /// The positions that were last rendered, so that nothing is sent when the trail is still.
+    rendered: Option<VecDeque<(usize, usize)>>,
+}
+
+impl CursorTrail {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Self {
+        let rng = state.tattoy_rng("cursor_trail").await;
+        let config = state.config.read().await.cursor_trail.clone();
+        let tattoy = Tattoyer::new(
+            "cursor_trail".to_owned(),
+            config.layer,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        Self {
+            tattoy,
+            config,
+            positions: VecDeque::new(),
+            rendered: None,
+        }
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await.run(&protocol_tx).await
+    }
+
+    /// Record the cursor's position for this frame, forgetting positions that are older than the
+    /// trail.
+    fn record(&mut self, position: (usize, usize)) {
+        self.positions.push_front(position);
+        self.positions.truncate(self.config.length.max(1));
+    }
+}
+
+impl Tattoy for CursorTrail {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        if let crate::run::Protocol::Config(config) = message {
+            self.config = config.cursor_trail.clone();
+            self.tattoy.layer = self.config.layer;
+            self.rendered = None;
+        }
+    }
+
+    /// Only render when the trail has changed, the compositor keeps the last frame.
+    async fn render(&mut self) -> Result<()> {
+        if !self.tattoy.is_ready() {
+            return Ok(());
+        }
+
+        // The cursor isn't where it appears to be whilst scrolling.
+        if self.tattoy.is_scrolling() {
+            self.positions.clear();
+        } else {
+            self.record(self.tattoy.screen.surface.cursor_position());
+        }
+
+        if self.rendered.as_ref() == Some(&self.positions) {
+            return Ok(());
+        }
+
+        self.tattoy.initialise_surface();
+        let width = usize::from(self.tattoy.width);
+        let height = usize::from(self.tattoy.height);
+        for (x, y, age) in trail(&self.positions, self.config.length) {
+            if x >= width || y >= height {
+                continue;
+            }
+            let (red, green, blue, alpha) = self.config.colour;
+            let opacity = alpha * self.config.fade.opacity(age);
+            self.tattoy
+                .surface
+                .add_text(x, y, " ".into(), Some((red, green, blue, opacity)), None);
+        }
+
+        // A dropped frame is retried on the next render.
+        if self.tattoy.send_output() {
+            self.rendered = Some(self.positions.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// Every cell along the trail, with its age from 0.0 at the cursor to 1.0 at the end of the
+/// trail. The gaps between the positions of consecutive frames are filled in, so that fast
+/// movements still leave a continuous trail. The cell under the cursor itself isn't included.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "Terminals are far too small to lose precision"
+)]
+fn trail(positions: &VecDeque<(usize, usize)>, length: usize) -> Vec<(usize, usize, f32)> {
+    let mut cells: Vec<(usize, usize, f32)> = Vec::new();
+    let Some(cursor) = positions.front().copied() else {
+        return cells;
+    };
+    let length = length.max(1) as f32;
+
+    for (index, pair) in positions.iter().collect::<Vec<_>>().windows(2).enumerate() {
+        let [newer, older] = pair else {
+            continue;
+        };
+        let (from_x, from_y) = (newer.0 as f32, newer.1 as f32);
+        let (to_x, to_y) = (older.0 as f32, older.1 as f32);
+        let steps = (to_x - from_x).abs().max((to_y - from_y).abs()).max(1.0);
+
+        for step in 1..=(steps as usize) {
+            let amount = step as f32 / steps;
+            let cell = (
+                (from_x + (to_x - from_x) * amount).round() as usize,
+                (from_y + (to_y - from_y) * amount).round() as usize,
+            );
+            let is_already_rendered = cells.iter().any(|(x, y, _)| (*x, *y) == cell);
+            if cell == cursor || is_already_rendered {
+                continue;
+            }
+            let age = (index as f32 + amount) / length;
+            cells.push((cell.0, cell.1, age));
+        }
+    }
+
+    cells
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fading() {
+        for fade in [Fade::Linear, Fade::Quadratic, Fade::Exponential] {
+            assert_eq!(fade.opacity(0.0), 1.0);
+            assert_eq!(fade.opacity(1.0), 0.0);
+            assert!(fade.opacity(0.25) > fade.opacity(0.75));
+        }
+        assert!(Fade::Quadratic.opacity(0.5) < Fade::Linear.opacity(0.5));
+    }
+
+    #[test]
+    fn gaps_between_frames_are_filled_in() {
+        let positions = VecDeque::from([(4, 0), (0, 0)]);
+        let cells = trail(&positions, 4);
+        let coords: Vec<_> = cells.iter().map(|(x, y, _)| (*x, *y)).collect();
+        assert_eq!(coords, vec![(3, 0), (2, 0), (1, 0), (0, 0)]);
+        assert!(cells
+            .iter()
+            .zip(cells.iter().skip(1))
+            .all(|(newer, older)| newer.2 < older.2));
+    }
+
+    #[test]
+    fn a_still_cursor_has_no_trail() {
+        let positions = VecDeque::from([(2, 3), (2, 3), (2, 3)]);
+        assert!(trail(&positions, 3).is_empty());
+    }
+}
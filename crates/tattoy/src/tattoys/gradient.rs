@@ -0,0 +1,264 @@
+//! A linear or radial gradient behind the terminal.
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// The shape of the gradient.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Kind {
+    /// The colours change along a straight line, in the direction of the angle.
+    #[default]
+    Linear,
+    /// The colours change outwards from the centre of the terminal.
+    Radial,
+}
+
+/// A colour at a position along the gradient.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Stop {
+    /// Where the colour is, from 0.0 at the start of the gradient to 1.0 at the end.
+    pub position: f32,
+    /// The colour, as red, green, blue and alpha, each from 0.0 to 1.0.
+    pub colour: crate::surface::Colour,
+}
+
+/// All the user config for the gradient tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the gradient
+    pub enabled: bool,
+    /// The shape of the gradient.
+    pub kind: Kind,
+    /// The direction of a linear gradient in degrees. 0 goes from left to right and 90 goes from
+    /// top to bottom.
+    pub angle: f32,
+    /// The colours of the gradient.
+    pub stops: Vec<Stop>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: Kind::default(),
+            angle: 90.0,
+            stops: vec![
+                Stop {
+                    position: 0.0,
+                    colour: (0.1, 0.05, 0.2, 1.0),
+                },
+                Stop {
+                    position: 1.0,
+                    colour: (0.0, 0.0, 0.0, 1.0),
+                },
+            ],
+        }
+    }
+}
+
+impl Config {
+    /// Sort the stops by their positions, so that they don't need sorting for every pixel.
+    fn sort_stops(&mut self) {
+        self.stops
+            .sort_by(|left, right| left.position.total_cmp(&right.position));
+    }
+}
+
+/// `Gradient`
+pub(crate) struct Gradient {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// The user's config for the gradient, with its stops sorted.
+    config: Config,
+    /// The size of the terminal that the gradient was last rendered for.
+    rendered_size: Option<(u16, u16)>,
+}
+
+impl Gradient {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Self {
+        let rng = state.tattoy_rng("gradient").await;
+        let tattoy = Tattoyer::new(
+            "gradient".to_owned(),
+            -100,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        let mut config = state.config.read().await.gradient.clone();
+        config.sort_stops();
+        Self {
+            tattoy,
+            config,
+            rendered_size: None,
+        }
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await.run(&protocol_tx).await
+    }
+}
+
+impl Tattoy for Gradient {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        if let crate::run::Protocol::Config(config) = message {
+            self.config = config.gradient.clone();
+            self.config.sort_stops();
+            self.rendered_size = None;
+        }
+    }
+
+    /// Only render when the terminal's size or the config has changed, the compositor keeps the
+    /// last frame.
+    async fn render(&mut self) -> Result<()> {
+        let size = (self.tattoy.width, self.tattoy.height);
+        if !self.tattoy.is_ready() || self.rendered_size == Some(size) {
+            return Ok(());
+        }
+
+        let width = usize::from(size.0);
+        let height = usize::from(size.1) * 2;
+        self.tattoy.initialise_surface();
+        for y in 0..height {
+            for x in 0..width {
+                let position = gradient_position(&self.config, (x, y), (width, height));
+                let colour = colour_at(&self.config.stops, position);
+                self.tattoy.surface.add_pixel(x, y, colour)?;
+            }
+        }
+
+        if self.tattoy.send_output() {
+            self.rendered_size = Some(size);
+        }
+
+        Ok(())
+    }
+}
+
+/// How far along the gradient a pixel is, from 0.0 to 1.0.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "Terminals are far too small to lose precision"
+)]
+fn gradient_position(config: &Config, pixel: (usize, usize), size: (usize, usize)) -> f32 {
+    let half_width = size.0 as f32 / 2.0;
+    let half_height = size.1 as f32 / 2.0;
+    let x = pixel.0 as f32 + 0.5 - half_width;
+    let y = pixel.1 as f32 + 0.5 - half_height;
+
+    let position = match config.kind {
+        Kind::Linear => {
+            let (sin, cos) = config.angle.to_radians().sin_cos();
+            let extent = (half_width * cos).abs() + (half_height * sin).abs();
+            0.5 + (x * cos + y * sin) / (extent.max(f32::EPSILON) * 2.0)
+        }
+        Kind::Radial => {
+            let extent = half_width.hypot(half_height);
+            x.hypot(y) / extent.max(f32::EPSILON)
+        }
+    };
+    position.clamp(0.0, 1.0)
+}
+
+/// The colour at a position along the gradient. The stops must be sorted by their positions.
+fn colour_at(stops: &[Stop], position: f32) -> crate::surface::Colour {
+    let Some(first) = stops.first() else {
+        return (0.0, 0.0, 0.0, 0.0);
+    };
+    if position <= first.position {
+        return first.colour;
+    }
+
+    for pair in stops.windows(2) {
+        let [start, end] = pair else {
+            continue;
+        };
+        if position <= end.position {
+            let span = (end.position - start.position).max(f32::EPSILON);
+            let amount = (position - start.position) / span;
+            return (
+                start.colour.0 + (end.colour.0 - start.colour.0) * amount,
+                start.colour.1 + (end.colour.1 - start.colour.1) * amount,
+                start.colour.2 + (end.colour.2 - start.colour.2) * amount,
+                start.colour.3 + (end.colour.3 - start.colour.3) * amount,
+            );
+        }
+    }
+
+    stops.last().map_or(first.colour, |last| last.colour)
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    const STOPS: [Stop; 2] = [
+        Stop {
+            position: 0.0,
+            colour: (0.0, 0.0, 0.0, 0.0),
+        },
+        Stop {
+            position: 1.0,
+            colour: (1.0, 1.0, 1.0, 1.0),
+        },
+    ];
+
+    #[test]
+    fn interpolating_stops() {
+        assert_eq!(colour_at(&STOPS, 0.0), (0.0, 0.0, 0.0, 0.0));
+        assert_eq!(colour_at(&STOPS, 0.5), (0.5, 0.5, 0.5, 0.5));
+        assert_eq!(colour_at(&STOPS, 1.0), (1.0, 1.0, 1.0, 1.0));
+        assert_eq!(colour_at(&[], 0.5), (0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn stops_are_sorted_by_position() {
+        let mut config = Config {
+            stops: STOPS.into_iter().rev().collect(),
+            ..Config::default()
+        };
+        config.sort_stops();
+        assert_eq!(config.stops, STOPS);
+    }
+
+    #[test]
+    fn linear_positions() {
+        let config = Config {
+            angle: 0.0,
+            ..Config::default()
+        };
+        assert!(gradient_position(&config, (0, 0), (10, 10)) < 0.1);
+        assert!(gradient_position(&config, (9, 0), (10, 10)) > 0.9);
+        assert_eq!(
+            gradient_position(&config, (4, 0), (10, 10)),
+            gradient_position(&config, (4, 9), (10, 10))
+        );
+    }
+
+    #[test]
+    fn radial_positions() {
+        let config = Config {
+            kind: Kind::Radial,
+            ..Config::default()
+        };
+        assert!(gradient_position(&config, (5, 5), (10, 10)) < 0.2);
+        assert!(gradient_position(&config, (0, 0), (10, 10)) > 0.9);
+    }
+}
@@ -4,11 +4,12 @@ use std::sync::Arc;
 
 use color_eyre::eyre::{ContextCompat as _, Result};
 use shadow_terminal::output::SurfaceKind;
+use tracing::Instrument as _;
 
 use super::tattoyer::Tattoyer;
 
 /// User-configurable settings for the minimap
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
 #[serde(default)]
 pub(crate) struct Config {
     /// Enable/disable the minimap
@@ -64,8 +65,10 @@ impl Minimap {
     fn new(
         output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
         state: Arc<crate::shared_state::SharedState>,
+        rng: rand::rngs::StdRng,
+        config: &crate::config::Config,
     ) -> Self {
-        let tattoy = Tattoyer::new("minimap".to_owned(), 90, output_channel);
+        let tattoy = Tattoyer::new("minimap".to_owned(), 90, output_channel, rng, config);
         Self {
             tattoy,
             scrollback: image::ImageBuffer::default(),
@@ -82,7 +85,9 @@ impl Minimap {
         output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
         state: Arc<crate::shared_state::SharedState>,
     ) -> Result<()> {
-        let mut minimap = Self::new(output, state);
+        let rng = state.tattoy_rng("minimap").await;
+        let config = state.config.read().await.clone();
+        let mut minimap = Self::new(output, state, rng, &config);
         let mut protocol = protocol_tx.subscribe();
 
         #[expect(
@@ -91,8 +96,11 @@ impl Minimap {
         )]
         loop {
             tokio::select! {
-                () = minimap.tattoy.sleep_until_next_frame_tick(), if minimap.needs_rerendering() => {
-                    minimap.render().await?;
+                () = minimap.tattoy.sleep_until_next_frame_tick(), if minimap.needs_rerendering() || minimap.tattoy.frame_clock.is_some() => {
+                    if minimap.needs_rerendering() {
+                        let span = minimap.tattoy.tick_span();
+                        minimap.render().instrument(span).await?;
+                    }
                 },
                 result = protocol.recv() => {
                     if matches!(result, Ok(crate::run::Protocol::End)) {
@@ -286,8 +294,9 @@ impl Minimap {
             }
         }
 
-        self.tattoy.send_output().await?;
-        self.output_changed = false;
+        if self.tattoy.send_output() {
+            self.output_changed = false;
+        }
 
         Ok(())
     }
@@ -0,0 +1,227 @@
+//! A small banner, rendered into the terminal, to let the user know that a tattoy failed, or that
+//! their config couldn't be reloaded. Most users won't read the logs, so without this a broken
+//! tattoy would just silently disappear, and a broken config would just silently be ignored.
+
+use color_eyre::eyre::Result;
+
+/// The compositing layer of the notice. It should be above everything else.
+const NOTICE_LAYER: i16 = 1000;
+
+/// How long the notice is fully visible before it starts fading, in milliseconds.
+const NOTICE_DISPLAY_DURATION: u64 = 3000;
+
+/// How long it takes for the notice to fade out, in milliseconds.
+const NOTICE_FADE_DURATION: u64 = 1000;
+
+/// The number of frames used to fade out the notice.
+const NOTICE_FADE_STEPS: u16 = 20;
+
+/// The background colour of the notice.
+const NOTICE_BACKGROUND: crate::surface::Colour = (0.6, 0.0, 0.0, 0.9);
+
+/// A dismissible banner that notifies the user of a failure.
+pub(crate) struct Notice {
+    /// A unique ID for the notice's surface.
+    id: String,
+    /// The text of the notice.
+    text: String,
+    /// A channel to send the notice's frames to the renderer.
+    output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+    /// Shared app state, for the size of the user's terminal.
+    state: std::sync::Arc<crate::shared_state::SharedState>,
+}
+
+impl Notice {
+    /// Show a notice that a tattoy failed, in its own task. It fades away after a few seconds, or
+    /// it can be dismissed early by any user input.
+    pub(crate) fn show_failure(
+        tattoy_id: &str,
+        reason: &str,
+        protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) {
+        let notice = Self {
+            id: format!("notice_{tattoy_id}"),
+            text: format!(" ⚠ The '{tattoy_id}' tattoy failed and was disabled: {reason} "),
+            output_channel,
+            state,
+        };
+        notice.show(protocol_tx);
+    }
+
+    /// Show a notice that the config couldn't be reloaded, in its own task. The previous config
+    /// is still being used.
+    pub(crate) fn show_config_error(
+        reason: &str,
+        protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) {
+        let notice = Self {
+            id: "notice_config".to_owned(),
+            text: format!(
+                " ⚠ The config couldn't be reloaded, the old one is still used: {reason} "
+            ),
+            output_channel,
+            state,
+        };
+        notice.show(protocol_tx);
+    }
+
+    /// Run the notice in its own task.
+    fn show(self, protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>) {
+        let protocol = protocol_tx.subscribe();
+        tokio::spawn(async move {
+            let result = self.run(protocol).await;
+            if let Err(error) = result {
+                tracing::error!("Showing notice: {error:?}");
+            }
+        });
+    }
+
+    /// Display the notice, then fade it out.
+    async fn run(
+        &self,
+        mut protocol: tokio::sync::broadcast::Receiver<crate::run::Protocol>,
+    ) -> Result<()> {
+        let mut deadline = tokio::time::Instant::now()
+            + tokio::time::Duration::from_millis(NOTICE_DISPLAY_DURATION);
+        let fade_step =
+            tokio::time::Duration::from_millis(NOTICE_FADE_DURATION / u64::from(NOTICE_FADE_STEPS));
+
+        self.render(1.0).await?;
+        for step in (0..NOTICE_FADE_STEPS).rev() {
+            if Self::wait_unless_dismissed(&mut protocol, deadline).await {
+                break;
+            }
+
+            deadline = tokio::time::Instant::now() + fade_step;
+            self.render(f32::from(step) / f32::from(NOTICE_FADE_STEPS))
+                .await?;
+        }
+
+        self.render(0.0).await
+    }
+
+    /// Wait until the deadline. Returns `true` if the notice was dismissed in the meantime.
+    async fn wait_unless_dismissed(
+        protocol: &mut tokio::sync::broadcast::Receiver<crate::run::Protocol>,
+        deadline: tokio::time::Instant,
+    ) -> bool {
+        #[expect(
+            clippy::integer_division_remainder_used,
+            reason = "`tokio::select!` generates this."
+        )]
+        loop {
+            tokio::select! {
+                () = tokio::time::sleep_until(deadline) => return false,
+                Ok(message) = protocol.recv() => {
+                    if matches!(
+                        message,
+                        crate::run::Protocol::Input(_) | crate::run::Protocol::End
+                    ) {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send a frame of the notice, with the given opacity, to the renderer.
+    async fn render(&self, opacity: f32) -> Result<()> {
+        let tty_size = self.state.get_tty_size().await;
+        let width = usize::from(tty_size.width);
+        let mut surface = crate::surface::Surface::new(
+            self.id.clone(),
+            width,
+            tty_size.height.into(),
+            NOTICE_LAYER,
+        );
+
+        if opacity > 0.0 {
+            let text: String = self.text.chars().take(width).collect();
+            let mut background = NOTICE_BACKGROUND;
+            background.3 *= opacity;
+            let mut foreground = crate::surface::WHITE;
+            foreground.3 = opacity;
+            surface.add_text(0, 0, text, Some(background), Some(foreground));
+        }
+
+        self.output_channel
+            .send(crate::run::FrameUpdate::TattoySurface(surface))
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::indexing_slicing, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    /// The opacity of the notice's background in a frame, or `None` if the notice isn't shown.
+    fn background_opacity(surface: &crate::surface::Surface) -> Option<f32> {
+        let cell = &surface.surface.screen_cells()[0][0];
+        match cell.attrs().background() {
+            termwiz::color::ColorAttribute::TrueColorWithDefaultFallback(colour) => Some(colour.3),
+            #[expect(
+                clippy::wildcard_enum_match_arm,
+                reason = "The notice only uses true colours"
+            )]
+            _ => None,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_are_shown_then_fade_away() {
+        let (protocol_tx, _) = tokio::sync::broadcast::channel(1);
+        let (output, mut output_rx) = tokio::sync::mpsc::channel(64);
+        let state = std::sync::Arc::new(crate::shared_state::SharedState::default());
+        state.set_tty_size(80, 24).await;
+
+        Notice::show_failure("broken", "oops", &protocol_tx, output, state);
+
+        let mut frames = Vec::new();
+        let mut record = |update| {
+            let crate::run::FrameUpdate::TattoySurface(surface) = update else {
+                panic!("The notice only sends tattoy surfaces");
+            };
+            assert_eq!(surface.id, "notice_broken");
+            let opacity = background_opacity(&surface);
+            frames.push((surface.surface.screen_chars_to_string(), opacity));
+            opacity.is_none()
+        };
+
+        record(output_rx.recv().await.unwrap());
+        // The notice stays fully visible until it's been displayed for long enough.
+        tokio::time::advance(tokio::time::Duration::from_millis(
+            NOTICE_DISPLAY_DURATION - 1,
+        ))
+        .await;
+        assert!(output_rx.try_recv().is_err());
+
+        let fade_step =
+            tokio::time::Duration::from_millis(NOTICE_FADE_DURATION / u64::from(NOTICE_FADE_STEPS));
+        tokio::time::advance(tokio::time::Duration::from_millis(1)).await;
+        loop {
+            let update = output_rx.recv().await.unwrap();
+            if record(update) {
+                break;
+            }
+            tokio::time::advance(fade_step).await;
+        }
+
+        let (first_text, first_opacity) = frames.first().unwrap();
+        assert!(first_text.contains("The 'broken' tattoy failed and was disabled: oops"));
+        assert_eq!(*first_opacity, Some(NOTICE_BACKGROUND.3));
+
+        let opacities: Vec<f32> = frames.iter().filter_map(|frame| frame.1).collect();
+        assert!(opacities.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert_eq!(opacities.len(), usize::from(NOTICE_FADE_STEPS));
+
+        let (last_text, _) = frames.last().unwrap();
+        assert!(last_text.trim().is_empty());
+    }
+}
@@ -3,6 +3,8 @@
 use color_eyre::eyre::Result;
 use rand::Rng as _;
 
+use super::tattoyer::Tattoy as _;
+
 /// `RandomWalker`
 pub struct RandomWalker {
     /// The base Tattoy struct
@@ -21,16 +23,25 @@ const COLOUR_CHANGE_RATE: f32 = 0.3;
 
 impl RandomWalker {
     /// Instatiate
-    fn new(output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>) -> Self {
-        let tattoy =
-            super::tattoyer::Tattoyer::new("random_walker".to_owned(), -10, output_channel);
+    fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        mut rng: rand::rngs::StdRng,
+        config: &crate::config::Config,
+    ) -> Self {
         let position: Position = (0, 0);
         let colour: crate::surface::Colour = (
-            rand::thread_rng().gen_range(0.1..1.0),
-            rand::thread_rng().gen_range(0.1..1.0),
-            rand::thread_rng().gen_range(0.1..1.0),
+            rng.gen_range(0.1..1.0),
+            rng.gen_range(0.1..1.0),
+            rng.gen_range(0.1..1.0),
             1.0,
         );
+        let tattoy = super::tattoyer::Tattoyer::new(
+            "random_walker".to_owned(),
+            -10,
+            output_channel,
+            rng,
+            config,
+        );
 
         Self {
             tattoy,
@@ -43,30 +54,17 @@ impl RandomWalker {
     pub(crate) async fn start(
         protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
         output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
     ) -> Result<()> {
-        let mut random_walker = Self::new(output);
-        let mut protocol = protocol_tx.subscribe();
-
-        #[expect(
-            clippy::integer_division_remainder_used,
-            reason = "This is caused by the `tokio::select!`"
-        )]
-        loop {
-            tokio::select! {
-                () = random_walker.tattoy.sleep_until_next_frame_tick() => {
-                    random_walker.render().await?;
-                },
-                Ok(message) = protocol.recv() => {
-                    if matches!(message, crate::run::Protocol::End) {
-                        break;
-                    }
-                    random_walker.handle_protocol_message(&message);
-                    random_walker.tattoy.handle_common_protocol_messages(message)?;
-                }
-            }
-        }
+        let rng = state.tattoy_rng("random_walker").await;
+        let random_walker = Self::new(output, rng, &*state.config.read().await);
+        random_walker.run(&protocol_tx).await
+    }
+}
 
-        Ok(())
+impl super::tattoyer::Tattoy for RandomWalker {
+    fn tattoyer(&mut self) -> &mut super::tattoyer::Tattoyer {
+        &mut self.tattoy
     }
 
     /// Custom behaviour for protocol messages.
@@ -79,8 +77,8 @@ impl RandomWalker {
         match message {
             crate::run::Protocol::Resize { width, height } => {
                 self.position = (
-                    rand::thread_rng().gen_range(0i32..i32::from(*width)),
-                    rand::thread_rng().gen_range(0i32..i32::from(*height) * 2i32),
+                    self.tattoy.rng.gen_range(0i32..i32::from(*width)),
+                    self.tattoy.rng.gen_range(0i32..i32::from(*height) * 2i32),
                 );
             }
             _ => (),
@@ -96,20 +94,20 @@ impl RandomWalker {
         let width_i32: i32 = self.tattoy.width.into();
         let height_i32: i32 = self.tattoy.height.into();
 
-        self.position.0 += rand::thread_rng().gen_range(0i32..=2i32) - 1i32;
+        self.position.0 += self.tattoy.rng.gen_range(0i32..=2i32) - 1i32;
         self.position.0 = self.position.0.clamp(1i32, width_i32 - 1i32);
 
-        self.position.1 += rand::thread_rng().gen_range(0i32..=2i32) - 1i32;
+        self.position.1 += self.tattoy.rng.gen_range(0i32..=2i32) - 1i32;
         self.position.1 = self.position.1.clamp(1i32, (height_i32 * 2i32) - 1i32);
 
         self.colour.0 +=
-            rand::thread_rng().gen_range(0.0..COLOUR_CHANGE_RATE) - COLOUR_CHANGE_RATE / 2.0;
+            self.tattoy.rng.gen_range(0.0..COLOUR_CHANGE_RATE) - COLOUR_CHANGE_RATE / 2.0;
         self.colour.0 = self.colour.0.clamp(0.0, 1.0);
         self.colour.1 +=
-            rand::thread_rng().gen_range(0.0..COLOUR_CHANGE_RATE) - COLOUR_CHANGE_RATE / 2.0;
+            self.tattoy.rng.gen_range(0.0..COLOUR_CHANGE_RATE) - COLOUR_CHANGE_RATE / 2.0;
         self.colour.1 = self.colour.1.clamp(0.0, 1.0);
         self.colour.2 +=
-            rand::thread_rng().gen_range(0.0..COLOUR_CHANGE_RATE) - COLOUR_CHANGE_RATE / 2.0;
+            self.tattoy.rng.gen_range(0.0..COLOUR_CHANGE_RATE) - COLOUR_CHANGE_RATE / 2.0;
         self.colour.2 = self.colour.2.clamp(0.0, 1.0);
 
         self.tattoy.initialise_surface();
@@ -119,6 +117,8 @@ impl RandomWalker {
             .surface
             .add_pixel(x_usize, y_usize, self.colour)?;
 
-        self.tattoy.send_output().await
+        self.tattoy.send_output();
+
+        Ok(())
     }
 }
@@ -0,0 +1,223 @@
+//! Fade in text as it first appears in the terminal.
+//!
+//! A copy of the text of every cell on the screen is kept, so that the cells that have changed
+//! since the previous frame can be found. Only the rows that the shadow terminal reports as changed
+//! are compared and copied again. Each changed cell starts its own animation, which is restarted if
+//! the cell changes again before it has finished.
+
+use std::collections::HashMap;
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// All the user config for the reveal tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the reveal animation
+    pub enabled: bool,
+    /// How long, in seconds, new text takes to fade in.
+    pub duration: f32,
+    /// How the fade progresses.
+    pub easing: crate::animation::Easing,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration: 0.3,
+            easing: crate::animation::Easing::EaseOut,
+        }
+    }
+}
+
+/// `Reveal`
+pub(crate) struct Reveal {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// Shared app state
+    state: std::sync::Arc<crate::shared_state::SharedState>,
+    /// The user's config for the reveal animation.
+    config: Config,
+    /// The text of every cell in the previous frame, row by row. `None` until the first frame, so
+    /// that the text that's already on the screen when Tattoy starts isn't animated.
+    previous: Option<Vec<Vec<String>>>,
+    /// When each cell that's still fading in last changed, keyed by its column and row.
+    reveals: HashMap<(usize, usize), std::time::Instant>,
+    /// Whether the previous frame that was sent had any cells fading in.
+    was_revealing: bool,
+}
+
+impl Reveal {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Self {
+        let rng = state.tattoy_rng("reveal").await;
+        let tattoy = Tattoyer::new(
+            "reveal".to_owned(),
+            5,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        let config = state.config.read().await.reveal.clone();
+        Self {
+            tattoy,
+            state,
+            config,
+            previous: None,
+            reveals: HashMap::new(),
+            was_revealing: false,
+        }
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await.run(&protocol_tx).await
+    }
+
+    /// Start animating every cell whose text has changed since the previous frame.
+    fn track_changes(&mut self, now: std::time::Instant) {
+        let changed_rows = self.tattoy.take_changed_rows();
+        let cells = self.tattoy.screen.surface.screen_cells();
+        let is_same_size = self.previous.as_ref().is_some_and(|previous| {
+            previous.len() == cells.len()
+                && previous
+                    .iter()
+                    .zip(&cells)
+                    .all(|(old, new)| old.len() == new.len())
+        });
+
+        let Some(previous) = self.previous.as_mut().filter(|_| is_same_size) else {
+            // A resize changes everything, so it isn't animated.
+            self.reveals.clear();
+            self.previous = Some(cells.iter().map(|line| row_text(line)).collect());
+            return;
+        };
+
+        for y in changed_rows {
+            let (Some(old), Some(new)) = (previous.get_mut(y), cells.get(y)) else {
+                continue;
+            };
+            let current = row_text(new);
+            for x in changed_cells(old, &current) {
+                self.reveals.insert((x, y), now);
+            }
+            *old = current;
+        }
+    }
+}
+
+impl Tattoy for Reveal {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        if let crate::run::Protocol::Config(config) = message {
+            self.config = config.reveal.clone();
+        }
+    }
+
+    /// One frame of the tattoy
+    async fn render(&mut self) -> Result<()> {
+        if !self.tattoy.is_ready() {
+            return Ok(());
+        }
+
+        let now = std::time::Instant::now();
+        self.track_changes(now);
+        let duration = self.config.duration.max(f32::EPSILON);
+        self.reveals
+            .retain(|_, changed| now.duration_since(*changed).as_secs_f32() < duration);
+
+        // The compositor keeps the last frame, so one blank frame is needed after the last cell
+        // has faded in.
+        if self.reveals.is_empty() && !self.was_revealing {
+            return Ok(());
+        }
+
+        // Text is faded in from whatever is behind it, which may be another tattoy, like a
+        // background image, not just the PTY's own background colour.
+        self.tattoy.sample_composited_frame(&self.state).await;
+        let behind: HashMap<(usize, usize), termwiz::color::ColorAttribute> = self
+            .reveals
+            .keys()
+            .filter_map(|&(x, y)| {
+                let cell = self.tattoy.composited_cell(x, y)?;
+                Some(((x, y), cell.attrs().background()))
+            })
+            .collect();
+
+        self.tattoy.initialise_surface();
+        let cells = self.tattoy.screen.surface.screen_cells();
+        for (&(x, y), changed) in &self.reveals {
+            let Some(cell) = cells.get(y).and_then(|line| line.get(x)) else {
+                continue;
+            };
+            let behind_colour = behind
+                .get(&(x, y))
+                .copied()
+                .unwrap_or_else(|| cell.attrs().background());
+            let background = crate::opaque_cell::OpaqueCell::extract_colour(behind_colour)
+                .unwrap_or(crate::opaque_cell::DEFAULT_COLOUR);
+            let progress = now.duration_since(*changed).as_secs_f32() / duration;
+            let opacity = 1.0 - self.config.easing.ease(progress);
+            self.tattoy.surface.add_text(
+                x,
+                y,
+                " ".into(),
+                Some((background.0, background.1, background.2, opacity)),
+                None,
+            );
+        }
+
+        // A dropped frame leaves an earlier one showing, so it still needs clearing.
+        let is_sent = self.tattoy.send_output();
+        self.was_revealing = !self.reveals.is_empty() || !is_sent;
+
+        Ok(())
+    }
+}
+
+/// The text of every cell in a row.
+fn row_text(line: &[termwiz::cell::Cell]) -> Vec<String> {
+    line.iter().map(|cell| cell.str().to_owned()).collect()
+}
+
+/// The column of every cell in a row that now has different, non-blank, text. Only cells that gain
+/// text are revealed, cells that are cleared just disappear.
+fn changed_cells(previous: &[String], current: &[String]) -> Vec<usize> {
+    let mut changed = Vec::new();
+    for (x, (old, new)) in previous.iter().zip(current).enumerate() {
+        let is_blank = new.is_empty() || new == " ";
+        if old != new && !is_blank {
+            changed.push(x);
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn row(text: &str) -> Vec<String> {
+        text.chars()
+            .map(|character| character.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn only_cells_that_gain_text_are_revealed() {
+        assert_eq!(changed_cells(&row("ab  "), &row("a cd")), vec![2, 3]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Ripples that spread out from the cursor whenever a character is typed.
+//!
+//! Each typed character starts a ring at the cursor, which is where the character is about to
+//! appear. The ring grows out to its maximum radius, fading as it goes. It's drawn below the
+//! terminal's text by default, so that it only ever lights up the background.
+
+use color_eyre::eyre::Result;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// The most rings that can be spreading at once. Fast typing would otherwise start far more rings
+/// than can be seen anyway.
+const MAX_RINGS: usize = 32;
+
+/// All the user config for the ripple tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the ripples
+    pub enabled: bool,
+    /// The colour of the rings, as red, green, blue and alpha, each from 0.0 to 1.0.
+    pub colour: crate::surface::Colour,
+    /// How fast, in columns per second, the rings spread out.
+    pub speed: f32,
+    /// How far, in columns, the rings spread before they've faded away completely.
+    pub max_radius: f32,
+    /// The compositing layer. Negative values are below the terminal's text, positive values are
+    /// above it.
+    pub layer: i16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            colour: (0.5, 0.8, 1.0, 0.6),
+            speed: 20.0,
+            max_radius: 6.0,
+            layer: -3,
+        }
+    }
+}
+
+/// A single ring, spreading out from where a character was typed.
+struct Ring {
+    /// The cell that the ring started from.
+    centre: (usize, usize),
+    /// The radius of the ring, in columns.
+    radius: crate::animation::Tween<f32>,
+}
+
+impl Ring {
+    /// Start a ring that spreads out from the given cell. `None` if the config can't make a ring.
+    fn new(centre: (usize, usize), config: &Config) -> Option<Self> {
+        let max_radius = config.max_radius.max(0.0);
+        let seconds = max_radius / config.speed;
+        let Ok(duration) = std::time::Duration::try_from_secs_f32(seconds) else {
+            tracing::error!(
+                "Invalid ripple speed or radius: {}, {}",
+                config.speed,
+                config.max_radius
+            );
+            return None;
+        };
+
+        Some(Self {
+            centre,
+            radius: crate::animation::Tween::new(
+                0.0,
+                max_radius,
+                duration,
+                crate::animation::Easing::EaseOut,
+            ),
+        })
+    }
+
+    /// How visible the ring is. It fades out as it spreads.
+    fn opacity(&self) -> f32 {
+        1.0 - self.radius.progress()
+    }
+}
+
+/// `Ripple`
+pub(crate) struct Ripple {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// The user's config for the ripples.
+    config: Config,
+    /// All the rings that are still spreading.
+    rings: Vec<Ring>,
+    /// Whether the compositor may still be showing rings, so that they need clearing.
+    is_drawn: bool,
+    /// When the previous frame was rendered.
+    last_tick: std::time::Instant,
+}
+
+impl Ripple {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Self {
+        let rng = state.tattoy_rng("ripple").await;
+        let config = state.config.read().await.ripple.clone();
+        let tattoy = Tattoyer::new(
+            "ripple".to_owned(),
+            config.layer,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        Self {
+            tattoy,
+            config,
+            rings: Vec::new(),
+            is_drawn: false,
+            last_tick: std::time::Instant::now(),
+        }
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await.run(&protocol_tx).await
+    }
+
+    /// Start a new ring at the cursor.
+    fn spawn_ring(&mut self) {
+        // The cursor isn't where it appears to be whilst scrolling.
+        if self.tattoy.is_scrolling() {
+            return;
+        }
+
+        let cursor = self.tattoy.screen.surface.cursor_position();
+        let Some(ring) = Ring::new(cursor, &self.config) else {
+            return;
+        };
+        if self.rings.len() >= MAX_RINGS {
+            self.rings.remove(0);
+        }
+        self.rings.push(ring);
+    }
+}
+
+impl Tattoy for Ripple {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        #[expect(
+            clippy::wildcard_enum_match_arm,
+            reason = "We only react to typing and config"
+        )]
+        match message {
+            crate::run::Protocol::Input(input) if is_typed_character(&input.event) => {
+                self.spawn_ring();
+            }
+            crate::run::Protocol::Config(config) => {
+                self.config = config.ripple.clone();
+                self.tattoy.layer = self.config.layer;
+            }
+            _ => (),
+        }
+    }
+
+    /// Only render whilst there are rings, the compositor keeps the last frame.
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        reason = "Terminals are far too small to lose precision"
+    )]
+    async fn render(&mut self) -> Result<()> {
+        let now = std::time::Instant::now();
+        let delta = now.saturating_duration_since(self.last_tick);
+        self.last_tick = now;
+
+        if !self.tattoy.is_ready() || (self.rings.is_empty() && !self.is_drawn) {
+            return Ok(());
+        }
+
+        self.tattoy.initialise_surface();
+        let (red, green, blue, alpha) = self.config.colour;
+        for ring in &mut self.rings {
+            let radius = ring.radius.advance(delta);
+            let style = crate::draw::Style {
+                colour: (red, green, blue, alpha * ring.opacity()),
+                ..crate::draw::Style::default()
+            };
+            // Centred in the middle of the cell.
+            let centre = (ring.centre.0 as f32 + 0.5, ring.centre.1 as f32 + 0.5);
+            crate::draw::circle(&mut self.tattoy.surface, centre, radius, &style);
+        }
+        self.rings.retain(|ring| !ring.radius.is_finished());
+        // When the frame is dropped, the compositor is still showing an earlier frame.
+        let is_sent = self.tattoy.send_output();
+        self.is_drawn = !self.rings.is_empty() || !is_sent;
+
+        Ok(())
+    }
+}
+
+/// Whether the input is a key that types a character.
+fn is_typed_character(event: &termwiz::input::InputEvent) -> bool {
+    let termwiz::input::InputEvent::Key(key) = event else {
+        return false;
+    };
+    let is_unmodified =
+        key.modifiers.is_empty() || key.modifiers == termwiz::input::Modifiers::SHIFT;
+    matches!(key.key, termwiz::input::KeyCode::Char(character) if !character.is_control())
+        && is_unmodified
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(key: termwiz::input::KeyCode, modifiers: termwiz::input::Modifiers) -> bool {
+        is_typed_character(&termwiz::input::InputEvent::Key(termwiz::input::KeyEvent {
+            key,
+            modifiers,
+        }))
+    }
+
+    #[test]
+    fn only_typed_characters_start_ripples() {
+        let none = termwiz::input::Modifiers::NONE;
+        assert!(key(termwiz::input::KeyCode::Char('a'), none));
+        assert!(key(
+            termwiz::input::KeyCode::Char('A'),
+            termwiz::input::Modifiers::SHIFT
+        ));
+        assert!(!key(
+            termwiz::input::KeyCode::Char('c'),
+            termwiz::input::Modifiers::CTRL
+        ));
+        assert!(!key(termwiz::input::KeyCode::Enter, none));
+        assert!(!key(termwiz::input::KeyCode::Char('\t'), none));
+    }
+
+    #[test]
+    fn rings_spread_out_and_fade_away() {
+        let config = Config {
+            speed: 10.0,
+            max_radius: 5.0,
+            ..Config::default()
+        };
+        let mut ring = Ring::new((3, 3), &config).unwrap();
+        assert!(ring.opacity() > 0.99);
+
+        let halfway = ring.radius.advance(std::time::Duration::from_millis(250));
+        assert!(halfway > 0.0 && halfway < 5.0);
+        assert!(ring.opacity() > 0.0 && ring.opacity() < 1.0);
+
+        let end = ring.radius.advance(std::time::Duration::from_millis(250));
+        assert!((end - 5.0).abs() < f32::EPSILON);
+        assert!(ring.radius.is_finished());
+        assert!(ring.opacity() < f32::EPSILON);
+
+        let stopped = Config {
+            speed: 0.0,
+            ..Config::default()
+        };
+        assert!(Ring::new((0, 0), &stopped).is_none());
+    }
+}
@@ -0,0 +1,363 @@
+//! A screensaver that takes over the whole terminal once it's been idle for a while.
+//!
+//! It's drawn above everything else, including the PTY, so it never touches the terminal's actual
+//! state. Any input dismisses it with a blank frame, which leaves the terminal exactly as it was.
+//! The input itself still goes to the PTY as normal.
+
+use color_eyre::eyre::Result;
+use rand::Rng as _;
+
+use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+/// How often to check whether the terminal has become idle, whilst the screensaver isn't showing.
+/// There's no need to check at the full frame rate.
+const IDLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// The characters that the rain is made of. They're half width katakana and digits, so that each
+/// is a single column wide.
+const GLYPHS: &[char] = &[
+    'ｱ', 'ｲ', 'ｳ', 'ｴ', 'ｵ', 'ｶ', 'ｷ', 'ｸ', 'ｹ', 'ｺ', 'ｻ', 'ｼ', 'ｽ', 'ｾ', 'ｿ', 'ﾀ', 'ﾁ', 'ﾂ', 'ﾃ',
+    'ﾄ', 'ﾅ', 'ﾆ', 'ﾇ', 'ﾈ', 'ﾉ', 'ﾊ', 'ﾋ', 'ﾌ', 'ﾍ', 'ﾎ', '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9',
+];
+
+/// The range of speeds, in rows per second, that the rain falls at.
+const RAIN_SPEEDS: std::ops::Range<f32> = 6.0..20.0;
+
+/// The range of lengths, in rows, of the rain's trails.
+const TRAIL_LENGTHS: std::ops::Range<f32> = 4.0..16.0;
+
+/// The colour of the rain's trails.
+const RAIN_COLOUR: crate::surface::Colour = (0.2, 1.0, 0.4, 1.0);
+
+/// The colour of the leading character of each trail.
+const HEAD_COLOUR: crate::surface::Colour = (0.8, 1.0, 0.85, 1.0);
+
+/// The effect that the screensaver shows.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Effect {
+    /// Green characters raining down the screen.
+    #[default]
+    Matrix,
+    /// Smoothly swirling colours.
+    Plasma,
+}
+
+/// All the user config for the screensaver tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Enable/disable the screensaver
+    pub enabled: bool,
+    /// The number of seconds without any input or output before the screensaver starts.
+    pub timeout: u64,
+    /// The effect to show.
+    pub effect: Effect,
+    /// The compositing layer. It should be above every other tattoy, so that it covers them.
+    pub layer: i16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout: 300,
+            effect: Effect::default(),
+            layer: 100,
+        }
+    }
+}
+
+/// A single column of rain.
+#[derive(Debug, Clone, Copy)]
+struct Raindrop {
+    /// How far, in rows, the drop had already fallen when the screensaver started.
+    offset: f32,
+    /// How fast the drop falls, in rows per second.
+    speed: f32,
+    /// The length of the drop's trail, in rows.
+    length: f32,
+}
+
+impl Raindrop {
+    /// A new drop, somewhere above or on the screen, with a random speed and length.
+    fn random(rng: &mut rand::rngs::StdRng, height: u16) -> Self {
+        Self {
+            offset: rng.gen_range(0.0..f32::from(height.max(1))),
+            speed: rng.gen_range(RAIN_SPEEDS),
+            length: rng.gen_range(TRAIL_LENGTHS),
+        }
+    }
+
+    /// How bright the drop's trail is in the given row, from 1.0 at its head down to 0.0 at its
+    /// tail. `None` when the trail isn't in the row. Once the whole trail has fallen off the
+    /// bottom of the screen, the drop starts again from the top.
+    fn brightness(&self, elapsed: f32, height: u16, row: u16) -> Option<f32> {
+        let fallen = self.speed.mul_add(elapsed, self.offset);
+        let head = fallen.rem_euclid(f32::from(height) + self.length);
+        let distance = head - f32::from(row);
+        (0.0..self.length)
+            .contains(&distance)
+            .then(|| 1.0 - distance / self.length)
+    }
+}
+
+/// `Screensaver`
+pub(crate) struct Screensaver {
+    /// The base Tattoy struct
+    tattoy: Tattoyer,
+    /// The user's config for the screensaver.
+    config: Config,
+    /// Shared app state, for when there was last any input or output.
+    state: std::sync::Arc<crate::shared_state::SharedState>,
+    /// When the screensaver started showing. `None` whilst it isn't showing.
+    started: Option<std::time::Instant>,
+    /// Whether there's been input since the screensaver started showing.
+    is_dismissing: bool,
+    /// The rain, one drop for each column.
+    drops: Vec<Raindrop>,
+}
+
+impl Screensaver {
+    /// Instantiate
+    async fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Self {
+        let rng = state.tattoy_rng("screensaver").await;
+        let config = state.config.read().await.screensaver.clone();
+        let mut tattoy = Tattoyer::new(
+            "screensaver".to_owned(),
+            config.layer,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
+        tattoy.frame_interval = Some(IDLE_CHECK_INTERVAL);
+        Self {
+            tattoy,
+            config,
+            state,
+            started: None,
+            is_dismissing: false,
+            drops: Vec::new(),
+        }
+    }
+
+    /// Our main entrypoint.
+    pub(crate) async fn start(
+        protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+        output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        Self::new(output, state).await.run(&protocol_tx).await
+    }
+
+    /// Start showing the screensaver, at the full frame rate.
+    fn show(&mut self) -> std::time::Instant {
+        tracing::debug!("Terminal is idle, starting the screensaver");
+        self.drops.clear();
+        self.tattoy.frame_interval = None;
+        std::time::Instant::now()
+    }
+
+    /// Stop showing the screensaver. The blank frame reveals the untouched terminal underneath.
+    /// Returns whether the blank frame was accepted, if it wasn't, dismissing has to be tried
+    /// again on the next frame.
+    fn dismiss(&mut self) -> bool {
+        tracing::debug!("Dismissing the screensaver");
+        if !self.tattoy.send_blank_output() {
+            return false;
+        }
+        self.tattoy.frame_interval = Some(IDLE_CHECK_INTERVAL);
+        true
+    }
+
+    /// Whether there's been no input or output for the configured timeout. Idleness is measured
+    /// from the same activity that the renderer dims the terminal by.
+    async fn is_idle(&self) -> bool {
+        let timeout = std::time::Duration::from_secs(self.config.timeout);
+        self.state
+            .last_activity
+            .read()
+            .await
+            .is_some_and(|last_activity| last_activity.elapsed() >= timeout)
+    }
+
+    /// Draw the rain over every cell, so that nothing underneath shows through.
+    fn draw_matrix(&mut self, elapsed: f32) {
+        let (width, height) = (self.tattoy.width, self.tattoy.height);
+        let rng = &mut self.tattoy.rng;
+        self.drops
+            .resize_with(usize::from(width), || Raindrop::random(rng, height));
+
+        for (x, drop) in self.drops.iter().enumerate() {
+            for row in 0..height {
+                let brightness = drop.brightness(elapsed, height, row);
+                let glyph = brightness.map_or(' ', |_| {
+                    let index = self.tattoy.rng.gen_range(0..GLYPHS.len());
+                    GLYPHS.get(index).copied().unwrap_or(' ')
+                });
+                let colour = match brightness {
+                    Some(bright) if bright > 1.0 - 1.0 / drop.length => HEAD_COLOUR,
+                    Some(bright) => (RAIN_COLOUR.0, RAIN_COLOUR.1, RAIN_COLOUR.2, bright),
+                    None => RAIN_COLOUR,
+                };
+                self.tattoy.surface.add_text(
+                    x,
+                    usize::from(row),
+                    glyph.into(),
+                    Some(crate::surface::BLACK),
+                    Some(colour),
+                );
+            }
+        }
+    }
+
+    /// Draw the plasma with half block pixels, so that it's twice as smooth vertically.
+    fn draw_plasma(&mut self, elapsed: f32) -> Result<()> {
+        for x in 0..self.tattoy.width {
+            for row in 0..self.tattoy.height {
+                for half in 0..2u8 {
+                    let y = f32::from(row).mul_add(2.0, f32::from(half));
+                    let colour = plasma_colour(f32::from(x), y, elapsed);
+                    let pixel_y = usize::from(row) * 2 + usize::from(half);
+                    self.tattoy
+                        .surface
+                        .add_pixel(usize::from(x), pixel_y, colour)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Tattoy for Screensaver {
+    fn tattoyer(&mut self) -> &mut Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        #[expect(
+            clippy::wildcard_enum_match_arm,
+            reason = "We only react to activity and config"
+        )]
+        match message {
+            // Only input dismisses the screensaver. Output still keeps it from starting, through
+            // the shared activity, but if output dismissed it then programs that update by
+            // themselves, like clocks, would never let it show.
+            crate::run::Protocol::Input(_) if self.started.is_some() => self.is_dismissing = true,
+            crate::run::Protocol::Config(config) => {
+                self.config = config.screensaver.clone();
+                self.tattoy.layer = self.config.layer;
+            }
+            _ => (),
+        }
+    }
+
+    async fn render(&mut self) -> Result<()> {
+        if !self.tattoy.is_ready() {
+            return Ok(());
+        }
+
+        if self.is_dismissing {
+            if self.dismiss() {
+                self.started = None;
+                self.is_dismissing = false;
+            }
+            return Ok(());
+        }
+
+        if self.started.is_none() && !self.is_idle().await {
+            return Ok(());
+        }
+
+        let started = match self.started {
+            Some(started) => started,
+            None => *self.started.insert(self.show()),
+        };
+        let elapsed = started.elapsed().as_secs_f32();
+        self.tattoy.initialise_surface();
+        match self.config.effect {
+            Effect::Matrix => self.draw_matrix(elapsed),
+            Effect::Plasma => self.draw_plasma(elapsed)?,
+        }
+
+        self.tattoy.send_output();
+
+        Ok(())
+    }
+}
+
+/// The colour of the plasma at the given pixel. It's the sum of a few sine waves, travelling in
+/// different directions, then mapped around the colour wheel.
+fn plasma_colour(x: f32, y: f32, time: f32) -> crate::surface::Colour {
+    let value = (x / 16.0 + time).sin()
+        + time.mul_add(-0.7, y / 8.0).sin()
+        + time.mul_add(0.5, (x + y) / 24.0).sin()
+        + (x.hypot(y) / 12.0 - time).sin();
+    let phase = value * std::f32::consts::FRAC_PI_2;
+    let third = std::f32::consts::TAU / 3.0;
+    let channel = |offset: f32| (phase + offset).sin().mul_add(0.5, 0.5);
+    (channel(0.0), channel(third), channel(third * 2.0), 1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rain_falls_and_starts_again_from_the_top() {
+        let drop = Raindrop {
+            offset: 0.0,
+            speed: 10.0,
+            length: 4.0,
+        };
+        assert_eq!(drop.brightness(0.5, 10, 5), Some(1.0));
+        assert_eq!(drop.brightness(0.5, 10, 2), Some(0.25));
+        assert_eq!(drop.brightness(0.5, 10, 6), None);
+        assert_eq!(drop.brightness(0.5, 10, 1), None);
+
+        // The trail has to leave the screen completely before it wraps.
+        assert_eq!(drop.brightness(1.25, 10, 9), Some(0.125));
+        assert_eq!(drop.brightness(1.5, 10, 1), Some(1.0));
+        assert_eq!(drop.brightness(1.5, 10, 9), None);
+    }
+
+    #[test]
+    fn every_digit_can_rain() {
+        assert!(('0'..='9').all(|digit| GLYPHS.contains(&digit)));
+    }
+
+    #[tokio::test]
+    async fn idleness_is_measured_from_the_shared_activity() {
+        let state = std::sync::Arc::new(crate::shared_state::SharedState::default());
+        let (output, _) = tokio::sync::mpsc::channel(1);
+        let mut screensaver = Screensaver::new(output, std::sync::Arc::clone(&state)).await;
+        screensaver.config.timeout = 1;
+        assert!(!screensaver.is_idle().await);
+
+        let long_ago = std::time::Instant::now()
+            .checked_sub(std::time::Duration::from_secs(2))
+            .unwrap();
+        *state.last_activity.write().await = Some(long_ago);
+        assert!(screensaver.is_idle().await);
+
+        *state.last_activity.write().await = Some(std::time::Instant::now());
+        assert!(!screensaver.is_idle().await);
+    }
+
+    #[test]
+    fn plasma_colours_are_always_valid() {
+        for step in 0..100u8 {
+            let coord = f32::from(step) * 1.7;
+            let (red, green, blue, alpha) = plasma_colour(coord, coord * 0.3, coord * 0.1);
+            for channel in [red, green, blue] {
+                assert!((0.0..=1.0).contains(&channel));
+            }
+            assert!((alpha - 1.0).abs() < f32::EPSILON);
+        }
+    }
+}
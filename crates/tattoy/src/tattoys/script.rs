@@ -0,0 +1,386 @@
+//! Simple effects written as Rhai scripts, for when you don't want to compile any Rust.
+//!
+//! The script must define a `frame` function, it's called once per frame:
+//!
+//! ```rhai
+//! fn frame(width, height, cursor_x, cursor_y) {
+//!     set_cell(cursor_x, cursor_y, "*", 1.0, 0.5, 0.0, 1.0);
+//! }
+//! ```
+//!
+//! Scripts can read the text of the terminal with `get_cell(x, y)`, and draw with
+//! `set_cell(x, y, text, red, green, blue, alpha)`. Each frame has a limit on both the number of
+//! operations and the time that the script can take, so a bad script can't hang Tattoy. The
+//! script is reloaded whenever Tattoy's config directory changes.
+//!
+//! Scripting is only available when Tattoy is built with the `script` feature.
+
+/// All the user config for the script tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+#[cfg_attr(
+    not(feature = "script"),
+    expect(dead_code, reason = "Only the `script` feature runs scripts")
+)]
+pub(crate) struct Config {
+    /// Enable/disable the script
+    pub enabled: bool,
+    /// The path to the Rhai script. Is relative to Tattoy's config directory.
+    pub path: std::path::PathBuf,
+    /// The maximum number of operations that the script can run each frame.
+    pub max_operations: u64,
+    /// The maximum number of milliseconds that the script can take to render a frame.
+    pub frame_budget: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "script.rhai".into(),
+            max_operations: 1_000_000,
+            frame_budget: 10,
+        }
+    }
+}
+
+#[cfg(feature = "script")]
+pub(crate) use host::Script;
+
+/// The script host, which needs the `rhai` dependency.
+#[cfg(feature = "script")]
+mod host {
+    use std::sync::{Arc, Mutex};
+
+    use color_eyre::eyre::Result;
+
+    use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+    /// A cell that the script has drawn.
+    type DrawnCell = (usize, usize, String, crate::surface::Colour);
+
+    /// The data that the script's functions read from and write to during a frame.
+    #[derive(Default)]
+    struct Frame {
+        /// The width of the terminal.
+        width: usize,
+        /// The text of every cell of the terminal's screen, row by row.
+        screen: Vec<String>,
+        /// The cells that the script has drawn.
+        drawn: Vec<DrawnCell>,
+        /// When the script started rendering the current frame.
+        started: Option<std::time::Instant>,
+    }
+
+    /// `Script`
+    pub(crate) struct Script {
+        /// The base Tattoy struct
+        tattoy: Tattoyer,
+        /// Shared app state
+        state: Arc<crate::shared_state::SharedState>,
+        /// The script engine, with our functions registered.
+        engine: rhai::Engine,
+        /// The compiled script.
+        ast: rhai::AST,
+        /// The data shared with the script's functions.
+        frame: Arc<Mutex<Frame>>,
+        /// Whether the config has changed since the script was last loaded.
+        is_reload_pending: bool,
+        /// The script's most recent error. A broken script fails in the same way on every frame,
+        /// so each error is only logged when it first happens.
+        last_error: Option<String>,
+    }
+
+    impl Script {
+        /// Instantiate
+        async fn new(
+            output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+            state: Arc<crate::shared_state::SharedState>,
+        ) -> Result<Self> {
+            let rng = state.tattoy_rng("script").await;
+            let tattoy = Tattoyer::new(
+                "script".to_owned(),
+                -10,
+                output_channel,
+                rng,
+                &*state.config.read().await,
+            );
+            let config = state.config.read().await.script.clone();
+            let frame = Arc::new(Mutex::new(Frame::default()));
+            let engine = Self::build_engine(&config, &frame);
+            let ast = Self::compile(&engine, &state).await?;
+
+            Ok(Self {
+                tattoy,
+                state,
+                engine,
+                ast,
+                frame,
+                is_reload_pending: false,
+                last_error: None,
+            })
+        }
+
+        /// Our main entrypoint.
+        pub(crate) async fn start(
+            protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+            output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+            state: Arc<crate::shared_state::SharedState>,
+        ) -> Result<()> {
+            Self::new(output, state).await?.run(&protocol_tx).await
+        }
+
+        /// Create the script engine and register the functions that scripts can call.
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_possible_truncation,
+            reason = "Colours are only ever between 0.0 and 1.0"
+        )]
+        fn build_engine(config: &super::Config, frame: &Arc<Mutex<Frame>>) -> rhai::Engine {
+            let mut engine = rhai::Engine::new();
+            engine.set_max_operations(config.max_operations);
+
+            let budget = std::time::Duration::from_millis(config.frame_budget);
+            let progress_frame = Arc::clone(frame);
+            engine.on_progress(move |_| {
+                let started = progress_frame.lock().ok()?.started?;
+                (started.elapsed() > budget).then(|| "Script exceeded its frame budget".into())
+            });
+
+            let get_frame = Arc::clone(frame);
+            engine.register_fn("get_cell", move |x: i64, y: i64| -> String {
+                let Ok(frame) = get_frame.lock() else {
+                    return String::new();
+                };
+                let (Ok(x_usize), Ok(y_usize)) = (usize::try_from(x), usize::try_from(y)) else {
+                    return String::new();
+                };
+                if x_usize >= frame.width {
+                    return String::new();
+                }
+                frame
+                    .screen
+                    .get(y_usize * frame.width + x_usize)
+                    .cloned()
+                    .unwrap_or_default()
+            });
+
+            let set_frame = Arc::clone(frame);
+            engine.register_fn(
+                "set_cell",
+                move |x: i64,
+                      y: i64,
+                      text: &str,
+                      red: rhai::FLOAT,
+                      green: rhai::FLOAT,
+                      blue: rhai::FLOAT,
+                      alpha: rhai::FLOAT| {
+                    let (Ok(x_usize), Ok(y_usize)) = (usize::try_from(x), usize::try_from(y))
+                    else {
+                        return;
+                    };
+                    if let Ok(mut frame) = set_frame.lock() {
+                        let colour = (red as f32, green as f32, blue as f32, alpha as f32);
+                        frame
+                            .drawn
+                            .push((x_usize, y_usize, text.to_owned(), colour));
+                    }
+                },
+            );
+
+            engine
+        }
+
+        /// Load and compile the user's script.
+        async fn compile(
+            engine: &rhai::Engine,
+            state: &Arc<crate::shared_state::SharedState>,
+        ) -> Result<rhai::AST> {
+            let path = state
+                .config_path
+                .read()
+                .await
+                .join(&state.config.read().await.script.path);
+            tracing::info!("Loading script from: {path:?}");
+            engine
+                .compile_file(path.clone())
+                .map_err(|error| color_eyre::eyre::eyre!("Couldn't compile {path:?}: {error}"))
+        }
+
+        /// Check that the script compiles and defines its `frame` function, without starting the
+        /// tattoy.
+        pub(crate) async fn check_setup(
+            state: Arc<crate::shared_state::SharedState>,
+        ) -> Result<()> {
+            let config = state.config.read().await.script.clone();
+            let engine = Self::build_engine(&config, &Arc::default());
+            let ast = Self::compile(&engine, &state).await?;
+            let has_frame_function = ast
+                .iter_functions()
+                .any(|function| function.name == "frame" && function.params.len() == 4);
+            if !has_frame_function {
+                color_eyre::eyre::bail!(
+                    "The script doesn't define `fn frame(width, height, cursor_x, cursor_y)`"
+                );
+            }
+
+            Ok(())
+        }
+
+        /// Reload the script after the config directory has changed. A script that doesn't
+        /// compile is logged and the previous one is kept, so that mistakes made whilst editing
+        /// don't kill the tattoy.
+        async fn reload(&mut self) {
+            let config = self.state.config.read().await.script.clone();
+            self.engine = Self::build_engine(&config, &self.frame);
+            match Self::compile(&self.engine, &self.state).await {
+                Ok(ast) => {
+                    self.ast = ast;
+                    self.last_error = None;
+                }
+                Err(error) => self.log_error(format!("Reloading script: {error:?}")),
+            }
+        }
+
+        /// Log an error, unless it's the same as the script's previous error.
+        fn log_error(&mut self, error: String) {
+            if self.last_error.as_ref() != Some(&error) {
+                tracing::error!("{error}");
+                self.last_error = Some(error);
+            }
+        }
+
+        /// Give the script the current state of the terminal.
+        fn prepare_frame(&mut self) -> Result<()> {
+            let width = usize::from(self.tattoy.width);
+            let screen = self
+                .tattoy
+                .screen
+                .surface
+                .screen_cells()
+                .iter()
+                .flat_map(|line| line.iter().map(|cell| cell.str().to_owned()))
+                .collect();
+
+            let mut frame = self
+                .frame
+                .lock()
+                .map_err(|error| color_eyre::eyre::eyre!("{error:?}"))?;
+            frame.width = width;
+            frame.screen = screen;
+            frame.drawn.clear();
+            frame.started = Some(std::time::Instant::now());
+            Ok(())
+        }
+    }
+
+    impl Tattoy for Script {
+        fn tattoyer(&mut self) -> &mut Tattoyer {
+            &mut self.tattoy
+        }
+
+        fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+            if matches!(message, crate::run::Protocol::Config(_)) {
+                self.is_reload_pending = true;
+            }
+        }
+
+        /// Tick the render
+        async fn render(&mut self) -> Result<()> {
+            if self.is_reload_pending {
+                self.is_reload_pending = false;
+                self.reload().await;
+            }
+            if !self.tattoy.is_ready() {
+                return Ok(());
+            }
+
+            self.prepare_frame()?;
+            let cursor = self.tattoy.screen.surface.cursor_position();
+            let arguments = (
+                rhai::INT::from(self.tattoy.width),
+                rhai::INT::from(self.tattoy.height),
+                rhai::INT::try_from(cursor.0)?,
+                rhai::INT::try_from(cursor.1)?,
+            );
+            let result = self.engine.call_fn::<rhai::Dynamic>(
+                &mut rhai::Scope::new(),
+                &self.ast,
+                "frame",
+                arguments,
+            );
+            match result {
+                Ok(_) => self.last_error = None,
+                Err(error) => self.log_error(format!("Running script: {error}")),
+            }
+
+            let drawn = std::mem::take(
+                &mut self
+                    .frame
+                    .lock()
+                    .map_err(|error| color_eyre::eyre::eyre!("{error:?}"))?
+                    .drawn,
+            );
+            self.tattoy.initialise_surface();
+            for (x, y, text, colour) in drawn {
+                self.tattoy.surface.add_text(x, y, text, None, Some(colour));
+            }
+
+            self.tattoy.send_output();
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        async fn script(source: &str, max_operations: u64) -> Script {
+            let config_directory = tempfile::tempdir().unwrap();
+            std::fs::write(config_directory.path().join("script.rhai"), source).unwrap();
+            let state = Arc::new(crate::shared_state::SharedState::default());
+            *state.config_path.write().await = config_directory.path().to_path_buf();
+            state.config.write().await.script.max_operations = max_operations;
+            let (output, _output_rx) = tokio::sync::mpsc::channel(1);
+
+            let mut script = Script::new(output, state).await.unwrap();
+            script.tattoy.width = 10;
+            script.tattoy.height = 5;
+            script
+        }
+
+        #[tokio::test]
+        async fn scripts_draw_cells() {
+            let mut script = script(
+                r#"fn frame(width, height, cursor_x, cursor_y) {
+                    set_cell(width - 1, 2, "*", 1.0, 0.0, 0.0, 1.0);
+                }"#,
+                1_000,
+            )
+            .await;
+
+            script.render().await.unwrap();
+            assert!(script.last_error.is_none());
+            let cells = script.tattoy.surface.surface.screen_cells();
+            let cell = cells.get(2).and_then(|line| line.get(9)).unwrap();
+            assert_eq!(cell.str(), "*");
+        }
+
+        #[tokio::test]
+        async fn scripts_are_stopped_at_their_operations_limit() {
+            let mut script = script(
+                "fn frame(width, height, cursor_x, cursor_y) { loop {} }",
+                1_000,
+            )
+            .await;
+
+            script.render().await.unwrap();
+            let error = script.last_error.clone().unwrap();
+            assert!(error.contains("Too many operations"), "{error}");
+
+            script.render().await.unwrap();
+            assert_eq!(script.last_error, Some(error));
+        }
+    }
+}
@@ -2,25 +2,50 @@
 
 use color_eyre::eyre::Result;
 
+/// How long to wait before resending a frame that the renderer was too busy to accept. The
+/// scrollbar only renders when the scroll position changes, so without retrying, a dropped frame
+/// would leave a stale scrollbar on the screen.
+const RETRY_DROPPED_FRAME_AFTER: std::time::Duration = std::time::Duration::from_millis(30);
+
 /// `Scrollbar`
 pub(crate) struct Scrollbar {
     /// The base Tattoy struct
     tattoy: super::tattoyer::Tattoyer,
+    /// Whether to smooth the ends of the scrollbar.
+    is_antialiased: bool,
+    /// Whether the last frame was dropped by the renderer and needs sending again.
+    is_frame_pending: bool,
 }
 
 impl Scrollbar {
     /// Instantiate
-    fn new(output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>) -> Self {
-        let tattoy = super::tattoyer::Tattoyer::new("scrollbar".to_owned(), 100, output_channel);
-        Self { tattoy }
+    fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        rng: rand::rngs::StdRng,
+        config: &crate::config::Config,
+    ) -> Self {
+        let tattoy = super::tattoyer::Tattoyer::new(
+            "scrollbar".to_owned(),
+            100,
+            output_channel,
+            rng,
+            config,
+        );
+        Self {
+            tattoy,
+            is_antialiased: config.antialiasing,
+            is_frame_pending: false,
+        }
     }
 
     /// Our main entrypoint.
     pub(crate) async fn start(
         protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
         output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
     ) -> Result<()> {
-        let mut scrollbar = Self::new(output);
+        let rng = state.tattoy_rng("scrollbar").await;
+        let mut scrollbar = Self::new(output, rng, &*state.config.read().await);
         let mut protocol = protocol_tx.subscribe();
 
         #[expect(
@@ -33,8 +58,15 @@ impl Scrollbar {
                     if matches!(result, Ok(crate::run::Protocol::End)) {
                         break;
                     }
-                    scrollbar.handle_protocol_message(result).await?;
+                    scrollbar.handle_protocol_message(result)?;
                 }
+                () = tokio::time::sleep(RETRY_DROPPED_FRAME_AFTER), if scrollbar.is_frame_pending => {
+                    let span = scrollbar.tattoy.tick_span();
+                    span.in_scope(|| scrollbar.render());
+                }
+            }
+            if scrollbar.tattoy.is_output_closed {
+                break;
             }
         }
 
@@ -42,15 +74,19 @@ impl Scrollbar {
     }
 
     /// Handle messages from the main Tattoy app.
-    async fn handle_protocol_message(
+    fn handle_protocol_message(
         &mut self,
         result: std::result::Result<crate::run::Protocol, tokio::sync::broadcast::error::RecvError>,
     ) -> Result<()> {
         match result {
             Ok(message) => {
+                if let crate::run::Protocol::Config(config) = &message {
+                    self.is_antialiased = config.antialiasing;
+                }
                 self.tattoy.handle_common_protocol_messages(message)?;
                 if self.tattoy.last_scroll_position != self.tattoy.scrollback.position {
-                    self.render().await?;
+                    let span = self.tattoy.tick_span();
+                    span.in_scope(|| self.render());
                 }
             }
             Err(error) => tracing::error!("Receiving protocol message: {error:?}"),
@@ -60,21 +96,23 @@ impl Scrollbar {
     }
 
     /// Tick the render
-    async fn render(&mut self) -> Result<()> {
+    fn render(&mut self) {
+        self.is_frame_pending = false;
         if self.tattoy.is_scrolling_end() {
             tracing::debug!("Scrolling finished.");
-            self.tattoy.send_blank_output().await?;
-            return Ok(());
+            self.is_frame_pending =
+                !self.tattoy.send_blank_output() && !self.tattoy.is_output_closed;
+            return;
         }
 
         if !self.tattoy.is_ready() {
             tracing::debug!("Scrolling tattoy not ready.");
-            return Ok(());
+            return;
         }
 
         if !self.tattoy.is_scrolling() {
             tracing::trace!("Not rendering scrollbar because we're not scrolling yet.");
-            return Ok(());
+            return;
         }
 
         // TODO: only render on scroll position change.
@@ -82,49 +120,51 @@ impl Scrollbar {
         let (start, end) = self.get_start_end();
         if start > end {
             tracing::error!("Bad scrollbar dimensions: {start:?} {end:?}");
-            return Ok(());
+            return;
         }
 
         self.tattoy.initialise_surface();
 
-        for y in start..end {
-            self.tattoy.surface.add_text(
-                (self.tattoy.width - 1).into(),
-                y,
-                " ".into(),
-                Some((1.0, 1.0, 1.0, 0.5)),
-                None,
-            );
-        }
-
-        self.tattoy.send_output().await
+        let shape = crate::draw::Shape::Rect {
+            x: f32::from(self.tattoy.width - 1),
+            y: start,
+            width: 1.0,
+            height: end - start,
+        };
+        let style = crate::draw::Style {
+            colour: (1.0, 1.0, 1.0, 0.5),
+            is_antialiased: self.is_antialiased,
+            ..crate::draw::Style::default()
+        };
+        crate::draw::fill(&mut self.tattoy.surface, &shape, &style);
+
+        self.is_frame_pending = !self.tattoy.send_output() && !self.tattoy.is_output_closed;
     }
 
-    /// Get the start and end y coordinates of the scrollbar
+    /// Get the start and end y coordinates of the scrollbar, in fractions of a row.
     #[expect(
         clippy::as_conversions,
         clippy::cast_precision_loss,
-        clippy::cast_sign_loss,
         clippy::cast_lossless,
-        clippy::cast_possible_truncation,
         reason = "It's just a scrollbar"
     )]
-    fn get_start_end(&self) -> (usize, usize) {
+    fn get_start_end(&self) -> (f32, f32) {
+        let height = f32::from(self.tattoy.height);
         let scrollback_height = self.tattoy.scrollback.surface.dimensions().1;
 
         let top_of_terminal_position =
             scrollback_height - self.tattoy.scrollback.position - self.tattoy.height as usize;
         let top_of_terminal_fraction = top_of_terminal_position as f32 / scrollback_height as f32;
-        let mut scrollbar_start = (top_of_terminal_fraction * self.tattoy.height as f32) as usize;
+        let scrollbar_start = top_of_terminal_fraction * height;
 
         let bottom_of_terminal_position = scrollback_height - self.tattoy.scrollback.position;
         let bottom_of_terminal_fraction =
             bottom_of_terminal_position as f32 / scrollback_height as f32;
-        let mut scrollbar_end = (bottom_of_terminal_fraction * self.tattoy.height as f32) as usize;
-
-        scrollbar_start = scrollbar_start.clamp(0, (self.tattoy.height - 1).into());
-        scrollbar_end = scrollbar_end.clamp(0, (self.tattoy.height - 1).into());
+        let scrollbar_end = bottom_of_terminal_fraction * height;
 
-        (scrollbar_start, scrollbar_end)
+        (
+            scrollbar_start.clamp(0.0, height),
+            scrollbar_end.clamp(0.0, height),
+        )
     }
 }
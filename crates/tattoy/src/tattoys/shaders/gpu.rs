@@ -361,6 +361,33 @@ impl GPU {
         })
     }
 
+    /// The source of the fragment shader.
+    ///
+    /// We are following the fragment shader standard used by the Shadertoy.com website. Therefore
+    /// we also need to provide some header and footer boilerplate to allow copy-pasting shaders
+    /// without alteration. Just little things like `main()` calling `mainImage()` and providing
+    /// known globals such as `iResolution`.
+    async fn fragment_shader_source(shader_path: &std::path::Path) -> Result<String> {
+        let file = tokio::fs::read(shader_path).await?;
+        let contents = String::from_utf8_lossy(&file);
+        let header = include_str!("header.glsl");
+        let footer = include_str!("footer.glsl");
+        Ok(format!("{header}\n{contents}\n{footer}"))
+    }
+
+    /// Check that the user's shader compiles. It's only parsed, so it doesn't need a GPU.
+    pub async fn check_shader(shader_path: &std::path::Path) -> Result<()> {
+        let shader = Self::fragment_shader_source(shader_path).await?;
+        let options = wgpu::naga::front::glsl::Options::from(wgpu::naga::ShaderStage::Fragment);
+        wgpu::naga::front::glsl::Frontend::default()
+            .parse(&options, &shader)
+            .map_err(|error| {
+                color_eyre::eyre::eyre!("Couldn't compile {shader_path:?}: {error:?}")
+            })?;
+
+        Ok(())
+    }
+
     /// Complile the GLSL shaders ready for consumption by the GPU.
     async fn compile_shaders(
         device: &wgpu::Device,
@@ -379,17 +406,7 @@ impl GPU {
         });
 
         // In our usage, the fragment shader is the code that actually omits pixels.
-        //
-        // We are also following the fragment shader standard used by the Shadertoy.com website.
-        // Therefore we also need to provide some header and footer boilerplate to allow
-        // copy-pasting shaders without alteration. Just little things like `main()` calling
-        // `mainImage()` and providing known globals such as `iResolution`.
-        let file = tokio::fs::read(shader_path).await?;
-        let contents = String::from_utf8_lossy(&file);
-        let header = include_str!("header.glsl");
-        let footer = include_str!("footer.glsl");
-        let shader = format!("{header}\n{contents}\n{footer}");
-
+        let shader = Self::fragment_shader_source(&shader_path).await?;
         let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Fragment Shader"),
             source: wgpu::ShaderSource::Glsl {
@@ -2,11 +2,12 @@
 //! <https://shadertoy.com>.
 
 use color_eyre::eyre::{ContextCompat as _, Result};
+use tracing::Instrument as _;
 
 use crate::tattoys::tattoyer::Tattoyer;
 
 /// All the user config for the shader tattoy.
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
 #[serde(default)]
 pub(crate) struct Config {
     /// Enable/disable the shaders on and off
@@ -43,13 +44,29 @@ impl Shaders {
         output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
         state: std::sync::Arc<crate::shared_state::SharedState>,
     ) -> Result<Self> {
-        let tattoy = Tattoyer::new("shaders".to_owned(), -10, output_channel);
+        let rng = state.tattoy_rng("shaders").await;
+        let tattoy = Tattoyer::new(
+            "shaders".to_owned(),
+            -10,
+            output_channel,
+            rng,
+            &*state.config.read().await,
+        );
         let shader_directory = state.config_path.read().await.clone();
         let shader_path = state.config.read().await.shader.path.clone();
         let gpu = super::gpu::GPU::new(shader_directory.join(shader_path)).await?;
         Ok(Self { tattoy, state, gpu })
     }
 
+    /// Check that the user's shader compiles, without starting the tattoy.
+    pub(crate) async fn check_setup(
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+    ) -> Result<()> {
+        let shader_directory = state.config_path.read().await.clone();
+        let shader_path = state.config.read().await.shader.path.clone();
+        super::gpu::GPU::check_shader(&shader_directory.join(shader_path)).await
+    }
+
     /// Our main entrypoint.
     pub(crate) async fn start(
         protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
@@ -66,7 +83,8 @@ impl Shaders {
         loop {
             tokio::select! {
                 () = shaders.tattoy.sleep_until_next_frame_tick() => {
-                    shaders.render().await?;
+                    let span = shaders.tattoy.tick_span();
+                    shaders.render().instrument(span).await?;
                 },
                 result = protocol.recv() => {
                     if matches!(result, Ok(crate::run::Protocol::End)) {
@@ -130,7 +148,7 @@ impl Shaders {
             }
         }
 
-        self.tattoy.send_output().await?;
+        self.tattoy.send_output();
 
         Ok(())
     }
@@ -1,7 +1,7 @@
 //! All the variables that can be configured for the simulation
 
 /// All the config for the simulation
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
 #[serde(default)]
 #[non_exhaustive]
 pub struct Config {
@@ -13,8 +13,25 @@ pub struct Config {
     pub initial_velocity: (f32, f32),
     /// How much bigger a partical is compared to a rendered pixel
     pub scale: f32,
-    /// The maximum number of particles in the simulation
+    /// The number of particles emitted per second whilst the cursor moves
+    pub spawn_rate: f32,
+    /// The maximum number of particles in the simulation. The oldest particles are recycled once
+    /// it's reached.
     pub max_particles: usize,
+    /// The most memory, in megabytes, that the particles can use. New particles stop being
+    /// spawned once it's reached. 0 disables the guard.
+    pub max_memory: usize,
+    /// How many particles are kicked up for every line scrolled. 0 disables it.
+    pub scroll_impulse: f32,
+    /// The strength of the swirling turbulence. 0 disables it.
+    pub turbulence_amplitude: f32,
+    /// The size of the turbulence's swirls. Smaller values make bigger swirls.
+    pub turbulence_scale: f32,
+    /// The glyphs used to render the particles. Braille gives the highest resolution.
+    pub glyphs: super::glyphs::GlyphSet,
+    /// The opacity of a cell containing 1 particle, 2 particles, and so on. Cells with more
+    /// particles use the last value. Only used by the "braille" and "ascii" glyphs.
+    pub density_ramp: Vec<f32>,
 }
 
 impl Default for Config {
@@ -24,7 +41,14 @@ impl Default for Config {
             gravity: (0.0, -9.81),
             initial_velocity: (0.0, 0.0),
             scale: 0.75,
+            spawn_rate: 30.0,
             max_particles: 3000,
+            max_memory: 64,
+            scroll_impulse: 1.0,
+            turbulence_amplitude: 5.0,
+            turbulence_scale: 0.05,
+            glyphs: super::glyphs::GlyphSet::default(),
+            density_ramp: vec![0.25, 0.5, 0.75, 1.0],
         }
     }
 }
@@ -0,0 +1,200 @@
+//! Rendering the particles at a higher resolution than the terminal's cells.
+//!
+//! Half blocks give 2 pixels per cell, each with its own colour. But Braille characters have 2×4
+//! dots per cell, so the smoke can look a lot smoother without needing any more particles. The
+//! catch is that each cell can only have one colour. So the particles that land in each cell are
+//! accumulated, and then a single glyph and colour is picked for the whole cell.
+//!
+//! The number of particles in a cell is its density. Denser cells are more opaque, according to
+//! the user's density ramp, so that sparse smoke fades away into the terminal behind it.
+
+/// The glyphs used to render the particles.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum GlyphSet {
+    /// Half blocks, giving 2 pixels per cell, each with its own colour.
+    #[default]
+    Blocks,
+    /// Braille dots, giving 2×4 dots per cell, with one colour per cell.
+    Braille,
+    /// ASCII characters of increasing density, depending on the number of particles in a cell.
+    Ascii,
+}
+
+/// The codepoint of the empty Braille character. Each of the 8 dots is a bit added to it.
+const BRAILLE_BASE: u32 = 0x2800;
+
+/// The bit for each Braille dot, by column and then row.
+const BRAILLE_DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// ASCII characters, from the least to the most dense.
+const ASCII_RAMP: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// The particles that have landed in a single cell.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct CellCoverage {
+    /// The Braille dots that have at least one particle.
+    pub dots: u8,
+    /// The number of particles.
+    pub count: usize,
+    /// The sum of the colours of the particles.
+    pub colour_sum: crate::surface::Colour,
+}
+
+/// The particles that have landed in each cell of the terminal.
+pub(crate) struct Coverage {
+    /// The width of the terminal.
+    pub width: usize,
+    /// The height of the terminal.
+    pub height: usize,
+    /// The coverage of each cell, row by row.
+    pub cells: Vec<CellCoverage>,
+}
+
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    clippy::float_arithmetic,
+    reason = "We're just rendering to a terminal grid"
+)]
+impl Coverage {
+    /// Start with no coverage.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![CellCoverage::default(); width * height],
+        }
+    }
+
+    /// Add a particle. Just like the simulation, `x` is in columns and `y` is in half rows.
+    pub fn add(&mut self, x: f32, y: f32, colour: crate::surface::Colour) {
+        if x < 0.0 || y < 0.0 {
+            return;
+        }
+
+        let dot_x = (x * 2.0) as usize;
+        let dot_y = (y * 2.0) as usize;
+        let col = dot_x.div_euclid(2);
+        let row = dot_y.div_euclid(4);
+        if col >= self.width || row >= self.height {
+            return;
+        }
+
+        let dot = BRAILLE_DOTS
+            .get(dot_x.rem_euclid(2))
+            .and_then(|column| column.get(dot_y.rem_euclid(4)))
+            .copied()
+            .unwrap_or_default();
+        let Some(cell) = self.cells.get_mut(row * self.width + col) else {
+            return;
+        };
+        cell.dots |= dot;
+        cell.count += 1;
+        cell.colour_sum.0 += colour.0;
+        cell.colour_sum.1 += colour.1;
+        cell.colour_sum.2 += colour.2;
+        cell.colour_sum.3 += colour.3;
+    }
+
+    /// The glyph and colour of every cell that has particles, along with its column and row.
+    /// `density_ramp` is the opacity of a cell with 1 particle, 2 particles, and so on. Cells
+    /// with more particles than the ramp has entries use its last entry.
+    pub fn glyphs<'ramp>(
+        &'ramp self,
+        glyph_set: GlyphSet,
+        density_ramp: &'ramp [f32],
+    ) -> impl Iterator<Item = (usize, usize, char, crate::surface::Colour)> + 'ramp {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.count > 0)
+            .map(move |(index, cell)| {
+                let glyph = match glyph_set {
+                    GlyphSet::Braille => {
+                        char::from_u32(BRAILLE_BASE + u32::from(cell.dots)).unwrap_or(' ')
+                    }
+                    GlyphSet::Ascii | GlyphSet::Blocks => ASCII_RAMP
+                        .get(cell.count.min(ASCII_RAMP.len() - 1))
+                        .copied()
+                        .unwrap_or(' '),
+                };
+                let count = cell.count as f32;
+                let opacity = density_opacity(density_ramp, cell.count);
+                let colour = (
+                    cell.colour_sum.0 / count,
+                    cell.colour_sum.1 / count,
+                    cell.colour_sum.2 / count,
+                    cell.colour_sum.3 / count * opacity,
+                );
+                (
+                    index.rem_euclid(self.width),
+                    index.div_euclid(self.width),
+                    glyph,
+                    colour,
+                )
+            })
+    }
+}
+
+/// The opacity of a cell with `count` particles.
+fn density_opacity(density_ramp: &[f32], count: usize) -> f32 {
+    density_ramp
+        .get(count.saturating_sub(1))
+        .or_else(|| density_ramp.last())
+        .copied()
+        .unwrap_or(1.0)
+        .clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GREY: crate::surface::Colour = (0.5, 0.5, 0.5, 1.0);
+
+    #[test]
+    fn braille_dots_are_sub_cell() {
+        let mut coverage = Coverage::new(2, 2);
+        coverage.add(0.0, 0.0, GREY);
+        coverage.add(0.5, 1.5, GREY);
+        coverage.add(1.0, 2.0, GREY);
+
+        let glyphs: Vec<_> = coverage.glyphs(GlyphSet::Braille, &[]).collect();
+        assert_eq!(
+            glyphs,
+            vec![(0, 0, '\u{2881}', GREY), (1, 1, '\u{2801}', GREY)]
+        );
+    }
+
+    #[test]
+    fn ascii_density() {
+        let mut coverage = Coverage::new(1, 1);
+        coverage.add(0.1, 0.1, GREY);
+        assert_eq!(coverage.glyphs(GlyphSet::Ascii, &[]).next().unwrap().2, '.');
+
+        for _ in 0..20 {
+            coverage.add(0.1, 0.1, GREY);
+        }
+        assert_eq!(coverage.glyphs(GlyphSet::Ascii, &[]).next().unwrap().2, '@');
+    }
+
+    #[test]
+    fn density_fades_sparse_cells() {
+        let ramp = [0.25, 0.5, 1.0];
+        let mut coverage = Coverage::new(2, 1);
+        coverage.add(0.1, 0.1, GREY);
+        for _ in 0..5 {
+            coverage.add(1.1, 0.1, GREY);
+        }
+
+        let opacities: Vec<_> = coverage
+            .glyphs(GlyphSet::Braille, &ramp)
+            .map(|(_, _, _, colour)| colour.3)
+            .collect();
+        assert_eq!(opacities, vec![0.25, 1.0]);
+    }
+}
@@ -3,8 +3,13 @@
 use std::collections::VecDeque;
 
 use color_eyre::eyre::Result;
+use rand::Rng as _;
 
-use super::simulation::Simulation;
+use super::{
+    glyphs::{Coverage, GlyphSet},
+    simulation::Simulation,
+};
+use crate::tattoys::tattoyer::Tattoy;
 
 /// `SmokeyCursor`
 pub(crate) struct SmokeyCursor {
@@ -14,30 +19,56 @@ pub(crate) struct SmokeyCursor {
     simulation: Simulation,
     /// Timestamp of last tick
     durations: VecDeque<f64>,
+    /// Shared app state
+    state: std::sync::Arc<crate::shared_state::SharedState>,
+    /// When the previous frame was rendered
+    last_tick: Option<std::time::Instant>,
+    /// The scroll position when the simulation was last given a scroll impulse. The Tattoyer's
+    /// `last_scroll_position` isn't updated when a frame is dropped, so the same impulse would be
+    /// given again in the next frame.
+    impulse_scroll_position: usize,
 }
 
 impl SmokeyCursor {
     /// Instatiate
-    fn new(output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>) -> Self {
-        let tattoy = crate::tattoys::tattoyer::Tattoyer::new(
+    fn new(
+        output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
+        rng: rand::rngs::StdRng,
+        config: &crate::config::Config,
+    ) -> Self {
+        let mut tattoy = crate::tattoys::tattoyer::Tattoyer::new(
             "smokey_cursor".to_owned(),
             -10,
             output_channel,
+            rng,
+            config,
         );
+        // Room for the debugging output in the top right corner.
+        tattoy.min_size = (20, 2);
 
         Self {
             tattoy,
-            simulation: Simulation::new(0, 0),
+            simulation: Simulation::new(0, 0, rand::SeedableRng::seed_from_u64(0)),
             durations: VecDeque::default(),
+            state,
+            last_tick: None,
+            impulse_scroll_position: 0,
         }
     }
 
     /// Initialise the simulation, because we don't have the dimensions when instantiating Self.
-    fn initialise(&mut self) {
+    /// The simulation gets its own generator, derived from the tattoy's, so that it's still
+    /// reproducible when it's reinitialised.
+    async fn initialise(&mut self) {
+        let config = self.state.config.read().await;
         self.simulation = Simulation::new(
             self.tattoy.width.into(),
             usize::from(self.tattoy.height) * 2,
+            rand::SeedableRng::seed_from_u64(self.tattoy.rng.gen()),
         );
+        self.simulation.update_config(&config.smokey_cursor);
+        drop(config);
         tracing::debug!("Simulation initialised.");
     }
 
@@ -45,31 +76,65 @@ impl SmokeyCursor {
     pub(crate) async fn start(
         protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
         output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        state: std::sync::Arc<crate::shared_state::SharedState>,
     ) -> Result<()> {
-        let mut random_walker = Self::new(output);
-        let mut protocol = protocol_tx.subscribe();
+        let rng = state.tattoy_rng("smokey_cursor").await;
+        let config = state.config.read().await.clone();
+        Self::new(output, state, rng, &config)
+            .run(&protocol_tx)
+            .await
+    }
 
-        #[expect(
-            clippy::integer_division_remainder_used,
-            reason = "This is caused by the `tokio::select!`"
-        )]
-        loop {
-            tokio::select! {
-                () = random_walker.tattoy.sleep_until_next_frame_tick() => {
-                    random_walker.render().await?;
-                },
-                Ok(message) = protocol.recv() => {
-                    if matches!(message, crate::run::Protocol::End) {
-                        break;
-                    }
-                    random_walker.tattoy.handle_common_protocol_messages(message)?;
-                }
-            }
+    /// Render each particle as a half block pixel.
+    fn render_blocks(&mut self) -> Result<()> {
+        for particle in &mut self.simulation.particles {
+            let position = particle.position_unscaled();
+
+            #[expect(
+                clippy::cast_sign_loss,
+                clippy::cast_possible_truncation,
+                clippy::as_conversions,
+                reason = "We're just rendering to a terminal grid"
+            )]
+            self.tattoy.surface.add_pixel(
+                position.x as usize,
+                position.y as usize,
+                particle.colour,
+            )?;
         }
 
         Ok(())
     }
 
+    /// Render the particles at sub-cell resolution, by accumulating the particles in each cell
+    /// and then picking a single glyph for it.
+    fn render_glyphs(&mut self) {
+        let mut coverage = Coverage::new(self.tattoy.width.into(), self.tattoy.height.into());
+        for particle in &self.simulation.particles {
+            let position = particle.position_unscaled();
+            coverage.add(position.x, position.y, particle.colour);
+        }
+
+        let config = &self.simulation.config;
+        for (x, y, glyph, colour) in coverage.glyphs(config.glyphs, &config.density_ramp) {
+            self.tattoy
+                .surface
+                .add_text(x, y, glyph.to_string(), None, Some(colour));
+        }
+    }
+}
+
+impl Tattoy for SmokeyCursor {
+    fn tattoyer(&mut self) -> &mut crate::tattoys::tattoyer::Tattoyer {
+        &mut self.tattoy
+    }
+
+    fn handle_protocol_message(&mut self, message: &crate::run::Protocol) {
+        if let crate::run::Protocol::Config(config) = message {
+            self.simulation.update_config(&config.smokey_cursor);
+        }
+    }
+
     /// One frame of the tattoy
     async fn render(&mut self) -> Result<()> {
         if !self.tattoy.is_ready() {
@@ -77,31 +142,36 @@ impl SmokeyCursor {
         }
 
         if !self.simulation.is_ready() {
-            self.initialise();
+            self.initialise().await;
         }
 
         let start = std::time::Instant::now();
+        let delta = self.last_tick.replace(start).map_or(0.0, |last_tick| {
+            start.duration_since(last_tick).as_secs_f32()
+        });
 
         self.tattoy.initialise_surface();
 
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_precision_loss,
+            clippy::float_arithmetic,
+            reason = "Scroll distances are far too small to lose precision"
+        )]
+        let scrolled_lines =
+            self.tattoy.scrollback.position as f32 - self.impulse_scroll_position as f32;
+        if scrolled_lines != 0.0 {
+            self.simulation.add_scroll_impulse(scrolled_lines);
+        }
+        self.impulse_scroll_position = self.tattoy.scrollback.position;
+
         let cursor = self.tattoy.screen.surface.cursor_position();
         let cells = self.tattoy.screen.surface.screen_cells();
-        self.simulation.tick(cursor, &cells);
-
-        for particle in &mut self.simulation.particles {
-            let position = particle.position_unscaled();
+        self.simulation.tick(cursor, &cells, delta);
 
-            #[expect(
-                clippy::cast_sign_loss,
-                clippy::cast_possible_truncation,
-                clippy::as_conversions,
-                reason = "We're just rendering to a terminal grid"
-            )]
-            self.tattoy.surface.add_pixel(
-                position.x as usize,
-                position.y as usize,
-                particle.colour,
-            )?;
+        match self.simulation.config.glyphs {
+            GlyphSet::Blocks => self.render_blocks()?,
+            GlyphSet::Braille | GlyphSet::Ascii => self.render_glyphs(),
         }
 
         let text_coloumn = usize::from(self.tattoy.width - 20);
@@ -129,6 +199,8 @@ impl SmokeyCursor {
             self.durations.pop_back();
         }
 
-        self.tattoy.send_output().await
+        self.tattoy.send_output();
+
+        Ok(())
     }
 }
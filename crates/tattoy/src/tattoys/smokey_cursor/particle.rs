@@ -3,7 +3,6 @@
 use std::f32::consts::PI;
 
 use glam::Vec2;
-use rand::Rng as _;
 
 /// "Size", or more "area of influence" of a particle
 pub const PARTICLE_SIZE: f32 = 16.0;
@@ -111,6 +110,13 @@ impl Particle {
         gravity * MASS / self.density
     }
 
+    /// The force from the swirling turbulence of the air
+    #[must_use]
+    pub fn force_from_turbulence(&self, time: f32, amplitude: f32, scale: f32) -> Vec2 {
+        let turbulence = super::turbulence::velocity_at(self.position_unscaled(), time, scale);
+        turbulence * amplitude * MASS / self.density
+    }
+
     /// Apply the forces to the velocity and then actually move the particle
     pub fn integrate(&mut self) {
         if self.is_immovable {
@@ -153,9 +159,15 @@ impl Particle {
 
     /// A particle that can move
     #[must_use]
-    pub fn default_movable(scale: f32, velocity: Vec2, x: f32, y: f32) -> Self {
+    pub fn default_movable<R: rand::Rng>(
+        scale: f32,
+        velocity: Vec2,
+        x: f32,
+        y: f32,
+        rng: &mut R,
+    ) -> Self {
         let ish_range = 0.01;
-        let colour_ish = rand::thread_rng().gen_range(-ish_range..ish_range);
+        let colour_ish = rng.gen_range(-ish_range..ish_range);
         let colour = (0.15 + colour_ish, 0.15 + colour_ish, 0.15 + colour_ish, 1.0);
         Self {
             created_at: std::time::Instant::now(),
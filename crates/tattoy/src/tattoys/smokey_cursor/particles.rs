@@ -13,6 +13,17 @@ use super::{
 /// The number of attempts allowed to try to find a safe place to add a new particle
 const ATTEMPTS_TO_FIND_SAFE_PLACE: usize = 100;
 
+/// The most particles that a single scroll can kick up, because finding safe places for them is
+/// expensive.
+const MAX_SCROLL_PARTICLES: usize = 50;
+
+/// The approximate number of bytes that each particle uses. Every particle is stored in both the
+/// queue and the neighbours lookup.
+pub const MEMORY_PER_PARTICLE: usize = std::mem::size_of::<Particle>() * 2;
+
+/// The speed, per line scrolled, of the particles kicked up by scrolling.
+const SCROLL_PARTICLE_SPEED: f32 = 0.05;
+
 #[expect(
     clippy::cast_precision_loss,
     clippy::as_conversions,
@@ -72,30 +83,90 @@ impl Simulation {
         count
     }
 
-    /// Remove first-in particles from FILO queue
+    /// Recycle the first-in particles from the FILO queue, so that there's never more than the
+    /// maximum
     pub fn remove_old_particles(&mut self) {
-        if self.particles.len() < self.config.max_particles {
-            return;
+        while self.particles.len() > self.config.max_particles {
+            self.particles.pop_back();
         }
-        self.particles.pop_back();
     }
 
     /// Safely add a particle without creating "explosions"
     pub fn add_particle(&mut self, x: f32, y: f32) {
+        self.add_particle_with_velocity(x, y, self.config.initial_velocity.into());
+    }
+
+    /// Whether the particles are using more than the configured maximum amount of memory. Warns
+    /// the first time that it happens, rather than for every particle that isn't spawned.
+    fn is_over_memory_limit(&mut self) -> bool {
+        if self.config.max_memory == 0 {
+            return false;
+        }
+
+        let used = self.particles.len() * MEMORY_PER_PARTICLE;
+        let is_over = used >= self.config.max_memory * 1024 * 1024;
+        if is_over && !self.is_memory_limited {
+            tracing::warn!(
+                "Smokey cursor particles are using over {}MB, not spawning any more",
+                self.config.max_memory
+            );
+        }
+        self.is_memory_limited = is_over;
+        is_over
+    }
+
+    /// Safely add a particle, with a particular velocity, without creating "explosions"
+    fn add_particle_with_velocity(&mut self, x: f32, y: f32, velocity: Vec2) {
+        if self.is_over_memory_limit() {
+            return;
+        }
+
         if let Some((x_safe, y_safe)) = self.find_safe_place(x, y) {
             let particle = Particle::default_movable(
                 self.config.scale * super::particle::PARTICLE_SIZE,
-                self.config.initial_velocity.into(),
+                velocity,
                 x_safe,
                 y_safe,
+                &mut self.rng,
             );
             self.particles.push_front(particle);
         }
     }
 
+    /// Kick up particles along the edge that text is scrolling in from, in proportion to how far
+    /// it scrolled. Positive `lines` means scrolling up into the history, so text moves down from
+    /// the top edge. Negative `lines` means text moves up from the bottom edge.
+    #[expect(
+        clippy::cast_sign_loss,
+        clippy::cast_possible_truncation,
+        reason = "The count is always positive and small"
+    )]
+    pub fn add_scroll_impulse(&mut self, lines: f32) {
+        let count =
+            ((lines.abs() * self.config.scroll_impulse).round() as usize).min(MAX_SCROLL_PARTICLES);
+        if count == 0 {
+            return;
+        }
+
+        let scale = self.config.scale * super::particle::PARTICLE_SIZE;
+        let width = self.width / scale;
+        let height = self.height / scale;
+        let (y, direction) = if lines > 0.0 {
+            (0.0, 1.0)
+        } else {
+            (height - 1.0, -1.0)
+        };
+        let velocity = Vec2::new(0.0, direction * lines.abs() * SCROLL_PARTICLE_SPEED);
+
+        for _ in 0..count {
+            let x = self.rng.gen_range(0.0..width.max(1.0));
+            self.add_particle_with_velocity(x, y, velocity);
+        }
+    }
+
     /// Based on the requested location of the new particle find a position near it, but also a
     /// safe distance from other particles, so as not to create unrealistic "explosive" responses.
-    fn find_safe_place(&self, mut x: f32, mut y: f32) -> Option<(f32, f32)> {
+    fn find_safe_place(&mut self, mut x: f32, mut y: f32) -> Option<(f32, f32)> {
         if self.particles.is_empty() {
             return Some((x, y));
         }
@@ -108,8 +179,8 @@ impl Simulation {
                 let distance = delta.length();
                 if distance < PARTICLE_SIZE {
                     too_close = true;
-                    x += rand::thread_rng().gen_range(-PARTICLE_SIZE..PARTICLE_SIZE);
-                    y += rand::thread_rng().gen_range(-PARTICLE_SIZE..PARTICLE_SIZE);
+                    x += self.rng.gen_range(-PARTICLE_SIZE..PARTICLE_SIZE);
+                    y += self.rng.gen_range(-PARTICLE_SIZE..PARTICLE_SIZE);
                     break;
                 }
             }
@@ -10,13 +10,11 @@ use super::{
     config::Config,
     particle::{Particle, PARTICLE_SIZE_SQUARED},
 };
-use crate::tattoys::utils::is_random_trigger;
 
 /// Number of times to iterate the simulation per graphical frame
 const NUMBER_OF_SIMULATION_STEPS_PER_TICK: usize = 5;
 
 /// The main code for the simulation, manages the `tick` etc
-#[derive(Default)]
 #[non_exhaustive]
 pub struct Simulation {
     /// Width of the simulation
@@ -29,6 +27,17 @@ pub struct Simulation {
     pub neighbours: rstar::RTree<Particle>,
     /// The configurable settings for the simulation
     pub config: Config,
+    /// The position of the cursor during the previous tick
+    pub last_cursor: Option<(usize, usize)>,
+    /// The fraction of a particle that's due to be emitted, carried over between ticks
+    pub spawn_remainder: f32,
+    /// The number of seconds that the simulation has been running, for animating turbulence
+    pub time: f32,
+    /// The source of all the simulation's randomness
+    pub rng: rand::rngs::StdRng,
+    /// Whether the memory guard has stopped particles from being spawned. So that it's only
+    /// warned about once each time it happens.
+    pub is_memory_limited: bool,
 }
 
 #[expect(
@@ -39,9 +48,9 @@ pub struct Simulation {
     reason = "This is a prototype"
 )]
 impl Simulation {
-    /// Initialise a new simulation
+    /// Initialise a new simulation. The same `rng` always gives the same simulation.
     #[must_use]
-    pub fn new(width: usize, height: usize) -> Self {
+    pub fn new(width: usize, height: usize, rng: rand::rngs::StdRng) -> Self {
         let config = Config {
             initial_velocity: Vec2::new(0.01, -0.1).into(),
             ..Default::default()
@@ -52,20 +61,37 @@ impl Simulation {
             particles: VecDeque::default(),
             neighbours: rstar::RTree::new(),
             config,
+            last_cursor: None,
+            spawn_remainder: 0.0,
+            time: 0.0,
+            rng,
+            is_memory_limited: false,
         }
     }
 
+    /// Apply the settings from the user's config that can be changed whilst the simulation is
+    /// running.
+    pub fn update_config(&mut self, config: &Config) {
+        self.config.spawn_rate = config.spawn_rate;
+        self.config.max_particles = config.max_particles;
+        self.config.max_memory = config.max_memory;
+        self.config.scroll_impulse = config.scroll_impulse;
+        self.config.turbulence_amplitude = config.turbulence_amplitude;
+        self.config.turbulence_scale = config.turbulence_scale;
+        self.config.glyphs = config.glyphs;
+        self.config.density_ramp.clone_from(&config.density_ramp);
+    }
+
     /// Is the simulation ready?
     #[must_use]
     pub fn is_ready(&self) -> bool {
         self.width > 0.0 && self.height > 0.0
     }
 
-    /// A tick of a graphical frame render
-    pub fn tick(&mut self, cursor: (usize, usize), pty: &[&mut [termwiz::cell::Cell]]) {
-        if is_random_trigger(1) {
-            self.add_particle(cursor.0 as f32, (cursor.1 * 2) as f32);
-        }
+    /// A tick of a graphical frame render. `delta` is the number of seconds since the last tick.
+    pub fn tick(&mut self, cursor: (usize, usize), pty: &[&mut [termwiz::cell::Cell]], delta: f32) {
+        self.time += delta;
+        self.emit_cursor_particles(cursor, delta);
 
         let pty_pixel_count = self.add_pty_particles(cursor, pty);
 
@@ -80,6 +106,30 @@ impl Simulation {
         self.remove_old_particles();
     }
 
+    /// Emit particles from the cursor, at the configured rate, whilst it's moving.
+    #[expect(
+        clippy::cast_sign_loss,
+        clippy::cast_possible_truncation,
+        reason = "The number of particles is always positive and small"
+    )]
+    fn emit_cursor_particles(&mut self, cursor: (usize, usize), delta: f32) {
+        let is_moving = self
+            .last_cursor
+            .is_some_and(|last_cursor| last_cursor != cursor);
+        self.last_cursor = Some(cursor);
+        if !is_moving {
+            self.spawn_remainder = 0.0;
+            return;
+        }
+
+        let due = self.spawn_remainder + self.config.spawn_rate.max(0.0) * delta;
+        let count = due.floor() as usize;
+        self.spawn_remainder = due.fract();
+        for _ in 0..count.min(self.config.max_particles) {
+            self.add_particle(cursor.0 as f32, (cursor.1 * 2) as f32);
+        }
+    }
+
     /// Step through the simulation
     fn evolve(&mut self) {
         self.build_neighbours_lookup();
@@ -147,6 +197,14 @@ impl Simulation {
 
             let gravity = particle.force_from_gravity(self.config.gravity.into());
             particle.force += gravity;
+
+            if self.config.turbulence_amplitude != 0.0 {
+                particle.force += particle.force_from_turbulence(
+                    self.time,
+                    self.config.turbulence_amplitude,
+                    self.config.turbulence_scale,
+                );
+            }
         });
     }
 }
@@ -161,9 +219,10 @@ mod test {
     use super::*;
 
     fn make_sim() -> Simulation {
-        let mut sim = Simulation::new(100, 100);
+        let mut sim = Simulation::new(100, 100, rand::SeedableRng::from_entropy());
         sim.config.gravity = Vec2::ZERO.into();
         sim.config.initial_velocity = Vec2::ZERO.into();
+        sim.config.turbulence_amplitude = 0.0;
         sim.config.scale = 1.0 * crate::tattoys::smokey_cursor::particle::PARTICLE_SIZE; // So we don't have to scale/unscale
         sim
     }
@@ -178,15 +237,65 @@ mod test {
 
     #[test]
     fn basic() {
-        let mut sim = Simulation::new(100, 100);
+        let mut sim = Simulation::new(100, 100, rand::SeedableRng::from_entropy());
         let mut surface = termwiz::surface::Surface::new(100, 100);
-        for _ in 0usize..10 {
-            sim.tick((50, 50), &surface.screen_cells());
+        for x in 0usize..10 {
+            sim.tick((50 + x, 50), &surface.screen_cells(), 0.1);
         }
         assert!(sim.particles.len() > 5);
         assert!(sim.neighbours.size() > 5);
     }
 
+    #[test]
+    fn the_same_seed_gives_the_same_simulation() {
+        let run = || {
+            let mut sim = Simulation::new(100, 100, rand::SeedableRng::seed_from_u64(42));
+            let mut surface = termwiz::surface::Surface::new(100, 100);
+            for x in 0usize..10 {
+                sim.tick((50 + x, 50), &surface.screen_cells(), 0.1);
+            }
+            sim.particles
+                .iter()
+                .map(|particle| (particle.position, particle.colour))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn scrolling_kicks_up_particles_from_the_edge() {
+        let mut sim = make_sim();
+        sim.config.scroll_impulse = 2.0;
+        sim.add_scroll_impulse(3.0);
+        assert_eq!(sim.particles.len(), 6);
+        for particle in &sim.particles {
+            assert!(particle.velocity.y > 0.0);
+        }
+
+        sim.config.scroll_impulse = 0.0;
+        sim.add_scroll_impulse(-3.0);
+        assert_eq!(sim.particles.len(), 6);
+    }
+
+    #[test]
+    fn the_memory_guard_stops_spawning() {
+        let mut sim = make_sim();
+        sim.config.max_memory = 1;
+        let limit = 1024 * 1024 / crate::tattoys::smokey_cursor::particles::MEMORY_PER_PARTICLE;
+        for _ in 0..=limit {
+            add_particle(&mut sim, Vec2::ZERO);
+        }
+
+        sim.add_particle(50.0, 50.0);
+        assert_eq!(sim.particles.len(), limit + 1);
+        assert!(sim.is_memory_limited);
+
+        sim.particles.clear();
+        sim.add_particle(50.0, 50.0);
+        assert_eq!(sim.particles.len(), 1);
+        assert!(!sim.is_memory_limited);
+    }
+
     #[test]
     fn distant_particles_dont_interact() {
         let mut sim = make_sim();
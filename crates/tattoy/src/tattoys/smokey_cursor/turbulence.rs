@@ -0,0 +1,64 @@
+//! Turbulence for the smoke, so that it swirls rather than just rising uniformly.
+//!
+//! It's a curl noise field: the curl of a smooth, animated, scalar potential. Curl fields have no
+//! divergence, so they stir the smoke around without bunching it up or spreading it out, which is
+//! what makes it look like a fluid. The potential is a handful of sine waves at different angles
+//! and frequencies, which is cheap and smooth enough that there's no need for Perlin noise.
+
+use glam::Vec2;
+
+/// The waves that are summed to make the potential: the direction and relative frequency of each
+/// wave, its speed through time and its phase.
+const WAVES: [(Vec2, f32, f32); 4] = [
+    (Vec2::new(1.0, 0.3), 0.7, 0.0),
+    (Vec2::new(-0.4, 1.1), 1.3, 1.7),
+    (Vec2::new(1.9, -1.2), 0.9, 4.1),
+    (Vec2::new(-2.3, -2.9), 1.9, 2.3),
+];
+
+/// The velocity of the turbulence at the given position and time. `scale` is the spatial
+/// frequency of the turbulence, so smaller values make bigger swirls.
+#[expect(
+    clippy::float_arithmetic,
+    reason = "It's all just maths for a visual effect"
+)]
+#[must_use]
+pub fn velocity_at(position: Vec2, time: f32, scale: f32) -> Vec2 {
+    let mut velocity = Vec2::ZERO;
+    for (weight, (direction, speed, phase)) in (1u8..).zip(WAVES) {
+        let wave_vector = direction * scale;
+        let angle = wave_vector.dot(position) + speed * time + phase;
+        // The gradient of `sin(k·p + c) / weight` is `k cos(k·p + c) / weight`, and the curl of
+        // a 2D potential is its gradient rotated by 90°.
+        let gradient = wave_vector * angle.cos() / f32::from(weight);
+        velocity += Vec2::new(gradient.y, -gradient.x);
+    }
+
+    velocity
+}
+
+#[cfg(test)]
+#[expect(clippy::float_arithmetic, reason = "Tests aren't so strict")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn turbulence_has_no_divergence() {
+        let step = 0.001;
+        for (x, y) in [(0.0, 0.0), (12.5, 3.0), (-40.0, 77.7)] {
+            let point = Vec2::new(x, y);
+            let dx = velocity_at(point + Vec2::X * step, 1.0, 0.1)
+                - velocity_at(point - Vec2::X * step, 1.0, 0.1);
+            let dy = velocity_at(point + Vec2::Y * step, 1.0, 0.1)
+                - velocity_at(point - Vec2::Y * step, 1.0, 0.1);
+            let divergence = (dx.x + dy.y) / (2.0 * step);
+            assert!(divergence.abs() < 0.01, "divergence: {divergence}");
+        }
+    }
+
+    #[test]
+    fn turbulence_is_animated() {
+        let point = Vec2::new(10.0, 10.0);
+        assert_ne!(velocity_at(point, 0.0, 0.1), velocity_at(point, 1.0, 0.1));
+    }
+}
@@ -1,6 +1,7 @@
 //! Shared state and behaviour useful to all tattoys.#
 
 use color_eyre::eyre::Result;
+use tracing::Instrument as _;
 
 /// Shared state and behaviour useful to all tattoys.
 pub(crate) struct Tattoyer {
@@ -20,21 +21,60 @@ pub(crate) struct Tattoyer {
     pub scrollback: shadow_terminal::output::CompleteScrollback,
     /// Our own copy of the screen. Saves taking costly read locks.
     pub screen: shadow_terminal::output::CompleteScreen,
+    /// The rows of the screen that have changed since they were last taken with
+    /// `take_changed_rows()`.
+    changed_rows: std::collections::BTreeSet<usize>,
     /// The target frame rate.
     pub target_frame_rate: u32,
+    /// The tattoy's own time between frames, for tattoys that don't need to render at the target
+    /// frame rate. It isn't changed by the user's config.
+    pub frame_interval: Option<std::time::Duration>,
     /// The time at which the previous frame was rendererd.
     pub last_frame_tick: tokio::time::Instant,
+    /// The clock that steps the tattoy's frames instead of real time, like when exporting.
+    pub frame_clock: Option<crate::frame_clock::FrameFollower>,
     /// The last known position of an active scroll.
     pub last_scroll_position: usize,
+    /// The source of all this tattoy's randomness. Use it rather than `rand::thread_rng()` so
+    /// that the user's `random_seed` makes the tattoy reproducible. It's owned by the tattoy, and
+    /// so only ever used from the tattoy's own task, which is why it doesn't need a lock.
+    pub rng: rand::rngs::StdRng,
+    /// The tattoy's own mask, to clip it to part of the terminal.
+    pub mask: Option<crate::mask::Mask>,
+    /// The mask from the user's `masks` config. It's intersected with the tattoy's own mask, and
+    /// updated whenever the config changes.
+    config_mask: Option<crate::mask::Mask>,
+    /// The number of frames that the tattoy has rendered, for profiling.
+    pub frame: u64,
+    /// The smallest terminal, in columns and rows, that the tattoy can render to. The tattoy
+    /// isn't rendered at all on smaller terminals.
+    pub min_size: (u16, u16),
+    /// The most recent composited frame, if the tattoy has sampled it.
+    pub composited: Option<std::sync::Arc<crate::renderer::CompositedFrame>>,
+    /// Whether the compositor has stopped receiving frames, normally because Tattoy is shutting
+    /// down. There's no point rendering any more frames once it has.
+    pub is_output_closed: bool,
+    /// The number of frames that were dropped because the compositor was too busy for them.
+    pub dropped_frames: u64,
+    /// The number of rows reserved from the bottom of the PTY, see `Config::status_rows`.
+    pub status_rows: u16,
+    /// The shape of the cursor that the application asked for.
+    pub cursor_shape: termwiz::surface::CursorShape,
+    /// Whether the application is showing its cursor.
+    pub is_cursor_visible: bool,
 }
 
 impl Tattoyer {
-    /// Instantiate
+    /// Instantiate. The settings that are common to all tattoys are read from the config, later
+    /// changes to them are handled in `handle_common_protocol_messages()`.
     pub(crate) fn new(
         id: String,
         layer: i16,
         output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+        rng: rand::rngs::StdRng,
+        config: &crate::config::Config,
     ) -> Self {
+        let config_mask = Self::config_mask(&id, config);
         Self {
             id: id.clone(),
             layer,
@@ -44,15 +84,53 @@ impl Tattoyer {
             height: 0,
             scrollback: shadow_terminal::output::CompleteScrollback::default(),
             screen: shadow_terminal::output::CompleteScreen::default(),
-            target_frame_rate: 30,
+            changed_rows: std::collections::BTreeSet::new(),
+            target_frame_rate: config.frame_rate,
+            frame_interval: None,
             last_frame_tick: tokio::time::Instant::now(),
+            frame_clock: crate::frame_clock::FrameTicks::follower(),
             last_scroll_position: 0,
+            rng,
+            mask: None,
+            config_mask,
+            frame: 0,
+            min_size: (1, 1),
+            composited: None,
+            is_output_closed: false,
+            dropped_frames: 0,
+            status_rows: config.status_rows,
+            cursor_shape: termwiz::surface::CursorShape::Default,
+            is_cursor_visible: true,
         }
     }
 
-    /// Is the tattoy ready to be built?
-    pub const fn is_ready(&self) -> bool {
-        self.width > 0 && self.height > 0
+    /// Is the tattoy ready to be built? Tattoys aren't built whilst the terminal is too small for
+    /// them.
+    pub fn is_ready(&self) -> bool {
+        let (min_width, min_height) = self.min_size;
+        self.width >= min_width.max(1) && self.height >= min_height.max(1)
+    }
+
+    /// The rows at the bottom of the terminal that are reserved from the PTY, for a tattoy to
+    /// draw a status line in, like the clock does. `None` when no rows are reserved.
+    pub fn status_area(&self) -> Option<crate::mask::Rect> {
+        let pty_height = crate::config::Config::pty_height(self.height, self.status_rows);
+        let rows = self.height.saturating_sub(pty_height);
+        if rows == 0 {
+            return None;
+        }
+
+        Some(crate::mask::Rect {
+            x: 0,
+            y: pty_height.into(),
+            width: self.width.into(),
+            height: rows.into(),
+        })
+    }
+
+    /// The mask for the tattoy from the user's `masks` config.
+    fn config_mask(id: &str, config: &crate::config::Config) -> Option<crate::mask::Mask> {
+        config.masks.get(id).copied().map(crate::mask::Mask::Rect)
     }
 
     /// Create an empty surface ready for building a new frame.
@@ -63,6 +141,9 @@ impl Tattoyer {
             self.height.into(),
             self.layer,
         );
+        self.surface.mask =
+            crate::mask::Mask::intersection(self.mask.as_ref(), self.config_mask.as_ref());
+        self.surface.min_size = self.min_size;
     }
 
     /// Keep track of the size of the underlying terminal.
@@ -90,13 +171,40 @@ impl Tattoyer {
                 self.set_tty_size(width, height);
             }
             crate::run::Protocol::Output(output) => self.handle_pty_output(output)?,
-            crate::run::Protocol::Config(config) => self.target_frame_rate = config.frame_rate,
+            crate::run::Protocol::Config(config) => {
+                self.target_frame_rate = config.frame_rate;
+                self.status_rows = config.status_rows;
+                self.config_mask = Self::config_mask(&self.id, &config);
+            }
             _ => (),
         }
 
         Ok(())
     }
 
+    /// Fetch the latest composited frame, so that the tattoy can react to what's actually on the
+    /// screen, not just to the PTY. Call it once per frame, before reading any cells with
+    /// [`Self::composited_cell`]. The first call asks the renderer to start sharing its frames,
+    /// so there isn't a frame until the next render.
+    ///
+    /// The frame contains the tattoys below the PTY and the PTY itself. Tattoys above the PTY
+    /// aren't included, so that effects drawn above the PTY never sample their own output. But
+    /// a tattoy below the PTY does see its own previous frame.
+    pub async fn sample_composited_frame(&mut self, state: &crate::shared_state::SharedState) {
+        state
+            .is_composited_frame_wanted
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        self.composited
+            .clone_from(&*state.composited_frame.read().await);
+    }
+
+    /// A cell from the latest sampled composited frame. It's read-only: the frame is shared with
+    /// the renderer and every other tattoy, so an effect can't change what's been composited.
+    /// Effects react by drawing on their own surface, like any other tattoy.
+    pub fn composited_cell(&self, x: usize, y: usize) -> Option<&termwiz::cell::Cell> {
+        self.composited.as_ref()?.get(y)?.get(x)
+    }
+
     /// Whether the user is scolling.
     pub const fn is_scrolling(&self) -> bool {
         self.scrollback.position != 0
@@ -138,7 +246,12 @@ impl Tattoyer {
                         screen_diff.size.0.try_into()?,
                         screen_diff.size.1.try_into()?,
                     );
+                    self.cursor_shape = screen_diff.cursor.shape;
+                    self.is_cursor_visible = screen_diff.cursor.visibility
+                        == termwiz::surface::CursorVisibility::Visible;
                     self.screen.surface.add_changes(screen_diff.changes);
+                    // Diffs don't say which rows they change.
+                    self.changed_rows.extend(0..screen_diff.size.1);
                 }
                 _ => (),
             },
@@ -151,6 +264,8 @@ impl Tattoyer {
                         complete_screen.surface.dimensions().0.try_into()?,
                         complete_screen.surface.dimensions().1.try_into()?,
                     );
+                    self.changed_rows
+                        .extend(complete_screen.changed_rows.iter().copied());
                     self.screen = complete_screen;
                 }
                 _ => (),
@@ -161,33 +276,81 @@ impl Tattoyer {
         Ok(())
     }
 
-    /// Send the final surface to the main renderer.
-    pub(crate) async fn send_output(&mut self) -> Result<()> {
-        self.output_channel
-            .send(crate::run::FrameUpdate::TattoySurface(self.surface.clone()))
-            .await?;
-
-        self.last_scroll_position = self.scrollback.position;
+    /// The rows of the screen that have changed since the last time they were taken. Useful for
+    /// only redrawing what's needed, rather than comparing entire screens.
+    pub fn take_changed_rows(&mut self) -> std::collections::BTreeSet<usize> {
+        std::mem::take(&mut self.changed_rows)
+    }
 
-        Ok(())
+    /// Send the final surface to the main renderer. If the renderer is still busy with earlier
+    /// frames then the frame is dropped, it would be out of date by the time it was rendered
+    /// anyway. A closed channel isn't an error, it just means that the tattoy should stop.
+    ///
+    /// Returns whether the renderer accepted the frame. Tattoys that only render when something
+    /// changes must only record a frame as rendered when it was accepted, otherwise a dropped
+    /// frame would never be sent again.
+    pub(crate) fn send_output(&mut self) -> bool {
+        let frame = crate::run::FrameUpdate::TattoySurface(self.surface.clone());
+        match self.output_channel.try_send(frame) {
+            Ok(()) => {
+                self.last_scroll_position = self.scrollback.position;
+                true
+            }
+            Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                self.dropped_frames = self.dropped_frames.wrapping_add(1);
+                tracing::trace!(
+                    "Compositor is busy, dropped frame {} from the {} tattoy ({} dropped so far)",
+                    self.frame,
+                    self.id,
+                    self.dropped_frames
+                );
+                false
+            }
+            Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                if !self.is_output_closed {
+                    tracing::info!(
+                        "Compositor's output channel closed, stopping the {} tattoy",
+                        self.id
+                    );
+                }
+                self.is_output_closed = true;
+                false
+            }
+        }
     }
 
-    /// Send a blank frame to the renderer.
-    pub(crate) async fn send_blank_output(&mut self) -> Result<()> {
+    /// Send a blank frame to the renderer. Returns whether the renderer accepted it.
+    pub(crate) fn send_blank_output(&mut self) -> bool {
         self.initialise_surface();
-        self.send_output().await
+        self.send_output()
     }
 
     /// Sleep until the next frame render is due.
     pub async fn sleep_until_next_frame_tick(&mut self) {
-        let target = crate::renderer::ONE_MICROSECOND.wrapping_div(self.target_frame_rate.into());
-        let target_frame_rate_micro = std::time::Duration::from_micros(target);
-        if let Some(wait) = target_frame_rate_micro.checked_sub(self.last_frame_tick.elapsed()) {
+        if let Some(frame_clock) = &mut self.frame_clock {
+            frame_clock.next_frame().await;
+            self.last_frame_tick = tokio::time::Instant::now();
+            return;
+        }
+
+        let interval = self.frame_interval.unwrap_or_else(|| {
+            let target =
+                crate::renderer::ONE_MICROSECOND.wrapping_div(self.target_frame_rate.into());
+            std::time::Duration::from_micros(target)
+        });
+        if let Some(wait) = interval.checked_sub(self.last_frame_tick.elapsed()) {
             tokio::time::sleep(wait).await;
         }
         self.last_frame_tick = tokio::time::Instant::now();
     }
 
+    /// Start profiling a new frame. The span is only recorded when tracing is at the debug level,
+    /// so it's free otherwise.
+    pub fn tick_span(&mut self) -> tracing::Span {
+        self.frame = self.frame.wrapping_add(1);
+        tracing::debug_span!("tick", tattoy = %self.id, frame = self.frame)
+    }
+
     /// Check if the scrollback output has changed.
     pub fn is_scrollback_output_changed(message: &crate::run::Protocol) -> bool {
         #[expect(
@@ -248,3 +411,226 @@ impl Tattoyer {
         false
     }
 }
+
+/// The behaviour of a tattoy that renders a new frame on every frame tick. Implementors only
+/// need to provide their `Tattoyer` and how to render a frame, the main loop is provided.
+pub(crate) trait Tattoy: Send + Sized {
+    /// The shared state and behaviour of the tattoy.
+    fn tattoyer(&mut self) -> &mut Tattoyer;
+
+    /// Render a single frame.
+    fn render(&mut self) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Handle the protocol messages that are specific to this tattoy. The common messages, like
+    /// resizing, are handled by the `Tattoyer` straight afterwards.
+    fn handle_protocol_message(&mut self, _message: &crate::run::Protocol) {}
+
+    /// Render on every frame tick and handle protocol messages, until Tattoy exits.
+    fn run(
+        mut self,
+        protocol_tx: &tokio::sync::broadcast::Sender<crate::run::Protocol>,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        let mut protocol = protocol_tx.subscribe();
+
+        async move {
+            #[expect(
+                clippy::integer_division_remainder_used,
+                reason = "This is caused by the `tokio::select!`"
+            )]
+            loop {
+                tokio::select! {
+                    () = self.tattoyer().sleep_until_next_frame_tick() => {
+                        let span = self.tattoyer().tick_span();
+                        self.render().instrument(span).await?;
+                        if self.tattoyer().is_output_closed {
+                            break;
+                        }
+                    },
+                    Ok(message) = protocol.recv() => {
+                        if matches!(message, crate::run::Protocol::End) {
+                            break;
+                        }
+                        self.handle_protocol_message(&message);
+                        self.tattoyer().handle_common_protocol_messages(message)?;
+                    }
+                }
+            }
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tattoyer(output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>) -> Tattoyer {
+        let rng = rand::SeedableRng::seed_from_u64(0);
+        Tattoyer::new(
+            "test".to_owned(),
+            1,
+            output,
+            rng,
+            &crate::config::Config::default(),
+        )
+    }
+
+    #[test]
+    fn tattoys_arent_ready_whilst_the_terminal_is_too_small() {
+        let (output_tx, _output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        tattoy.min_size = (20, 2);
+
+        tattoy.set_tty_size(19, 10);
+        assert!(!tattoy.is_ready());
+        tattoy.set_tty_size(20, 1);
+        assert!(!tattoy.is_ready());
+        tattoy.set_tty_size(20, 2);
+        assert!(tattoy.is_ready());
+
+        tattoy.initialise_surface();
+        assert_eq!(tattoy.surface.min_size, (20, 2));
+    }
+
+    #[test]
+    fn closed_output_channels_stop_the_tattoy() {
+        let (output_tx, output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        tattoy.send_output();
+        assert!(!tattoy.is_output_closed);
+
+        drop(output_rx);
+        tattoy.send_output();
+        assert!(tattoy.is_output_closed);
+    }
+
+    #[tokio::test]
+    async fn frames_are_dropped_whilst_the_compositor_is_busy() {
+        let (output_tx, mut output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        assert!(tattoy.send_output());
+        assert!(!tattoy.send_output());
+        assert!(!tattoy.send_output());
+        assert_eq!(tattoy.dropped_frames, 2);
+        assert!(!tattoy.is_output_closed);
+
+        // The compositor catches up.
+        assert!(output_rx.recv().await.is_some());
+        assert!(tattoy.send_output());
+        assert_eq!(tattoy.dropped_frames, 2);
+        assert!(output_rx.try_recv().is_ok());
+        assert!(output_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn changed_rows_are_gathered_until_theyre_taken() {
+        let (output_tx, _output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        for changed_rows in [vec![3], vec![1, 3]] {
+            let mut screen = shadow_terminal::output::CompleteScreen::default();
+            screen.changed_rows = changed_rows;
+            let output = shadow_terminal::output::Output::Complete(
+                shadow_terminal::output::CompleteSurface::Screen(screen),
+            );
+            tattoy.handle_pty_output(output).unwrap();
+        }
+
+        assert_eq!(
+            tattoy.take_changed_rows().into_iter().collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert!(tattoy.take_changed_rows().is_empty());
+    }
+
+    #[test]
+    fn the_cursors_shape_and_visibility_are_tracked() {
+        let (output_tx, _output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        let mut diff = shadow_terminal::output::ScreenDiff::default();
+        diff.size = (2, 2);
+        diff.cursor.shape = termwiz::surface::CursorShape::BlinkingBar;
+        diff.cursor.visibility = termwiz::surface::CursorVisibility::Hidden;
+        let output = shadow_terminal::output::Output::Diff(
+            shadow_terminal::output::SurfaceDiff::Screen(diff),
+        );
+
+        tattoy.handle_pty_output(output).unwrap();
+        assert_eq!(
+            tattoy.cursor_shape,
+            termwiz::surface::CursorShape::BlinkingBar
+        );
+        assert!(!tattoy.is_cursor_visible);
+    }
+
+    #[test]
+    fn masks_come_from_the_config() {
+        let (output_tx, _output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        let rect = crate::mask::Rect {
+            x: 1,
+            y: 2,
+            width: 3,
+            height: 4,
+        };
+        let mut config = crate::config::Config::default();
+        config.masks.insert("test".to_owned(), rect);
+
+        tattoy
+            .handle_common_protocol_messages(crate::run::Protocol::Config(config))
+            .unwrap();
+        tattoy.initialise_surface();
+        assert!(matches!(
+            tattoy.surface.mask,
+            Some(crate::mask::Mask::Rect(masked)) if masked == rect
+        ));
+
+        tattoy
+            .handle_common_protocol_messages(crate::run::Protocol::Config(
+                crate::config::Config::default(),
+            ))
+            .unwrap();
+        assert!(tattoy.config_mask.is_none());
+    }
+
+    #[test]
+    fn config_masks_are_intersected_with_the_tattoys_own_mask() {
+        let (output_tx, _output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        let mut config = crate::config::Config::default();
+        config.masks.insert(
+            "test".to_owned(),
+            crate::mask::Rect {
+                x: 0,
+                y: 0,
+                width: 4,
+                height: 4,
+            },
+        );
+        tattoy
+            .handle_common_protocol_messages(crate::run::Protocol::Config(config))
+            .unwrap();
+        tattoy.mask = Some(crate::mask::Mask::Predicate(std::sync::Arc::new(|x, y| {
+            x == y
+        })));
+
+        tattoy.initialise_surface();
+        let mask = tattoy.surface.mask.unwrap();
+        assert!(mask.contains(1, 1));
+        assert!(!mask.contains(1, 2));
+        assert!(!mask.contains(5, 5));
+    }
+
+    #[test]
+    fn the_status_area_is_below_the_pty() {
+        let (output_tx, _output_rx) = tokio::sync::mpsc::channel(1);
+        let mut tattoy = tattoyer(output_tx);
+        tattoy.width = 80;
+        tattoy.height = 24;
+        assert!(tattoy.status_area().is_none());
+
+        tattoy.status_rows = 2;
+        let area = tattoy.status_area().unwrap();
+        assert_eq!((area.x, area.y, area.width, area.height), (0, 22, 80, 2));
+    }
+}
@@ -0,0 +1,315 @@
+//! Tattoys compiled to WASM, so that effects can be shared without recompiling Tattoy.
+//!
+//! Plugins are sandboxed, they can't import anything from the host. They only need to export
+//! their `memory` and a single function:
+//!
+//! ```text
+//! tattoy_frame(width: i32, height: i32, cursor_x: i32, cursor_y: i32) -> i32
+//! ```
+//!
+//! It's called once per frame and returns a pointer, into the plugin's memory, to a buffer of
+//! `width * height` cells, row by row. Each cell is 12 bytes: a little endian `u32`
+//! Unicode codepoint, then the foreground RGBA and then the background RGBA, one byte per channel.
+//! A codepoint of 0 means that the cell is left empty.
+//!
+//! Every frame has a time budget. A plugin that goes over it is stopped, so that a runaway plugin
+//! can't stall Tattoy.
+//!
+//! Plugins are only available when Tattoy is built with the `wasm` feature.
+
+/// All the user config for the WASM tattoy.
+#[derive(serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+#[serde(default)]
+#[cfg_attr(
+    not(feature = "wasm"),
+    expect(dead_code, reason = "Only the `wasm` feature runs plugins")
+)]
+pub(crate) struct Config {
+    /// Enable/disable the WASM plugin
+    pub enabled: bool,
+    /// The path to the compiled WASM plugin. Is relative to Tattoy's config directory.
+    pub path: std::path::PathBuf,
+    /// The maximum number of milliseconds that the plugin can take to render a frame.
+    pub frame_budget: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "plugins/plugin.wasm".into(),
+            frame_budget: 10,
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+pub(crate) use host::Wasm;
+
+/// The plugin host, which needs the `wasmtime` dependency.
+#[cfg(feature = "wasm")]
+mod host {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use color_eyre::eyre::{ContextCompat as _, Result};
+
+    use crate::tattoys::tattoyer::{Tattoy, Tattoyer};
+
+    /// The number of bytes in each cell of a plugin's frame.
+    pub const CELL_SIZE: usize = 12;
+
+    /// How often the engine's epoch is incremented. Frame budgets are measured in these ticks.
+    const EPOCH_TICK: std::time::Duration = std::time::Duration::from_millis(1);
+
+    /// `Wasm`
+    pub(crate) struct Wasm {
+        /// The base Tattoy struct
+        tattoy: Tattoyer,
+        /// The plugin's state.
+        store: wasmtime::Store<()>,
+        /// The plugin's exported memory.
+        memory: wasmtime::Memory,
+        /// The plugin's exported frame function.
+        frame: wasmtime::TypedFunc<(i32, i32, i32, i32), i32>,
+        /// The maximum number of epoch ticks that the plugin can take to render a frame.
+        frame_budget: u64,
+        /// Tells the epoch ticker thread to stop.
+        is_stopping: std::sync::Arc<AtomicBool>,
+    }
+
+    impl Wasm {
+        /// Instantiate
+        async fn new(
+            output_channel: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+            state: std::sync::Arc<crate::shared_state::SharedState>,
+        ) -> Result<Self> {
+            let rng = state.tattoy_rng("wasm").await;
+            let tattoy = Tattoyer::new(
+                "wasm".to_owned(),
+                -10,
+                output_channel,
+                rng,
+                &*state.config.read().await,
+            );
+            let config = state.config.read().await.wasm.clone();
+            let path = state.config_path.read().await.join(config.path);
+            tracing::info!("Loading WASM plugin from: {path:?}");
+
+            let mut engine_config = wasmtime::Config::new();
+            engine_config.epoch_interruption(true);
+            let engine = wasmtime::Engine::new(&engine_config)
+                .map_err(|error| color_eyre::eyre::eyre!("{error:?}"))?;
+            let module = wasmtime::Module::from_file(&engine, &path)
+                .map_err(|error| color_eyre::eyre::eyre!("{error:?}"))?;
+            let mut store = wasmtime::Store::new(&engine, ());
+            let instance = wasmtime::Instance::new(&mut store, &module, &[])
+                .map_err(|error| color_eyre::eyre::eyre!("{error:?}"))?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .context("WASM plugin doesn't export its `memory`")?;
+            let frame = instance
+                .get_typed_func::<(i32, i32, i32, i32), i32>(&mut store, "tattoy_frame")
+                .map_err(|error| color_eyre::eyre::eyre!("{error:?}"))?;
+
+            let is_stopping = std::sync::Arc::new(AtomicBool::new(false));
+            Self::start_epoch_ticker(engine, std::sync::Arc::clone(&is_stopping));
+
+            Ok(Self {
+                tattoy,
+                store,
+                memory,
+                frame,
+                frame_budget: config.frame_budget.max(1),
+                is_stopping,
+            })
+        }
+
+        /// Check that the plugin compiles and exports everything that it needs to, without starting
+        /// the tattoy. The plugin isn't instantiated, so none of its code is run.
+        pub(crate) async fn check_setup(
+            state: std::sync::Arc<crate::shared_state::SharedState>,
+        ) -> Result<()> {
+            let config = state.config.read().await.wasm.clone();
+            let path = state.config_path.read().await.join(config.path);
+            let engine = wasmtime::Engine::default();
+            let module = wasmtime::Module::from_file(&engine, &path)
+                .map_err(|error| color_eyre::eyre::eyre!("Couldn't compile {path:?}: {error:?}"))?;
+
+            if module.imports().next().is_some() {
+                color_eyre::eyre::bail!("WASM plugins can't import anything");
+            }
+            for export in ["memory", "tattoy_frame"] {
+                if module.get_export(export).is_none() {
+                    color_eyre::eyre::bail!("WASM plugin doesn't export its `{export}`");
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Our main entrypoint.
+        pub(crate) async fn start(
+            protocol_tx: tokio::sync::broadcast::Sender<crate::run::Protocol>,
+            output: tokio::sync::mpsc::Sender<crate::run::FrameUpdate>,
+            state: std::sync::Arc<crate::shared_state::SharedState>,
+        ) -> Result<()> {
+            Self::new(output, state).await?.run(&protocol_tx).await
+        }
+
+        /// The plugin is interrupted when the engine's epoch reaches its deadline. So something has to
+        /// keep incrementing the epoch, independently of the plugin.
+        fn start_epoch_ticker(engine: wasmtime::Engine, is_stopping: std::sync::Arc<AtomicBool>) {
+            std::thread::spawn(move || {
+                while !is_stopping.load(Ordering::Relaxed) {
+                    std::thread::sleep(EPOCH_TICK);
+                    engine.increment_epoch();
+                }
+            });
+        }
+
+        /// Ask the plugin for a frame and return its raw cells.
+        fn call_plugin(&mut self) -> Result<Vec<u8>> {
+            let cursor = self.tattoy.screen.surface.cursor_position();
+            let width = usize::from(self.tattoy.width);
+            let height = usize::from(self.tattoy.height);
+
+            self.store.set_epoch_deadline(self.frame_budget);
+            let pointer = self
+                .frame
+                .call(
+                    &mut self.store,
+                    (
+                        i32::from(self.tattoy.width),
+                        i32::from(self.tattoy.height),
+                        cursor.0.try_into()?,
+                        cursor.1.try_into()?,
+                    ),
+                )
+                .map_err(|error| {
+                    color_eyre::eyre::eyre!(
+                        "WASM plugin failed, or took longer than its {}ms frame budget: {error:?}",
+                        self.frame_budget
+                    )
+                })?;
+
+            let start = usize::try_from(pointer)?;
+            let end = start + width * height * CELL_SIZE;
+            let cells = self
+                .memory
+                .data(&self.store)
+                .get(start..end)
+                .context("WASM plugin's frame is outside of its memory")?;
+            Ok(cells.to_vec())
+        }
+    }
+
+    impl Tattoy for Wasm {
+        fn tattoyer(&mut self) -> &mut Tattoyer {
+            &mut self.tattoy
+        }
+
+        /// Tick the render
+        async fn render(&mut self) -> Result<()> {
+            if !self.tattoy.is_ready() {
+                return Ok(());
+            }
+
+            let cells = self.call_plugin()?;
+            self.tattoy.initialise_surface();
+            let width = usize::from(self.tattoy.width);
+            for (index, cell) in cells.chunks_exact(CELL_SIZE).enumerate() {
+                let Some((character, foreground, background)) = decode_cell(cell) else {
+                    continue;
+                };
+                self.tattoy.surface.add_text(
+                    index.rem_euclid(width),
+                    index.div_euclid(width),
+                    character.to_string(),
+                    Some(background),
+                    Some(foreground),
+                );
+            }
+
+            self.tattoy.send_output();
+
+            Ok(())
+        }
+    }
+
+    impl Drop for Wasm {
+        fn drop(&mut self) {
+            self.is_stopping.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Decode a single cell from a plugin's frame. Returns `None` for empty cells.
+    fn decode_cell(cell: &[u8]) -> Option<(char, crate::surface::Colour, crate::surface::Colour)> {
+        let [c0, c1, c2, c3, fr, fg, fb, fa, br, bg, bb, ba] = cell else {
+            return None;
+        };
+        let codepoint = u32::from_le_bytes([*c0, *c1, *c2, *c3]);
+        if codepoint == 0 {
+            return None;
+        }
+
+        let character = char::from_u32(codepoint)?;
+        Some((
+            character,
+            colour_from_bytes([*fr, *fg, *fb, *fa]),
+            colour_from_bytes([*br, *bg, *bb, *ba]),
+        ))
+    }
+
+    /// Convert RGBA bytes into a colour.
+    fn colour_from_bytes(bytes: [u8; 4]) -> crate::surface::Colour {
+        let [red, green, blue, alpha] = bytes.map(|byte| f32::from(byte) / 255.0);
+        (red, green, blue, alpha)
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn decoding_cells() {
+            let empty = [0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0];
+            assert!(decode_cell(&empty).is_none());
+
+            let cell = [b'x', 0, 0, 0, 255, 0, 0, 255, 0, 0, 255, 0];
+            assert_eq!(
+                decode_cell(&cell),
+                Some(('x', (1.0, 0.0, 0.0, 1.0), (0.0, 0.0, 1.0, 0.0)))
+            );
+        }
+
+        #[tokio::test]
+        async fn plugins_that_go_over_their_frame_budget_are_stopped() {
+            let config_directory = tempfile::tempdir().unwrap();
+            std::fs::create_dir(config_directory.path().join("plugins")).unwrap();
+            std::fs::write(
+                config_directory.path().join("plugins/plugin.wasm"),
+                r#"
+                    (module
+                        (memory (export "memory") 1)
+                        (func (export "tattoy_frame")
+                            (param i32 i32 i32 i32) (result i32)
+                            (loop $forever (br $forever))
+                            (i32.const 0)))
+                "#,
+            )
+            .unwrap();
+            let state = std::sync::Arc::new(crate::shared_state::SharedState::default());
+            *state.config_path.write().await = config_directory.path().to_path_buf();
+            let (output, _output_rx) = tokio::sync::mpsc::channel(1);
+
+            let mut wasm = Wasm::new(output, state).await.unwrap();
+            wasm.tattoy.width = 10;
+            wasm.tattoy.height = 5;
+            let started = std::time::Instant::now();
+            let error = wasm.call_plugin().unwrap_err();
+
+            assert!(error.to_string().contains("10ms frame budget"));
+            assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        }
+    }
+}
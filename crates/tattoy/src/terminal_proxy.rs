@@ -19,6 +19,8 @@ pub(crate) struct TerminalProxy {
     tattoy_protocol: tokio::sync::broadcast::Sender<crate::run::Protocol>,
     /// A hash map linking palette indexes to true colour values.
     palette: Option<crate::palette::converter::Palette>,
+    /// The number of rows reserved from the bottom of the PTY for tattoys.
+    status_rows: u16,
 }
 
 impl TerminalProxy {
@@ -38,6 +40,7 @@ impl TerminalProxy {
             surfaces_tx,
             tattoy_protocol,
             palette: crate::config::Config::load_palette(state).await?,
+            status_rows: state.config.read().await.status_rows,
         })
     }
 
@@ -51,6 +54,7 @@ impl TerminalProxy {
         let shadow_terminal = shadow_terminal::active_terminal::ActiveTerminal::start(config);
 
         let mut tattoy_protocol_rx = tattoy_protocol.subscribe();
+        let mut shadow_protocol_rx = shadow_terminal.control_tx.subscribe();
         let mut proxy = Self::new(state, shadow_terminal, surfaces_tx, tattoy_protocol).await?;
 
         #[expect(
@@ -64,14 +68,18 @@ impl TerminalProxy {
                     proxy.handle_tattoy_protocol_message(message).await?;
                 }
                 result = &mut proxy.shadow_terminal.task_handle => {
-                    if let Err(error) = result {
-                        tracing::error!("{error:?}");
+                    match result {
+                        Ok(result) => result?,
+                        Err(error) => tracing::error!("{error:?}"),
                     }
                     break;
                 }
                 Some(output) = proxy.shadow_terminal.surface_output_rx.recv() => {
                     proxy.handle_output(output).await?;
                 }
+                Ok(message) = shadow_protocol_rx.recv() => {
+                    proxy.handle_shadow_terminal_protocol_message(&message);
+                }
             }
         }
 
@@ -99,9 +107,7 @@ impl TerminalProxy {
 
                     let is_alternate_screen =
                         matches!(screen.mode, shadow_terminal::output::ScreenMode::Alternate);
-                    self.state
-                        .set_is_alternate_screen(is_alternate_screen)
-                        .await;
+                    self.update_alternate_screen(is_alternate_screen).await;
                 }
                 _ => (),
             },
@@ -132,9 +138,7 @@ impl TerminalProxy {
                     screen_diff.mode,
                     shadow_terminal::output::ScreenMode::Alternate
                 );
-                self.state
-                    .set_is_alternate_screen(is_alternate_screen)
-                    .await;
+                self.update_alternate_screen(is_alternate_screen).await;
                 self.reconstruct_screen_diff(screen_diff).await;
             }
             _ => (),
@@ -143,6 +147,28 @@ impl TerminalProxy {
         Ok(())
     }
 
+    /// Keep track of whether the PTY is in the alternate screen, and let everyone know when it
+    /// switches.
+    async fn update_alternate_screen(&self, is_alternate_screen: bool) {
+        if !self
+            .state
+            .set_is_alternate_screen(is_alternate_screen)
+            .await
+        {
+            return;
+        }
+
+        tracing::debug!("Alternate screen: {is_alternate_screen}");
+        let result = self
+            .tattoy_protocol
+            .send(crate::run::Protocol::AlternateScreen(is_alternate_screen));
+        if let Err(error) = result {
+            tracing::error!(
+                "Couldn't notify protocol channel about the alternate screen: {error:?}"
+            );
+        }
+    }
+
     /// Reconstruct the scrollback surface from a diff of changes.
     async fn reconstruct_scrollback_diff(
         &self,
@@ -185,33 +211,81 @@ impl TerminalProxy {
     /// Reconstruct the alternate screen surface from a diff of changes.
     async fn reconstruct_screen_diff(&self, diff: shadow_terminal::output::ScreenDiff) {
         let mut shadow_tty_screen = self.state.shadow_tty_screen.write().await;
-        let size = self.state.get_tty_size().await;
 
+        // The PTY isn't always the same size as the user's terminal, because of any status rows.
         if shadow_tty_screen.dimensions() != diff.size {
-            shadow_tty_screen.resize(size.width.into(), size.height.into());
+            shadow_tty_screen.resize(diff.size.0, diff.size.1);
         }
         shadow_tty_screen.add_changes(diff.changes);
     }
 
     /// Handle protocol messages from Tattoy.
-    async fn handle_tattoy_protocol_message(&self, message: crate::run::Protocol) -> Result<()> {
+    async fn handle_tattoy_protocol_message(
+        &mut self,
+        message: crate::run::Protocol,
+    ) -> Result<()> {
         #[expect(clippy::wildcard_enum_match_arm, reason = "It's our internal protocol")]
         match message {
             crate::run::Protocol::End => {
                 self.shadow_terminal.kill()?;
             }
             crate::run::Protocol::Resize { width, height } => {
-                self.shadow_terminal.resize(width, height)?;
+                let pty_height = crate::config::Config::pty_height(height, self.status_rows);
+                self.shadow_terminal.resize(width, pty_height)?;
             }
             crate::run::Protocol::Input(input) => {
                 self.handle_input(&input).await?;
             }
+            crate::run::Protocol::Config(config) => {
+                if let Some(palette) = &mut self.palette {
+                    if palette.bold_is_bright != config.bold_is_bright {
+                        palette.bold_is_bright = config.bold_is_bright;
+                        self.request_full_redraw();
+                    }
+                }
+                if config.status_rows != self.status_rows {
+                    self.status_rows = config.status_rows;
+                    let size = self.state.get_tty_size().await;
+                    let pty_height =
+                        crate::config::Config::pty_height(size.height, self.status_rows);
+                    self.shadow_terminal.resize(size.width, pty_height)?;
+                }
+            }
+            crate::run::Protocol::PaletteChanged => {
+                match crate::config::Config::load_palette(&self.state).await {
+                    Ok(palette) => {
+                        self.palette = palette;
+                        self.request_full_redraw();
+                    }
+                    Err(error) => tracing::error!("Couldn't reload the palette: {error:?}"),
+                }
+            }
             _ => (),
         }
 
         Ok(())
     }
 
+    /// Ask the shadow terminal to send the whole screen and scrollback again. Cells keep their
+    /// palette indexes in the shadow terminal, so they're all converted to true colour again, with
+    /// the current palette. Everything that uses the PTY's output then re-resolves its colours.
+    fn request_full_redraw(&self) {
+        let result = self.shadow_terminal.request_full_redraw();
+        if let Err(error) = result {
+            tracing::error!("Couldn't request a full redraw from the shadow terminal: {error:?}");
+        }
+    }
+
+    /// Handle the protocol messages that the shadow terminal broadcasts.
+    fn handle_shadow_terminal_protocol_message(&self, message: &shadow_terminal::Protocol) {
+        if matches!(message, shadow_terminal::Protocol::Bell) {
+            let result = self.tattoy_protocol.send(crate::run::Protocol::Bell);
+            if let Err(error) = result {
+                tracing::error!("Couldn't notify protocol channel about the bell: {error:?}");
+            }
+        }
+    }
+
     // TODO:
     // It is a bit odd that we send 3 notifications about new PTY output. I'm sure the
     // receiver of the `Protocol::Output` message could do everything that the receiver of the
@@ -16,3 +16,26 @@ pub const CLEAR_SCREEN: &str = "\x1b[2J";
 
 /// OSC code to reset the terminal screen.
 pub const RESET_SCREEN: &str = "\x1bc";
+
+/// The offset basis for the FNV-1a hash.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The prime for the FNV-1a hash.
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// A random number generator for a tattoy. When the user has set a `random_seed` then it's seeded
+/// from that, mixed with the tattoy's ID, so that each tattoy gets its own different, yet still
+/// reproducible, random numbers. Otherwise it's seeded from the OS.
+pub fn seeded_rng(maybe_seed: Option<u64>, id: &str) -> rand::rngs::StdRng {
+    use rand::SeedableRng as _;
+
+    match maybe_seed {
+        Some(seed) => {
+            let id_hash = id.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+            });
+            rand::rngs::StdRng::seed_from_u64(seed ^ id_hash)
+        }
+        None => rand::rngs::StdRng::from_entropy(),
+    }
+}
@@ -303,4 +303,92 @@ mod e2e {
 
         tattoy.wait_for_string("co▀▀▀▀▀▀▀▀▀▀", None).await.unwrap();
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn export_a_recording() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::copy(
+            "resources/palette.toml",
+            temp_dir.path().join("palette.toml"),
+        )
+        .unwrap();
+        let recording = temp_dir.path().join("recording.pty");
+        std::fs::write(&recording, "hello\r\n\x1b[31mworld\x1b[m\r\n").unwrap();
+        let gif = temp_dir.path().join("export.gif");
+
+        let mut stepper = SteppableTerminal::start(Config {
+            width: 50,
+            height: 10,
+            command: shadow_terminal::steppable_terminal::get_canonical_shell(),
+            ..Config::default()
+        })
+        .await
+        .unwrap();
+
+        // Exporting still needs a TTY, so it's run from a shell in a steppable terminal.
+        let command = format!(
+            "\
+            {} \
+            --use random_walker \
+            --config-dir {} \
+            --log-path ./tests.log \
+            --export {} \
+            --export-to {} \
+            --export-duration 0.5 \
+            --export-frame-rate 10 \
+            --export-columns 20 \
+            --export-rows 5 \
+            && echo exported-$((20+3))",
+            tattoy_binary_path(),
+            temp_dir.path().display(),
+            recording.display(),
+            gif.display(),
+        );
+        stepper.send_command(&command).unwrap();
+        stepper.wait_for_string("exported-23", None).await.unwrap();
+
+        let image = std::fs::read(&gif).unwrap();
+        assert!(image.starts_with(b"GIF89a"));
+        let dimension = |offset: usize| {
+            u16::from_le_bytes([*image.get(offset).unwrap(), *image.get(offset + 1).unwrap()])
+        };
+        // Each cell is 6 pixels wide and 12 pixels high.
+        assert_eq!((dimension(6), dimension(8)), (120, 60));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn validating_a_broken_config_fails() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::copy(
+            "resources/palette.toml",
+            temp_dir.path().join("palette.toml"),
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("tattoy.toml"), "frame_rate = \"fast\"").unwrap();
+
+        let mut stepper = SteppableTerminal::start(Config {
+            width: 50,
+            height: 10,
+            command: shadow_terminal::steppable_terminal::get_canonical_shell(),
+            ..Config::default()
+        })
+        .await
+        .unwrap();
+
+        let command = format!(
+            "\
+            {} \
+            --validate \
+            --config-dir {} \
+            --log-path ./tests.log; \
+            echo exited-with-$?",
+            tattoy_binary_path(),
+            temp_dir.path().display(),
+        );
+        stepper.send_command(&command).unwrap();
+        stepper
+            .wait_for_string("exited-with-1", None)
+            .await
+            .unwrap();
+    }
 }